@@ -19,8 +19,12 @@ typedef struct _dc_msg      dc_msg_t;
 typedef struct _dc_contact  dc_contact_t;
 typedef struct _dc_lot      dc_lot_t;
 typedef struct _dc_provider dc_provider_t;
+typedef struct _dc_reactions dc_reactions_t;
+typedef struct _dc_search_results dc_search_results_t;
 typedef struct _dc_event    dc_event_t;
 typedef struct _dc_event_emitter dc_event_emitter_t;
+typedef struct _dc_accounts dc_accounts_t;
+typedef struct _dc_accounts_event_emitter dc_accounts_event_emitter_t;
 
 
 /**
@@ -257,6 +261,13 @@ char*           dc_get_blobdir               (const dc_context_t* context);
  * - `server_flags` = IMAP-/SMTP-flags as a combination of @ref DC_LP flags, guessed if left out
  * - `imap_certificate_checks` = how to check IMAP certificates, one of the @ref DC_CERTCK flags, defaults to #DC_CERTCK_AUTO (0)
  * - `smtp_certificate_checks` = how to check SMTP certificates, one of the @ref DC_CERTCK flags, defaults to #DC_CERTCK_AUTO (0)
+ * - `proxy_type`   = proxy to route all IMAP, SMTP and HTTP connections through,
+ *                    0=no proxy (default), 1=SOCKS5, 2=HTTP proxy supporting `CONNECT`;
+ *                    dc_configure() checks the proxy before anything else
+ * - `proxy_host`   = hostname or IP address of the proxy
+ * - `proxy_port`   = port of the proxy, defaults to 1080 for SOCKS5 and 8080 for HTTP proxies
+ * - `proxy_user`   = username for the proxy, leave empty if the proxy needs no authentication
+ * - `proxy_pw`     = password for the proxy
  * - `displayname`  = Own name to use when sending messages.  MUAs are allowed to spread this way eg. using CC, defaults to empty
  * - `selfstatus`   = Own status to display eg. in email footers, defaults to a standard text
  * - `selfavatar`   = File containing avatar. Will immediately be copied to the 
@@ -265,6 +276,9 @@ char*           dc_get_blobdir               (const dc_context_t* context);
  *                    It is planned for future versions
  *                    to send this image together with the next messages.
  * - `e2ee_enabled` = 0=no end-to-end-encryption, 1=prefer end-to-end-encryption (default)
+ * - `sign_unencrypted` = 1=sign messages that are not end-to-end-encrypted
+ *                    using OpenPGP/MIME (multipart/signed),
+ *                    0=send messages that are not end-to-end-encrypted unsigned (default)
  * - `mdns_enabled` = 0=do not send or request read receipts,
  *                    1=send and request read receipts (default)
  * - `inbox_watch`  = 1=watch `INBOX`-folder for changes (default),
@@ -307,9 +321,8 @@ char*           dc_get_blobdir               (const dc_context_t* context);
  *                    DC_MEDIA_QUALITY_WORSE (1)
  *                    allow worse images/videos/voice quality to gain smaller sizes,
  *                    suitable for providers or areas known to have a bad connection.
- *                    In contrast to other options, the implementation of this option is currently up to the UIs;
- *                    this may change in future, however,
- *                    having the option in the core allows provider-specific-defaults already today.
+ *                    Outgoing images are recoded by the core accordingly,
+ *                    for videos and voice messages, the implementation of this option is currently up to the UIs.
  *
  * If you want to retrieve a value, use dc_get_config().
  *
@@ -539,6 +552,46 @@ void            dc_stop_io(dc_context_t* context);
 void            dc_maybe_network             (dc_context_t* context);
 
 
+/**
+ * Get the current connectivity, i.e. whether the device is connected to the IMAP server.
+ * One of:
+ * - DC_CONNECTIVITY_NOT_CONNECTED (1000-1999): Show e.g. the string "Not connected" or a red dot
+ * - DC_CONNECTIVITY_CONNECTING (2000-2999): Show e.g. the string "Connecting…" or a yellow dot
+ * - DC_CONNECTIVITY_WORKING (3000-3999): Show e.g. the string "Getting new messages" or a spinning wheel
+ * - DC_CONNECTIVITY_CONNECTED (>=4000): Show e.g. the string "Connected" or a green dot
+ *
+ * We don't use exact values but ranges here so that we can split up
+ * states into multiple states in the future.
+ *
+ * Meant as a rough overview that can be shown
+ * e.g. in the title of the main screen.
+ *
+ * If the connectivity changes, a #DC_EVENT_CONNECTIVITY_CHANGED will be emitted.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @return The current connectivity.
+ */
+int             dc_get_connectivity          (dc_context_t* context);
+
+
+/**
+ * Get an overview of the current connectivity, and possibly more statistics.
+ * Meant to give the user more insight about the current status than
+ * the basic connectivity info returned by dc_get_connectivity();
+ * the report lists the state and the last error of every watched IMAP folder
+ * and of SMTP, as well as the number of pending jobs.
+ *
+ * If the connectivity changes, a #DC_EVENT_CONNECTIVITY_CHANGED will be emitted.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @return An HTML page with some info about the current connectivity and status.
+ *     The returned string must be released using dc_str_unref().
+ */
+char*           dc_get_connectivity_html     (dc_context_t* context);
+
+
 
 /**
  * Save a keypair as the default keys for the user.
@@ -561,6 +614,43 @@ void            dc_maybe_network             (dc_context_t* context);
 int             dc_preconfigure_keypair        (dc_context_t* context, const char *addr, const char *public_data, const char *secret_data);
 
 
+/**
+ * Generate a new keypair and use it for all following messages.
+ *
+ * The previous keys are kept and are still used to decrypt older messages.
+ * Other peers learn about the new key from the Autocrypt header
+ * of the next messages sent to them.
+ * Note that verifications done with the previous key, eg. by QR-code scanning,
+ * do not apply to the new key.
+ *
+ * Generating a key may take some time, so you should not call this function
+ * from the UI thread.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @param key_gen_type Type of the new key, one of DC_KEY_GEN_DEFAULT, DC_KEY_GEN_RSA2048, DC_KEY_GEN_ED25519.
+ * @return 1 on success, 0 on failure.
+ */
+int             dc_rotate_self_key             (dc_context_t* context, int key_gen_type);
+
+
+/**
+ * Get the keys owned by this device as JSON.
+ *
+ * The result is an array of objects with the fields
+ * `addr`, `fingerprint` (uppercase hex), `created` (unix timestamp)
+ * and `is_default`.
+ * The default key, used for new messages, comes first,
+ * followed by the previous keys, newest first.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @return JSON-array, must be released using dc_str_unref().
+ *     On errors, an empty string is returned.
+ */
+char*           dc_get_self_keys_json          (dc_context_t* context);
+
+
 // handle chatlists
 
 #define         DC_GCL_ARCHIVED_ONLY         0x01
@@ -784,6 +874,86 @@ uint32_t        dc_send_msg                  (dc_context_t* context, uint32_t ch
 uint32_t        dc_send_msg_sync                  (dc_context_t* context, uint32_t chat_id, dc_msg_t* msg);
 
 
+/**
+ * Schedule a message defined by a dc_msg_t object for sending at a later time.
+ *
+ * The message gets the state #DC_STATE_OUT_SCHEDULED
+ * and is not shown in the chat until it is due,
+ * use dc_get_scheduled_msgs() to list the scheduled messages of a chat.
+ * Until then, the message can be changed using dc_set_scheduled_msg_text() and dc_reschedule_msg()
+ * or deleted using dc_cancel_scheduled_msg().
+ * When the message is due, it is sent as if dc_send_msg() was called at that time;
+ * this also works if the app was closed in between,
+ * in this case the message is sent as soon as the IO is started again.
+ *
+ * Sends the event #DC_EVENT_MSGS_CHANGED on succcess.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param chat_id Chat ID to send the message to.
+ * @param msg Message object to send to the chat defined by the chat ID.
+ *     On succcess, msg_id of the object is set up,
+ *     The function does not take ownership of the object,
+ *     so you have to free it using dc_msg_unref() as usual.
+ * @param timestamp Time to send the message at, in seconds since 1970. Must be in the future.
+ * @return The ID of the scheduled message. 0 in case of errors.
+ */
+uint32_t        dc_send_msg_at               (dc_context_t* context, uint32_t chat_id, dc_msg_t* msg, int64_t timestamp);
+
+
+/**
+ * Get the messages scheduled for a chat using dc_send_msg_at().
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param chat_id The chat ID to get the scheduled messages for.
+ * @return Array of message IDs, the message that is due next first.
+ *     Must be dc_array_unref()'d after usage.
+ */
+dc_array_t*     dc_get_scheduled_msgs        (dc_context_t* context, uint32_t chat_id);
+
+
+/**
+ * Change the time a message scheduled using dc_send_msg_at() is sent at.
+ *
+ * Sends the event #DC_EVENT_MSGS_CHANGED on succcess.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param msg_id The ID of the scheduled message.
+ * @param timestamp New time to send the message at, in seconds since 1970. Must be in the future.
+ * @return 1=success, 0=error, eg. the message is already sent.
+ */
+int             dc_reschedule_msg            (dc_context_t* context, uint32_t msg_id, int64_t timestamp);
+
+
+/**
+ * Change the text of a message scheduled using dc_send_msg_at().
+ *
+ * Sends the event #DC_EVENT_MSGS_CHANGED on succcess.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param msg_id The ID of the scheduled message.
+ * @param text The new text of the message.
+ * @return 1=success, 0=error, eg. the message is already sent.
+ */
+int             dc_set_scheduled_msg_text    (dc_context_t* context, uint32_t msg_id, const char* text);
+
+
+/**
+ * Delete a message scheduled using dc_send_msg_at() before it is sent.
+ *
+ * Sends the event #DC_EVENT_MSGS_CHANGED on succcess.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param msg_id The ID of the scheduled message.
+ * @return 1=success, 0=error, eg. the message is already sent.
+ */
+int             dc_cancel_scheduled_msg      (dc_context_t* context, uint32_t msg_id);
+
+
 /**
  * Send a simple text message a given chat.
  *
@@ -1153,6 +1323,11 @@ dc_array_t*     dc_get_chat_contacts         (dc_context_t* context, uint32_t ch
  * search results may just hilite the corresponding messages and present a
  * prev/next button.
  *
+ * The message texts are searched using a full-text index:
+ * all words of the query must be found,
+ * words match as prefixes and case and diacritics are ignored.
+ * Messages from contacts whose name starts with the query are returned as well.
+ *
  * @memberof dc_context_t
  * @param context The context object as returned from dc_context_new().
  * @param chat_id ID of the chat to search messages in.
@@ -1164,6 +1339,33 @@ dc_array_t*     dc_get_chat_contacts         (dc_context_t* context, uint32_t ch
 dc_array_t*     dc_search_msgs               (dc_context_t* context, uint32_t chat_id, const char* query);
 
 
+/**
+ * Search the message texts using the full-text index
+ * and return the matches together with a snippet of the text.
+ * Searching can be done globally (chat_id=0) or in a specified chat only (chat_id
+ * set).
+ *
+ * All words of the query must be found in a message,
+ * words match as prefixes and case and diacritics are ignored.
+ * The best matching messages are returned first.
+ * Unlike dc_search_msgs(), contact names are not searched.
+ *
+ * To load more results when the user scrolls down,
+ * call the function again with offset set to the number of results loaded so far.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param chat_id ID of the chat to search messages in.
+ *     Set this to 0 for a global search.
+ * @param query The query to search for.
+ * @param offset Number of results to skip.
+ * @param limit Maximum number of results to return.
+ * @return The search results, must be released using dc_search_results_unref() after usage.
+ *     NULL on errors.
+ */
+dc_search_results_t* dc_search              (dc_context_t* context, uint32_t chat_id, const char* query, size_t offset, size_t limit);
+
+
 /**
  * Get chat object by a chat ID.
  *
@@ -1210,6 +1412,50 @@ dc_chat_t*      dc_get_chat                  (dc_context_t* context, uint32_t ch
 uint32_t        dc_create_group_chat         (dc_context_t* context, int verified, const char* name);
 
 
+/**
+ * Create a new broadcast list.
+ *
+ * Broadcast lists are similar to groups on the sending device,
+ * however, recipients get the messages in their one-to-one chat with the sender
+ * and do not see the other recipients.
+ * Replies to broadcasts are sent to the one-to-one chat with the recipient as well.
+ *
+ * Every recipient gets a separate copy of the message.
+ * The message is in the state DC_STATE_OUT_DELIVERED once all copies are delivered,
+ * dc_get_msg_info() lists when the copy for each recipient was delivered.
+ *
+ * Members are added and removed using dc_add_contact_to_chat()
+ * and dc_remove_contact_from_chat(); these changes as well as changes of the name
+ * or the image of the list are not sent to the recipients.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @param name The name of the broadcast list to create.
+ *     The name may be changed later using dc_set_chat_name().
+ * @return The chat ID of the new broadcast list, 0 on errors.
+ */
+uint32_t        dc_create_broadcast_list     (dc_context_t* context, const char* name);
+
+
+/**
+ * Unsubscribe from a mailing list, see #DC_CHAT_TYPE_MAILINGLIST.
+ *
+ * If the list offers unsubscribing by e-mail, an unsubscribe request is sent to the list.
+ * Otherwise, the URL of a web page is returned
+ * and the ui should open it in the browser to finish unsubscribing.
+ * The chat is not deleted, use dc_delete_chat() or dc_block_chat() for that.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @param chat_id The ID of the mailing list chat.
+ * @return Empty string if the unsubscribe request was sent,
+ *     the URL to open if unsubscribing has to be done on a web page,
+ *     NULL on errors, eg. if the list does not offer unsubscribing at all.
+ *     Must be released using dc_str_unref() after usage.
+ */
+char*           dc_unsubscribe_mailinglist   (dc_context_t* context, uint32_t chat_id);
+
+
 /**
  * Check if a given contact ID is a member of a group chat.
  *
@@ -1314,6 +1560,36 @@ int             dc_set_chat_profile_image    (dc_context_t* context, uint32_t ch
  */
 int             dc_set_chat_mute_duration             (dc_context_t* context, uint32_t chat_id, int64_t duration);
 
+
+/**
+ * Get the ephemeral message timer of a chat.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @param chat_id The chat ID to get the ephemeral timer for.
+ * @return Ephemeral timer in seconds, 0 if the timer is disabled.
+ */
+uint32_t        dc_get_chat_ephemeral_timer           (dc_context_t* context, uint32_t chat_id);
+
+
+/**
+ * Set the ephemeral message timer of a chat.
+ *
+ * Messages sent to the chat afterwards are deleted on all devices
+ * and on the server once the timer has run out.  The timer starts
+ * when a message is sent or marked as seen.
+ * All chat members are informed about the change.
+ *
+ * Sends out #DC_EVENT_CHAT_EPHEMERAL_TIMER_MODIFIED and #DC_EVENT_MSGS_CHANGED.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @param chat_id The chat ID to set the ephemeral timer for.
+ * @param timer The timer value in seconds, 0 disables the timer.
+ * @return 1=success, 0=error
+ */
+int             dc_set_chat_ephemeral_timer           (dc_context_t* context, uint32_t chat_id, uint32_t timer);
+
 // handle messages
 
 /**
@@ -1331,6 +1607,39 @@ int             dc_set_chat_mute_duration             (dc_context_t* context, ui
 char*           dc_get_msg_info              (dc_context_t* context, uint32_t msg_id);
 
 
+/**
+ * Send a reaction to a message.
+ *
+ * A reaction is typically a single emoji.
+ * Each contact has at most one reaction per message,
+ * sending a new reaction replaces the previous one.
+ * To remove a reaction, send an empty string.
+ *
+ * Reactions are sent as hidden messages;
+ * they neither appear in the chat nor bump the chat in the chatlist.
+ * Sends out #DC_EVENT_REACTIONS_CHANGED.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param msg_id The message to react to.
+ * @param reaction The reaction, eg. an emoji, or an empty string to remove the reaction.
+ * @return The ID of the hidden message carrying the reaction, 0 on errors.
+ */
+uint32_t        dc_send_reaction             (dc_context_t* context, uint32_t msg_id, const char* reaction);
+
+
+/**
+ * Get the reactions to a message.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param msg_id The message to get the reactions for.
+ * @return The reactions, must be released using dc_reactions_unref() after usage.
+ *     NULL on errors.
+ */
+dc_reactions_t* dc_get_msg_reactions         (dc_context_t* context, uint32_t msg_id);
+
+
 /**
  * Get the raw mime-headers of the given message.
  * Raw headers are saved for incoming messages
@@ -1360,6 +1669,85 @@ char*           dc_get_mime_headers          (dc_context_t* context, uint32_t ms
  */
 void            dc_delete_msgs               (dc_context_t* context, const uint32_t* msg_ids, int msg_cnt);
 
+
+/**
+ * Change the text of a sent message for all chat members.
+ *
+ * The text is changed on the current device right away
+ * and a hidden message is sent to the chat,
+ * so that the recipients change the text as well.
+ * Edited messages are marked, see dc_msg_is_edited().
+ * Only outgoing messages can be edited.
+ *
+ * Sends out #DC_EVENT_MSGS_CHANGED.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new()
+ * @param msg_id The ID of the outgoing message to edit.
+ * @param new_text The new text of the message.
+ * @return 1=success, 0=error.
+ */
+int             dc_send_edit_request         (dc_context_t* context, uint32_t msg_id, const char* new_text);
+
+
+/**
+ * Delete sent messages for all chat members.
+ *
+ * A hidden message is sent to the chat,
+ * so that the recipients delete the messages as well,
+ * then the messages are deleted as with dc_delete_msgs().
+ * Only outgoing messages of the same chat can be deleted for everyone.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new()
+ * @param msg_ids an array of uint32_t containing all message IDs that should be deleted
+ * @param msg_cnt The number of messages IDs in the msg_ids array
+ * @return 1=success, 0=error.
+ */
+int             dc_send_delete_request       (dc_context_t* context, const uint32_t* msg_ids, int msg_cnt);
+
+
+/**
+ * Pin a message in a group for all group members.
+ *
+ * An info message is added to the chat
+ * and a hidden message is sent to the group,
+ * so that the message is pinned for the other members as well.
+ * At most 5 messages can be pinned in a group.
+ * The pinned messages are returned by dc_get_pinned_msgs().
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new()
+ * @param chat_id The ID of the group chat.
+ * @param msg_id The ID of the message to pin, must belong to the chat.
+ * @return 1=success, 0=error.
+ */
+int             dc_pin_msg                   (dc_context_t* context, uint32_t chat_id, uint32_t msg_id);
+
+
+/**
+ * Unpin a message in a group for all group members, see dc_pin_msg().
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new()
+ * @param chat_id The ID of the group chat.
+ * @param msg_id The ID of the message to unpin.
+ * @return 1=success, 0=error.
+ */
+int             dc_unpin_msg                 (dc_context_t* context, uint32_t chat_id, uint32_t msg_id);
+
+
+/**
+ * Get the pinned messages of a chat.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new()
+ * @param chat_id The ID of the chat.
+ * @return Array of message IDs, the most recently pinned message first.
+ *     Must be dc_array_unref()'d after usage.
+ */
+dc_array_t*     dc_get_pinned_msgs           (dc_context_t* context, uint32_t chat_id);
+
 /*
  * Empty IMAP server folder: delete all messages.
  * Deprecated, use dc_set_config() with the key "delete_server_after" instead.
@@ -1598,6 +1986,122 @@ void            dc_block_contact             (dc_context_t* context, uint32_t co
 char*           dc_get_contact_encrinfo      (dc_context_t* context, uint32_t contact_id);
 
 
+/**
+ * Get the keys known for a contact.
+ *
+ * Other than dc_get_contact_encrinfo(), the result is meant to be parsed,
+ * eg. to show the keys in an advanced settings screen.
+ * The result is a JSON array of objects with the following fields:
+ *
+ * - `type`: DC_CONTACT_KEY_PUBLIC (1)=key received from the contact or imported,
+ *   DC_CONTACT_KEY_GOSSIP (2)=key gossiped by other members of a group,
+ *   DC_CONTACT_KEY_VERIFIED (3)=key verified by a QR code scan.
+ * - `fingerprint`: fingerprint of the key as uppercase hex string.
+ * - `timestamp`: timestamp of the last message the key was seen in,
+ *   0 for verified keys.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param contact_id ID of the contact to get the keys for.
+ * @return JSON array, `[]` if no keys are known for the contact,
+ *     empty string on errors.
+ *     The returned string must be released using dc_str_unref().
+ */
+char*           dc_get_contact_keys_json     (dc_context_t* context, uint32_t contact_id);
+
+
+/**
+ * Export a key of a contact as ASCII armored string.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param contact_id ID of the contact to export the key of.
+ * @param key_type One of the DC_CONTACT_KEY_* constants, see dc_get_contact_keys_json().
+ * @return ASCII armored key, NULL if there is no such key.
+ *     The returned string must be released using dc_str_unref().
+ */
+char*           dc_export_contact_key        (dc_context_t* context, uint32_t contact_id, int key_type);
+
+
+/**
+ * Import an ASCII armored public key for a contact.
+ *
+ * The key is used as if it was received from the contact just now,
+ * so messages to the contact are encrypted to it from now on.
+ * If the contact had another key before, an info message is added
+ * to the chat with the contact.
+ * Gossiped and verified keys are not changed.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param contact_id ID of the contact to import the key for.
+ * @param file Path of the file containing the ASCII armored key.
+ * @return 1=success, 0=error, eg. the file does not contain a valid key.
+ */
+int             dc_import_contact_key        (dc_context_t* context, uint32_t contact_id, const char* file);
+
+
+/**
+ * Add the contacts of a vCard file to the address book.
+ *
+ * The file may contain several entries in vCard 3.0 or 4.0 format,
+ * eg. exported from the address book of the OS
+ * or received as attachment of a #DC_MSG_VCARD message.
+ * Each e-mail address of an entry is added as a contact.
+ * The photo of an entry is used as profile image if the contact has none yet.
+ *
+ * Anyone can put any key into a vCard,
+ * so keys are only imported if the user confirmed to trust the file,
+ * eg. after the fingerprints were shown.
+ * Then, if nothing is known about the encryption state of a contact,
+ * the key contained in the entry is used
+ * as if it was gossiped by another member of a group.
+ *
+ * May result in a #DC_EVENT_CONTACTS_CHANGED event.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param file Path of the vCard file.
+ * @param import_keys 1=import the keys of the entries, 0=ignore them.
+ * @return Array of the IDs of the added contacts,
+ *     NULL on errors, eg. if the file cannot be read.
+ *     The array must be released using dc_array_unref().
+ */
+dc_array_t*     dc_import_vcard              (dc_context_t* context, const char* file, int import_keys);
+
+
+/**
+ * Create a vCard 4.0 with an entry for each of the given contacts.
+ *
+ * Besides name and e-mail address, the entries contain the profile image
+ * and the key of the contacts, if known.
+ * To send contacts to a chat, dc_msg_set_vcard() can be used.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param contact_ids Array of the IDs of the contacts to export.
+ * @param contact_ids_cnt Number of contact IDs in the array.
+ * @return The vCard, NULL on errors.
+ *     The returned string must be released using dc_str_unref().
+ */
+char*           dc_make_vcard                (dc_context_t* context, const uint32_t* contact_ids, int contact_ids_cnt);
+
+
+/**
+ * Forget all keys and the encryption state of a contact.
+ *
+ * Messages to the contact are sent unencrypted
+ * until a new key is received from the contact or imported using dc_import_contact_key().
+ * The contact is no longer verified afterwards.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param contact_id ID of the contact to reset.
+ * @return 1=success, 0=error.
+ */
+int             dc_reset_contact_peerstate   (dc_context_t* context, uint32_t contact_id);
+
+
 /**
  * Delete a contact.  The contact is deleted from the local device.  It may happen that this is not
  * possible as the contact is in use.  In this case, the contact can be blocked.
@@ -1632,8 +2136,10 @@ dc_contact_t*   dc_get_contact               (dc_context_t* context, uint32_t co
 
 #define         DC_IMEX_EXPORT_SELF_KEYS      1 // param1 is a directory where the keys are written to
 #define         DC_IMEX_IMPORT_SELF_KEYS      2 // param1 is a directory where the keys are searched in and read from
-#define         DC_IMEX_EXPORT_BACKUP        11 // param1 is a directory where the backup is written to
-#define         DC_IMEX_IMPORT_BACKUP        12 // param1 is the file with the backup to import
+#define         DC_IMEX_EXPORT_BACKUP        11 // param1 is a directory where the backup is written to, param2 is an optional passphrase
+#define         DC_IMEX_IMPORT_BACKUP        12 // param1 is the file with the backup to import, param2 is the passphrase of encrypted backups
+#define         DC_IMEX_SEND_BACKUP_OVER_NETWORK    21 // param1 is an optional IP address to listen on
+#define         DC_IMEX_RECEIVE_BACKUP_OVER_NETWORK 22 // param1 is the scanned QR code
 
 
 /**
@@ -1643,12 +2149,31 @@ dc_contact_t*   dc_get_contact               (dc_context_t* context, uint32_t co
  * - **DC_IMEX_EXPORT_BACKUP** (11) - Export a backup to the directory given as `param1`.
  *   The backup contains all contacts, chats, images and other data and device independent settings.
  *   The backup does not contain device dependent settings as ringtones or LED notification settings.
- *   The name of the backup is typically `delta-chat.<day>.bak`, if more than one backup is create on a day,
- *   the format is `delta-chat.<day>-<number>.bak`
+ *   The backup is a tar archive named `delta-chat-backup-<day>-<number>.tar`.
+ *   If a passphrase is given as `param2`, the backup is encrypted with it
+ *   and named `delta-chat-backup-<day>-<number>.tar.dcenc`.
+ *   Encrypted backups are no OpenPGP messages and can only be read by Delta Chat.
  *
  * - **DC_IMEX_IMPORT_BACKUP** (12) - `param1` is the file (not: directory) to import. The file is normally
  *   created by DC_IMEX_EXPORT_BACKUP and detected by dc_imex_has_backup(). Importing a backup
  *   is only possible as long as the context is not configured or used in another way.
+ *   Encrypted backups require the passphrase given on export as `param2`.
+ *   Backups in the old `.bak` format can still be imported.
+ *
+ * - **DC_IMEX_SEND_BACKUP_OVER_NETWORK** (21) - Serve a backup to another device in the local network.
+ *   A #DC_EVENT_IMEX_BACKUP_QR event with a QR code is sent, the QR code has to be scanned by the other device.
+ *   The backup is sent to the first device connecting with the secret contained in the QR code,
+ *   the transfer is encrypted with this secret.
+ *   `param1` is the IP address to listen on and to put into the QR code,
+ *   if NULL, a private IPv4 address of the device is used if there is one,
+ *   otherwise another non-loopback address.  On Windows, `param1` is required.
+ *   After 5 connections with a wrong secret or if no device connects within 10 minutes,
+ *   serving the backup fails.
+ *
+ * - **DC_IMEX_RECEIVE_BACKUP_OVER_NETWORK** (22) - Receive and import a backup from another device
+ *   serving it with DC_IMEX_SEND_BACKUP_OVER_NETWORK. `param1` is the scanned QR code,
+ *   dc_check_qr() returns DC_QR_BACKUP for it.
+ *   As for DC_IMEX_IMPORT_BACKUP, the context must not be configured.
  *
  * - **DC_IMEX_EXPORT_SELF_KEYS** (1) - Export all private keys and all public keys of the user to the
  *   directory given as `param1`.  The default key is written to the files `public-key-default.asc`
@@ -1675,7 +2200,8 @@ dc_contact_t*   dc_get_contact               (dc_context_t* context, uint32_t co
  * @param what One of the DC_IMEX_* constants.
  * @param param1 Meaning depends on the DC_IMEX_* constants. If this parameter is a directory, it should not end with
  *     a slash (otherwise you'll get double slashes when receiving #DC_EVENT_IMEX_FILE_WRITTEN). Set to NULL if not used.
- * @param param2 Meaning depends on the DC_IMEX_* constants. Set to NULL if not used.
+ * @param param2 Passphrase to encrypt or decrypt backups with,
+ *     set to NULL for unencrypted backups and if not used.
  * @return None.
  */
 void            dc_imex                      (dc_context_t* context, int what, const char* param1, const char* param2);
@@ -1804,6 +2330,49 @@ char*           dc_initiate_key_transfer     (dc_context_t* context);
 int             dc_continue_key_transfer     (dc_context_t* context, uint32_t msg_id, const char* setup_code);
 
 
+/**
+ * Check if the key of an Autocrypt Setup Message differs from the current key.
+ *
+ * This is typically the case if the setup message was sent by another MUA
+ * that generated its own key.
+ * In this case, you should ask the user how to proceed
+ * and call dc_continue_key_transfer_with() then.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param msg_id ID of the setup message to decrypt.
+ * @param setup_code Setup code entered by the user, see dc_continue_key_transfer().
+ * @return If the keys differ, a JSON-object with the fields
+ *     `current_fingerprint` and `imported_fingerprint` (uppercase hex) is returned.
+ *     If there is no conflict, an empty string is returned
+ *     and dc_continue_key_transfer() can be called directly.
+ *     On errors, eg. a bad setup code, NULL is returned.
+ *     The returned string must be released using dc_str_unref().
+ */
+char*           dc_check_key_transfer        (dc_context_t* context, uint32_t msg_id, const char* setup_code);
+
+
+/**
+ * Continue the Autocrypt Key Transfer, resolving a key conflict
+ * as decided by the user.
+ *
+ * If there is a conflict, the decision is saved with the setup message.
+ * dc_continue_key_transfer() is the same as using DC_KEY_CONFLICT_KEEP_BOTH.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param msg_id ID of the setup message to decrypt.
+ * @param setup_code Setup code entered by the user, see dc_continue_key_transfer().
+ * @param resolution DC_KEY_CONFLICT_KEEP_CURRENT (1)=keep the current key and do not import the key,
+ *     DC_KEY_CONFLICT_REPLACE (2)=use the imported key and delete the current key,
+ *     messages encrypted to the current key cannot be read afterwards,
+ *     DC_KEY_CONFLICT_KEEP_BOTH (3)=use the imported key
+ *     and keep the current key to read older messages.
+ * @return 1=success, 0=key transfer failed eg. due to a bad setup code.
+ */
+int             dc_continue_key_transfer_with (dc_context_t* context, uint32_t msg_id, const char* setup_code, int resolution);
+
+
 /**
  * Signal an ongoing process to stop.
  *
@@ -1832,6 +2401,7 @@ void            dc_stop_ongoing_process      (dc_context_t* context);
 #define         DC_QR_FPR_MISMATCH           220 // id=contact
 #define         DC_QR_FPR_WITHOUT_ADDR       230 // test1=formatted fingerprint
 #define         DC_QR_ACCOUNT                250 // text1=domain
+#define         DC_QR_BACKUP                 251 // text1=address of the sending device
 #define         DC_QR_ADDR                   320 // id=contact
 #define         DC_QR_TEXT                   330 // text1=text
 #define         DC_QR_URL                    332 // text1=URL
@@ -1850,6 +2420,8 @@ void            dc_stop_ongoing_process      (dc_context_t* context);
  * - DC_QR_FPR_MISMATCH with dc_lot_t::id=Contact ID
  * - DC_QR_FPR_WITHOUT_ADDR with dc_lot_t::test1=Formatted fingerprint
  * - DC_QR_ACCOUNT allows creation of an account, dc_lot_t::text1=domain
+ * - DC_QR_BACKUP allows receiving a backup from another device using dc_imex(),
+ *   dc_lot_t::text1=address of the sending device
  * - DC_QR_ADDR with dc_lot_t::id=Contact ID
  * - DC_QR_TEXT with dc_lot_t::text1=Text
  * - DC_QR_URL with dc_lot_t::text1=URL
@@ -1888,6 +2460,25 @@ dc_lot_t*       dc_check_qr                  (dc_context_t* context, const char*
 char*           dc_get_securejoin_qr         (dc_context_t* context, uint32_t chat_id);
 
 
+/**
+ * Get QR code text that offers the Setup-Contact protocol to a single contact.
+ *
+ * This is used to verify a contact again after #DC_EVENT_VERIFIED_CONTACT_KEY_CHANGED,
+ * the QR code has to be scanned by the given contact,
+ * using the QR code from another device does not verify anyone.
+ * When the handshake is done, the messages the contact has sent to verified groups
+ * using the new key are shown.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param contact_id ID of the contact to verify.
+ * @return Text that should go to the QR code,
+ *     On errors, an empty QR code is returned, NULL is never returned.
+ *     The returned string must be released using dc_str_unref() after usage.
+ */
+char*           dc_get_securejoin_qr_for_contact (dc_context_t* context, uint32_t contact_id);
+
+
 /**
  * Continue a Setup-Contact or Verified-Group-Invite protocol
  * started on another device with dc_get_securejoin_qr().
@@ -2437,6 +3028,8 @@ char*            dc_chat_get_info_json       (dc_context_t* context, size_t chat
 #define         DC_CHAT_TYPE_SINGLE          100
 #define         DC_CHAT_TYPE_GROUP           120
 #define         DC_CHAT_TYPE_VERIFIED_GROUP  130
+#define         DC_CHAT_TYPE_MAILINGLIST     140
+#define         DC_CHAT_TYPE_BROADCAST       160
 
 
 /**
@@ -2470,7 +3063,7 @@ uint32_t        dc_chat_get_id               (const dc_chat_t* chat);
 /**
  * Get chat type.
  *
- * Currently, there are two chat types:
+ * Currently, there are the following chat types:
  *
  * - DC_CHAT_TYPE_SINGLE (100) - a normal chat is a chat with a single contact,
  *   chats_contacts contains one record for the user.  DC_CONTACT_ID_SELF
@@ -2482,6 +3075,18 @@ uint32_t        dc_chat_get_id               (const dc_chat_t* chat);
  * - DC_CHAT_TYPE_VERIFIED_GROUP  (130) - a verified group chat. In verified groups,
  *   all members are verified and encryption is always active and cannot be disabled.
  *
+ * - DC_CHAT_TYPE_MAILINGLIST  (140) - a mailing list, created for received messages with a `List-Id` header.
+ *   Mailing lists have no members, messages are sent to the posting address of the list
+ *   and are not encrypted.  If the list does not allow posting, the chat is read-only,
+ *   see dc_chat_can_send().  New mailing lists appear as contact requests.
+ *   Mailing list messages are classic emails, so they are only shown
+ *   as allowed by the `show_emails` setting.
+ *   Note that the `List-*` headers can be forged by any sender.
+ *   To unsubscribe, use dc_unsubscribe_mailinglist().
+ *
+ * - DC_CHAT_TYPE_BROADCAST  (160) - a broadcast list, see dc_create_broadcast_list().
+ *   chats_contacts contain all recipients, incl. DC_CONTACT_ID_SELF.
+ *
  * @memberof dc_chat_t
  * @param chat The chat object.
  * @return Chat type.
@@ -2598,7 +3203,8 @@ int             dc_chat_is_device_talk       (const dc_chat_t* chat);
 
 /**
  * Check if messages can be sent to a give chat.
- * This is not true eg. for the deaddrop or for the device-talk, cmp. dc_chat_is_device_talk().
+ * This is not true eg. for the deaddrop or for the device-talk, cmp. dc_chat_is_device_talk(),
+ * or for mailing lists that do not allow posting.
  *
  * Calling dc_send_msg() for these chats will fail
  * and the ui may decide to hide input controls therefore.
@@ -2676,6 +3282,7 @@ int64_t          dc_chat_get_remaining_mute_duration (const dc_chat_t* chat);
 #define         DC_STATE_OUT_PREPARING       18
 #define         DC_STATE_OUT_DRAFT           19
 #define         DC_STATE_OUT_PENDING         20
+#define         DC_STATE_OUT_SCHEDULED       21
 #define         DC_STATE_OUT_FAILED          24
 #define         DC_STATE_OUT_DELIVERED       26 // to check if a mail was sent, use dc_msg_is_sent()
 #define         DC_STATE_OUT_MDN_RCVD        28
@@ -2777,6 +3384,8 @@ int             dc_msg_get_viewtype           (const dc_msg_t* msg);
  * - DC_STATE_OUT_DRAFT (19) - Message saved as draft using dc_set_draft()
  * - DC_STATE_OUT_PENDING (20) - The user has pressed the "send" button but the
  *   message is not yet sent and is pending in some way. Maybe we're offline (no checkmark).
+ * - DC_STATE_OUT_SCHEDULED (21) - Message scheduled using dc_send_msg_at(),
+ *   it enters the state DC_STATE_OUT_PENDING when it is due.
  * - DC_STATE_OUT_FAILED (24) - _Unrecoverable_ error (_recoverable_ errors result in pending messages), you'll receive the event #DC_EVENT_MSG_FAILED.
  * - DC_STATE_OUT_DELIVERED (26) - Outgoing message successfully delivered to server (one checkmark). Note, that already delivered messages may get into the state DC_STATE_OUT_FAILED if we get such a hint from the server.
  *   If a sent message changes to this state, you'll receive the event #DC_EVENT_MSG_DELIVERED.
@@ -2845,6 +3454,27 @@ int64_t          dc_msg_get_received_timestamp (const dc_msg_t* msg);
 int64_t          dc_msg_get_sort_timestamp     (const dc_msg_t* msg);
 
 
+/**
+ * Get the ephemeral timer duration for a message.
+ * This is the value of the chat's ephemeral timer at the time the message was sent or received.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return Duration in seconds, 0 if the message is not ephemeral.
+ */
+uint32_t         dc_msg_get_ephemeral_timer    (const dc_msg_t* msg);
+
+
+/**
+ * Get the timestamp of the ephemeral message removal.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return Time of the message removal, 0 if the timer has not started yet.
+ */
+int64_t          dc_msg_get_ephemeral_timestamp (const dc_msg_t* msg);
+
+
 /**
  * Get the text of the message.
  * If there is no text associated with the message, an empty string is returned.
@@ -2868,19 +3498,77 @@ char*           dc_msg_get_text               (const dc_msg_t* msg);
 
 
 /**
- * Find out full path, file name and extension of the file associated with a
- * message.
+ * Get quoted text, if any.
+ * You can use this function also to check if there is a quote for a message.
+ *
+ * For messages sent by us, the text is the text of the quoted message
+ * or a summary, similar to what is shown in the chatlist,
+ * if the quoted message has no text.
+ * For received messages, this is the text quoted at the top of the message.
+ *
+ * If available, you can get the whole quoted message by dc_msg_get_quoted_msg().
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return The quoted text or NULL if there is no quote.
+ *     Returned value must be released with dc_str_unref().
+ */
+char*           dc_msg_get_quoted_text        (const dc_msg_t* msg);
+
+
+/**
+ * Get quoted message, if available.
+ * UIs might use this information to offer "jumping back" to the quoted message
+ * or to enrich displaying the quote.
+ *
+ * If this function returns NULL,
+ * this does not mean there is no quote for the message -
+ * it might also mean that a quote exist but the quoted message is deleted meanwhile.
+ * Therefore, do not use this function to check if there is a quote for a message.
+ * To check if a message has a quote, use dc_msg_get_quoted_text().
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return The quoted message or NULL.
+ *     Must be freed using dc_msg_unref() after usage.
+ */
+dc_msg_t*       dc_msg_get_quoted_msg         (const dc_msg_t* msg);
+
+
+/**
+ * Find out full path, file name and extension of the file associated with a
+ * message.
+ *
+ * Typically files are associated with images, videos, audios, documents.
+ * Plain text messages do not have a file.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return Full path, file name and extension of the file associated with the message.
+ *     If there is no file associated with the message, an emtpy string is returned.
+ *     NULL is never returned and the returned value must be released using dc_str_unref().
+ */
+char*           dc_msg_get_file               (const dc_msg_t* msg);
+
+
+/**
+ * Get full path of a small preview of the image, GIF or sticker
+ * associated with a message.
  *
- * Typically files are associated with images, videos, audios, documents.
- * Plain text messages do not have a file.
+ * The preview is a JPEG-file with a width and height of at most 256 pixels,
+ * useful eg. for chat lists, notifications or the gallery.
+ * Previews are created when a message is received;
+ * for older messages, the preview is created on the first call of this function.
  *
  * @memberof dc_msg_t
  * @param msg The message object.
- * @return Full path, file name and extension of the file associated with the message.
- *     If there is no file associated with the message, an emtpy string is returned.
+ * @return Full path of the preview.
+ *     If the message has no image, GIF or sticker
+ *     or if the preview cannot be created, an empty string is returned.
+ *     In this case, use dc_msg_get_file() instead.
  *     NULL is never returned and the returned value must be released using dc_str_unref().
  */
-char*           dc_msg_get_file               (const dc_msg_t* msg);
+char*           dc_msg_get_thumbnail          (dc_msg_t* msg);
 
 
 /**
@@ -2980,6 +3668,24 @@ int             dc_msg_get_duration           (const dc_msg_t* msg);
 int             dc_msg_get_showpadlock        (const dc_msg_t* msg);
 
 
+/**
+ * Check if a message is signed but not end-to-end encrypted.
+ *
+ * Such messages carry a valid OpenPGP signature of the sender,
+ * so the content was not altered on the way,
+ * but the message could be read by others.
+ * If dc_msg_get_showpadlock() returns 1, this function returns 0.
+ *
+ * Outgoing messages are signed if the option `sign_unencrypted` is enabled,
+ * see dc_set_config().
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return 1=message is signed but not encrypted, 0=message is encrypted or not signed.
+ */
+int             dc_msg_is_signed_only         (const dc_msg_t* msg);
+
+
 /**
  * Get a summary for a message.
  *
@@ -3095,6 +3801,19 @@ int             dc_msg_is_starred             (const dc_msg_t* msg);
 int             dc_msg_is_forwarded           (const dc_msg_t* msg);
 
 
+/**
+ * Check if the text of the message was changed by its sender,
+ * see dc_send_edit_request().
+ *
+ * Typically, the UI shows a little hint, eg. "edited", next to edited messages.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return 1=message text was edited, 0=message text is unchanged.
+ */
+int             dc_msg_is_edited              (const dc_msg_t* msg);
+
+
 /**
  * Check if the message is an informational message, created by the
  * device or by another users. Such messages are not "typed" by the user but
@@ -3175,6 +3894,22 @@ char*           dc_msg_get_setupcodebegin     (const dc_msg_t* msg);
 void            dc_msg_set_text               (dc_msg_t* msg, const char* text);
 
 
+/**
+ * Set the message replying to.
+ * This allows optionally to reply to an explicit message
+ * instead of the last message in the chat.
+ * The quoted text is added to the top of the message
+ * and the quoted message is used as the parent in the `In-Reply-To` header.
+ * This does not alter any information in the database; this may be done by dc_send_msg() later.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object to modify.
+ * @param quote The quote to set for msg.
+ * @return None.
+ */
+void            dc_msg_set_quote              (dc_msg_t* msg, const dc_msg_t* quote);
+
+
 /**
  * Set the file associated with a message object.
  * This does not alter any information in the database
@@ -3218,6 +3953,20 @@ void            dc_msg_set_dimension          (dc_msg_t* msg, int width, int hei
 void            dc_msg_set_duration           (dc_msg_t* msg, int duration);
 
 
+/**
+ * Attach a contact as vCard to a message object
+ * and change the type of the message to #DC_MSG_VCARD.
+ * The vCard is created as by dc_make_vcard().
+ * This does not alter any information in the database; this may be done by dc_send_msg() later.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @param contact_id ID of the contact to attach.
+ * @return 1=success, 0=error.
+ */
+int             dc_msg_set_vcard              (dc_msg_t* msg, uint32_t contact_id);
+
+
 /**
  * Set any location that should be bound to the message object.
  * The function is useful to add a marker to the map
@@ -3436,6 +4185,124 @@ int             dc_contact_is_blocked        (const dc_contact_t* contact);
 int             dc_contact_is_verified       (dc_contact_t* contact);
 
 
+/**
+ * @class dc_reactions_t
+ *
+ * Opaque object containing the reactions to a single message,
+ * as returned by dc_get_msg_reactions().
+ */
+
+
+/**
+ * Get the contacts who reacted to the message.
+ *
+ * @memberof dc_reactions_t
+ * @param reactions The object as returned by dc_get_msg_reactions().
+ * @return An array of contact IDs, may contain DC_CONTACT_ID_SELF.
+ *     Must be released using dc_array_unref() after usage.
+ */
+dc_array_t*     dc_reactions_get_contacts             (dc_reactions_t* reactions);
+
+
+/**
+ * Get the reaction of a contact.
+ *
+ * @memberof dc_reactions_t
+ * @param reactions The object as returned by dc_get_msg_reactions().
+ * @param contact_id The ID of the contact, eg. as returned by dc_reactions_get_contacts().
+ * @return The reaction, typically an emoji.
+ *     An empty string if the contact did not react, NULL is never returned.
+ *     The returned value must be released using dc_str_unref().
+ */
+char*           dc_reactions_get_by_contact_id        (dc_reactions_t* reactions, uint32_t contact_id);
+
+
+/**
+ * Free the reactions object.
+ *
+ * @memberof dc_reactions_t
+ * @param reactions The object as returned by dc_get_msg_reactions().
+ *     If NULL is given, nothing is done.
+ */
+void            dc_reactions_unref                    (dc_reactions_t* reactions);
+
+
+/**
+ * @class dc_search_results_t
+ *
+ * Opaque object containing the messages found by dc_search().
+ */
+
+
+/**
+ * Get the number of search results.
+ *
+ * @memberof dc_search_results_t
+ * @param results The object as returned by dc_search().
+ * @return The number of results.
+ */
+size_t          dc_search_results_get_cnt             (dc_search_results_t* results);
+
+
+/**
+ * Get the ID of the message found at a given index.
+ *
+ * @memberof dc_search_results_t
+ * @param results The object as returned by dc_search().
+ * @param index The index of the result, 0 to dc_search_results_get_cnt()-1.
+ * @return The message ID, 0 if the index is out of range.
+ */
+uint32_t        dc_search_results_get_msg_id          (dc_search_results_t* results, size_t index);
+
+
+/**
+ * Get the ID of the chat the message found at a given index belongs to.
+ *
+ * @memberof dc_search_results_t
+ * @param results The object as returned by dc_search().
+ * @param index The index of the result, 0 to dc_search_results_get_cnt()-1.
+ * @return The chat ID, 0 if the index is out of range.
+ */
+uint32_t        dc_search_results_get_chat_id         (dc_search_results_t* results, size_t index);
+
+
+/**
+ * Get the part of the message text around the matches.
+ * Omitted text at the beginning or at the end is marked by an ellipsis.
+ *
+ * @memberof dc_search_results_t
+ * @param results The object as returned by dc_search().
+ * @param index The index of the result, 0 to dc_search_results_get_cnt()-1.
+ * @return The snippet, an empty string if the index is out of range, NULL is never returned.
+ *     The returned value must be released using dc_str_unref().
+ */
+char*           dc_search_results_get_snippet         (dc_search_results_t* results, size_t index);
+
+
+/**
+ * Get the positions of the matches in the snippet,
+ * eg. to show them in bold.
+ *
+ * @memberof dc_search_results_t
+ * @param results The object as returned by dc_search().
+ * @param index The index of the result, 0 to dc_search_results_get_cnt()-1.
+ * @return An array with two entries per match:
+ *     the start and the exclusive end of the match, counted in Unicode characters.
+ *     Must be released using dc_array_unref() after usage.
+ */
+dc_array_t*     dc_search_results_get_highlights      (dc_search_results_t* results, size_t index);
+
+
+/**
+ * Free the search results.
+ *
+ * @memberof dc_search_results_t
+ * @param results The object as returned by dc_search().
+ *     If NULL is given, nothing is done.
+ */
+void            dc_search_results_unref               (dc_search_results_t* results);
+
+
 /**
  * @class dc_provider_t
  *
@@ -3511,6 +4378,194 @@ int             dc_provider_get_status                (const dc_provider_t* prov
 void            dc_provider_unref                     (dc_provider_t* provider);
 
 
+/**
+ * @class dc_accounts_t
+ *
+ * Opaque object managing several accounts.
+ * Every account lives in its own subdirectory of the accounts directory
+ * and is represented by a normal dc_context_t object.
+ */
+
+
+/**
+ * Create a new accounts object.
+ * The directory is created if it does not exist yet;
+ * an existing directory is opened and all accounts in it are loaded.
+ *
+ * @memberof dc_accounts_t
+ * @param os_name See dc_context_new().
+ * @param dir The directory to store the accounts in.
+ * @return An accounts object, must be released using dc_accounts_unref().
+ *     On errors, NULL is returned.
+ */
+dc_accounts_t*  dc_accounts_new                       (const char* os_name, const char* dir);
+
+
+/**
+ * Free an accounts object.
+ * The contexts of all accounts are closed
+ * once the last reference to them is released.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ *     If NULL is given, nothing is done and an error is logged.
+ */
+void            dc_accounts_unref                     (dc_accounts_t* accounts);
+
+
+/**
+ * Get an account context by its id.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @param id The account id as returned by dc_accounts_add_account() or dc_accounts_get_all().
+ * @return The account context, must be released using dc_context_unref().
+ *     NULL if there is no account with the given id.
+ */
+dc_context_t*   dc_accounts_get_account               (dc_accounts_t* accounts, uint32_t id);
+
+
+/**
+ * Get the currently selected account context.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @return The account context, must be released using dc_context_unref().
+ *     NULL if no account is selected.
+ */
+dc_context_t*   dc_accounts_get_selected_account      (dc_accounts_t* accounts);
+
+
+/**
+ * Get the id of the currently selected account.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @return The account id, 0 if no account is selected.
+ */
+uint32_t        dc_accounts_get_selected_account_id   (dc_accounts_t* accounts);
+
+
+/**
+ * Select an account.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @param id The account id to select.
+ * @return 1 on success, 0 on errors, e.g. if there is no account with the given id.
+ */
+int             dc_accounts_select_account            (dc_accounts_t* accounts, uint32_t id);
+
+
+/**
+ * Add a new, unconfigured account.
+ * The new account is selected.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @return The id of the new account, 0 on errors.
+ */
+uint32_t        dc_accounts_add_account               (dc_accounts_t* accounts);
+
+
+/**
+ * Remove an account.
+ * The account is closed and its directory, including the database and all blobs, is deleted.
+ * If the account was selected, another account is selected.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @param id The account id to remove.
+ * @return 1 on success, 0 on errors.
+ */
+int             dc_accounts_remove_account            (dc_accounts_t* accounts, uint32_t id);
+
+
+/**
+ * Move an existing account into the accounts directory.
+ * The database file and its blob directory are moved,
+ * the database must not be opened by any other context.
+ * The migrated account is selected.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @param dbfile The database file of the account to migrate.
+ * @return The id of the migrated account, 0 on errors.
+ */
+uint32_t        dc_accounts_migrate_account           (dc_accounts_t* accounts, const char* dbfile);
+
+
+/**
+ * Get the ids of all accounts.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @return An array of account ids, must be released using dc_array_unref().
+ */
+dc_array_t*     dc_accounts_get_all                   (dc_accounts_t* accounts);
+
+
+/**
+ * Start IO of all accounts.
+ * See dc_start_io() for details.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ */
+void            dc_accounts_start_io                  (dc_accounts_t* accounts);
+
+
+/**
+ * Stop IO of all accounts.
+ * See dc_stop_io() for details.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ */
+void            dc_accounts_stop_io                   (dc_accounts_t* accounts);
+
+
+/**
+ * Create an event emitter for the events of all accounts.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @return The event emitter, must be released using dc_accounts_event_emitter_unref().
+ */
+dc_accounts_event_emitter_t* dc_accounts_get_event_emitter(dc_accounts_t* accounts);
+
+
+/**
+ * @class dc_accounts_event_emitter_t
+ *
+ * Opaque object that is used to get the events of all accounts.
+ * You can get an event emitter from an accounts object using dc_accounts_get_event_emitter().
+ */
+
+
+/**
+ * Get the next event from an accounts event emitter object.
+ *
+ * @memberof dc_accounts_event_emitter_t
+ * @param emitter Event emitter object as returned from dc_accounts_get_event_emitter().
+ * @param[out] account_id If not NULL, the id of the account the event belongs to is stored here.
+ * @return An event as an dc_event_t object, see dc_get_next_event() for details.
+ *     If NULL is returned, no more events will come;
+ *     in this case, free the event emitter using dc_accounts_event_emitter_unref().
+ */
+dc_event_t*     dc_accounts_get_next_event            (dc_accounts_event_emitter_t* emitter, uint32_t* account_id);
+
+
+/**
+ * Free an accounts event emitter object.
+ *
+ * @memberof dc_accounts_event_emitter_t
+ * @param emitter Event emitter object as returned from dc_accounts_get_event_emitter().
+ *     If NULL is given, nothing is done and an error is logged.
+ */
+void            dc_accounts_event_emitter_unref       (dc_accounts_event_emitter_t* emitter);
+
+
 /**
  * @class dc_lot_t
  *
@@ -3698,6 +4753,16 @@ int64_t          dc_lot_get_timestamp     (const dc_lot_t* lot);
  */
 #define DC_MSG_FILE      60
 
+
+/**
+ * Message containing one or more contacts as vCard attachment.
+ * The file is set via dc_msg_set_vcard()
+ * and retrieved via dc_msg_get_file();
+ * dc_msg_get_summarytext() contains the name of the first contact.
+ * To add the contacts, pass the file to dc_import_vcard().
+ */
+#define DC_MSG_VCARD     90
+
 /**
  * @}
  */
@@ -3814,6 +4879,42 @@ int64_t          dc_lot_get_timestamp     (const dc_lot_t* lot);
  */
 
 
+/**
+ * @defgroup DC_CONNECTIVITY DC_CONNECTIVITY
+ *
+ * These constants are returned by dc_get_connectivity().
+ * Compare against ranges, eg. values from 2000 to 2999 all mean "connecting",
+ * so that states can be split up in the future.
+ *
+ * @addtogroup DC_CONNECTIVITY
+ * @{
+ */
+
+/**
+ * Not connected, eg. because the network is down or IO is not started.
+ */
+#define DC_CONNECTIVITY_NOT_CONNECTED 1000
+
+/**
+ * Trying to connect to the server.
+ */
+#define DC_CONNECTIVITY_CONNECTING 2000
+
+/**
+ * Connected, but fetching messages at the moment.
+ */
+#define DC_CONNECTIVITY_WORKING 3000
+
+/**
+ * Connected and all messages are fetched.
+ */
+#define DC_CONNECTIVITY_CONNECTED 4000
+
+/**
+ * @}
+ */
+
+
 #define DC_EMPTY_MVBOX 0x01 // Deprecated, flag for dc_empty_server(): Clear all mvbox messages
 #define DC_EMPTY_INBOX 0x02 // Deprecated, flag for dc_empty_server(): Clear all INBOX messages
 
@@ -4094,6 +5195,17 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_MSGS_CHANGED             2000
 
 
+/**
+ * Reactions to the message changed.
+ * A reaction was added, changed or removed by a contact or by ourself.
+ * The message itself is not changed, call dc_get_msg_reactions() to get the new reactions.
+ *
+ * @param data1 (int) chat_id
+ * @param data2 (int) msg_id
+ */
+#define DC_EVENT_REACTIONS_CHANGED        2001
+
+
 /**
  * There is a fresh message. Typically, the user will show an notification
  * when receiving this message.
@@ -4148,6 +5260,15 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_CHAT_MODIFIED            2020
 
 
+/**
+ * Chat ephemeral timer changed.
+ *
+ * @param data1 (int) chat_id
+ * @param data2 (int) Timer value in seconds, 0 if the timer is disabled.
+ */
+#define DC_EVENT_CHAT_EPHEMERAL_TIMER_MODIFIED 2021
+
+
 /**
  * Contact(s) created, renamed, verified, blocked or deleted.
  *
@@ -4157,6 +5278,20 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_CONTACTS_CHANGED         2030
 
 
+/**
+ * A verified contact uses a new key.
+ *
+ * Messages of the contact in verified groups are held back
+ * until the contact is verified again,
+ * eg. by letting the contact scan the QR code returned by dc_get_securejoin_qr_for_contact().
+ * The held back messages are shown automatically after that.
+ *
+ * @param data1 (int) ID of the contact.
+ * @param data2 (char*) Fingerprint of the verified key and of the new key, separated by a space.
+ */
+#define DC_EVENT_VERIFIED_CONTACT_KEY_CHANGED 2032
+
+
 
 /**
  * Location of one or more contact has changed.
@@ -4200,6 +5335,20 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_IMEX_FILE_WRITTEN        2052
 
 
+/**
+ * A backup is ready to be sent to another device,
+ * see DC_IMEX_SEND_BACKUP_OVER_NETWORK in dc_imex().
+ *
+ * The QR code has to be shown to the user,
+ * it is scanned by the receiving device and passed to dc_imex()
+ * with DC_IMEX_RECEIVE_BACKUP_OVER_NETWORK there.
+ *
+ * @param data1 0
+ * @param data2 (char*) QR code text.
+ */
+#define DC_EVENT_IMEX_BACKUP_QR           2053
+
+
 /**
  * Progress information of a secure-join handshake from the view of the inviter
  * (Alice, the person who shows the QR code).
@@ -4231,6 +5380,18 @@ void dc_event_unref(dc_event_t* event);
  */
 #define DC_EVENT_SECUREJOIN_JOINER_PROGRESS       2061
 
+
+/**
+ * The connectivity to the server changed.
+ * This means that you should refresh the connectivity view
+ * and possibly the connectivity HTML; see dc_get_connectivity() and
+ * dc_get_connectivity_html() for details.
+ *
+ * @param data1 0
+ * @param data2 0
+ */
+#define DC_EVENT_CONNECTIVITY_CHANGED             2100
+
 /**
  * @}
  */
@@ -4243,7 +5404,7 @@ void dc_event_unref(dc_event_t* event);
 #define DC_ERROR_SELF_NOT_IN_GROUP   1    // not used anymore
 #define DC_STR_SELFNOTINGRP          21   // not used anymore
 #define DC_EVENT_DATA1_IS_STRING(e)  0    // not used anymore 
-#define DC_EVENT_DATA2_IS_STRING(e)  ((e)==DC_EVENT_IMEX_FILE_WRITTEN || ((e)>=100 && (e)<=499))
+#define DC_EVENT_DATA2_IS_STRING(e)  ((e)==DC_EVENT_IMEX_FILE_WRITTEN || (e)==DC_EVENT_IMEX_BACKUP_QR || (e)==DC_EVENT_VERIFIED_CONTACT_KEY_CHANGED || ((e)>=100 && (e)<=499))
 #define DC_EVENT_RETURNS_INT(e)      ((e)==DC_EVENT_IS_OFFLINE) // not used anymore
 #define DC_EVENT_RETURNS_STRING(e)   ((e)==DC_EVENT_GET_STRING) // not used anymore
 #define dc_archive_chat(a,b,c)  dc_set_chat_visibility((a), (b), (c)? 1 : 0) // not used anymore
@@ -4273,6 +5434,22 @@ void dc_event_unref(dc_event_t* event);
 #define DC_KEY_GEN_ED25519 2
 
 
+/*
+ * Values for dc_continue_key_transfer_with()
+ */
+#define DC_KEY_CONFLICT_KEEP_CURRENT 1
+#define DC_KEY_CONFLICT_REPLACE      2
+#define DC_KEY_CONFLICT_KEEP_BOTH    3
+
+
+/*
+ * Key types for dc_get_contact_keys_json() and dc_export_contact_key()
+ */
+#define DC_CONTACT_KEY_PUBLIC   1
+#define DC_CONTACT_KEY_GOSSIP   2
+#define DC_CONTACT_KEY_VERIFIED 3
+
+
 /**
  * @defgroup DC_PROVIDER_STATUS DC_PROVIDER_STATUS
  *
@@ -4427,7 +5604,17 @@ void dc_event_unref(dc_event_t* event);
 #define DC_STR_DEVICE_MESSAGES_HINT       70
 #define DC_STR_WELCOME_MESSAGE            71
 #define DC_STR_UNKNOWN_SENDER_FOR_CHAT    72
-#define DC_STR_COUNT                      72
+#define DC_STR_EPHEMERAL_DISABLED         73
+#define DC_STR_EPHEMERAL_SECONDS          74
+#define DC_STR_EPHEMERAL_MINUTE           75
+#define DC_STR_EPHEMERAL_HOUR             76
+#define DC_STR_EPHEMERAL_DAY              77
+#define DC_STR_EPHEMERAL_WEEK             78
+#define DC_STR_HELD_BACK                  79
+#define DC_STR_MSG_PINNED                 80
+#define DC_STR_MSG_UNPINNED               81
+#define DC_STR_CONTACT                    82
+#define DC_STR_COUNT                      82
 
 /*
  * @}
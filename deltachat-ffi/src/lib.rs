@@ -25,8 +25,9 @@ use num_traits::{FromPrimitive, ToPrimitive};
 
 use deltachat::chat::{ChatId, ChatVisibility, MuteDuration};
 use deltachat::constants::DC_MSG_ID_LAST_SPECIAL;
-use deltachat::contact::{Contact, Origin};
+use deltachat::contact::{Contact, ContactKeyType, Origin};
 use deltachat::context::Context;
+use deltachat::ephemeral::Timer as EphemeralTimer;
 use deltachat::key::DcKey;
 use deltachat::message::MsgId;
 use deltachat::stock::StockMessage;
@@ -343,13 +344,16 @@ pub unsafe extern "C" fn dc_event_get_data1_int(event: *mut dc_event_t) -> libc:
         | Event::Warning(_)
         | Event::Error(_)
         | Event::ErrorNetwork(_)
-        | Event::ErrorSelfNotInGroup(_) => 0,
+        | Event::ErrorSelfNotInGroup(_)
+        | Event::ConnectivityChanged => 0,
         Event::MsgsChanged { chat_id, .. }
+        | Event::ReactionsChanged { chat_id, .. }
         | Event::IncomingMsg { chat_id, .. }
         | Event::MsgDelivered { chat_id, .. }
         | Event::MsgFailed { chat_id, .. }
         | Event::MsgRead { chat_id, .. }
-        | Event::ChatModified(chat_id) => chat_id.to_u32() as libc::c_int,
+        | Event::ChatModified(chat_id)
+        | Event::ChatEphemeralTimerModified { chat_id, .. } => chat_id.to_u32() as libc::c_int,
         Event::ContactsChanged(id) | Event::LocationChanged(id) => {
             let id = id.unwrap_or_default();
             id as libc::c_int
@@ -357,9 +361,10 @@ pub unsafe extern "C" fn dc_event_get_data1_int(event: *mut dc_event_t) -> libc:
         Event::ConfigureProgress(progress) | Event::ImexProgress(progress) => {
             *progress as libc::c_int
         }
-        Event::ImexFileWritten(_) => 0,
+        Event::ImexFileWritten(_) | Event::ImexBackupQr(_) => 0,
         Event::SecurejoinInviterProgress { contact_id, .. }
-        | Event::SecurejoinJoinerProgress { contact_id, .. } => *contact_id as libc::c_int,
+        | Event::SecurejoinJoinerProgress { contact_id, .. }
+        | Event::VerifiedContactKeyChanged { contact_id, .. } => *contact_id as libc::c_int,
     }
 }
 
@@ -391,12 +396,17 @@ pub unsafe extern "C" fn dc_event_get_data2_int(event: *mut dc_event_t) -> libc:
         | Event::ConfigureProgress(_)
         | Event::ImexProgress(_)
         | Event::ImexFileWritten(_)
-        | Event::ChatModified(_) => 0,
+        | Event::ImexBackupQr(_)
+        | Event::ChatModified(_)
+        | Event::VerifiedContactKeyChanged { .. }
+        | Event::ConnectivityChanged => 0,
         Event::MsgsChanged { msg_id, .. }
+        | Event::ReactionsChanged { msg_id, .. }
         | Event::IncomingMsg { msg_id, .. }
         | Event::MsgDelivered { msg_id, .. }
         | Event::MsgFailed { msg_id, .. }
         | Event::MsgRead { msg_id, .. } => msg_id.to_u32() as libc::c_int,
+        Event::ChatEphemeralTimerModified { timer, .. } => *timer as libc::c_int,
         Event::SecurejoinInviterProgress { progress, .. }
         | Event::SecurejoinJoinerProgress { progress, .. } => *progress as libc::c_int,
     }
@@ -424,26 +434,40 @@ pub unsafe extern "C" fn dc_event_get_data2_str(event: *mut dc_event_t) -> *mut
         | Event::Warning(msg)
         | Event::Error(msg)
         | Event::ErrorNetwork(msg)
-        | Event::ErrorSelfNotInGroup(msg) => {
+        | Event::ErrorSelfNotInGroup(msg)
+        | Event::ImexBackupQr(msg) => {
             let data2 = msg.to_c_string().unwrap_or_default();
             data2.into_raw()
         }
         Event::MsgsChanged { .. }
+        | Event::ReactionsChanged { .. }
         | Event::IncomingMsg { .. }
         | Event::MsgDelivered { .. }
         | Event::MsgFailed { .. }
         | Event::MsgRead { .. }
         | Event::ChatModified(_)
+        | Event::ChatEphemeralTimerModified { .. }
         | Event::ContactsChanged(_)
         | Event::LocationChanged(_)
         | Event::ConfigureProgress(_)
         | Event::ImexProgress(_)
         | Event::SecurejoinInviterProgress { .. }
-        | Event::SecurejoinJoinerProgress { .. } => ptr::null_mut(),
+        | Event::SecurejoinJoinerProgress { .. }
+        | Event::ConnectivityChanged => ptr::null_mut(),
         Event::ImexFileWritten(file) => {
             let data2 = file.to_c_string().unwrap_or_default();
             data2.into_raw()
         }
+        Event::VerifiedContactKeyChanged {
+            old_fingerprint,
+            new_fingerprint,
+            ..
+        } => {
+            let data2 = format!("{} {}", old_fingerprint, new_fingerprint)
+                .to_c_string()
+                .unwrap_or_default();
+            data2.into_raw()
+        }
     }
 }
 
@@ -509,6 +533,28 @@ pub unsafe extern "C" fn dc_maybe_network(context: *mut dc_context_t) {
     block_on(async move { ctx.maybe_network().await })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_connectivity(context: *mut dc_context_t) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_connectivity()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(async move { ctx.get_connectivity().await as u32 as libc::c_int })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_connectivity_html(context: *mut dc_context_t) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_connectivity_html()");
+        return "".strdup();
+    }
+    let ctx = &*context;
+
+    block_on(async move { ctx.get_connectivity_html().await.strdup() })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_preconfigure_keypair(
     context: *mut dc_context_t,
@@ -537,6 +583,58 @@ pub unsafe extern "C" fn dc_preconfigure_keypair(
     .unwrap_or(0)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_rotate_self_key(
+    context: *mut dc_context_t,
+    key_gen_type: libc::c_int,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_rotate_self_key()");
+        return 0;
+    }
+    let ctx = &*context;
+    let keytype = constants::KeyGenType::from_i32(key_gen_type).unwrap_or_default();
+    block_on(async move {
+        key::rotate_self_key(&ctx, keytype)
+            .await
+            .map(|_| 1)
+            .log_err(ctx, "Failed to rotate key")
+            .unwrap_or(0)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_self_keys_json(context: *mut dc_context_t) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_self_keys_json()");
+        return "".strdup();
+    }
+    let ctx = &*context;
+    block_on(async move {
+        let keys = match key::get_self_key_history(&ctx).await {
+            Ok(keys) => keys,
+            Err(err) => {
+                error!(&ctx, "dc_get_self_keys_json() failed to load keys: {}", err);
+                return "".strdup();
+            }
+        };
+        let keys: Vec<serde_json::Value> = keys
+            .into_iter()
+            .map(|info| {
+                serde_json::json!({
+                    "addr": info.addr,
+                    "fingerprint": info.fingerprint.hex(),
+                    "created": info.created,
+                    "is_default": info.is_default,
+                })
+            })
+            .collect();
+        serde_json::to_string(&keys)
+            .unwrap_or_log_default(&ctx, "dc_get_self_keys_json() failed to serialise to json")
+            .strdup()
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_chatlist(
     context: *mut dc_context_t,
@@ -712,6 +810,113 @@ pub unsafe extern "C" fn dc_send_text_msg(
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_send_msg_at(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    msg: *mut dc_msg_t,
+    timestamp: i64,
+) -> u32 {
+    if context.is_null() || msg.is_null() {
+        eprintln!("ignoring careless call to dc_send_msg_at()");
+        return 0;
+    }
+    let ctx = &mut *context;
+    let ffi_msg = &mut *msg;
+
+    block_on(async move {
+        chat::send_msg_at(&ctx, ChatId::new(chat_id), &mut ffi_msg.message, timestamp)
+            .await
+            .unwrap_or_log_default(&ctx, "Failed to schedule message")
+    })
+    .to_u32()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_scheduled_msgs(
+    context: *mut dc_context_t,
+    chat_id: u32,
+) -> *mut dc_array::dc_array_t {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_scheduled_msgs()");
+        return ptr::null_mut();
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        let arr = dc_array_t::from(
+            ChatId::new(chat_id)
+                .get_scheduled_msgs(&ctx)
+                .await
+                .unwrap_or_log_default(&ctx, "Failed to get scheduled messages")
+                .iter()
+                .map(|msg_id| msg_id.to_u32())
+                .collect::<Vec<u32>>(),
+        );
+        Box::into_raw(Box::new(arr))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_reschedule_msg(
+    context: *mut dc_context_t,
+    msg_id: u32,
+    timestamp: i64,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_reschedule_msg()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        chat::reschedule_msg(&ctx, MsgId::new(msg_id), timestamp)
+            .await
+            .map(|_| 1)
+            .unwrap_or_log_default(&ctx, "Failed to reschedule message")
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_scheduled_msg_text(
+    context: *mut dc_context_t,
+    msg_id: u32,
+    text: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() || text.is_null() {
+        eprintln!("ignoring careless call to dc_set_scheduled_msg_text()");
+        return 0;
+    }
+    let ctx = &*context;
+    let text = to_string_lossy(text);
+
+    block_on(async move {
+        chat::set_scheduled_msg_text(&ctx, MsgId::new(msg_id), text)
+            .await
+            .map(|_| 1)
+            .unwrap_or_log_default(&ctx, "Failed to set text of scheduled message")
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_cancel_scheduled_msg(
+    context: *mut dc_context_t,
+    msg_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_cancel_scheduled_msg()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        chat::cancel_scheduled_msg(&ctx, MsgId::new(msg_id))
+            .await
+            .map(|_| 1)
+            .unwrap_or_log_default(&ctx, "Failed to cancel scheduled message")
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_set_draft(
     context: *mut dc_context_t,
@@ -1126,6 +1331,36 @@ pub unsafe extern "C" fn dc_search_msgs(
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_search(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    query: *const libc::c_char,
+    offset: libc::size_t,
+    limit: libc::size_t,
+) -> *mut dc_search_results_t {
+    if context.is_null() || query.is_null() {
+        eprintln!("ignoring careless call to dc_search()");
+        return ptr::null_mut();
+    }
+    let ctx = &*context;
+    let chat_id = if chat_id == 0 {
+        None
+    } else {
+        Some(ChatId::new(chat_id))
+    };
+
+    block_on(async move {
+        match search::search(&ctx, &to_string_lossy(query), chat_id, offset, limit).await {
+            Ok(results) => Box::into_raw(Box::new(results)),
+            Err(err) => {
+                error!(ctx, "Failed to search: {}", err);
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_chat(context: *mut dc_context_t, chat_id: u32) -> *mut dc_chat_t {
     if context.is_null() {
@@ -1171,6 +1406,48 @@ pub unsafe extern "C" fn dc_create_group_chat(
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_create_broadcast_list(
+    context: *mut dc_context_t,
+    name: *const libc::c_char,
+) -> u32 {
+    if context.is_null() || name.is_null() {
+        eprintln!("ignoring careless call to dc_create_broadcast_list()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        chat::create_broadcast_list(&ctx, to_string_lossy(name))
+            .await
+            .log_err(ctx, "Failed to create broadcast list")
+            .map(|id| id.to_u32())
+            .unwrap_or(0)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_unsubscribe_mailinglist(
+    context: *mut dc_context_t,
+    chat_id: u32,
+) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_unsubscribe_mailinglist()");
+        return ptr::null_mut();
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        match mailinglist::unsubscribe(&ctx, ChatId::new(chat_id)).await {
+            Ok(url) => url.unwrap_or_default().strdup(),
+            Err(err) => {
+                error!(ctx, "Failed to unsubscribe from mailing list: {}", err);
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_is_contact_in_chat(
     context: *mut dc_context_t,
@@ -1264,6 +1541,47 @@ pub unsafe extern "C" fn dc_set_chat_profile_image(
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_chat_ephemeral_timer(
+    context: *mut dc_context_t,
+    chat_id: u32,
+) -> u32 {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_chat_ephemeral_timer()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        ChatId::new(chat_id)
+            .get_ephemeral_timer(ctx)
+            .await
+            .map(|timer| timer.to_u32())
+            .unwrap_or_log_default(ctx, "Failed to get ephemeral timer")
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_chat_ephemeral_timer(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    timer: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_set_chat_ephemeral_timer()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        ChatId::new(chat_id)
+            .set_ephemeral_timer(ctx, EphemeralTimer::from_u32(timer))
+            .await
+            .map(|_| 1)
+            .unwrap_or_log_default(ctx, "Failed to set ephemeral timer")
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_set_chat_mute_duration(
     context: *mut dc_context_t,
@@ -1310,6 +1628,49 @@ pub unsafe extern "C" fn dc_get_msg_info(
     block_on(message::get_msg_info(&ctx, MsgId::new(msg_id))).strdup()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_send_reaction(
+    context: *mut dc_context_t,
+    msg_id: u32,
+    reaction: *const libc::c_char,
+) -> u32 {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_send_reaction()");
+        return 0;
+    }
+    let ctx = &*context;
+    let reaction = to_string_lossy(reaction);
+
+    block_on(async move {
+        reaction::send_reaction(&ctx, MsgId::new(msg_id), &reaction)
+            .await
+            .map(|msg_id| msg_id.to_u32())
+            .unwrap_or_log_default(&ctx, "Failed to send reaction")
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_msg_reactions(
+    context: *mut dc_context_t,
+    msg_id: u32,
+) -> *mut dc_reactions_t {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_msg_reactions()");
+        return ptr::null_mut();
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        match reaction::get_reactions(&ctx, MsgId::new(msg_id)).await {
+            Ok(reactions) => Box::into_raw(Box::new(reactions)),
+            Err(err) => {
+                error!(ctx, "Failed to get reactions: {}", err);
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_mime_headers(
     context: *mut dc_context_t,
@@ -1346,16 +1707,125 @@ pub unsafe extern "C" fn dc_delete_msgs(
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_empty_server(context: *mut dc_context_t, flags: u32) {
-    if context.is_null() || flags == 0 {
-        eprintln!("ignoring careless call to dc_empty_server()");
-        return;
+pub unsafe extern "C" fn dc_send_edit_request(
+    context: *mut dc_context_t,
+    msg_id: u32,
+    new_text: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() || new_text.is_null() {
+        eprintln!("ignoring careless call to dc_send_edit_request()");
+        return 0;
     }
     let ctx = &*context;
+    let new_text = to_string_lossy(new_text);
+
+    block_on(async move {
+        message::send_edit_request(&ctx, MsgId::new(msg_id), new_text)
+            .await
+            .map(|_| 1)
+            .unwrap_or_log_default(&ctx, "Failed to send edit request")
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_send_delete_request(
+    context: *mut dc_context_t,
+    msg_ids: *const u32,
+    msg_cnt: libc::c_int,
+) -> libc::c_int {
+    if context.is_null() || msg_ids.is_null() || msg_cnt <= 0 {
+        eprintln!("ignoring careless call to dc_send_delete_request()");
+        return 0;
+    }
+    let ctx = &*context;
+    let msg_ids = convert_and_prune_message_ids(msg_ids, msg_cnt);
+
+    block_on(async move {
+        message::send_delete_request(&ctx, &msg_ids)
+            .await
+            .map(|_| 1)
+            .unwrap_or_log_default(&ctx, "Failed to send delete request")
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_pin_msg(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    msg_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_pin_msg()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        ChatId::new(chat_id)
+            .pin_msg(&ctx, MsgId::new(msg_id))
+            .await
+            .map(|_| 1)
+            .unwrap_or_log_default(&ctx, "Failed to pin message")
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_unpin_msg(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    msg_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_unpin_msg()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        ChatId::new(chat_id)
+            .unpin_msg(&ctx, MsgId::new(msg_id))
+            .await
+            .map(|_| 1)
+            .unwrap_or_log_default(&ctx, "Failed to unpin message")
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_pinned_msgs(
+    context: *mut dc_context_t,
+    chat_id: u32,
+) -> *mut dc_array::dc_array_t {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_pinned_msgs()");
+        return ptr::null_mut();
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        let arr = dc_array_t::from(
+            ChatId::new(chat_id)
+                .get_pinned_msgs(&ctx)
+                .await
+                .unwrap_or_log_default(&ctx, "Failed to get pinned messages")
+                .iter()
+                .map(|msg_id| msg_id.to_u32())
+                .collect::<Vec<u32>>(),
+        );
+        Box::into_raw(Box::new(arr))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_empty_server(context: *mut dc_context_t, flags: u32) {
+    if context.is_null() || flags == 0 {
+        eprintln!("ignoring careless call to dc_empty_server()");
+        return;
+    }
+    let ctx = &*context;
+
+    block_on(message::dc_empty_server(&ctx, flags))
+}
 
-    block_on(message::dc_empty_server(&ctx, flags))
-}
-
 #[no_mangle]
 pub unsafe extern "C" fn dc_forward_msgs(
     context: *mut dc_context_t,
@@ -1617,6 +2087,164 @@ pub unsafe extern "C" fn dc_get_contact_encrinfo(
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_contact_keys_json(
+    context: *mut dc_context_t,
+    contact_id: u32,
+) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_contact_keys_json()");
+        return "".strdup();
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        let keys = match Contact::get_keys(&ctx, contact_id).await {
+            Ok(keys) => keys,
+            Err(err) => {
+                error!(
+                    &ctx,
+                    "dc_get_contact_keys_json() failed to load keys: {}", err
+                );
+                return "".strdup();
+            }
+        };
+        let keys: Vec<serde_json::Value> = keys
+            .into_iter()
+            .map(|key| {
+                serde_json::json!({
+                    "type": key.typ as u8,
+                    "fingerprint": key.fingerprint.hex(),
+                    "timestamp": key.timestamp,
+                })
+            })
+            .collect();
+        serde_json::to_string(&keys)
+            .unwrap_or_log_default(
+                &ctx,
+                "dc_get_contact_keys_json() failed to serialise to json",
+            )
+            .strdup()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_export_contact_key(
+    context: *mut dc_context_t,
+    contact_id: u32,
+    key_type: libc::c_int,
+) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_export_contact_key()");
+        return ptr::null_mut();
+    }
+    let ctx = &*context;
+    let key_type = match ContactKeyType::from_i32(key_type) {
+        Some(key_type) => key_type,
+        None => {
+            eprintln!("ignoring careless call to dc_export_contact_key(): unknown key type");
+            return ptr::null_mut();
+        }
+    };
+
+    block_on(async move {
+        match Contact::export_key(&ctx, contact_id, key_type).await {
+            Ok(Some(armored)) => armored.strdup(),
+            Ok(None) => ptr::null_mut(),
+            Err(err) => {
+                error!(&ctx, "dc_export_contact_key() failed: {}", err);
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_import_contact_key(
+    context: *mut dc_context_t,
+    contact_id: u32,
+    file: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() || file.is_null() {
+        eprintln!("ignoring careless call to dc_import_contact_key()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        Contact::import_key_from_file(&ctx, contact_id, to_string_lossy(file))
+            .await
+            .log_err(ctx, "Failed to import contact key")
+            .is_ok() as libc::c_int
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_import_vcard(
+    context: *mut dc_context_t,
+    file: *const libc::c_char,
+    import_keys: libc::c_int,
+) -> *mut dc_array::dc_array_t {
+    if context.is_null() || file.is_null() {
+        eprintln!("ignoring careless call to dc_import_vcard()");
+        return ptr::null_mut();
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        match Contact::import_vcard_file(&ctx, to_string_lossy(file), import_keys != 0)
+            .await
+            .log_err(ctx, "Failed to import vCard")
+        {
+            Ok(contact_ids) => Box::into_raw(Box::new(dc_array_t::from(contact_ids))),
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_make_vcard(
+    context: *mut dc_context_t,
+    contact_ids: *const u32,
+    contact_ids_cnt: libc::c_int,
+) -> *mut libc::c_char {
+    if context.is_null() || contact_ids.is_null() || contact_ids_cnt <= 0 {
+        eprintln!("ignoring careless call to dc_make_vcard()");
+        return ptr::null_mut();
+    }
+    let ctx = &*context;
+    let contact_ids = std::slice::from_raw_parts(contact_ids, contact_ids_cnt as usize);
+
+    block_on(async move {
+        match Contact::make_vcard(&ctx, contact_ids)
+            .await
+            .log_err(ctx, "Failed to make vCard")
+        {
+            Ok(vcard) => vcard.strdup(),
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_reset_contact_peerstate(
+    context: *mut dc_context_t,
+    contact_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_reset_contact_peerstate()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        Contact::reset_peerstate(&ctx, contact_id)
+            .await
+            .log_err(ctx, "Failed to reset peerstate")
+            .is_ok() as libc::c_int
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_delete_contact(
     context: *mut dc_context_t,
@@ -1660,7 +2288,7 @@ pub unsafe extern "C" fn dc_imex(
     context: *mut dc_context_t,
     what_raw: libc::c_int,
     param1: *const libc::c_char,
-    _param2: *const libc::c_char,
+    param2: *const libc::c_char,
 ) {
     if context.is_null() {
         eprintln!("ignoring careless call to dc_imex()");
@@ -1677,9 +2305,10 @@ pub unsafe extern "C" fn dc_imex(
     let ctx = &*context;
 
     let param1 = to_opt_string_lossy(param1);
+    let param2 = to_opt_string_lossy(param2);
 
     spawn(async move {
-        imex::imex(&ctx, what, param1)
+        imex::imex(&ctx, what, param1, param2)
             .await
             .log_err(ctx, "IMEX failed")
     });
@@ -1756,6 +2385,80 @@ pub unsafe extern "C" fn dc_continue_key_transfer(
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_check_key_transfer(
+    context: *mut dc_context_t,
+    msg_id: u32,
+    setup_code: *const libc::c_char,
+) -> *mut libc::c_char {
+    if context.is_null()
+        || msg_id <= constants::DC_MSG_ID_LAST_SPECIAL as u32
+        || setup_code.is_null()
+    {
+        eprintln!("ignoring careless call to dc_check_key_transfer()");
+        return ptr::null_mut();
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        match imex::check_key_transfer(&ctx, MsgId::new(msg_id), &to_string_lossy(setup_code)).await
+        {
+            Ok(Some(conflict)) => serde_json::json!({
+                "current_fingerprint": conflict.current_fingerprint.hex(),
+                "imported_fingerprint": conflict.imported_fingerprint.hex(),
+            })
+            .to_string()
+            .strdup(),
+            Ok(None) => "".strdup(),
+            Err(err) => {
+                error!(&ctx, "dc_check_key_transfer: {}", err);
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_continue_key_transfer_with(
+    context: *mut dc_context_t,
+    msg_id: u32,
+    setup_code: *const libc::c_char,
+    resolution: libc::c_int,
+) -> libc::c_int {
+    if context.is_null()
+        || msg_id <= constants::DC_MSG_ID_LAST_SPECIAL as u32
+        || setup_code.is_null()
+    {
+        eprintln!("ignoring careless call to dc_continue_key_transfer_with()");
+        return 0;
+    }
+    let ctx = &*context;
+    let resolution = match imex::KeyConflictResolution::from_i32(resolution) {
+        Some(resolution) => resolution,
+        None => {
+            eprintln!("ignoring careless call to dc_continue_key_transfer_with()");
+            return 0;
+        }
+    };
+
+    block_on(async move {
+        match imex::continue_key_transfer_with(
+            &ctx,
+            MsgId::new(msg_id),
+            &to_string_lossy(setup_code),
+            resolution,
+        )
+        .await
+        {
+            Ok(()) => 1,
+            Err(err) => {
+                error!(&ctx, "dc_continue_key_transfer_with: {}", err);
+                0
+            }
+        }
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_stop_ongoing_process(context: *mut dc_context_t) {
     if context.is_null() {
@@ -1802,6 +2505,25 @@ pub unsafe extern "C" fn dc_get_securejoin_qr(
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_securejoin_qr_for_contact(
+    context: *mut dc_context_t,
+    contact_id: u32,
+) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_securejoin_qr_for_contact()");
+        return "".strdup();
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        securejoin::dc_get_securejoin_qr_for_contact(&ctx, contact_id)
+            .await
+            .unwrap_or_else(|| "".to_string())
+            .strdup()
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_join_securejoin(
     context: *mut dc_context_t,
@@ -2540,6 +3262,26 @@ pub unsafe extern "C" fn dc_msg_get_received_timestamp(msg: *mut dc_msg_t) -> i6
     ffi_msg.message.get_received_timestamp()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_ephemeral_timer(msg: *mut dc_msg_t) -> u32 {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_ephemeral_timer()");
+        return 0;
+    }
+    let ffi_msg = &*msg;
+    ffi_msg.message.get_ephemeral_timer().to_u32()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_ephemeral_timestamp(msg: *mut dc_msg_t) -> i64 {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_ephemeral_timestamp()");
+        return 0;
+    }
+    let ffi_msg = &*msg;
+    ffi_msg.message.get_ephemeral_timestamp()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_sort_timestamp(msg: *mut dc_msg_t) -> i64 {
     if msg.is_null() {
@@ -2560,6 +3302,46 @@ pub unsafe extern "C" fn dc_msg_get_text(msg: *mut dc_msg_t) -> *mut libc::c_cha
     ffi_msg.message.get_text().unwrap_or_default().strdup()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_quoted_text(msg: *const dc_msg_t) -> *mut libc::c_char {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_quoted_text()");
+        return ptr::null_mut();
+    }
+    let ffi_msg: &MessageWrapper = &*msg;
+    ffi_msg
+        .message
+        .quoted_text()
+        .map(|s| s.strdup())
+        .unwrap_or_else(ptr::null_mut)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_quoted_msg(msg: *const dc_msg_t) -> *mut dc_msg_t {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_quoted_msg()");
+        return ptr::null_mut();
+    }
+    let ffi_msg: &MessageWrapper = &*msg;
+    let context = &*ffi_msg.context;
+    let res = block_on(async move {
+        ffi_msg
+            .message
+            .quoted_message(context)
+            .await
+            .log_err(context, "failed to get quoted message")
+            .unwrap_or(None)
+    });
+
+    match res {
+        Some(message) => Box::into_raw(Box::new(MessageWrapper {
+            context: ffi_msg.context,
+            message,
+        })),
+        None => ptr::null_mut(),
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_file(msg: *mut dc_msg_t) -> *mut libc::c_char {
     if msg.is_null() {
@@ -2568,9 +3350,22 @@ pub unsafe extern "C" fn dc_msg_get_file(msg: *mut dc_msg_t) -> *mut libc::c_cha
     }
     let ffi_msg = &*msg;
     let ctx = &*ffi_msg.context;
-    ffi_msg
-        .message
-        .get_file(ctx)
+    ffi_msg
+        .message
+        .get_file(ctx)
+        .map(|p| p.to_string_lossy().strdup())
+        .unwrap_or_else(|| "".strdup())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_thumbnail(msg: *mut dc_msg_t) -> *mut libc::c_char {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_thumbnail()");
+        return "".strdup();
+    }
+    let ffi_msg = &mut *msg;
+    let ctx = &*ffi_msg.context;
+    block_on(ffi_msg.message.get_thumbnail(&ctx))
         .map(|p| p.to_string_lossy().strdup())
         .unwrap_or_else(|| "".strdup())
 }
@@ -2651,6 +3446,16 @@ pub unsafe extern "C" fn dc_msg_get_showpadlock(msg: *mut dc_msg_t) -> libc::c_i
     ffi_msg.message.get_showpadlock() as libc::c_int
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_is_signed_only(msg: *mut dc_msg_t) -> libc::c_int {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_is_signed_only()");
+        return 0;
+    }
+    let ffi_msg = &*msg;
+    ffi_msg.message.is_signed_only() as libc::c_int
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_summary(
     msg: *mut dc_msg_t,
@@ -2745,6 +3550,16 @@ pub unsafe extern "C" fn dc_msg_is_forwarded(msg: *mut dc_msg_t) -> libc::c_int
     ffi_msg.message.is_forwarded().into()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_is_edited(msg: *mut dc_msg_t) -> libc::c_int {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_is_edited()");
+        return 0;
+    }
+    let ffi_msg = &*msg;
+    ffi_msg.message.is_edited().into()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_is_info(msg: *mut dc_msg_t) -> libc::c_int {
     if msg.is_null() {
@@ -2799,6 +3614,26 @@ pub unsafe extern "C" fn dc_msg_set_text(msg: *mut dc_msg_t, text: *const libc::
     ffi_msg.message.set_text(to_opt_string_lossy(text))
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_set_quote(msg: *mut dc_msg_t, quote: *const dc_msg_t) {
+    if msg.is_null() || quote.is_null() {
+        eprintln!("ignoring careless call to dc_msg_set_quote()");
+        return;
+    }
+    let ffi_msg = &mut *msg;
+    let ffi_quote = &*quote;
+    let ctx = &*ffi_msg.context;
+
+    block_on(async move {
+        ffi_msg
+            .message
+            .set_quote(ctx, &ffi_quote.message)
+            .await
+            .log_err(ctx, "failed to set quote")
+            .ok();
+    });
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_set_file(
     msg: *mut dc_msg_t,
@@ -2840,6 +3675,25 @@ pub unsafe extern "C" fn dc_msg_set_duration(msg: *mut dc_msg_t, duration: libc:
     ffi_msg.message.set_duration(duration)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_set_vcard(msg: *mut dc_msg_t, contact_id: u32) -> libc::c_int {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_set_vcard()");
+        return 0;
+    }
+    let ffi_msg = &mut *msg;
+    let ctx = &*ffi_msg.context;
+
+    block_on(async move {
+        ffi_msg
+            .message
+            .set_vcard(&ctx, &[contact_id])
+            .await
+            .log_err(ctx, "Failed to attach vCard")
+            .is_ok() as libc::c_int
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_set_location(
     msg: *mut dc_msg_t,
@@ -3161,6 +4015,151 @@ fn convert_and_prune_message_ids(msg_ids: *const u32, msg_cnt: libc::c_int) -> V
     msg_ids
 }
 
+// dc_reactions_t
+
+#[no_mangle]
+pub type dc_reactions_t = reaction::Reactions;
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_reactions_get_contacts(
+    reactions: *mut dc_reactions_t,
+) -> *mut dc_array::dc_array_t {
+    if reactions.is_null() {
+        eprintln!("ignoring careless call to dc_reactions_get_contacts()");
+        return ptr::null_mut();
+    }
+    let reactions = &*reactions;
+
+    Box::into_raw(Box::new(dc_array_t::from(reactions.contacts())))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_reactions_get_by_contact_id(
+    reactions: *mut dc_reactions_t,
+    contact_id: u32,
+) -> *mut libc::c_char {
+    if reactions.is_null() {
+        eprintln!("ignoring careless call to dc_reactions_get_by_contact_id()");
+        return "".strdup();
+    }
+    let reactions = &*reactions;
+
+    reactions.get(contact_id).strdup()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_reactions_unref(reactions: *mut dc_reactions_t) {
+    if reactions.is_null() {
+        eprintln!("ignoring careless call to dc_reactions_unref()");
+        return;
+    }
+
+    Box::from_raw(reactions);
+}
+
+// dc_search_results_t
+
+#[no_mangle]
+pub type dc_search_results_t = Vec<search::SearchResult>;
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_search_results_get_cnt(
+    results: *mut dc_search_results_t,
+) -> libc::size_t {
+    if results.is_null() {
+        eprintln!("ignoring careless call to dc_search_results_get_cnt()");
+        return 0;
+    }
+    let results = &*results;
+
+    results.len()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_search_results_get_msg_id(
+    results: *mut dc_search_results_t,
+    index: libc::size_t,
+) -> u32 {
+    if results.is_null() {
+        eprintln!("ignoring careless call to dc_search_results_get_msg_id()");
+        return 0;
+    }
+    let results = &*results;
+
+    results
+        .get(index)
+        .map(|result| result.msg_id.to_u32())
+        .unwrap_or_default()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_search_results_get_chat_id(
+    results: *mut dc_search_results_t,
+    index: libc::size_t,
+) -> u32 {
+    if results.is_null() {
+        eprintln!("ignoring careless call to dc_search_results_get_chat_id()");
+        return 0;
+    }
+    let results = &*results;
+
+    results
+        .get(index)
+        .map(|result| result.chat_id.to_u32())
+        .unwrap_or_default()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_search_results_get_snippet(
+    results: *mut dc_search_results_t,
+    index: libc::size_t,
+) -> *mut libc::c_char {
+    if results.is_null() {
+        eprintln!("ignoring careless call to dc_search_results_get_snippet()");
+        return "".strdup();
+    }
+    let results = &*results;
+
+    match results.get(index) {
+        Some(result) => result.snippet.strdup(),
+        None => "".strdup(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_search_results_get_highlights(
+    results: *mut dc_search_results_t,
+    index: libc::size_t,
+) -> *mut dc_array::dc_array_t {
+    if results.is_null() {
+        eprintln!("ignoring careless call to dc_search_results_get_highlights()");
+        return ptr::null_mut();
+    }
+    let results = &*results;
+
+    let highlights = results
+        .get(index)
+        .map(|result| {
+            result
+                .highlights
+                .iter()
+                .flat_map(|(start, end)| vec![*start as u32, *end as u32])
+                .collect::<Vec<u32>>()
+        })
+        .unwrap_or_default();
+    Box::into_raw(Box::new(dc_array_t::from(highlights)))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_search_results_unref(results: *mut dc_search_results_t) {
+    if results.is_null() {
+        eprintln!("ignoring careless call to dc_search_results_unref()");
+        return;
+    }
+
+    Box::from_raw(results);
+}
+
 // dc_provider_t
 
 #[no_mangle]
@@ -3225,3 +4224,246 @@ pub unsafe extern "C" fn dc_provider_unref(provider: *mut dc_provider_t) {
     // currently, there is nothing to free, the provider info is a static object.
     // this may change once we start localizing string.
 }
+
+// dc_accounts_t
+
+pub type dc_accounts_t = accounts::Accounts;
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_new(
+    os_name: *const libc::c_char,
+    dir: *const libc::c_char,
+) -> *mut dc_accounts_t {
+    setup_panic!();
+
+    if dir.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_new()");
+        return ptr::null_mut();
+    }
+
+    let os_name = if os_name.is_null() {
+        String::from("DcFFI")
+    } else {
+        to_string_lossy(os_name)
+    };
+
+    match block_on(accounts::Accounts::new(
+        os_name,
+        as_path(dir).to_path_buf().into(),
+    )) {
+        Ok(accounts) => Box::into_raw(Box::new(accounts)),
+        Err(err) => {
+            eprintln!("failed to create accounts: {}", err);
+            ptr::null_mut()
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_unref(accounts: *mut dc_accounts_t) {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_unref()");
+        return;
+    }
+    Box::from_raw(accounts);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_get_account(
+    accounts: *mut dc_accounts_t,
+    id: u32,
+) -> *mut dc_context_t {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_get_account()");
+        return ptr::null_mut();
+    }
+    let accounts = &*accounts;
+
+    block_on(accounts.get_account(id))
+        .map(|ctx| Box::into_raw(Box::new(ctx)))
+        .unwrap_or_else(ptr::null_mut)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_get_selected_account(
+    accounts: *mut dc_accounts_t,
+) -> *mut dc_context_t {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_get_selected_account()");
+        return ptr::null_mut();
+    }
+    let accounts = &*accounts;
+
+    block_on(accounts.get_selected_account())
+        .map(|ctx| Box::into_raw(Box::new(ctx)))
+        .unwrap_or_else(ptr::null_mut)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_get_selected_account_id(accounts: *mut dc_accounts_t) -> u32 {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_get_selected_account_id()");
+        return 0;
+    }
+    let accounts = &*accounts;
+
+    block_on(accounts.get_selected_account_id())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_select_account(
+    accounts: *mut dc_accounts_t,
+    id: u32,
+) -> libc::c_int {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_select_account()");
+        return 0;
+    }
+    let accounts = &*accounts;
+
+    match block_on(accounts.select_account(id)) {
+        Ok(()) => 1,
+        Err(err) => {
+            eprintln!("failed to select account {}: {}", id, err);
+            0
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_add_account(accounts: *mut dc_accounts_t) -> u32 {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_add_account()");
+        return 0;
+    }
+    let accounts = &*accounts;
+
+    match block_on(accounts.add_account()) {
+        Ok(id) => id,
+        Err(err) => {
+            eprintln!("failed to add account: {}", err);
+            0
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_remove_account(
+    accounts: *mut dc_accounts_t,
+    id: u32,
+) -> libc::c_int {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_remove_account()");
+        return 0;
+    }
+    let accounts = &*accounts;
+
+    match block_on(accounts.remove_account(id)) {
+        Ok(()) => 1,
+        Err(err) => {
+            eprintln!("failed to remove account {}: {}", id, err);
+            0
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_migrate_account(
+    accounts: *mut dc_accounts_t,
+    dbfile: *const libc::c_char,
+) -> u32 {
+    if accounts.is_null() || dbfile.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_migrate_account()");
+        return 0;
+    }
+    let accounts = &*accounts;
+
+    match block_on(accounts.migrate_account(as_path(dbfile).to_path_buf().into())) {
+        Ok(id) => id,
+        Err(err) => {
+            eprintln!("failed to migrate account: {}", err);
+            0
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_get_all(accounts: *mut dc_accounts_t) -> *mut dc_array_t {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_get_all()");
+        return ptr::null_mut();
+    }
+    let accounts = &*accounts;
+
+    let ids = block_on(accounts.get_all());
+    Box::into_raw(Box::new(dc_array_t::from(ids)))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_start_io(accounts: *mut dc_accounts_t) {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_start_io()");
+        return;
+    }
+    let accounts = &*accounts;
+
+    block_on(accounts.start_io());
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_stop_io(accounts: *mut dc_accounts_t) {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_stop_io()");
+        return;
+    }
+    let accounts = &*accounts;
+
+    block_on(accounts.stop_io());
+}
+
+pub type dc_accounts_event_emitter_t = accounts::AccountsEventEmitter;
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_get_event_emitter(
+    accounts: *mut dc_accounts_t,
+) -> *mut dc_accounts_event_emitter_t {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_get_event_emitter()");
+        return ptr::null_mut();
+    }
+    let accounts = &*accounts;
+
+    Box::into_raw(Box::new(accounts.get_event_emitter()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_event_emitter_unref(
+    emitter: *mut dc_accounts_event_emitter_t,
+) {
+    if emitter.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_event_emitter_unref()");
+        return;
+    }
+    Box::from_raw(emitter);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_get_next_event(
+    emitter: *mut dc_accounts_event_emitter_t,
+    account_id: *mut u32,
+) -> *mut dc_event_t {
+    if emitter.is_null() {
+        return ptr::null_mut();
+    }
+    let emitter = &*emitter;
+
+    match emitter.recv_sync() {
+        Some(ev) => {
+            if !account_id.is_null() {
+                *account_id = ev.id;
+            }
+            Box::into_raw(Box::new(ev.event))
+        }
+        None => ptr::null_mut(),
+    }
+}
@@ -11,8 +11,10 @@ use deltachat::dc_receive_imf::*;
 use deltachat::dc_tools::*;
 use deltachat::error::Error;
 use deltachat::imex::*;
+use deltachat::key;
 use deltachat::location;
 use deltachat::lot::LotState;
+use deltachat::mailinglist;
 use deltachat::message::{self, Message, MessageState, MsgId};
 use deltachat::peerstate::*;
 use deltachat::qr::*;
@@ -313,11 +315,14 @@ pub async fn cmdline(context: Context, line: &str, chat_id: &mut ChatId) -> Resu
                  initiate-key-transfer\n\
                  get-setupcodebegin <msg-id>\n\
                  continue-key-transfer <msg-id> <setup-code>\n\
+                 check-key-transfer <msg-id> <setup-code>\n\
                  has-backup\n\
-                 export-backup\n\
-                 import-backup <backup-file>\n\
+                 export-backup [<passphrase>]\n\
+                 import-backup <backup-file> [<passphrase>]\n\
                  export-keys\n\
                  import-keys\n\
+                 rotate-key [rsa2048|ed25519]\n\
+                 list-keys\n\
                  export-setup\n\
                  poke [<eml-file>|<folder>|<addr> <key-file>]\n\
                  reset <flags>\n\
@@ -336,6 +341,7 @@ pub async fn cmdline(context: Context, line: &str, chat_id: &mut ChatId) -> Resu
                  connect\n\
                  disconnect\n\
                  maybenetwork\n\
+                 connectivity\n\
                  housekeeping\n\
                  help imex (Import/Export)\n\
                  ==============================Chat commands==\n\
@@ -346,6 +352,7 @@ pub async fn cmdline(context: Context, line: &str, chat_id: &mut ChatId) -> Resu
                  createchatbymsg <msg-id>\n\
                  creategroup <name>\n\
                  createverified <name>\n\
+                 createbroadcast <name>\n\
                  addmember <contact-id>\n\
                  removemember <contact-id>\n\
                  groupname <name>\n\
@@ -359,6 +366,7 @@ pub async fn cmdline(context: Context, line: &str, chat_id: &mut ChatId) -> Resu
                  send-garbage\n\
                  sendimage <file> [<text>]\n\
                  sendfile <file> [<text>]\n\
+                 sendvcard <contact-id> [<text>]\n\
                  draft [<text>]\n\
                  devicemsg <text>\n\
                  listmedia\n\
@@ -367,6 +375,7 @@ pub async fn cmdline(context: Context, line: &str, chat_id: &mut ChatId) -> Resu
                  pin <chat-id>\n\
                  unpin <chat-id>\n\
                  delchat <chat-id>\n\
+                 unsubscribe <chat-id>\n\
                  ===========================Message commands==\n\
                  listmsgs <query>\n\
                  msginfo <msg-id>\n\
@@ -382,6 +391,11 @@ pub async fn cmdline(context: Context, line: &str, chat_id: &mut ChatId) -> Resu
                  addcontact [<name>] <addr>\n\
                  contactinfo <contact-id>\n\
                  delcontact <contact-id>\n\
+                 contactkeys <contact-id>\n\
+                 importcontactkey <contact-id> <file>\n\
+                 resetcontactkeys <contact-id>\n\
+                 importvcard <file> [keys]\n\
+                 makevcard <contact-id> [<contact-id> ...]\n\
                  cleanupcontacts\n\
                  ======================================Misc.==\n\
                  getqr [<chat-id>]\n\
@@ -426,21 +440,58 @@ pub async fn cmdline(context: Context, line: &str, chat_id: &mut ChatId) -> Resu
             );
             continue_key_transfer(&context, MsgId::new(arg1.parse()?), &arg2).await?;
         }
+        "check-key-transfer" => {
+            ensure!(
+                !arg1.is_empty() && !arg2.is_empty(),
+                "Arguments <msg-id> <setup-code> expected"
+            );
+            match check_key_transfer(&context, MsgId::new(arg1.parse()?), &arg2).await? {
+                Some(conflict) => println!(
+                    "Setup message contains a different key:\ncurrent:  {}\nimported: {}",
+                    conflict.current_fingerprint.hex(),
+                    conflict.imported_fingerprint.hex()
+                ),
+                None => println!("No key conflict."),
+            }
+        }
         "has-backup" => {
             has_backup(&context, blobdir).await?;
         }
         "export-backup" => {
-            imex(&context, ImexMode::ExportBackup, Some(blobdir)).await?;
+            let passphrase = Some(arg1.to_string()).filter(|s| !s.is_empty());
+            imex(&context, ImexMode::ExportBackup, Some(blobdir), passphrase).await?;
         }
         "import-backup" => {
             ensure!(!arg1.is_empty(), "Argument <backup-file> missing.");
-            imex(&context, ImexMode::ImportBackup, Some(arg1)).await?;
+            let passphrase = Some(arg2.to_string()).filter(|s| !s.is_empty());
+            imex(&context, ImexMode::ImportBackup, Some(arg1), passphrase).await?;
         }
         "export-keys" => {
-            imex(&context, ImexMode::ExportSelfKeys, Some(blobdir)).await?;
+            imex(&context, ImexMode::ExportSelfKeys, Some(blobdir), None).await?;
         }
         "import-keys" => {
-            imex(&context, ImexMode::ImportSelfKeys, Some(blobdir)).await?;
+            imex(&context, ImexMode::ImportSelfKeys, Some(blobdir), None).await?;
+        }
+        "rotate-key" => {
+            let keytype = match arg1 {
+                "" => KeyGenType::Default,
+                "rsa2048" => KeyGenType::Rsa2048,
+                "ed25519" => KeyGenType::Ed25519,
+                _ => bail!("Unknown key type: {}", arg1),
+            };
+            let fingerprint = key::rotate_self_key(&context, keytype).await?;
+            println!("New key generated:\n{}", fingerprint);
+        }
+        "list-keys" => {
+            for info in key::get_self_key_history(&context).await? {
+                println!(
+                    "{} {} created {}{}",
+                    info.addr,
+                    info.fingerprint.hex(),
+                    dc_timestamp_to_str(info.created),
+                    if info.is_default { " [default]" } else { "" }
+                );
+            }
         }
         "export-setup" => {
             let setup_code = create_setup_code(&context);
@@ -483,6 +534,10 @@ pub async fn cmdline(context: Context, line: &str, chat_id: &mut ChatId) -> Resu
         "maybenetwork" => {
             context.maybe_network().await;
         }
+        "connectivity" => {
+            println!("{:?}", context.get_connectivity().await);
+            println!("{}", context.get_connectivity_html().await);
+        }
         "housekeeping" => {
             sql::housekeeping(&context).await;
         }
@@ -636,6 +691,12 @@ pub async fn cmdline(context: Context, line: &str, chat_id: &mut ChatId) -> Resu
 
             println!("VerifiedGroup#{} created successfully.", chat_id);
         }
+        "createbroadcast" => {
+            ensure!(!arg1.is_empty(), "Argument <name> missing.");
+            let chat_id = chat::create_broadcast_list(&context, arg1).await?;
+
+            println!("Broadcast#{} created successfully.", chat_id);
+        }
         "addmember" => {
             ensure!(sel_chat.is_some(), "No chat selected");
             ensure!(!arg1.is_empty(), "Argument <contact-id> missing.");
@@ -794,6 +855,17 @@ pub async fn cmdline(context: Context, line: &str, chat_id: &mut ChatId) -> Resu
             }
             chat::send_msg(&context, sel_chat.as_ref().unwrap().get_id(), &mut msg).await?;
         }
+        "sendvcard" => {
+            ensure!(sel_chat.is_some(), "No chat selected.");
+            ensure!(!arg1.is_empty(), "Argument <contact-id> missing.");
+
+            let mut msg = Message::new(Viewtype::Vcard);
+            msg.set_vcard(&context, &[arg1.parse()?]).await?;
+            if !arg2.is_empty() {
+                msg.set_text(Some(arg2.to_string()));
+            }
+            chat::send_msg(&context, sel_chat.as_ref().unwrap().get_id(), &mut msg).await?;
+        }
         "listmsgs" => {
             ensure!(!arg1.is_empty(), "Argument <query> missing.");
 
@@ -884,6 +956,14 @@ pub async fn cmdline(context: Context, line: &str, chat_id: &mut ChatId) -> Resu
             let chat_id = ChatId::new(arg1.parse()?);
             chat_id.delete(&context).await?;
         }
+        "unsubscribe" => {
+            ensure!(!arg1.is_empty(), "Argument <chat-id> missing.");
+            let chat_id = ChatId::new(arg1.parse()?);
+            match mailinglist::unsubscribe(&context, chat_id).await? {
+                Some(url) => println!("Open {} to unsubscribe.", url),
+                None => println!("Unsubscribe request sent."),
+            }
+        }
         "msginfo" => {
             ensure!(!arg1.is_empty(), "Argument <msg-id> missing.");
             let id = MsgId::new(arg1.parse()?);
@@ -989,6 +1069,42 @@ pub async fn cmdline(context: Context, line: &str, chat_id: &mut ChatId) -> Resu
             ensure!(!arg1.is_empty(), "Argument <contact-id> missing.");
             Contact::delete(&context, arg1.parse()?).await?;
         }
+        "contactkeys" => {
+            ensure!(!arg1.is_empty(), "Argument <contact-id> missing.");
+            for key in Contact::get_keys(&context, arg1.parse()?).await? {
+                println!(
+                    "{:?}: {} (seen {})",
+                    key.typ,
+                    key.fingerprint,
+                    dc_timestamp_to_str(key.timestamp)
+                );
+            }
+        }
+        "importcontactkey" => {
+            ensure!(
+                !arg1.is_empty() && !arg2.is_empty(),
+                "Arguments <contact-id> <file> expected."
+            );
+            let fingerprint = Contact::import_key_from_file(&context, arg1.parse()?, arg2).await?;
+            println!("Imported key {}.", fingerprint);
+        }
+        "resetcontactkeys" => {
+            ensure!(!arg1.is_empty(), "Argument <contact-id> missing.");
+            Contact::reset_peerstate(&context, arg1.parse()?).await?;
+        }
+        "importvcard" => {
+            ensure!(!arg1.is_empty(), "Argument <file> missing.");
+            let contact_ids = Contact::import_vcard_file(&context, arg1, arg2 == "keys").await?;
+            println!("Imported {} contacts: {:?}", contact_ids.len(), contact_ids);
+        }
+        "makevcard" => {
+            ensure!(!arg1.is_empty(), "Argument <contact-id> missing.");
+            let contact_ids = format!("{} {}", arg1, arg2)
+                .split_whitespace()
+                .map(|id| id.parse())
+                .collect::<Result<Vec<u32>, _>>()?;
+            print!("{}", Contact::make_vcard(&context, &contact_ids).await?);
+        }
         "checkqr" => {
             ensure!(!arg1.is_empty(), "Argument <qr-content> missing.");
             let res = check_qr(&context, arg1).await;
@@ -131,22 +131,25 @@ impl Completer for DcHelper {
     }
 }
 
-const IMEX_COMMANDS: [&str; 12] = [
+const IMEX_COMMANDS: [&str; 15] = [
     "initiate-key-transfer",
     "get-setupcodebegin",
     "continue-key-transfer",
+    "check-key-transfer",
     "has-backup",
     "export-backup",
     "import-backup",
     "export-keys",
     "import-keys",
+    "rotate-key",
+    "list-keys",
     "export-setup",
     "poke",
     "reset",
     "stop",
 ];
 
-const DB_COMMANDS: [&str; 9] = [
+const DB_COMMANDS: [&str; 10] = [
     "info",
     "set",
     "get",
@@ -155,10 +158,11 @@ const DB_COMMANDS: [&str; 9] = [
     "connect",
     "disconnect",
     "maybenetwork",
+    "connectivity",
     "housekeeping",
 ];
 
-const CHAT_COMMANDS: [&str; 26] = [
+const CHAT_COMMANDS: [&str; 29] = [
     "listchats",
     "listarchived",
     "chat",
@@ -166,6 +170,7 @@ const CHAT_COMMANDS: [&str; 26] = [
     "createchatbymsg",
     "creategroup",
     "createverified",
+    "createbroadcast",
     "addmember",
     "removemember",
     "groupname",
@@ -178,6 +183,7 @@ const CHAT_COMMANDS: [&str; 26] = [
     "send",
     "sendimage",
     "sendfile",
+    "sendvcard",
     "draft",
     "listmedia",
     "archive",
@@ -185,6 +191,7 @@ const CHAT_COMMANDS: [&str; 26] = [
     "pin",
     "unpin",
     "delchat",
+    "unsubscribe",
 ];
 const MESSAGE_COMMANDS: [&str; 8] = [
     "listmsgs",
@@ -196,12 +203,17 @@ const MESSAGE_COMMANDS: [&str; 8] = [
     "unstar",
     "delmsg",
 ];
-const CONTACT_COMMANDS: [&str; 6] = [
+const CONTACT_COMMANDS: [&str; 11] = [
     "listcontacts",
     "listverified",
     "addcontact",
     "contactinfo",
     "delcontact",
+    "contactkeys",
+    "importcontactkey",
+    "resetcontactkeys",
+    "importvcard",
+    "makevcard",
     "cleanupcontacts",
 ];
 const MISC_COMMANDS: [&str; 10] = [
@@ -89,6 +89,14 @@ class Account(object):
             d[key.lower()] = value
         return d
 
+    def get_connectivity(self):
+        """ return the connectivity to the server, one of const.DC_CONNECTIVITY_*. """
+        return lib.dc_get_connectivity(self._dc_context)
+
+    def get_connectivity_html(self):
+        """ return an HTML report about the connectivity to the server. """
+        return from_dc_charpointer(lib.dc_get_connectivity_html(self._dc_context))
+
     def set_stock_translation(self, id, string):
         """ set stock translation string.
 
@@ -271,6 +279,32 @@ class Account(object):
         )
         return list(iter_array(dc_array, lambda x: Contact(self, x)))
 
+    def import_vcard(self, path, import_keys=False):
+        """ add the contacts of a vCard file to the address book.
+
+        :param path: path of the vCard file.
+        :param import_keys: if True, the keys contained in the vCard are used
+            for contacts without known keys.  Only set this if the file is trusted.
+        :returns: list of the added :class:`deltachat.contact.Contact` objects.
+        """
+        dc_array = lib.dc_import_vcard(self._dc_context, as_dc_charpointer(path), int(import_keys))
+        if dc_array == ffi.NULL:
+            raise ValueError("could not import vCard {!r}".format(path))
+        dc_array = ffi.gc(dc_array, lib.dc_array_unref)
+        return list(iter_array(dc_array, lambda x: Contact(self, x)))
+
+    def make_vcard(self, contacts):
+        """ create a vCard for a list of contacts.
+
+        :param contacts: list of :class:`deltachat.contact.Contact` objects.
+        :returns: the vCard as string.
+        """
+        contact_ids = [contact.id for contact in contacts]
+        res = lib.dc_make_vcard(self._dc_context, contact_ids, len(contact_ids))
+        if res == ffi.NULL:
+            raise ValueError("could not make vCard")
+        return from_dc_charpointer(res)
+
     def get_fresh_messages(self):
         """ yield all fresh messages from all chats. """
         dc_array = ffi.gc(
@@ -411,21 +445,21 @@ class Account(object):
         """
         return self._export(path, imex_cmd=1)
 
-    def export_all(self, path):
+    def export_all(self, path, passphrase=None):
         """return new file containing a backup of all database state
         (chats, contacts, keys, media, ...). The file is created in the
-        the `path` directory.
+        the `path` directory and encrypted if a `passphrase` is given.
 
         Note that the account does not have to be started.
         """
-        export_files = self._export(path, 11)
+        export_files = self._export(path, 11, passphrase)
         if len(export_files) != 1:
             raise RuntimeError("found more than one new file")
         return export_files[0]
 
-    def _export(self, path, imex_cmd):
+    def _export(self, path, imex_cmd, passphrase=None):
         with self.temp_plugin(ImexTracker()) as imex_tracker:
-            lib.dc_imex(self._dc_context, imex_cmd, as_dc_charpointer(path), ffi.NULL)
+            lib.dc_imex(self._dc_context, imex_cmd, as_dc_charpointer(path), as_dc_charpointer(passphrase))
             return imex_tracker.wait_finish()
 
     def import_self_keys(self, path):
@@ -437,17 +471,18 @@ class Account(object):
         """
         self._import(path, imex_cmd=2)
 
-    def import_all(self, path):
+    def import_all(self, path, passphrase=None):
         """import delta chat state from the specified backup `path` (a file).
+        Encrypted backups require the `passphrase` used on export.
 
         The account must be in unconfigured state for import to attempted.
         """
         assert not self.is_configured(), "cannot import into configured account"
-        self._import(path, imex_cmd=12)
+        self._import(path, imex_cmd=12, passphrase=passphrase)
 
-    def _import(self, path, imex_cmd):
+    def _import(self, path, imex_cmd, passphrase=None):
         with self.temp_plugin(ImexTracker()) as imex_tracker:
-            lib.dc_imex(self._dc_context, imex_cmd, as_dc_charpointer(path), ffi.NULL)
+            lib.dc_imex(self._dc_context, imex_cmd, as_dc_charpointer(path), as_dc_charpointer(passphrase))
             imex_tracker.wait_finish()
 
     def initiate_key_transfer(self):
@@ -0,0 +1,74 @@
+""" Accounts class implementation. """
+
+from .capi import ffi, lib
+from .cutil import as_dc_charpointer, iter_array
+
+
+class Accounts(object):
+    """ Manages several accounts stored in subdirectories of one directory.
+
+    Accounts are identified by their numeric id; the id of the
+    currently selected account is persisted in the directory.
+    """
+
+    def __init__(self, path, os_name=None):
+        """ open or create an accounts directory.
+
+        :param path: a path to the accounts directory. The directory
+                     will be created if it doesn't exist.
+        :param os_name: this will be put to the X-Mailer header in outgoing messages
+        """
+        self.path = path
+        if hasattr(path, "encode"):
+            path = path.encode("utf8")
+
+        self._dc_accounts = ffi.gc(
+            lib.dc_accounts_new(as_dc_charpointer(os_name), path),
+            lib.dc_accounts_unref,
+        )
+        if self._dc_accounts == ffi.NULL:
+            raise ValueError("Could not dc_accounts_new: {} {}".format(os_name, path))
+
+    def get_all(self):
+        """ return list of all account ids. """
+        dc_array = ffi.gc(
+            lib.dc_accounts_get_all(self._dc_accounts),
+            lib.dc_array_unref
+        )
+        return list(iter_array(dc_array, lambda x: x))
+
+    def add_account(self):
+        """ add and select a new, unconfigured account.
+
+        :returns: the id of the new account.
+        """
+        account_id = lib.dc_accounts_add_account(self._dc_accounts)
+        if account_id == 0:
+            raise ValueError("could not add account")
+        return account_id
+
+    def migrate_account(self, db_path):
+        """ move an existing account database into the accounts directory
+        and select it. The database must not be opened by any :class:`Account`.
+
+        :param db_path: path to the account database.
+        :returns: the id of the migrated account.
+        """
+        account_id = lib.dc_accounts_migrate_account(self._dc_accounts, as_dc_charpointer(db_path))
+        if account_id == 0:
+            raise ValueError("could not migrate account {!r}".format(db_path))
+        return account_id
+
+    def remove_account(self, account_id):
+        """ remove an account and delete its database and blobs. """
+        if not lib.dc_accounts_remove_account(self._dc_accounts, account_id):
+            raise ValueError("could not remove account {}".format(account_id))
+
+    def select_account(self, account_id):
+        """ select an account. """
+        if not lib.dc_accounts_select_account(self._dc_accounts, account_id):
+            raise ValueError("could not select account {}".format(account_id))
+
+    def get_selected_account_id(self):
+        """ return the id of the selected account or 0 if there is none. """
+        return lib.dc_accounts_get_selected_account_id(self._dc_accounts)
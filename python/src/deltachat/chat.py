@@ -60,6 +60,13 @@ class Chat(object):
             const.DC_CHAT_TYPE_VERIFIED_GROUP
         )
 
+    def is_mailinglist(self):
+        """ return true if this chat is a mailing list.
+
+        :returns: True if chat is a mailing list, False otherwise.
+        """
+        return lib.dc_chat_get_type(self._dc_chat) == const.DC_CHAT_TYPE_MAILINGLIST
+
     def is_deaddrop(self):
         """ return true if this chat is a deaddrop chat.
 
@@ -180,6 +187,22 @@ class Chat(object):
         msg._dc_msg = Message.from_db(self.account, sent_id)._dc_msg
         return msg
 
+    def send_msg_at(self, msg, timestamp):
+        """schedule a message for sending at a later time.
+
+        :param msg: a :class:`deltachat.message.Message` instance.
+        :param timestamp: time to send the message at, in seconds since the epoch.
+        :raises ValueError: if message can not be scheduled.
+
+        :returns: the passed :class:`deltachat.message.Message` instance,
+           modified with the new state of the core.
+        """
+        msg_id = lib.dc_send_msg_at(self.account._dc_context, self.id, msg._dc_msg, int(timestamp))
+        if msg_id == 0:
+            raise ValueError("message could not be scheduled")
+        msg._dc_msg = Message.from_db(self.account, msg_id)._dc_msg
+        return msg
+
     def send_text(self, text):
         """ send a text message and return the resulting Message instance.
 
@@ -306,6 +329,58 @@ class Chat(object):
         )
         return list(iter_array(dc_array, lambda x: Message.from_db(self.account, x)))
 
+    def unsubscribe(self):
+        """ unsubscribe from this mailing list.
+
+        :returns: None if an unsubscribe request was sent to the list,
+            otherwise the URL of the web page to unsubscribe on.
+        :raises ValueError: if the list does not offer unsubscribing.
+        """
+        res = lib.dc_unsubscribe_mailinglist(self.account._dc_context, self.id)
+        if res == ffi.NULL:
+            raise ValueError("cannot unsubscribe from mailing list")
+        return from_dc_charpointer(res) or None
+
+    def get_pinned_messages(self):
+        """ return list of pinned messages in this chat, the most recently pinned first.
+
+        :returns: list of :class:`deltachat.message.Message` objects.
+        """
+        dc_array = ffi.gc(
+            lib.dc_get_pinned_msgs(self.account._dc_context, self.id),
+            lib.dc_array_unref
+        )
+        return list(iter_array(dc_array, lambda x: Message.from_db(self.account, x)))
+
+    def get_scheduled_messages(self):
+        """ return list of messages scheduled for this chat, the next due first.
+
+        :returns: list of :class:`deltachat.message.Message` objects.
+        """
+        dc_array = ffi.gc(
+            lib.dc_get_scheduled_msgs(self.account._dc_context, self.id),
+            lib.dc_array_unref
+        )
+        return list(iter_array(dc_array, lambda x: Message.from_db(self.account, x)))
+
+    def pin_message(self, message):
+        """ pin a message in this group for all members.
+
+        :param message: a :class:`deltachat.message.Message` of this chat.
+        :raises ValueError: if the message could not be pinned.
+        """
+        if not lib.dc_pin_msg(self.account._dc_context, self.id, message.id):
+            raise ValueError("could not pin message {}".format(message.id))
+
+    def unpin_message(self, message):
+        """ unpin a message in this group for all members.
+
+        :param message: a pinned :class:`deltachat.message.Message` of this chat.
+        :raises ValueError: if the message could not be unpinned.
+        """
+        if not lib.dc_unpin_msg(self.account._dc_context, self.id, message.id):
+            raise ValueError("could not unpin message {}".format(message.id))
+
     def count_fresh_messages(self):
         """ return number of fresh messages in this chat.
 
@@ -20,6 +20,7 @@ DC_QR_FPR_OK = 210
 DC_QR_FPR_MISMATCH = 220
 DC_QR_FPR_WITHOUT_ADDR = 230
 DC_QR_ACCOUNT = 250
+DC_QR_BACKUP = 251
 DC_QR_ADDR = 320
 DC_QR_TEXT = 330
 DC_QR_URL = 332
@@ -35,6 +36,8 @@ DC_CHAT_TYPE_UNDEFINED = 0
 DC_CHAT_TYPE_SINGLE = 100
 DC_CHAT_TYPE_GROUP = 120
 DC_CHAT_TYPE_VERIFIED_GROUP = 130
+DC_CHAT_TYPE_MAILINGLIST = 140
+DC_CHAT_TYPE_BROADCAST = 160
 DC_MSG_ID_MARKER1 = 1
 DC_MSG_ID_DAYMARKER = 9
 DC_MSG_ID_LAST_SPECIAL = 9
@@ -45,6 +48,7 @@ DC_STATE_IN_SEEN = 16
 DC_STATE_OUT_PREPARING = 18
 DC_STATE_OUT_DRAFT = 19
 DC_STATE_OUT_PENDING = 20
+DC_STATE_OUT_SCHEDULED = 21
 DC_STATE_OUT_FAILED = 24
 DC_STATE_OUT_DELIVERED = 26
 DC_STATE_OUT_MDN_RCVD = 28
@@ -60,6 +64,7 @@ DC_MSG_AUDIO = 40
 DC_MSG_VOICE = 41
 DC_MSG_VIDEO = 50
 DC_MSG_FILE = 60
+DC_MSG_VCARD = 90
 DC_LP_AUTH_OAUTH2 = 0x2
 DC_LP_AUTH_NORMAL = 0x4
 DC_LP_IMAP_SOCKET_STARTTLS = 0x100
@@ -71,6 +76,10 @@ DC_LP_SMTP_SOCKET_PLAIN = 0x40000
 DC_CERTCK_AUTO = 0
 DC_CERTCK_STRICT = 1
 DC_CERTCK_ACCEPT_INVALID_CERTIFICATES = 3
+DC_CONNECTIVITY_NOT_CONNECTED = 1000
+DC_CONNECTIVITY_CONNECTING = 2000
+DC_CONNECTIVITY_WORKING = 3000
+DC_CONNECTIVITY_CONNECTED = 4000
 DC_EMPTY_MVBOX = 0x01
 DC_EMPTY_INBOX = 0x02
 DC_EVENT_INFO = 100
@@ -87,18 +96,23 @@ DC_EVENT_ERROR = 400
 DC_EVENT_ERROR_NETWORK = 401
 DC_EVENT_ERROR_SELF_NOT_IN_GROUP = 410
 DC_EVENT_MSGS_CHANGED = 2000
+DC_EVENT_REACTIONS_CHANGED = 2001
 DC_EVENT_INCOMING_MSG = 2005
 DC_EVENT_MSG_DELIVERED = 2010
 DC_EVENT_MSG_FAILED = 2012
 DC_EVENT_MSG_READ = 2015
 DC_EVENT_CHAT_MODIFIED = 2020
+DC_EVENT_CHAT_EPHEMERAL_TIMER_MODIFIED = 2021
 DC_EVENT_CONTACTS_CHANGED = 2030
+DC_EVENT_VERIFIED_CONTACT_KEY_CHANGED = 2032
 DC_EVENT_LOCATION_CHANGED = 2035
 DC_EVENT_CONFIGURE_PROGRESS = 2041
 DC_EVENT_IMEX_PROGRESS = 2051
 DC_EVENT_IMEX_FILE_WRITTEN = 2052
+DC_EVENT_IMEX_BACKUP_QR = 2053
 DC_EVENT_SECUREJOIN_INVITER_PROGRESS = 2060
 DC_EVENT_SECUREJOIN_JOINER_PROGRESS = 2061
+DC_EVENT_CONNECTIVITY_CHANGED = 2100
 DC_EVENT_FILE_COPIED = 2055
 DC_EVENT_IS_OFFLINE = 2081
 DC_EVENT_GET_STRING = 2091
@@ -106,6 +120,12 @@ DC_STR_SELFNOTINGRP = 21
 DC_KEY_GEN_DEFAULT = 0
 DC_KEY_GEN_RSA2048 = 1
 DC_KEY_GEN_ED25519 = 2
+DC_KEY_CONFLICT_KEEP_CURRENT = 1
+DC_KEY_CONFLICT_REPLACE = 2
+DC_KEY_CONFLICT_KEEP_BOTH = 3
+DC_CONTACT_KEY_PUBLIC = 1
+DC_CONTACT_KEY_GOSSIP = 2
+DC_CONTACT_KEY_VERIFIED = 3
 DC_PROVIDER_STATUS_OK = 1
 DC_PROVIDER_STATUS_PREPARATION = 2
 DC_PROVIDER_STATUS_BROKEN = 3
@@ -84,11 +84,21 @@ class Message(object):
             raise ValueError("path does not exist: {!r}".format(path))
         lib.dc_msg_set_file(self._dc_msg, as_dc_charpointer(path), mtype)
 
+    def set_vcard(self, contact):
+        """ attach a contact as vCard, making this a vCard message. """
+        if not lib.dc_msg_set_vcard(self._dc_msg, contact.id):
+            raise ValueError("could not attach vCard of {!r}".format(contact))
+
     @props.with_doc
     def basename(self):
         """basename of the attachment if it exists, otherwise empty string. """
         return from_dc_charpointer(lib.dc_msg_get_filename(self._dc_msg))
 
+    @props.with_doc
+    def thumbnail(self):
+        """path of a small preview of an image, gif or sticker, otherwise empty string. """
+        return from_dc_charpointer(lib.dc_msg_get_thumbnail(self._dc_msg))
+
     @props.with_doc
     def filemime(self):
         """mime type of the file (if it exists)"""
@@ -110,6 +120,10 @@ class Message(object):
         """ return True if this message was encrypted. """
         return bool(lib.dc_msg_get_showpadlock(self._dc_msg))
 
+    def is_signed_only(self):
+        """ return True if this message was signed but not encrypted. """
+        return bool(lib.dc_msg_is_signed_only(self._dc_msg))
+
     def is_forwarded(self):
         """ return True if this message was forwarded. """
         return bool(lib.dc_msg_is_forwarded(self._dc_msg))
@@ -235,7 +249,7 @@ class Message(object):
         """Return True if Message is outgoing. """
         return self._msgstate in (
             const.DC_STATE_OUT_PREPARING, const.DC_STATE_OUT_PENDING,
-            const.DC_STATE_OUT_FAILED, const.DC_STATE_OUT_MDN_RCVD,
+            const.DC_STATE_OUT_SCHEDULED, const.DC_STATE_OUT_FAILED, const.DC_STATE_OUT_MDN_RCVD,
             const.DC_STATE_OUT_DELIVERED)
 
     def is_out_preparing(self):
@@ -248,6 +262,11 @@ class Message(object):
         """
         return self._msgstate == const.DC_STATE_OUT_PENDING
 
+    def is_out_scheduled(self):
+        """Return True if Message is scheduled for sending at a later time.
+        """
+        return self._msgstate == const.DC_STATE_OUT_SCHEDULED
+
     def is_out_failed(self):
         """Return True if Message is unrecoverably failed.
         """
@@ -300,6 +319,10 @@ class Message(object):
         """ return True if it's a file message. """
         return self._view_type == const.DC_MSG_FILE
 
+    def is_vcard(self):
+        """ return True if it's a vCard message. """
+        return self._view_type == const.DC_MSG_VCARD
+
     def mark_seen(self):
         """ mark this message as seen. """
         self.account.mark_seen_messages([self.id])
@@ -313,7 +336,8 @@ _view_type_mapping = {
     const.DC_MSG_GIF: 'gif',
     const.DC_MSG_AUDIO: 'audio',
     const.DC_MSG_VIDEO: 'video',
-    const.DC_MSG_FILE: 'file'
+    const.DC_MSG_FILE: 'file',
+    const.DC_MSG_VCARD: 'vcard',
 }
 
 
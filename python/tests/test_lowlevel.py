@@ -1,5 +1,6 @@
 from __future__ import print_function
 
+import pytest
 from queue import Queue
 from deltachat import capi, cutil, const
 from deltachat import register_global_plugin
@@ -102,3 +103,21 @@ def test_get_info_open(tmpdir):
     info = cutil.from_dc_charpointer(lib.dc_get_info(ctx))
     assert 'deltachat_core_version' in info
     assert 'database_dir' in info
+
+
+def test_accounts(tmpdir):
+    from deltachat import Accounts
+    accounts = Accounts(tmpdir.join("accounts").strpath)
+    assert accounts.get_all() == []
+    assert accounts.get_selected_account_id() == 0
+    id1 = accounts.add_account()
+    id2 = accounts.add_account()
+    assert accounts.get_selected_account_id() == id2
+    assert accounts.get_all() == [id1, id2]
+    accounts.select_account(id1)
+    assert accounts.get_selected_account_id() == id1
+    accounts.remove_account(id1)
+    assert accounts.get_all() == [id2]
+    assert accounts.get_selected_account_id() == id2
+    with pytest.raises(ValueError):
+        accounts.select_account(id1)
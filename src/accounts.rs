@@ -0,0 +1,520 @@
+//! # Account manager
+//!
+//! [Accounts] manages several [Context]s, one per account, inside a
+//! single directory.  Every account lives in its own subdirectory
+//! containing the database and the blob directory:
+//!
+//! ```text
+//! <dir>/accounts.json
+//! <dir>/1/dc.db
+//! <dir>/1/dc.db-blobs/
+//! <dir>/2/dc.db
+//! ...
+//! ```
+//!
+//! The list of accounts and the selected account are stored in
+//! `accounts.json`.  Events of all accounts are merged into a single
+//! stream, see [Accounts::get_event_emitter].
+
+use std::collections::BTreeMap;
+
+use async_std::fs;
+use async_std::path::{Path, PathBuf};
+use async_std::sync::{channel, Receiver, RwLock, Sender};
+use async_std::task;
+use serde::{Deserialize, Serialize};
+
+use crate::context::Context;
+use crate::error::{ensure, format_err, Result};
+use crate::events::Event;
+
+/// Name of the file storing the list of accounts.
+const CONFIG_NAME: &str = "accounts.json";
+
+/// Name of the database file inside an account directory.
+const DB_NAME: &str = "dc.db";
+
+/// Manager of multiple accounts.
+#[derive(Debug)]
+pub struct Accounts {
+    os_name: String,
+    dir: PathBuf,
+    config: RwLock<AccountsConfig>,
+    accounts: RwLock<BTreeMap<u32, Account>>,
+    sender: Sender<AccountEvent>,
+    receiver: Receiver<AccountEvent>,
+}
+
+impl Accounts {
+    /// Opens the accounts stored in `dir`.
+    ///
+    /// The directory and an empty list of accounts are created
+    /// if they do not exist yet.
+    pub async fn new(os_name: String, dir: PathBuf) -> Result<Self> {
+        if !dir.exists().await {
+            fs::create_dir_all(&dir).await?;
+        }
+        let config = AccountsConfig::load(&dir).await?;
+
+        let (sender, receiver) = channel(1_000);
+        let mut accounts = BTreeMap::new();
+        for account_config in &config.accounts {
+            let dbfile = dir.join(&account_config.dir).join(DB_NAME);
+            let context = Context::new(os_name.clone(), dbfile).await?;
+            let account = Account::new(account_config.id, context, sender.clone());
+            accounts.insert(account_config.id, account);
+        }
+
+        Ok(Self {
+            os_name,
+            dir,
+            config: RwLock::new(config),
+            accounts: RwLock::new(accounts),
+            sender,
+            receiver,
+        })
+    }
+
+    /// Returns the context of the account, if it exists.
+    pub async fn get_account(&self, id: u32) -> Option<Context> {
+        self.accounts
+            .read()
+            .await
+            .get(&id)
+            .map(|account| account.context.clone())
+    }
+
+    /// Returns the context of the selected account,
+    /// or `None` if there are no accounts.
+    pub async fn get_selected_account(&self) -> Option<Context> {
+        let id = self.config.read().await.selected_account;
+        self.get_account(id).await
+    }
+
+    /// Returns the ID of the selected account, 0 if there are no accounts.
+    pub async fn get_selected_account_id(&self) -> u32 {
+        self.config.read().await.selected_account
+    }
+
+    /// Selects the account with the given ID.
+    pub async fn select_account(&self, id: u32) -> Result<()> {
+        ensure!(
+            self.accounts.read().await.contains_key(&id),
+            "invalid account id: {}",
+            id
+        );
+
+        let mut config = self.config.write().await;
+        config.selected_account = id;
+        config.save(&self.dir).await
+    }
+
+    /// Returns the IDs of all accounts.
+    pub async fn get_all(&self) -> Vec<u32> {
+        self.accounts.read().await.keys().copied().collect()
+    }
+
+    /// Adds a new, unconfigured account and selects it.
+    ///
+    /// Returns the ID of the new account.
+    pub async fn add_account(&self) -> Result<u32> {
+        let mut config = self.config.write().await;
+        let id = config.next_id;
+        let account_dir = id.to_string();
+        let dbfile = self.dir.join(&account_dir).join(DB_NAME);
+        fs::create_dir_all(self.dir.join(&account_dir)).await?;
+        let context = Context::new(self.os_name.clone(), dbfile).await?;
+
+        config.next_id += 1;
+        config.selected_account = id;
+        config.accounts.push(AccountConfig {
+            id,
+            dir: account_dir,
+        });
+        config.save(&self.dir).await?;
+
+        let account = Account::new(id, context, self.sender.clone());
+        self.accounts.write().await.insert(id, account);
+        Ok(id)
+    }
+
+    /// Moves an existing database and its blob directory into a new
+    /// account and selects it.
+    ///
+    /// The database must not be opened by another [Context]
+    /// while it is migrated.  If the migrated database cannot be
+    /// opened, the files are moved back.  Returns the ID of the new
+    /// account.
+    pub async fn migrate_account(&self, dbfile: PathBuf) -> Result<u32> {
+        ensure!(
+            dbfile.is_file().await,
+            "database {} does not exist",
+            dbfile.display()
+        );
+
+        let mut config = self.config.write().await;
+        let id = config.next_id;
+        let account_dir = id.to_string();
+        let new_dbfile = self.dir.join(&account_dir).join(DB_NAME);
+        fs::create_dir_all(self.dir.join(&account_dir)).await?;
+
+        // Move the database together with its WAL files and blobs.
+        let mut moved = Vec::new();
+        for suffix in &["", "-wal", "-shm", "-blobs"] {
+            let from = with_suffix(&dbfile, suffix);
+            let to = with_suffix(&new_dbfile, suffix);
+            if !from.exists().await {
+                continue;
+            }
+            if let Err(err) = fs::rename(&from, &to).await {
+                undo_migration(moved, &self.dir.join(&account_dir)).await;
+                return Err(format_err!(
+                    "failed to move {} to {}: {}",
+                    from.display(),
+                    to.display(),
+                    err
+                ));
+            }
+            moved.push((from, to));
+        }
+        let context = match Context::new(self.os_name.clone(), new_dbfile).await {
+            Ok(context) => context,
+            Err(err) => {
+                undo_migration(moved, &self.dir.join(&account_dir)).await;
+                return Err(err);
+            }
+        };
+
+        let mut new_config = config.clone();
+        new_config.next_id += 1;
+        new_config.selected_account = id;
+        new_config.accounts.push(AccountConfig {
+            id,
+            dir: account_dir.clone(),
+        });
+        if let Err(err) = new_config.save(&self.dir).await {
+            context.sql.close().await;
+            drop(context);
+            undo_migration(moved, &self.dir.join(&account_dir)).await;
+            return Err(err);
+        }
+        *config = new_config;
+
+        let account = Account::new(id, context, self.sender.clone());
+        self.accounts.write().await.insert(id, account);
+        Ok(id)
+    }
+
+    /// Removes an account and deletes its database and blobs.
+    ///
+    /// If the removed account was selected,
+    /// the first remaining account is selected.
+    /// The account is removed from `accounts.json` first,
+    /// so it is never listed there without its directory.
+    pub async fn remove_account(&self, id: u32) -> Result<()> {
+        let mut config = self.config.write().await;
+        let pos = config
+            .accounts
+            .iter()
+            .position(|a| a.id == id)
+            .ok_or_else(|| format_err!("invalid account id: {}", id))?;
+
+        let mut new_config = config.clone();
+        let account_dir = new_config.accounts.remove(pos).dir;
+        if new_config.selected_account == id {
+            new_config.selected_account = new_config
+                .accounts
+                .first()
+                .map(|a| a.id)
+                .unwrap_or_default();
+        }
+        new_config.save(&self.dir).await?;
+        *config = new_config;
+
+        if let Some(account) = self.accounts.write().await.remove(&id) {
+            account.context.stop_io().await;
+            account.events_task.cancel().await;
+            account.context.sql.close().await;
+        }
+        fs::remove_dir_all(self.dir.join(account_dir)).await?;
+        Ok(())
+    }
+
+    /// Starts IO for all accounts.
+    pub async fn start_io(&self) {
+        for account in self.accounts.read().await.values() {
+            account.context.start_io().await;
+        }
+    }
+
+    /// Stops IO for all accounts.
+    pub async fn stop_io(&self) {
+        for account in self.accounts.read().await.values() {
+            account.context.stop_io().await;
+        }
+    }
+
+    /// Returns an emitter for the events of all accounts.
+    ///
+    /// The events of the accounts are forwarded to this emitter,
+    /// so the emitters of the single contexts must not be used.
+    pub fn get_event_emitter(&self) -> AccountsEventEmitter {
+        AccountsEventEmitter(self.receiver.clone())
+    }
+}
+
+/// An opened account.
+#[derive(Debug)]
+struct Account {
+    context: Context,
+
+    /// Task forwarding the events of the context to the [Accounts].
+    events_task: task::JoinHandle<()>,
+}
+
+impl Account {
+    fn new(id: u32, context: Context, sender: Sender<AccountEvent>) -> Self {
+        let emitter = context.get_event_emitter();
+        let events_task = task::spawn(async move {
+            while let Some(event) = emitter.recv().await {
+                sender.send(AccountEvent { id, event }).await;
+            }
+        });
+        Self {
+            context,
+            events_task,
+        }
+    }
+}
+
+/// An event emitted by one of the accounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountEvent {
+    /// ID of the account which emitted the event.
+    pub id: u32,
+    pub event: Event,
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountsEventEmitter(Receiver<AccountEvent>);
+
+impl AccountsEventEmitter {
+    /// Blocking recv of an event. Return `None` if the `Sender` has been droped.
+    pub fn recv_sync(&self) -> Option<AccountEvent> {
+        task::block_on(self.recv())
+    }
+
+    /// Blocking async recv of an event. Return `None` if the `Sender` has been droped.
+    pub async fn recv(&self) -> Option<AccountEvent> {
+        self.0.recv().await.ok()
+    }
+}
+
+/// Contents of `accounts.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct AccountsConfig {
+    /// ID of the selected account, 0 if there are no accounts.
+    selected_account: u32,
+
+    /// ID the next added account will get.
+    next_id: u32,
+
+    accounts: Vec<AccountConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct AccountConfig {
+    id: u32,
+
+    /// Directory of the account, relative to the accounts directory.
+    dir: String,
+}
+
+impl Default for AccountsConfig {
+    fn default() -> Self {
+        Self {
+            selected_account: 0,
+            next_id: 1,
+            accounts: Vec::new(),
+        }
+    }
+}
+
+impl AccountsConfig {
+    /// Loads the configuration from `dir`, creating it if it does not exist.
+    async fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(CONFIG_NAME);
+        if !path.exists().await {
+            let config = Self::default();
+            config.save(dir).await?;
+            return Ok(config);
+        }
+        let bytes = fs::read(&path).await?;
+        let config = serde_json::from_slice(&bytes)
+            .map_err(|err| format_err!("failed to parse {}: {}", path.display(), err))?;
+        Ok(config)
+    }
+
+    /// Saves the configuration to `dir`.
+    ///
+    /// The file is written to a temporary file first and then renamed,
+    /// so it is never left half-written.
+    async fn save(&self, dir: &Path) -> Result<()> {
+        let path = dir.join(CONFIG_NAME);
+        let tmp_path = with_suffix(&path, ".tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(self)?).await?;
+        fs::rename(&tmp_path, &path).await?;
+        Ok(())
+    }
+}
+
+/// Moves the files of a failed migration back
+/// and removes the directory of the new account.
+async fn undo_migration(moved: Vec<(PathBuf, PathBuf)>, account_dir: &Path) {
+    for (from, to) in moved.into_iter().rev() {
+        fs::rename(&to, &from).await.ok();
+    }
+    fs::remove_dir_all(account_dir).await.ok();
+}
+
+/// Appends `suffix` to the file name of `path`.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::config::Config;
+
+    #[async_std::test]
+    async fn test_add_remove_account() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir: PathBuf = dir.path().join("accounts").into();
+
+        let accounts = Accounts::new("my_os".into(), dir.clone()).await.unwrap();
+        assert!(accounts.get_all().await.is_empty());
+        assert!(accounts.get_selected_account().await.is_none());
+
+        let id1 = accounts.add_account().await.unwrap();
+        let id2 = accounts.add_account().await.unwrap();
+        assert_ne!(id1, id2);
+        assert_eq!(accounts.get_all().await, vec![id1, id2]);
+
+        // The added account is selected.
+        let ctx = accounts.get_selected_account().await.unwrap();
+        assert_eq!(
+            ctx.get_dbfile(),
+            accounts.get_account(id2).await.unwrap().get_dbfile()
+        );
+
+        accounts.select_account(id1).await.unwrap();
+        assert_eq!(accounts.get_selected_account_id().await, id1);
+        let ctx = accounts.get_selected_account().await.unwrap();
+        ctx.set_config(Config::Addr, Some("alice@example.org"))
+            .await
+            .unwrap();
+        assert!(accounts.select_account(1234).await.is_err());
+        drop(ctx);
+        drop(accounts);
+
+        // The accounts are persisted.
+        let accounts = Accounts::new("my_os".into(), dir.clone()).await.unwrap();
+        assert_eq!(accounts.get_all().await, vec![id1, id2]);
+        let ctx = accounts.get_selected_account().await.unwrap();
+        assert_eq!(
+            ctx.get_config(Config::Addr).await,
+            Some("alice@example.org".to_string())
+        );
+        drop(ctx);
+
+        accounts.remove_account(id1).await.unwrap();
+        assert_eq!(accounts.get_all().await, vec![id2]);
+        assert!(!dir.join(id1.to_string()).exists().await);
+        let ctx = accounts.get_selected_account().await.unwrap();
+        assert_eq!(
+            ctx.get_dbfile(),
+            accounts.get_account(id2).await.unwrap().get_dbfile()
+        );
+        assert!(accounts.remove_account(id1).await.is_err());
+
+        // IDs of removed accounts are not reused.
+        let id3 = accounts.add_account().await.unwrap();
+        assert!(id3 > id2);
+    }
+
+    #[async_std::test]
+    async fn test_migrate_account() {
+        let dir = tempfile::tempdir().unwrap();
+        let dbfile: PathBuf = dir.path().join("old.db").into();
+        let ctx = Context::new("my_os".into(), dbfile.clone()).await.unwrap();
+        ctx.set_config(Config::Addr, Some("alice@example.org"))
+            .await
+            .unwrap();
+        ctx.sql.close().await;
+        drop(ctx);
+
+        let accounts_dir: PathBuf = dir.path().join("accounts").into();
+        let accounts = Accounts::new("my_os".into(), accounts_dir).await.unwrap();
+        let id = accounts.migrate_account(dbfile.clone()).await.unwrap();
+        assert!(!dbfile.exists().await);
+        assert!(!with_suffix(&dbfile, "-blobs").exists().await);
+
+        let ctx = accounts.get_selected_account().await.unwrap();
+        assert_eq!(
+            ctx.get_dbfile(),
+            accounts.get_account(id).await.unwrap().get_dbfile()
+        );
+        assert_eq!(
+            ctx.get_config(Config::Addr).await,
+            Some("alice@example.org".to_string())
+        );
+
+        assert!(accounts.migrate_account(dbfile).await.is_err());
+        assert_eq!(accounts.get_all().await, vec![id]);
+    }
+
+    #[async_std::test]
+    async fn test_migrate_account_rollback() {
+        let dir = tempfile::tempdir().unwrap();
+        let dbfile: PathBuf = dir.path().join("broken.db").into();
+        fs::write(&dbfile, b"not a database").await.unwrap();
+
+        let accounts_dir: PathBuf = dir.path().join("accounts").into();
+        let accounts = Accounts::new("my_os".into(), accounts_dir.clone())
+            .await
+            .unwrap();
+        assert!(accounts.migrate_account(dbfile.clone()).await.is_err());
+
+        // The database is moved back if it cannot be opened.
+        assert_eq!(fs::read(&dbfile).await.unwrap(), b"not a database");
+        assert!(!accounts_dir.join("1").exists().await);
+        assert!(accounts.get_all().await.is_empty());
+
+        // The failed migration does not use up an ID.
+        assert_eq!(accounts.add_account().await.unwrap(), 1);
+    }
+
+    #[async_std::test]
+    async fn test_account_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let accounts = Accounts::new("my_os".into(), dir.path().to_path_buf().into())
+            .await
+            .unwrap();
+        let id = accounts.add_account().await.unwrap();
+        let emitter = accounts.get_event_emitter();
+
+        let ctx = accounts.get_account(id).await.unwrap();
+        ctx.emit_event(Event::Info("hello".to_string()));
+        loop {
+            let event = emitter.recv().await.unwrap();
+            if event.event == Event::Info("hello".to_string()) {
+                assert_eq!(event.id, id);
+                break;
+            }
+        }
+    }
+}
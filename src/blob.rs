@@ -10,7 +10,10 @@ use async_std::{fs, io};
 use image::GenericImageView;
 use thiserror::Error;
 
-use crate::constants::AVATAR_SIZE;
+use crate::constants::{
+    MediaQuality, AVATAR_SIZE, BALANCED_IMAGE_QUALITY, BALANCED_IMAGE_SIZE, THUMBNAIL_MAX_PIXELS,
+    THUMBNAIL_QUALITY, THUMBNAIL_SIZE, WORSE_IMAGE_QUALITY, WORSE_IMAGE_SIZE,
+};
 use crate::context::Context;
 use crate::events::Event;
 
@@ -377,6 +380,219 @@ impl<'a> BlobObject<'a> {
 
         Ok(())
     }
+
+    /// Recodes an image to be sent with the given media quality.
+    ///
+    /// The format is detected from the content, not from the file
+    /// name.  Images are only re-encoded if they exceed the maximum
+    /// size or have to be rotated according to their EXIF orientation.
+    /// EXIF and XMP data, including GPS locations, is always removed,
+    /// from JPEG images without re-encoding them.  GIF images are sent
+    /// as they are so that animations are kept.
+    ///
+    /// The result is written to a new blob, the original blob is not
+    /// modified.  Returns the blob to send, which is this blob if
+    /// nothing had to be changed, and the width and height of the image.
+    pub async fn recode_to_image_size(
+        &self,
+        context: &'a Context,
+        media_quality: MediaQuality,
+    ) -> Result<(BlobObject<'a>, u32, u32), BlobError> {
+        let blob_abs = self.to_abs_path();
+        let (max_size, quality) = match media_quality {
+            MediaQuality::Balanced => (BALANCED_IMAGE_SIZE, BALANCED_IMAGE_QUALITY),
+            MediaQuality::Worse => (WORSE_IMAGE_SIZE, WORSE_IMAGE_QUALITY),
+        };
+        let recode_failure = |err| BlobError::RecodeFailure {
+            blobdir: context.get_blobdir().to_path_buf(),
+            blobname: blob_abs.to_str().unwrap_or_default().to_string(),
+            cause: err,
+        };
+
+        let data = fs::read(&blob_abs)
+            .await
+            .map_err(|err| recode_failure(image::ImageError::IoError(err)))?;
+        let (recoded, width, height) =
+            async_std::task::spawn_blocking(move || recode_image(&data, max_size, quality))
+                .await
+                .map_err(recode_failure)?;
+
+        match recoded {
+            Some((data, suffix)) => {
+                let stem = std::path::Path::new(self.as_file_name())
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .unwrap_or("image");
+                let blob =
+                    BlobObject::create(context, format!("{}.{}", stem, suffix), &data).await?;
+                Ok((blob, width, height))
+            }
+            None => Ok((self.clone(), width, height)),
+        }
+    }
+
+    /// Creates a small preview of an image, GIF or sticker.
+    ///
+    /// The preview is a JPEG stored as new blob named after the image,
+    /// usually `<name>-preview.jpg`, transparent areas are filled with white.
+    /// For animations, the first frame is used.
+    /// Images with more than [THUMBNAIL_MAX_PIXELS] pixels are not decoded
+    /// and no preview is created for them.
+    pub async fn create_thumbnail(
+        &self,
+        context: &'a Context,
+    ) -> Result<BlobObject<'a>, BlobError> {
+        let blob_abs = self.to_abs_path();
+
+        // decoding and scaling large images takes a while, do not block the executor
+        let path = blob_abs.clone();
+        let encoded = async_std::task::spawn_blocking(move || create_thumbnail_data(&path))
+            .await
+            .map_err(|err| BlobError::RecodeFailure {
+                blobdir: context.get_blobdir().to_path_buf(),
+                blobname: blob_abs.to_str().unwrap_or_default().to_string(),
+                cause: err,
+            })?;
+        BlobObject::create(
+            context,
+            format!("{}-preview.jpg", self.as_file_name()),
+            &encoded,
+        )
+        .await
+    }
+}
+
+/// Decodes an image and returns its preview encoded as JPEG.
+///
+/// The dimensions are read from the image header first,
+/// so that huge images are rejected before allocating memory for them.
+fn create_thumbnail_data(path: &Path) -> Result<Vec<u8>, image::ImageError> {
+    let (width, height) = image::image_dimensions(path)?;
+    if u64::from(width) * u64::from(height) > THUMBNAIL_MAX_PIXELS {
+        return Err(image::ImageError::DimensionError);
+    }
+    let img = image::open(path)?;
+    let img = img.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgba();
+    let mut background =
+        image::RgbaImage::from_pixel(img.width(), img.height(), image::Rgba([255, 255, 255, 255]));
+    image::imageops::overlay(&mut background, &img, 0, 0);
+    let img = image::DynamicImage::ImageRgba8(background).to_rgb();
+
+    let mut encoded = Vec::new();
+    image::DynamicImage::ImageRgb8(img).write_to(
+        &mut encoded,
+        image::ImageOutputFormat::JPEG(THUMBNAIL_QUALITY),
+    )?;
+    Ok(encoded)
+}
+
+/// Recodes image data to the given maximum size and JPEG quality.
+///
+/// Returns the recoded data with the matching file suffix, or `None`
+/// if the image can be sent as it is, and the width and height of the
+/// resulting image.
+#[allow(clippy::type_complexity)]
+fn recode_image(
+    data: &[u8],
+    max_size: u32,
+    quality: u8,
+) -> Result<(Option<(Vec<u8>, &'static str)>, u32, u32), image::ImageError> {
+    let format = image::guess_format(data)?;
+    let mut img = image::load_from_memory_with_format(data, format)?;
+    let exif = exif::Reader::new()
+        .read_from_container(&mut std::io::Cursor::new(data))
+        .ok();
+    let orientation = exif.as_ref().and_then(|exif| {
+        exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?
+            .value
+            .get_uint(0)
+    });
+    let exceeds_max_size = img.width() > max_size || img.height() > max_size;
+    let needs_rotation = match orientation {
+        Some(orientation) => (2..=8).contains(&orientation),
+        None => false,
+    };
+
+    if format == image::ImageFormat::GIF {
+        return Ok((None, img.width(), img.height()));
+    }
+    if !exceeds_max_size && !needs_rotation {
+        if format == image::ImageFormat::JPEG {
+            if let Some(stripped) = strip_jpeg_metadata(data) {
+                if stripped.len() == data.len() {
+                    return Ok((None, img.width(), img.height()));
+                }
+                return Ok((Some((stripped, "jpg")), img.width(), img.height()));
+            }
+        } else if exif.is_none() {
+            return Ok((None, img.width(), img.height()));
+        }
+    }
+
+    img = match orientation {
+        Some(2) => img.fliph(),
+        Some(3) => img.rotate180(),
+        Some(4) => img.flipv(),
+        Some(5) => img.rotate90().fliph(),
+        Some(6) => img.rotate90(),
+        Some(7) => img.rotate270().fliph(),
+        Some(8) => img.rotate270(),
+        _ => img,
+    };
+    if exceeds_max_size {
+        img = img.resize(max_size, max_size, image::imageops::FilterType::Triangle);
+    }
+
+    // the encoders do not write any EXIF data
+    let mut encoded = Vec::new();
+    let suffix = if format == image::ImageFormat::PNG {
+        img.write_to(&mut encoded, image::ImageOutputFormat::PNG)?;
+        "png"
+    } else {
+        image::DynamicImage::ImageRgb8(img.to_rgb())
+            .write_to(&mut encoded, image::ImageOutputFormat::JPEG(quality))?;
+        "jpg"
+    };
+    Ok((Some((encoded, suffix)), img.width(), img.height()))
+}
+
+/// Removes the APP1 segments, which hold EXIF and XMP data, from a JPEG.
+///
+/// Returns `None` if the data is not a well-formed JPEG.
+fn strip_jpeg_metadata(data: &[u8]) -> Option<Vec<u8>> {
+    if !data.starts_with(&[0xff, 0xd8]) {
+        return None;
+    }
+    let mut stripped = data[..2].to_vec();
+    let mut pos = 2;
+    loop {
+        if *data.get(pos)? != 0xff {
+            return None;
+        }
+        match *data.get(pos + 1)? {
+            // fill byte
+            0xff => pos += 1,
+            // start of scan or end of image, the rest is image data
+            0xda | 0xd9 => {
+                stripped.extend_from_slice(&data[pos..]);
+                return Some(stripped);
+            }
+            // markers without a length
+            0x01 | 0xd0..=0xd7 => {
+                stripped.extend_from_slice(&data[pos..pos + 2]);
+                pos += 2;
+            }
+            marker => {
+                let len = u16::from_be_bytes([*data.get(pos + 2)?, *data.get(pos + 3)?]);
+                let end = pos + 2 + len as usize;
+                let segment = data.get(pos..end)?;
+                if marker != 0xe1 {
+                    stripped.extend_from_slice(segment);
+                }
+                pos = end;
+            }
+        }
+    }
 }
 
 impl<'a> fmt::Display for BlobObject<'a> {
@@ -570,6 +786,170 @@ mod tests {
         let data = fs::read(blob.to_abs_path()).await.unwrap();
         assert_eq!(data, b"boo");
     }
+    /// Returns a JPEG of the given size with an EXIF segment holding
+    /// the orientation and a GPS version.
+    fn jpeg_with_exif(width: u32, height: u32, orientation: u8) -> Vec<u8> {
+        let mut jpeg = Vec::new();
+        image::DynamicImage::new_rgb8(width, height)
+            .write_to(&mut jpeg, image::ImageOutputFormat::JPEG(90))
+            .unwrap();
+
+        let mut tiff = b"MM\0\x2a\0\0\0\x08".to_vec();
+        // IFD0 with the orientation and a pointer to the GPS IFD at offset 38
+        tiff.extend_from_slice(b"\0\x02");
+        tiff.extend_from_slice(b"\x01\x12\0\x03\0\0\0\x01\0");
+        tiff.extend_from_slice(&[orientation, 0, 0]);
+        tiff.extend_from_slice(b"\x88\x25\0\x04\0\0\0\x01\0\0\0\x26");
+        tiff.extend_from_slice(b"\0\0\0\0");
+        // GPS IFD with the GPS version
+        tiff.extend_from_slice(b"\0\x01");
+        tiff.extend_from_slice(b"\0\0\0\x01\0\0\0\x04\x02\x02\0\0");
+        tiff.extend_from_slice(b"\0\0\0\0");
+
+        let mut segment = vec![0xff, 0xe1];
+        segment.extend_from_slice(&(2 + 6 + tiff.len() as u16).to_be_bytes());
+        segment.extend_from_slice(b"Exif\0\0");
+        segment.extend_from_slice(&tiff);
+        let mut data = jpeg[..2].to_vec();
+        data.extend_from_slice(&segment);
+        data.extend_from_slice(&jpeg[2..]);
+        data
+    }
+
+    fn has_exif(data: &[u8]) -> bool {
+        exif::Reader::new()
+            .read_from_container(&mut std::io::Cursor::new(data))
+            .is_ok()
+    }
+
+    #[async_std::test]
+    async fn test_recode_to_image_size() {
+        let t = dummy_context().await;
+        let jpeg = include_bytes!("../test-data/image/avatar1000x1000.jpg");
+        let blob = BlobObject::create(&t.ctx, "image.jpg", jpeg).await.unwrap();
+
+        // small enough and no EXIF data, sent as it is
+        let (recoded, width, height) = blob
+            .recode_to_image_size(&t.ctx, MediaQuality::Balanced)
+            .await
+            .unwrap();
+        assert_eq!((width, height), (1000, 1000));
+        assert_eq!(recoded, blob);
+
+        let (recoded, width, height) = blob
+            .recode_to_image_size(&t.ctx, MediaQuality::Worse)
+            .await
+            .unwrap();
+        assert_eq!((width, height), (640, 640));
+        assert_ne!(recoded, blob);
+        assert_eq!(recoded.suffix(), Some("jpg"));
+        let img = image::open(recoded.to_abs_path()).unwrap();
+        assert_eq!(img.dimensions(), (640, 640));
+        assert_eq!(fs::read(blob.to_abs_path()).await.unwrap(), &jpeg[..]);
+
+        // other formats are only recoded if they are too large
+        let png = include_bytes!("../test-data/image/avatar900x900.png");
+        let blob = BlobObject::create(&t.ctx, "image.png", png).await.unwrap();
+        let (recoded, width, height) = blob
+            .recode_to_image_size(&t.ctx, MediaQuality::Balanced)
+            .await
+            .unwrap();
+        assert_eq!((width, height), (900, 900));
+        assert_eq!(recoded, blob);
+        assert_eq!(fs::read(blob.to_abs_path()).await.unwrap(), &png[..]);
+    }
+
+    #[async_std::test]
+    async fn test_recode_image_orientation() {
+        let t = dummy_context().await;
+        // orientation 6 needs a rotation by 90 degrees
+        let jpeg = jpeg_with_exif(200, 100, 6);
+        assert!(has_exif(&jpeg));
+        let blob = BlobObject::create(&t.ctx, "image.jpg", &jpeg)
+            .await
+            .unwrap();
+
+        let (recoded, width, height) = blob
+            .recode_to_image_size(&t.ctx, MediaQuality::Balanced)
+            .await
+            .unwrap();
+        assert_eq!((width, height), (100, 200));
+        let data = fs::read(recoded.to_abs_path()).await.unwrap();
+        assert!(!has_exif(&data));
+        let img = image::load_from_memory(&data).unwrap();
+        assert_eq!(img.dimensions(), (100, 200));
+    }
+
+    #[async_std::test]
+    async fn test_recode_image_strip_exif() {
+        let t = dummy_context().await;
+        let jpeg = jpeg_with_exif(200, 100, 1);
+        // the content is detected, not the file name
+        let blob = BlobObject::create(&t.ctx, "image.png", &jpeg)
+            .await
+            .unwrap();
+
+        let (recoded, width, height) = blob
+            .recode_to_image_size(&t.ctx, MediaQuality::Balanced)
+            .await
+            .unwrap();
+        assert_eq!((width, height), (200, 100));
+        assert_eq!(recoded.suffix(), Some("jpg"));
+        let data = fs::read(recoded.to_abs_path()).await.unwrap();
+        assert!(!has_exif(&data));
+
+        // the image data is not re-encoded
+        let mut plain = Vec::new();
+        image::DynamicImage::new_rgb8(200, 100)
+            .write_to(&mut plain, image::ImageOutputFormat::JPEG(90))
+            .unwrap();
+        assert_eq!(data, plain);
+        assert_eq!(fs::read(blob.to_abs_path()).await.unwrap(), jpeg);
+    }
+
+    #[async_std::test]
+    async fn test_create_thumbnail() {
+        let t = dummy_context().await;
+        let blob = BlobObject::create(
+            &t.ctx,
+            "sticker.png",
+            include_bytes!("../test-data/image/avatar900x900.png"),
+        )
+        .await
+        .unwrap();
+
+        let thumbnail = blob.create_thumbnail(&t.ctx).await.unwrap();
+        assert_eq!(
+            thumbnail.as_name(),
+            format!("{}-preview.jpg", blob.as_name())
+        );
+        let img = image::open(thumbnail.to_abs_path()).unwrap();
+        assert_eq!(img.dimensions(), (THUMBNAIL_SIZE, THUMBNAIL_SIZE));
+
+        // an existing preview is not overwritten
+        let jpeg = fs::read(thumbnail.to_abs_path()).await.unwrap();
+        let second = blob.create_thumbnail(&t.ctx).await.unwrap();
+        assert_ne!(second, thumbnail);
+        assert_eq!(fs::read(thumbnail.to_abs_path()).await.unwrap(), jpeg);
+    }
+
+    #[async_std::test]
+    async fn test_create_thumbnail_too_large() {
+        let t = dummy_context().await;
+        // only the header of an image with 60000x60000 pixels
+        let blob = BlobObject::create(&t.ctx, "huge.ppm", b"P6\n60000 60000\n255\n")
+            .await
+            .unwrap();
+        assert!(blob.create_thumbnail(&t.ctx).await.is_err());
+        assert!(
+            !t.ctx
+                .get_blobdir()
+                .join("huge.ppm-preview.jpg")
+                .exists()
+                .await
+        );
+    }
+
     #[async_std::test]
     async fn test_create_from_name_long() {
         let t = dummy_context().await;
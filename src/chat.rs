@@ -15,6 +15,7 @@ use crate::constants::*;
 use crate::contact::*;
 use crate::context::Context;
 use crate::dc_tools::*;
+use crate::ephemeral::{delete_expired_messages, schedule_ephemeral_task, Timer as EphemeralTimer};
 use crate::error::{bail, ensure, format_err, Error};
 use crate::events::Event;
 use crate::job::{self, Action};
@@ -213,6 +214,35 @@ impl ChatId {
             )
             .await?;
 
+        context
+            .sql
+            .execute(
+                "DELETE FROM reactions WHERE msg_id IN (SELECT id FROM msgs WHERE chat_id=?);",
+                paramsv![self],
+            )
+            .await?;
+
+        context
+            .sql
+            .execute(
+                "DELETE FROM msgs_delivered WHERE msg_id IN (SELECT id FROM msgs WHERE chat_id=?);",
+                paramsv![self],
+            )
+            .await?;
+
+        context
+            .sql
+            .execute("DELETE FROM pinned_msgs WHERE chat_id=?;", paramsv![self])
+            .await?;
+
+        context
+            .sql
+            .execute(
+                "DELETE FROM group_member_changes WHERE chat_id=?;",
+                paramsv![self],
+            )
+            .await?;
+
         context
             .sql
             .execute("DELETE FROM msgs WHERE chat_id=?;", paramsv![self])
@@ -333,8 +363,8 @@ impl ChatId {
         context
             .sql
             .execute(
-                "INSERT INTO msgs (chat_id, from_id, timestamp, type, state, txt, param, hidden)
-         VALUES (?,?,?, ?,?,?,?,?);",
+                "INSERT INTO msgs (chat_id, from_id, timestamp, type, state, txt, quoted_text, param, hidden, mime_in_reply_to)
+         VALUES (?,?,?, ?,?,?,?,?,?,?);",
                 paramsv![
                     self,
                     DC_CONTACT_ID_SELF,
@@ -342,8 +372,10 @@ impl ChatId {
                     msg.viewtype,
                     MessageState::OutDraft,
                     msg.text.as_deref().unwrap_or(""),
+                    msg.quoted_text.as_deref().unwrap_or(""),
                     msg.param.to_string(),
                     1,
+                    msg.in_reply_to.as_deref().unwrap_or_default(),
                 ],
             )
             .await?;
@@ -399,6 +431,160 @@ impl ChatId {
         Ok(self.get_param(context).await?.exists(Param::Devicetalk))
     }
 
+    /// Pins a message in a group for all group members.
+    ///
+    /// At most [DC_MAX_PINNED_MSGS] messages can be pinned in a chat.
+    /// An info message is added to the chat and a hidden message is sent
+    /// to the other members, so the message is pinned for them as well.
+    pub async fn pin_msg(self, context: &Context, msg_id: MsgId) -> Result<(), Error> {
+        self.send_pin_request(context, msg_id, true).await
+    }
+
+    /// Unpins a message in a group for all group members, see [ChatId::pin_msg].
+    pub async fn unpin_msg(self, context: &Context, msg_id: MsgId) -> Result<(), Error> {
+        self.send_pin_request(context, msg_id, false).await
+    }
+
+    /// Returns the pinned messages of the chat, the most recently pinned first.
+    pub async fn get_pinned_msgs(self, context: &Context) -> Result<Vec<MsgId>, Error> {
+        context
+            .sql
+            .query_map(
+                "SELECT p.msg_id FROM pinned_msgs p \
+                 INNER JOIN msgs m ON m.id=p.msg_id AND m.chat_id=p.chat_id \
+                 WHERE p.chat_id=? \
+                 ORDER BY p.timestamp DESC, p.rowid DESC;",
+                paramsv![self],
+                |row| row.get::<_, MsgId>(0),
+                |ids| {
+                    ids.collect::<std::result::Result<Vec<_>, _>>()
+                        .map_err(Into::into)
+                },
+            )
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Returns the messages scheduled with `send_msg_at()` for this chat,
+    /// the next due message first.
+    pub async fn get_scheduled_msgs(self, context: &Context) -> Result<Vec<MsgId>, Error> {
+        context
+            .sql
+            .query_map(
+                "SELECT id FROM msgs WHERE chat_id=? AND state=? ORDER BY timestamp, id;",
+                paramsv![self, MessageState::OutScheduled],
+                |row| row.get::<_, MsgId>(0),
+                |ids| {
+                    ids.collect::<std::result::Result<Vec<_>, _>>()
+                        .map_err(Into::into)
+                },
+            )
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn send_pin_request(
+        self,
+        context: &Context,
+        msg_id: MsgId,
+        pinned: bool,
+    ) -> Result<(), Error> {
+        let chat = Chat::load_from_db(context, self).await?;
+        ensure!(
+            chat.typ == Chattype::Group || chat.typ == Chattype::VerifiedGroup,
+            "Messages can only be pinned in groups"
+        );
+        ensure!(
+            is_contact_in_chat(context, self, DC_CONTACT_ID_SELF).await,
+            "Cannot pin messages in group {} as we are not a member",
+            self
+        );
+        let msg = Message::load_from_db(context, msg_id).await?;
+        ensure!(
+            msg.chat_id == self,
+            "Message {} does not belong to chat {}",
+            msg_id,
+            self
+        );
+        ensure!(!msg.is_info(), "Cannot pin info messages");
+        ensure!(!msg.rfc724_mid.is_empty(), "Cannot pin unsent message");
+        if pinned {
+            ensure!(
+                self.get_pinned_msgs(context).await?.len() < DC_MAX_PINNED_MSGS,
+                "At most {} messages can be pinned",
+                DC_MAX_PINNED_MSGS
+            );
+        }
+
+        if !self
+            .set_msg_pinned(context, &msg, pinned, DC_CONTACT_ID_SELF)
+            .await?
+        {
+            return Ok(());
+        }
+
+        let mut request = Message::new(Viewtype::Text);
+        request.hidden = true;
+        request.param.set(
+            if pinned {
+                Param::PinRequestFor
+            } else {
+                Param::UnpinRequestFor
+            },
+            &msg.rfc724_mid,
+        );
+        send_msg(context, self, &mut request).await?;
+        Ok(())
+    }
+
+    /// Pins or unpins a message locally and adds an info message to the chat.
+    ///
+    /// Returns false if the message was already (un)pinned.
+    async fn set_msg_pinned(
+        self,
+        context: &Context,
+        msg: &Message,
+        pinned: bool,
+        from_id: u32,
+    ) -> Result<bool, Error> {
+        let changed = if pinned {
+            context
+                .sql
+                .execute(
+                    "INSERT OR IGNORE INTO pinned_msgs (chat_id, msg_id, timestamp) VALUES (?, ?, ?);",
+                    paramsv![self, msg.id, time()],
+                )
+                .await?
+        } else {
+            context
+                .sql
+                .execute(
+                    "DELETE FROM pinned_msgs WHERE chat_id=? AND msg_id=?;",
+                    paramsv![self, msg.id],
+                )
+                .await?
+        };
+        if changed == 0 {
+            return Ok(false);
+        }
+
+        let text = context
+            .stock_system_msg(
+                if pinned {
+                    StockMessage::MsgPinned
+                } else {
+                    StockMessage::MsgUnpinned
+                },
+                msg.get_summarytext(context, 32).await,
+                "",
+                from_id,
+            )
+            .await;
+        add_info_msg(context, self, text).await;
+        context.emit_event(Event::ChatModified(self));
+        Ok(true)
+    }
+
     async fn parent_query<T, F>(
         self,
         context: &Context,
@@ -442,7 +628,7 @@ impl ChatId {
         .flatten()
     }
 
-    async fn parent_is_encrypted(self, context: &Context) -> Result<bool, Error> {
+    pub(crate) async fn parent_is_encrypted(self, context: &Context) -> Result<bool, Error> {
         let collect = |row: &rusqlite::Row| Ok(row.get(0)?);
         let packed: Option<String> = self.parent_query(context, "param", collect).await?;
 
@@ -607,7 +793,9 @@ impl Chat {
 
     /// Returns true if user can send messages to this chat.
     pub fn can_send(&self) -> bool {
-        !self.id.is_special() && !self.is_device_talk()
+        !self.id.is_special()
+            && !self.is_device_talk()
+            && (self.typ != Chattype::Mailinglist || self.param.exists(Param::ListPost))
     }
 
     pub async fn update_param(&mut self, context: &Context) -> Result<(), Error> {
@@ -699,6 +887,7 @@ impl Chat {
                 .unwrap_or_else(std::path::PathBuf::new),
             draft,
             is_muted: self.is_muted(),
+            pinned_msgs: self.id.get_pinned_msgs(context).await?,
         })
     }
 
@@ -732,27 +921,97 @@ impl Chat {
         }
     }
 
+    /// Returns the In-Reply-To and References headers of a message
+    /// about to be sent to this chat.
+    async fn get_reply_headers(&self, context: &Context, msg: &Message) -> (String, String) {
+        // set "In-Reply-To:" to identify the message to which the composed message is a reply;
+        // set "References:" to identify the "thread" of the conversation;
+        // both according to RFC 5322 3.6.4, page 25
+        //
+        // as self-talks are mainly used to transfer data between devices,
+        // we do not set In-Reply-To/References in this case.
+        //
+        // if a message is quoted or reacted to, this message is used as the parent,
+        // so that the receiver can find it.
+        let mut new_in_reply_to = String::new();
+        let mut new_references = String::new();
+        let quoted_rfc724_mid = match &msg.in_reply_to {
+            Some(in_reply_to)
+                if msg.quoted_text.is_some() || msg.param.get_cmd() == SystemMessage::Reaction =>
+            {
+                Some(in_reply_to.clone())
+            }
+            _ => None,
+        };
+        if let Some(quoted_rfc724_mid) = quoted_rfc724_mid {
+            new_in_reply_to = quoted_rfc724_mid.clone();
+            new_references = match self.id.get_parent_mime_headers(context).await {
+                Some((_, _, parent_references)) if !parent_references.is_empty() => {
+                    // only use the oldest message of the thread and the quoted message
+                    let oldest = parent_references.split(' ').next().unwrap_or_default();
+                    if oldest != quoted_rfc724_mid {
+                        format!("{} {}", oldest, quoted_rfc724_mid)
+                    } else {
+                        quoted_rfc724_mid
+                    }
+                }
+                _ => quoted_rfc724_mid,
+            };
+        } else if !self.is_self_talk() {
+            if let Some((parent_rfc724_mid, parent_in_reply_to, parent_references)) =
+                self.id.get_parent_mime_headers(context).await
+            {
+                if !parent_rfc724_mid.is_empty() {
+                    new_in_reply_to = parent_rfc724_mid.clone();
+                }
+
+                // the whole list of messages referenced may be huge;
+                // only use the oldest and and the parent message
+                let parent_references = if let Some(n) = parent_references.find(' ') {
+                    &parent_references[0..n]
+                } else {
+                    &parent_references
+                };
+
+                if !parent_references.is_empty() && !parent_rfc724_mid.is_empty() {
+                    // angle brackets are added by the mimefactory later
+                    new_references = format!("{} {}", parent_references, parent_rfc724_mid);
+                } else if !parent_references.is_empty() {
+                    new_references = parent_references.to_string();
+                } else if !parent_in_reply_to.is_empty() && !parent_rfc724_mid.is_empty() {
+                    new_references = format!("{} {}", parent_in_reply_to, parent_rfc724_mid);
+                } else if !parent_in_reply_to.is_empty() {
+                    new_references = parent_in_reply_to;
+                }
+            }
+        }
+
+        (new_in_reply_to, new_references)
+    }
+
     async fn prepare_msg_raw(
         &mut self,
         context: &Context,
         msg: &mut Message,
         timestamp: i64,
     ) -> Result<MsgId, Error> {
-        let mut new_references = "".into();
-        let mut new_in_reply_to = "".into();
         let mut msg_id = 0;
         let mut to_id = 0;
         let mut location_id = 0;
 
         if !(self.typ == Chattype::Single
             || self.typ == Chattype::Group
-            || self.typ == Chattype::VerifiedGroup)
+            || self.typ == Chattype::VerifiedGroup
+            || self.typ == Chattype::Mailinglist
+            || self.typ == Chattype::Broadcast)
         {
             error!(context, "Cannot send to chat type #{}.", self.typ,);
             bail!("Cannot set to chat type #{}", self.typ);
         }
 
-        if (self.typ == Chattype::Group || self.typ == Chattype::VerifiedGroup)
+        if (self.typ == Chattype::Group
+            || self.typ == Chattype::VerifiedGroup
+            || self.typ == Chattype::Broadcast)
             && !is_contact_in_chat(context, self.id, DC_CONTACT_ID_SELF).await
         {
             emit_event!(
@@ -799,8 +1058,11 @@ impl Chat {
 
             /* check if we want to encrypt this message.  If yes and circumstances change
             so that E2EE is no longer available at a later point (reset, changed settings),
-            we might not send the message out at all */
-            if msg.param.get_int(Param::ForcePlaintext).unwrap_or_default() == 0 {
+            we might not send the message out at all;
+            mailing lists are never encrypted */
+            if self.typ != Chattype::Mailinglist
+                && msg.param.get_int(Param::ForcePlaintext).unwrap_or_default() == 0
+            {
                 let mut can_encrypt = true;
                 let mut all_mutual = context.get_config_bool(Config::E2eeEnabled).await;
 
@@ -859,40 +1121,7 @@ impl Chat {
             // reset encrypt error state eg. for forwarding
             msg.param.remove(Param::ErroneousE2ee);
 
-            // set "In-Reply-To:" to identify the message to which the composed message is a reply;
-            // set "References:" to identify the "thread" of the conversation;
-            // both according to RFC 5322 3.6.4, page 25
-            //
-            // as self-talks are mainly used to transfer data between devices,
-            // we do not set In-Reply-To/References in this case.
-            if !self.is_self_talk() {
-                if let Some((parent_rfc724_mid, parent_in_reply_to, parent_references)) =
-                    self.id.get_parent_mime_headers(context).await
-                {
-                    if !parent_rfc724_mid.is_empty() {
-                        new_in_reply_to = parent_rfc724_mid.clone();
-                    }
-
-                    // the whole list of messages referenced may be huge;
-                    // only use the oldest and and the parent message
-                    let parent_references = if let Some(n) = parent_references.find(' ') {
-                        &parent_references[0..n]
-                    } else {
-                        &parent_references
-                    };
-
-                    if !parent_references.is_empty() && !parent_rfc724_mid.is_empty() {
-                        // angle brackets are added by the mimefactory later
-                        new_references = format!("{} {}", parent_references, parent_rfc724_mid);
-                    } else if !parent_references.is_empty() {
-                        new_references = parent_references.to_string();
-                    } else if !parent_in_reply_to.is_empty() && !parent_rfc724_mid.is_empty() {
-                        new_references = format!("{} {}", parent_in_reply_to, parent_rfc724_mid);
-                    } else if !parent_in_reply_to.is_empty() {
-                        new_references = parent_in_reply_to;
-                    }
-                }
-            }
+            let (new_in_reply_to, new_references) = self.get_reply_headers(context, msg).await;
 
             // add independent location to database
 
@@ -927,10 +1156,18 @@ impl Chat {
                     .await?;
             }
 
+            let ephemeral_timer = self.id.get_ephemeral_timer(context).await?;
+            let ephemeral_timestamp = match ephemeral_timer {
+                EphemeralTimer::Disabled => 0,
+                EphemeralTimer::Enabled { duration } => timestamp + i64::from(duration),
+            };
+            msg.ephemeral_timer = ephemeral_timer;
+            msg.ephemeral_timestamp = ephemeral_timestamp;
+
             // add message to the database
 
             if context.sql.execute(
-                        "INSERT INTO msgs (rfc724_mid, chat_id, from_id, to_id, timestamp, type, state, txt, param, hidden, mime_in_reply_to, mime_references, location_id) VALUES (?,?,?,?,?, ?,?,?,?,?, ?,?,?);",
+                        "INSERT INTO msgs (rfc724_mid, chat_id, from_id, to_id, timestamp, type, state, txt, quoted_text, param, hidden, mime_in_reply_to, mime_references, location_id, ephemeral_timer, ephemeral_timestamp) VALUES (?,?,?,?,?, ?,?,?,?,?, ?,?,?,?,?, ?);",
                         paramsv![
                             new_rfc724_mid,
                             self.id,
@@ -940,11 +1177,14 @@ impl Chat {
                             msg.viewtype,
                             msg.state,
                             msg.text.as_ref().cloned().unwrap_or_default(),
+                            msg.quoted_text.as_deref().unwrap_or_default(),
                             msg.param.to_string(),
                             msg.hidden,
                             new_in_reply_to,
                             new_references,
                             location_id as i32,
+                            ephemeral_timer,
+                            ephemeral_timestamp,
                         ]
                     ).await.is_ok() {
                         msg_id = context.sql.get_rowid(
@@ -1059,6 +1299,9 @@ pub struct ChatInfo {
     ///
     /// The exact time its muted can be found out via the `chat.mute_duration` property
     pub is_muted: bool,
+
+    /// The pinned messages, the most recently pinned first.
+    pub pinned_msgs: Vec<MsgId>,
     // ToDo:
     // - [ ] deaddrop,
     // - [ ] summary,
@@ -1327,6 +1570,7 @@ pub(crate) fn msgtype_has_file(msgtype: Viewtype) -> bool {
         Viewtype::Voice => true,
         Viewtype::Video => true,
         Viewtype::File => true,
+        Viewtype::Vcard => true,
     }
 }
 
@@ -1334,7 +1578,7 @@ async fn prepare_msg_blob(context: &Context, msg: &mut Message) -> Result<(), Er
     if msg.viewtype == Viewtype::Text {
         // the caller should check if the message text is empty
     } else if msgtype_has_file(msg.viewtype) {
-        let blob = msg
+        let mut blob = msg
             .param
             .get_blob(Param::File, context, !msg.is_increation())
             .await?
@@ -1361,6 +1605,30 @@ async fn prepare_msg_blob(context: &Context, msg: &mut Message) -> Result<(), Er
                 msg.param.set(Param::MimeType, mime);
             }
         }
+
+        // files in creation may still be written to by the UI
+        if msg.viewtype == Viewtype::Image && !msg.is_increation() {
+            let media_quality =
+                MediaQuality::from_i32(context.get_config_int(Config::MediaQuality).await)
+                    .unwrap_or_default();
+            let recoded = blob.recode_to_image_size(context, media_quality).await;
+            match recoded {
+                Ok((recoded, width, height)) => {
+                    if recoded != blob {
+                        msg.param.set(Param::File, recoded.as_name());
+                        if let Some((_, mime)) =
+                            message::guess_msgtype_from_suffix(&recoded.to_abs_path())
+                        {
+                            msg.param.set(Param::MimeType, mime);
+                        }
+                        blob = recoded;
+                    }
+                    msg.param.set_int(Param::Width, width as i32);
+                    msg.param.set_int(Param::Height, height as i32);
+                }
+                Err(err) => warn!(context, "Cannot recode image, sending original: {}", err),
+            }
+        }
         info!(
             context,
             "Attaching \"{}\" for message type #{}.",
@@ -1380,16 +1648,27 @@ async fn prepare_msg_common(
 ) -> Result<MsgId, Error> {
     msg.id = MsgId::new_unset();
     prepare_msg_blob(context, msg).await?;
-    chat_id.unarchive(context).await?;
+    if !msg.hidden {
+        // hidden messages, eg. reactions, do not bring the chat back to the chatlist
+        chat_id.unarchive(context).await?;
+    }
 
     let mut chat = Chat::load_from_db(context, chat_id).await?;
-    ensure!(chat.can_send(), "cannot send to {}", chat_id);
+    // unsubscribing is possible also from read-only mailing lists
+    ensure!(
+        chat.can_send()
+            || (chat.typ == Chattype::Mailinglist
+                && msg.param.get_cmd() == SystemMessage::MailinglistUnsubscribe),
+        "cannot send to {}",
+        chat_id
+    );
 
     // The OutPreparing state is set by dc_prepare_msg() before it
     // calls this function and the message is left in the OutPreparing
-    // state.  Otherwise we got called by send_msg() and we change the
-    // state to OutPending.
-    if msg.state != MessageState::OutPreparing {
+    // state, the same applies to the OutScheduled state set by
+    // send_msg_at().  Otherwise we got called by send_msg() and we
+    // change the state to OutPending.
+    if msg.state != MessageState::OutPreparing && msg.state != MessageState::OutScheduled {
         msg.state = MessageState::OutPending;
     }
 
@@ -1418,6 +1697,54 @@ pub async fn is_contact_in_chat(context: &Context, chat_id: ChatId, contact_id:
         .unwrap_or_default()
 }
 
+/// Applies a received pin or unpin request to the message with the given Message-ID.
+///
+/// The request is ignored unless it is sent by a member of the group
+/// the message belongs to.  Like for pins sent by us, at most
+/// [DC_MAX_PINNED_MSGS] messages can be pinned.
+pub(crate) async fn apply_pin_request(
+    context: &Context,
+    chat_id: ChatId,
+    rfc724_mid: &str,
+    from_id: u32,
+    pinned: bool,
+) -> Result<(), Error> {
+    ensure!(!chat_id.is_special(), "Pin request outside of a group");
+    ensure!(
+        is_contact_in_chat(context, chat_id, from_id).await,
+        "Pin request from contact {} who is not a member of group {}",
+        from_id,
+        chat_id
+    );
+    let rfc724_mid = crate::mimeparser::parse_message_id(rfc724_mid)?;
+    if let Some((_, _, msg_id)) = message::rfc724_mid_exists(context, &rfc724_mid).await? {
+        let msg = Message::load_from_db(context, msg_id).await?;
+        ensure!(
+            msg.chat_id == chat_id,
+            "Pin request for message {} of another chat",
+            msg_id
+        );
+        if pinned {
+            let pinned_msgs = chat_id.get_pinned_msgs(context).await?;
+            ensure!(
+                pinned_msgs.contains(&msg_id) || pinned_msgs.len() < DC_MAX_PINNED_MSGS,
+                "Pin request for message {} exceeds the maximum of {} pinned messages",
+                msg_id,
+                DC_MAX_PINNED_MSGS
+            );
+        }
+        chat_id
+            .set_msg_pinned(context, &msg, pinned, from_id)
+            .await?;
+    } else {
+        info!(
+            context,
+            "Pin request for unknown message {} ignored.", rfc724_mid
+        );
+    }
+    Ok(())
+}
+
 /// Send a message defined by a dc_msg_t object to a chat.
 ///
 /// Sends the event #DC_EVENT_MSGS_CHANGED on succcess.
@@ -1469,29 +1796,36 @@ pub async fn send_msg_sync(
         return send_msg(context, chat_id, msg).await;
     }
 
-    if let Some(mut job) = prepare_send_msg(context, chat_id, msg).await? {
-        let mut smtp = crate::smtp::Smtp::new();
+    let jobs = prepare_send_msg(context, chat_id, msg).await?;
+    if jobs.is_empty() {
+        // Nothing to do
+        return Ok(msg.id);
+    }
 
+    let mut smtp = crate::smtp::Smtp::new();
+    let mut queued = false;
+    for mut job in jobs {
         let status = job.send_msg_to_smtp(context, &mut smtp).await;
 
         match status {
-            job::Status::Finished(Ok(_)) => {
-                context.emit_event(Event::MsgsChanged {
-                    chat_id: msg.chat_id,
-                    msg_id: msg.id,
-                });
-
-                Ok(msg.id)
-            }
+            job::Status::Finished(Ok(_)) => {}
             _ => {
                 job.save(context).await?;
-                Err(format_err!(
-                    "failed to send message, queued for later sending"
-                ))
+                queued = true;
             }
         }
+    }
+
+    if queued {
+        Err(format_err!(
+            "failed to send message, queued for later sending"
+        ))
     } else {
-        // Nothing to do
+        context.emit_event(Event::MsgsChanged {
+            chat_id: msg.chat_id,
+            msg_id: msg.id,
+        });
+
         Ok(msg.id)
     }
 }
@@ -1501,8 +1835,11 @@ async fn send_msg_inner(
     chat_id: ChatId,
     msg: &mut Message,
 ) -> Result<MsgId, Error> {
-    if let Some(send_job) = prepare_send_msg(context, chat_id, msg).await? {
-        job::add(context, send_job).await;
+    let send_jobs = prepare_send_msg(context, chat_id, msg).await?;
+    if !send_jobs.is_empty() {
+        for send_job in send_jobs {
+            job::add(context, send_job).await;
+        }
 
         context.emit_event(Event::MsgsChanged {
             chat_id: msg.chat_id,
@@ -1512,6 +1849,10 @@ async fn send_msg_inner(
         if msg.param.exists(Param::SetLatitude) {
             context.emit_event(Event::LocationChanged(Some(DC_CONTACT_ID_SELF)));
         }
+
+        if msg.ephemeral_timer != EphemeralTimer::Disabled {
+            schedule_ephemeral_task(context).await;
+        }
     }
 
     Ok(msg.id)
@@ -1521,7 +1862,7 @@ async fn prepare_send_msg(
     context: &Context,
     chat_id: ChatId,
     msg: &mut Message,
-) -> Result<Option<crate::job::Job>, Error> {
+) -> Result<Vec<crate::job::Job>, Error> {
     // dc_prepare_msg() leaves the message state to OutPreparing, we
     // only have to change the state to OutPending in this case.
     // Otherwise we still have to prepare the message, which will set
@@ -1537,9 +1878,9 @@ async fn prepare_send_msg(
         );
         message::update_msg_state(context, msg.id, MessageState::OutPending).await;
     }
-    let job = job::send_msg_job(context, msg.id).await?;
+    let jobs = job::send_msg_job(context, msg.id).await?;
 
-    Ok(job)
+    Ok(jobs)
 }
 
 pub async fn send_text_msg(
@@ -1558,13 +1899,199 @@ pub async fn send_text_msg(
     send_msg(context, chat_id, &mut msg).await
 }
 
+/// Schedules a message for sending at the given time.
+///
+/// The message is stored in the OutScheduled state and hidden from the
+/// chat until it is due; use `ChatId::get_scheduled_msgs()` to list it.
+/// Until then it can be changed with `set_scheduled_msg_text()` and
+/// `reschedule_msg()` or be cancelled with `cancel_scheduled_msg()`.
+/// Once due, the message is sent as if `send_msg()` was called then,
+/// also if the app was restarted in the meantime.
+pub async fn send_msg_at(
+    context: &Context,
+    chat_id: ChatId,
+    msg: &mut Message,
+    timestamp: i64,
+) -> Result<MsgId, Error> {
+    ensure!(
+        !chat_id.is_special(),
+        "Cannot schedule message for special chat"
+    );
+    ensure!(
+        timestamp > time(),
+        "Scheduled time {} is not in the future",
+        timestamp
+    );
+
+    msg.hidden = true;
+    msg.state = MessageState::OutScheduled;
+    prepare_msg_common(context, chat_id, msg).await?;
+    schedule_msg(context, msg.id, timestamp).await?;
+    msg.timestamp_sort = timestamp;
+
+    context.emit_event(Event::MsgsChanged {
+        chat_id: msg.chat_id,
+        msg_id: msg.id,
+    });
+
+    Ok(msg.id)
+}
+
+/// Changes the time a scheduled message is sent at.
+pub async fn reschedule_msg(context: &Context, msg_id: MsgId, timestamp: i64) -> Result<(), Error> {
+    let msg = load_scheduled_msg(context, msg_id).await?;
+    ensure!(
+        timestamp > time(),
+        "Scheduled time {} is not in the future",
+        timestamp
+    );
+    schedule_msg(context, msg_id, timestamp).await?;
+
+    context.emit_event(Event::MsgsChanged {
+        chat_id: msg.chat_id,
+        msg_id,
+    });
+    Ok(())
+}
+
+/// Replaces the text of a scheduled message.
+pub async fn set_scheduled_msg_text(
+    context: &Context,
+    msg_id: MsgId,
+    text: String,
+) -> Result<(), Error> {
+    let msg = load_scheduled_msg(context, msg_id).await?;
+    ensure!(
+        !text.is_empty() || msg.viewtype != Viewtype::Text,
+        "Cannot remove text of a text message"
+    );
+    context
+        .sql
+        .execute("UPDATE msgs SET txt=? WHERE id=?;", paramsv![text, msg_id])
+        .await?;
+
+    context.emit_event(Event::MsgsChanged {
+        chat_id: msg.chat_id,
+        msg_id,
+    });
+    Ok(())
+}
+
+/// Deletes a scheduled message before it is sent.
+pub async fn cancel_scheduled_msg(context: &Context, msg_id: MsgId) -> Result<(), Error> {
+    let msg = load_scheduled_msg(context, msg_id).await?;
+    job::kill_action_for(context, Action::SendScheduledMsg, msg_id.to_u32()).await;
+    msg_id.delete_from_db(context).await?;
+
+    context.emit_event(Event::MsgsChanged {
+        chat_id: msg.chat_id,
+        msg_id: MsgId::new(0),
+    });
+    Ok(())
+}
+
+async fn load_scheduled_msg(context: &Context, msg_id: MsgId) -> Result<Message, Error> {
+    let msg = Message::load_from_db(context, msg_id).await?;
+    ensure!(
+        msg.state == MessageState::OutScheduled,
+        "Message {} is not scheduled",
+        msg_id
+    );
+    Ok(msg)
+}
+
+/// Sets the time a message is due and replaces its `SendScheduledMsg` job.
+async fn schedule_msg(context: &Context, msg_id: MsgId, timestamp: i64) -> Result<(), Error> {
+    context
+        .sql
+        .execute(
+            "UPDATE msgs SET state=?, timestamp=? WHERE id=?;",
+            paramsv![MessageState::OutScheduled, timestamp, msg_id],
+        )
+        .await?;
+
+    job::kill_action_for(context, Action::SendScheduledMsg, msg_id.to_u32()).await;
+    job::add(
+        context,
+        job::Job::new(
+            Action::SendScheduledMsg,
+            msg_id.to_u32(),
+            Params::new(),
+            timestamp - time(),
+        ),
+    )
+    .await;
+    // Delayed jobs do not interrupt the smtp loop,
+    // but it has to learn about the new due time.
+    context.interrupt_smtp(false).await;
+    Ok(())
+}
+
+/// Sends a scheduled message if it is due.
+///
+/// Called by the `SendScheduledMsg` job. Nothing is done if the message
+/// was cancelled or rescheduled to a later time in the meantime.
+pub(crate) async fn send_scheduled_msg(context: &Context, msg_id: MsgId) -> Result<(), Error> {
+    if !message::exists(context, msg_id).await {
+        info!(context, "Scheduled message {} was cancelled.", msg_id);
+        return Ok(());
+    }
+    let msg = Message::load_from_db(context, msg_id).await?;
+    if msg.state != MessageState::OutScheduled || msg.timestamp_sort > time() {
+        info!(context, "Scheduled message {} is not due.", msg_id);
+        return Ok(());
+    }
+
+    // the chat may have continued since the message was scheduled,
+    // so the message replies to the messages present when it is sent
+    let chat = Chat::load_from_db(context, msg.chat_id).await?;
+    let (in_reply_to, references) = chat.get_reply_headers(context, &msg).await;
+
+    let timestamp = dc_create_smeared_timestamp(context).await;
+    context
+        .sql
+        .execute(
+            "UPDATE msgs SET state=?, hidden=0, timestamp=?, \
+             ephemeral_timestamp=CASE WHEN ephemeral_timer=0 THEN 0 ELSE ?+ephemeral_timer END, \
+             mime_in_reply_to=?, mime_references=? \
+             WHERE id=?;",
+            paramsv![
+                MessageState::OutPending,
+                timestamp,
+                timestamp,
+                in_reply_to,
+                references,
+                msg_id
+            ],
+        )
+        .await?;
+    msg.chat_id.unarchive(context).await?;
+
+    for send_job in job::send_msg_job(context, msg_id).await? {
+        job::add(context, send_job).await;
+    }
+
+    context.emit_event(Event::MsgsChanged {
+        chat_id: msg.chat_id,
+        msg_id,
+    });
+    if msg.param.exists(Param::SetLatitude) {
+        context.emit_event(Event::LocationChanged(Some(DC_CONTACT_ID_SELF)));
+    }
+    if msg.ephemeral_timer != EphemeralTimer::Disabled {
+        schedule_ephemeral_task(context).await;
+    }
+
+    Ok(())
+}
+
 pub async fn get_chat_msgs(
     context: &Context,
     chat_id: ChatId,
     flags: u32,
     marker1before: Option<MsgId>,
 ) -> Vec<MsgId> {
-    match delete_device_expired_messages(context).await {
+    match delete_expired_messages(context).await {
         Err(err) => warn!(context, "Failed to delete expired messages: {}", err),
         Ok(messages_deleted) => {
             if messages_deleted {
@@ -1729,52 +2256,6 @@ pub async fn marknoticed_all_chats(context: &Context) -> Result<(), Error> {
     Ok(())
 }
 
-/// Deletes messages which are expired according to "delete_device_after" setting.
-///
-/// Returns true if any message is deleted, so event can be emitted. If nothing
-/// has been deleted, returns false.
-pub async fn delete_device_expired_messages(context: &Context) -> Result<bool, Error> {
-    if let Some(delete_device_after) = context.get_config_delete_device_after().await {
-        let threshold_timestamp = time() - delete_device_after;
-
-        let self_chat_id = lookup_by_contact_id(context, DC_CONTACT_ID_SELF)
-            .await
-            .unwrap_or_default()
-            .0;
-        let device_chat_id = lookup_by_contact_id(context, DC_CONTACT_ID_DEVICE)
-            .await
-            .unwrap_or_default()
-            .0;
-
-        // Delete expired messages
-        //
-        // Only update the rows that have to be updated, to avoid emitting
-        // unnecessary "chat modified" events.
-        let rows_modified = context
-            .sql
-            .execute(
-                "UPDATE msgs \
-             SET txt = 'DELETED', chat_id = ? \
-             WHERE timestamp < ? \
-             AND chat_id > ? \
-             AND chat_id != ? \
-             AND chat_id != ?",
-                paramsv![
-                    DC_CHAT_ID_TRASH,
-                    threshold_timestamp,
-                    DC_CHAT_ID_LAST_SPECIAL,
-                    self_chat_id,
-                    device_chat_id
-                ],
-            )
-            .await?;
-
-        Ok(rows_modified > 0)
-    } else {
-        Ok(false)
-    }
-}
-
 pub async fn get_chat_media(
     context: &Context,
     chat_id: ChatId,
@@ -1947,6 +2428,48 @@ pub async fn create_group_chat(
     Ok(chat_id)
 }
 
+/// Creates a new broadcast list.
+///
+/// Messages sent to a broadcast list are sent to every member as a
+/// separate copy, so that members do not see each other; recipients get
+/// the messages in their one-to-one chat with the sender and reply there.
+/// The message is delivered once all copies are delivered.
+/// Changes of the list, like adding members, are never sent out.
+pub async fn create_broadcast_list(
+    context: &Context,
+    chat_name: impl AsRef<str>,
+) -> Result<ChatId, Error> {
+    ensure!(!chat_name.as_ref().is_empty(), "Invalid chat name");
+
+    let grpid = dc_create_id();
+    context
+        .sql
+        .execute(
+            "INSERT INTO chats (type, name, grpid, param, created_timestamp) VALUES(?, ?, ?, '', ?);",
+            paramsv![
+                Chattype::Broadcast,
+                chat_name.as_ref().to_string(),
+                grpid,
+                time(),
+            ],
+        )
+        .await?;
+
+    let row_id = context
+        .sql
+        .get_rowid(context, "chats", "grpid", grpid)
+        .await?;
+    let chat_id = ChatId::new(row_id);
+    add_to_chat_contacts_table(context, chat_id, DC_CONTACT_ID_SELF).await;
+
+    context.emit_event(Event::MsgsChanged {
+        msg_id: MsgId::new(0),
+        chat_id: ChatId::new(0),
+    });
+
+    Ok(chat_id)
+}
+
 /// add a contact to the chats_contact table
 pub(crate) async fn add_to_chat_contacts_table(
     context: &Context,
@@ -2080,7 +2603,9 @@ pub(crate) async fn add_contact_to_chat_ex(
             return Ok(false);
         }
     }
-    if chat.param.get_int(Param::Unpromoted).unwrap_or_default() == 0 {
+    if chat.typ != Chattype::Broadcast
+        && chat.param.get_int(Param::Unpromoted).unwrap_or_default() == 0
+    {
         msg.viewtype = Viewtype::Text;
         msg.text = Some(
             context
@@ -2102,7 +2627,7 @@ pub(crate) async fn add_contact_to_chat_ex(
 }
 
 async fn real_group_exists(context: &Context, chat_id: ChatId) -> bool {
-    // check if a group or a verified group exists under the given ID
+    // check if a group, a verified group or a broadcast list exists under the given ID
     if !context.sql.is_open().await || chat_id.is_special() {
         return false;
     }
@@ -2110,7 +2635,7 @@ async fn real_group_exists(context: &Context, chat_id: ChatId) -> bool {
     context
         .sql
         .exists(
-            "SELECT id FROM chats WHERE id=? AND (type=120 OR type=130);",
+            "SELECT id FROM chats WHERE id=? AND (type=120 OR type=130 OR type=160);",
             paramsv![chat_id],
         )
         .await
@@ -2297,7 +2822,7 @@ pub async fn remove_contact_from_chat(
                 );
             } else {
                 if let Ok(contact) = Contact::get_by_id(context, contact_id).await {
-                    if chat.is_promoted() {
+                    if chat.typ != Chattype::Broadcast && chat.is_promoted() {
                         msg.viewtype = Viewtype::Text;
                         if contact.id == DC_CONTACT_ID_SELF {
                             set_group_explicitly_left(context, chat.grpid).await?;
@@ -2412,7 +2937,7 @@ pub async fn set_chat_name(
                 .await
                 .is_ok()
             {
-                if chat.is_promoted() {
+                if chat.typ != Chattype::Broadcast && chat.is_promoted() {
                     msg.viewtype = Viewtype::Text;
                     msg.text = Some(
                         context
@@ -2502,7 +3027,7 @@ pub async fn set_chat_profile_image(
         );
     }
     chat.update_param(context).await?;
-    if chat.is_promoted() {
+    if chat.typ != Chattype::Broadcast && chat.is_promoted() {
         msg.id = send_msg(context, chat_id, &mut msg).await?;
         emit_event!(
             context,
@@ -2563,6 +3088,10 @@ pub async fn forward_msgs(
             msg.param.remove(Param::ForcePlaintext);
             msg.param.remove(Param::Cmd);
 
+            // do not forward the quote, the quoted message may be unknown in the new chat
+            msg.in_reply_to = None;
+            msg.quoted_text = None;
+
             let new_msg_id: MsgId;
             if msg.state == MessageState::OutPreparing {
                 let fresh9 = curr_timestamp;
@@ -2587,7 +3116,7 @@ pub async fn forward_msgs(
                 let fresh10 = curr_timestamp;
                 curr_timestamp += 1;
                 new_msg_id = chat.prepare_msg_raw(context, &mut msg, fresh10).await?;
-                if let Some(send_job) = job::send_msg_job(context, new_msg_id).await? {
+                for send_job in job::send_msg_job(context, new_msg_id).await? {
                     job::add(context, send_job).await;
                 }
             }
@@ -2800,6 +3329,7 @@ mod tests {
     use super::*;
 
     use crate::contact::Contact;
+    use crate::mimefactory::MimeFactory;
     use crate::test_utils::*;
 
     #[async_std::test]
@@ -2827,7 +3357,8 @@ mod tests {
                 "color": 15895624,
                 "profile_image": "",
                 "draft": "",
-                "is_muted": false
+                "is_muted": false,
+                "pinned_msgs": []
             }
         "#;
 
@@ -3460,4 +3991,286 @@ mod tests {
         chat_id.set_draft(&t.ctx, Some(&mut msg)).await;
         assert!(!chat_id.parent_is_encrypted(&t.ctx).await.unwrap());
     }
+
+    #[async_std::test]
+    async fn test_broadcast() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let bob = Contact::create(&t.ctx, "Bob", "bob@example.net")
+            .await
+            .unwrap();
+        let claire = Contact::create(&t.ctx, "", "claire@example.org")
+            .await
+            .unwrap();
+
+        let chat_id = create_broadcast_list(&t.ctx, "my list").await.unwrap();
+        let chat = Chat::load_from_db(&t.ctx, chat_id).await.unwrap();
+        assert_eq!(chat.get_type(), Chattype::Broadcast);
+        assert_eq!(chat.get_name(), "my list");
+
+        assert!(add_contact_to_chat(&t.ctx, chat_id, bob).await);
+        assert!(add_contact_to_chat(&t.ctx, chat_id, claire).await);
+        assert_eq!(get_chat_contacts(&t.ctx, chat_id).await.len(), 3);
+        set_chat_name(&t.ctx, chat_id, "renamed").await.unwrap();
+
+        // changes of the list are not sent to the recipients
+        assert!(get_chat_msgs(&t.ctx, chat_id, 0, None).await.is_empty());
+
+        let msg_id = send_text_msg(&t.ctx, chat_id, "hi all".to_string())
+            .await
+            .unwrap();
+        assert_eq!(get_chat_msgs(&t.ctx, chat_id, 0, None).await, vec![msg_id]);
+
+        // every recipient gets a separate copy and does not see the others
+        let jobs = t
+            .ctx
+            .sql
+            .query_map(
+                "SELECT param FROM jobs WHERE action=? AND foreign_id=? ORDER BY id;",
+                paramsv![Action::SendMsgToSmtp, msg_id],
+                |row| row.get::<_, String>(0),
+                |params| params.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await
+            .unwrap();
+        assert_eq!(jobs.len(), 2);
+        let mut recipients = Vec::new();
+        for param in jobs {
+            let param: Params = param.parse().unwrap();
+            let addr = param.get(Param::Recipients).unwrap().to_string();
+            assert_eq!(param.get_int(Param::BroadcastCopies), Some(2));
+            let path = param.get_path(Param::File, &t.ctx).unwrap().unwrap();
+            let payload = std::fs::read_to_string(path).unwrap();
+            let other_addr = if addr == "bob@example.net" {
+                "claire@example.org"
+            } else {
+                "bob@example.net"
+            };
+            assert!(payload.contains(&addr));
+            assert!(!payload.contains(other_addr));
+            assert!(payload.contains(&format!("Chat-Group-ID: {}", chat.grpid)));
+            assert!(!payload.contains("Chat-Group-Name"));
+            recipients.push(addr);
+        }
+        recipients.sort();
+        assert_eq!(recipients, vec!["bob@example.net", "claire@example.org"]);
+
+        // the copy sent to self has no visible recipients
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        let rendered = MimeFactory::from_msg(&t.ctx, &msg, false)
+            .await
+            .unwrap()
+            .for_recipient("alice@example.org")
+            .render()
+            .await
+            .unwrap();
+        let payload = String::from_utf8_lossy(&rendered.message);
+        assert!(payload.contains("To: hidden-recipients: ;"));
+        assert!(!payload.contains("bob@example.net"));
+        assert!(!payload.contains("claire@example.org"));
+        assert!(!rendered.rfc724_mid.starts_with("Gr."));
+    }
+
+    #[async_std::test]
+    async fn test_pin_msg() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let bob = Contact::create(&t.ctx, "Bob", "bob@example.net")
+            .await
+            .unwrap();
+        let chat_id = create_group_chat(&t.ctx, VerifiedStatus::Unverified, "foo")
+            .await
+            .unwrap();
+        assert!(add_contact_to_chat(&t.ctx, chat_id, bob).await);
+        let msg_id = send_text_msg(&t.ctx, chat_id, "rules".to_string())
+            .await
+            .unwrap();
+        assert!(chat_id.get_pinned_msgs(&t.ctx).await.unwrap().is_empty());
+
+        chat_id.pin_msg(&t.ctx, msg_id).await.unwrap();
+        assert_eq!(chat_id.get_pinned_msgs(&t.ctx).await.unwrap(), vec![msg_id]);
+        let chat = Chat::load_from_db(&t.ctx, chat_id).await.unwrap();
+        assert_eq!(
+            chat.get_info(&t.ctx).await.unwrap().pinned_msgs,
+            vec![msg_id]
+        );
+        let msgs = get_chat_msgs(&t.ctx, chat_id, 0, None).await;
+        let info = Message::load_from_db(&t.ctx, *msgs.last().unwrap())
+            .await
+            .unwrap();
+        assert!(info.is_info());
+        assert_eq!(info.get_text().unwrap(), "Message \"rules\" pinned by me.");
+
+        // The request is sent to the other members.
+        let request_id = t
+            .ctx
+            .sql
+            .query_get_value::<MsgId>(
+                &t.ctx,
+                "SELECT id FROM msgs WHERE chat_id=? AND hidden=1 ORDER BY id DESC LIMIT 1;",
+                paramsv![chat_id],
+            )
+            .await
+            .unwrap();
+        let request = Message::load_from_db(&t.ctx, request_id).await.unwrap();
+        let rendered = MimeFactory::from_msg(&t.ctx, &request, false)
+            .await
+            .unwrap()
+            .render()
+            .await
+            .unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        let payload = String::from_utf8_lossy(&rendered.message);
+        assert!(payload.contains(&format!("Chat-Pin: <{}>", msg.rfc724_mid)));
+
+        // Pinning again does not add another info message.
+        chat_id.pin_msg(&t.ctx, msg_id).await.unwrap();
+        assert_eq!(get_chat_msgs(&t.ctx, chat_id, 0, None).await, msgs);
+
+        // Info messages cannot be pinned.
+        assert!(chat_id.pin_msg(&t.ctx, info.id).await.is_err());
+
+        chat_id.unpin_msg(&t.ctx, msg_id).await.unwrap();
+        assert!(chat_id.get_pinned_msgs(&t.ctx).await.unwrap().is_empty());
+        assert_eq!(
+            get_chat_msgs(&t.ctx, chat_id, 0, None).await.len(),
+            msgs.len() + 1
+        );
+
+        // Only group messages can be pinned.
+        let bob_chat_id = create_by_contact_id(&t.ctx, bob).await.unwrap();
+        let msg_id = send_text_msg(&t.ctx, bob_chat_id, "hi".to_string())
+            .await
+            .unwrap();
+        assert!(bob_chat_id.pin_msg(&t.ctx, msg_id).await.is_err());
+        assert!(chat_id.pin_msg(&t.ctx, msg_id).await.is_err());
+    }
+
+    #[async_std::test]
+    async fn test_max_pinned_msgs() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let bob = Contact::create(&t.ctx, "Bob", "bob@example.net")
+            .await
+            .unwrap();
+        let chat_id = create_group_chat(&t.ctx, VerifiedStatus::Unverified, "foo")
+            .await
+            .unwrap();
+        assert!(add_contact_to_chat(&t.ctx, chat_id, bob).await);
+        let mut msg_ids = Vec::new();
+        for i in 0..=DC_MAX_PINNED_MSGS {
+            msg_ids.push(
+                send_text_msg(&t.ctx, chat_id, format!("msg {}", i))
+                    .await
+                    .unwrap(),
+            );
+        }
+        for msg_id in &msg_ids[..DC_MAX_PINNED_MSGS] {
+            chat_id.pin_msg(&t.ctx, *msg_id).await.unwrap();
+        }
+        assert!(chat_id
+            .pin_msg(&t.ctx, msg_ids[DC_MAX_PINNED_MSGS])
+            .await
+            .is_err());
+        let pinned = chat_id.get_pinned_msgs(&t.ctx).await.unwrap();
+        assert_eq!(pinned.len(), DC_MAX_PINNED_MSGS);
+        assert!(!pinned.contains(&msg_ids[DC_MAX_PINNED_MSGS]));
+    }
+
+    #[async_std::test]
+    async fn test_send_msg_at() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let bob = Contact::create(&t.ctx, "Bob", "bob@example.net")
+            .await
+            .unwrap();
+        let chat_id = create_by_contact_id(&t.ctx, bob).await.unwrap();
+
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("later".to_string()));
+        assert!(send_msg_at(&t.ctx, chat_id, &mut msg, time() - 10)
+            .await
+            .is_err());
+        let msg_id = send_msg_at(&t.ctx, chat_id, &mut msg, time() + 3600)
+            .await
+            .unwrap();
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("even later".to_string()));
+        let later_id = send_msg_at(&t.ctx, chat_id, &mut msg, time() + 7200)
+            .await
+            .unwrap();
+
+        // Scheduled messages are not shown in the chat until they are sent.
+        assert_eq!(
+            chat_id.get_scheduled_msgs(&t.ctx).await.unwrap(),
+            vec![msg_id, later_id]
+        );
+        assert!(get_chat_msgs(&t.ctx, chat_id, 0, None).await.is_empty());
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert_eq!(msg.get_state(), MessageState::OutScheduled);
+
+        // Nothing is sent before the message is due.
+        send_scheduled_msg(&t.ctx, msg_id).await.unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert_eq!(msg.get_state(), MessageState::OutScheduled);
+
+        set_scheduled_msg_text(&t.ctx, msg_id, "edited".to_string())
+            .await
+            .unwrap();
+        reschedule_msg(&t.ctx, later_id, time() + 60).await.unwrap();
+        assert_eq!(
+            chat_id.get_scheduled_msgs(&t.ctx).await.unwrap(),
+            vec![later_id, msg_id]
+        );
+
+        cancel_scheduled_msg(&t.ctx, later_id).await.unwrap();
+        assert!(!message::exists(&t.ctx, later_id).await);
+        assert!(cancel_scheduled_msg(&t.ctx, later_id).await.is_err());
+
+        // The chat continues until the message is sent.
+        let sent_id = send_text_msg(&t.ctx, chat_id, "now".to_string())
+            .await
+            .unwrap();
+        message::update_msg_state(&t.ctx, sent_id, MessageState::OutDelivered).await;
+        let sent = Message::load_from_db(&t.ctx, sent_id).await.unwrap();
+
+        // Pretend the message is due.
+        t.ctx
+            .sql
+            .execute(
+                "UPDATE msgs SET timestamp=? WHERE id=?;",
+                paramsv![time() - 1, msg_id],
+            )
+            .await
+            .unwrap();
+        send_scheduled_msg(&t.ctx, msg_id).await.unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert_eq!(msg.get_state(), MessageState::OutPending);
+        assert_eq!(msg.get_text().unwrap(), "edited");
+        assert_eq!(msg.in_reply_to, Some(sent.rfc724_mid));
+        assert!(chat_id.get_scheduled_msgs(&t.ctx).await.unwrap().is_empty());
+        assert_eq!(
+            get_chat_msgs(&t.ctx, chat_id, 0, None).await,
+            vec![sent_id, msg_id]
+        );
+        assert!(
+            set_scheduled_msg_text(&t.ctx, msg_id, "too late".to_string())
+                .await
+                .is_err()
+        );
+
+        // The message is sent out by the usual job.
+        let jobs = t
+            .ctx
+            .sql
+            .query_map(
+                "SELECT foreign_id FROM jobs WHERE action=? ORDER BY id;",
+                paramsv![Action::SendMsgToSmtp],
+                |row| row.get::<_, u32>(0),
+                |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await
+            .unwrap();
+        assert_eq!(jobs, vec![sent_id.to_u32(), msg_id.to_u32()]);
+    }
 }
@@ -32,6 +32,14 @@ pub enum Config {
     SmtpCertificateChecks,
     ServerFlags,
 
+    /// Type of the proxy to use for all connections,
+    /// 0=no proxy (default), 1=SOCKS5, 2=HTTP `CONNECT`.
+    ProxyType,
+    ProxyHost,
+    ProxyPort,
+    ProxyUser,
+    ProxyPw,
+
     #[strum(props(default = "INBOX"))]
     ImapFolder,
 
@@ -45,6 +53,10 @@ pub enum Config {
     #[strum(props(default = "1"))]
     E2eeEnabled,
 
+    /// Whether to sign messages which are not end-to-end encrypted.
+    #[strum(props(default = "0"))]
+    SignUnencrypted,
+
     #[strum(props(default = "1"))]
     MdnsEnabled,
 
@@ -102,6 +114,11 @@ pub enum Config {
     ConfiguredSendPort,
     ConfiguredSmtpCertificateChecks,
     ConfiguredServerFlags,
+    ConfiguredProxyType,
+    ConfiguredProxyHost,
+    ConfiguredProxyPort,
+    ConfiguredProxyUser,
+    ConfiguredProxyPw,
     ConfiguredSendSecurity,
     ConfiguredE2EEEnabled,
     Configured,
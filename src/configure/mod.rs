@@ -116,6 +116,19 @@ async fn configure(ctx: &Context, param: &mut LoginParam) -> Result<()> {
     progress!(ctx, 1);
     ensure!(!param.addr.is_empty(), "Please enter an email address.");
 
+    // Check the proxy first, all further connections go through it
+    if let Some(proxy) = &param.proxy {
+        info!(ctx, "Checking proxy {}", proxy);
+        if let Err(err) = proxy.check().await {
+            bail!(
+                "Cannot connect to proxy {}:{}: {}",
+                proxy.host,
+                proxy.port,
+                err
+            );
+        }
+    }
+
     // Step 1: Load the parameters and check email-address and password
 
     if 0 != param.server_flags & DC_LP_AUTH_OAUTH2 {
@@ -635,6 +648,45 @@ mod tests {
         assert!(t.ctx.configure().await.is_err());
     }
 
+    #[async_std::test]
+    async fn test_configure_unreachable_proxy() {
+        let t = dummy_context().await;
+        t.ctx
+            .set_config(Config::Addr, Some("alice@example.org"))
+            .await
+            .unwrap();
+        t.ctx
+            .set_config(Config::MailPw, Some("123456"))
+            .await
+            .unwrap();
+
+        // Find a local port nobody listens on.
+        let port = async_std::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+            .to_string();
+        t.ctx
+            .set_config(Config::ProxyType, Some("1"))
+            .await
+            .unwrap();
+        t.ctx
+            .set_config(Config::ProxyHost, Some("127.0.0.1"))
+            .await
+            .unwrap();
+        t.ctx
+            .set_config(Config::ProxyPort, Some(&port))
+            .await
+            .unwrap();
+
+        let err = t.ctx.configure().await.unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Cannot connect to proxy 127.0.0.1:"));
+    }
+
     #[async_std::test]
     async fn test_get_offline_autoconfig() {
         let context = dummy_context().await.ctx;
@@ -1,15 +1,16 @@
 use crate::context::Context;
+use crate::proxy;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("URL request error")]
-    GetError(surf::Error),
+    GetError(#[source] proxy::Error),
 }
 
 pub async fn read_url(context: &Context, url: &str) -> Result<String, Error> {
     info!(context, "Requesting URL {}", url);
 
-    match surf::get(url).recv_string().await {
+    match proxy::http_get(context, url).await {
         Ok(res) => Ok(res),
         Err(err) => {
             info!(context, "Can\'t read URL {}", url);
@@ -0,0 +1,287 @@
+//! # Connectivity status
+//!
+//! Every IMAP folder loop and the SMTP loop of the scheduler track the
+//! state of their connection in a [ConnectivityStore]. The states are
+//! combined to an overall [Connectivity] which is meant to be shown in
+//! the title bar of the UI, and to a human readable report.
+
+use async_std::sync::{Arc, Mutex};
+
+use crate::context::Context;
+use crate::dc_tools::{dc_timestamp_to_str, time};
+use crate::events::Event;
+use crate::job::{self, Thread};
+use crate::scheduler::Scheduler;
+
+/// Basic connectivity of the context.
+///
+/// Values can be compared, a greater value is a better connectivity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, FromPrimitive, ToPrimitive)]
+#[repr(u32)]
+pub enum Connectivity {
+    /// Not connected, eg. because the network is down or the IO is not started.
+    NotConnected = 1000,
+
+    /// Trying to connect.
+    Connecting = 2000,
+
+    /// Connected, but fetching or sending messages at the moment.
+    Working = 3000,
+
+    /// Connected and idle, all messages are fetched.
+    Connected = 4000,
+}
+
+/// Detailed state of one connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DetailedConnectivity {
+    /// The connection was not used yet.
+    Uninitialized,
+    Connecting,
+    Working,
+    Connected,
+
+    /// The last attempt to connect or to use the connection failed.
+    Error(String),
+
+    /// The folder is not configured, so there is nothing to watch.
+    NotConfigured,
+}
+
+impl Default for DetailedConnectivity {
+    fn default() -> Self {
+        DetailedConnectivity::Uninitialized
+    }
+}
+
+impl DetailedConnectivity {
+    /// Returns the basic connectivity, `None` if the connection does not count.
+    fn to_basic(&self) -> Option<Connectivity> {
+        match self {
+            DetailedConnectivity::Uninitialized | DetailedConnectivity::Error(_) => {
+                Some(Connectivity::NotConnected)
+            }
+            DetailedConnectivity::Connecting => Some(Connectivity::Connecting),
+            DetailedConnectivity::Working => Some(Connectivity::Working),
+            DetailedConnectivity::Connected => Some(Connectivity::Connected),
+            DetailedConnectivity::NotConfigured => None,
+        }
+    }
+
+    fn to_html(&self) -> String {
+        match self {
+            DetailedConnectivity::Uninitialized => "Not started".to_string(),
+            DetailedConnectivity::Connecting => "Connecting…".to_string(),
+            DetailedConnectivity::Working => "Working…".to_string(),
+            DetailedConnectivity::Connected => "Connected".to_string(),
+            DetailedConnectivity::Error(err) => format!("Error: {}", escaper::encode_minimal(err)),
+            DetailedConnectivity::NotConfigured => "Not configured".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ConnectivityState {
+    detailed: DetailedConnectivity,
+
+    /// Timestamp and text of the last error, kept after the connection recovered.
+    last_error: Option<(i64, String)>,
+}
+
+/// Connectivity of one IMAP or SMTP connection, shared between the
+/// connection and the scheduler.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ConnectivityStore(Arc<Mutex<ConnectivityState>>);
+
+impl ConnectivityStore {
+    async fn set(&self, context: &Context, detailed: DetailedConnectivity) {
+        let changed = {
+            let mut state = self.0.lock().await;
+            if let DetailedConnectivity::Error(err) = &detailed {
+                state.last_error = Some((time(), err.clone()));
+            }
+            let changed = state.detailed != detailed;
+            state.detailed = detailed;
+            changed
+        };
+        if changed {
+            context.emit_event(Event::ConnectivityChanged);
+        }
+    }
+
+    pub(crate) async fn set_connecting(&self, context: &Context) {
+        self.set(context, DetailedConnectivity::Connecting).await;
+    }
+
+    pub(crate) async fn set_working(&self, context: &Context) {
+        self.set(context, DetailedConnectivity::Working).await;
+    }
+
+    pub(crate) async fn set_connected(&self, context: &Context) {
+        self.set(context, DetailedConnectivity::Connected).await;
+    }
+
+    pub(crate) async fn set_err(&self, context: &Context, err: impl ToString) {
+        self.set(context, DetailedConnectivity::Error(err.to_string()))
+            .await;
+    }
+
+    pub(crate) async fn set_not_configured(&self, context: &Context) {
+        self.set(context, DetailedConnectivity::NotConfigured).await;
+    }
+
+    async fn get_basic(&self) -> Option<Connectivity> {
+        self.0.lock().await.detailed.to_basic()
+    }
+
+    async fn to_html(&self) -> String {
+        let state = self.0.lock().await;
+        let mut html = state.detailed.to_html();
+        if let Some((timestamp, err)) = &state.last_error {
+            if !matches!(state.detailed, DetailedConnectivity::Error(_)) {
+                html += &format!(
+                    "<br>Last error at {}: {}",
+                    dc_timestamp_to_str(*timestamp),
+                    escaper::encode_minimal(err)
+                );
+            }
+        }
+        html
+    }
+}
+
+impl Context {
+    /// Returns the overall connectivity.
+    ///
+    /// This is the worst connectivity of the watched IMAP folders,
+    /// SMTP is connected on demand only and does not count.
+    /// Whenever the connectivity changes, `Event::ConnectivityChanged` is emitted.
+    pub async fn get_connectivity(&self) -> Connectivity {
+        let stores = match &*self.scheduler.read().await {
+            Scheduler::Running {
+                inbox,
+                mvbox,
+                sentbox,
+                ..
+            } => vec![
+                inbox.connectivity.clone(),
+                mvbox.connectivity.clone(),
+                sentbox.connectivity.clone(),
+            ],
+            Scheduler::Stopped => return Connectivity::NotConnected,
+        };
+
+        let mut connectivity = Connectivity::Connected;
+        for store in stores {
+            if let Some(basic) = store.get_basic().await {
+                connectivity = connectivity.min(basic);
+            }
+        }
+        connectivity
+    }
+
+    /// Returns a human readable HTML report about the connectivity.
+    ///
+    /// It lists the state and the last error of every IMAP folder
+    /// and of SMTP, together with the number of pending jobs.
+    pub async fn get_connectivity_html(&self) -> String {
+        let stores = match &*self.scheduler.read().await {
+            Scheduler::Running {
+                inbox,
+                mvbox,
+                sentbox,
+                smtp,
+                ..
+            } => Some((
+                inbox.connectivity.clone(),
+                mvbox.connectivity.clone(),
+                sentbox.connectivity.clone(),
+                smtp.connectivity.clone(),
+            )),
+            Scheduler::Stopped => None,
+        };
+
+        let mut html =
+            String::from("<!DOCTYPE html>\n<html><head><meta charset=\"UTF-8\" /></head><body>\n");
+        let (inbox, mvbox, sentbox, smtp) = match stores {
+            Some(stores) => stores,
+            None => {
+                html += "<p>Not started, no connections are made.</p>\n</body></html>\n";
+                return html;
+            }
+        };
+
+        html += "<h3>Incoming messages</h3>\n<ul>\n";
+        for (folder_config, store) in &[
+            ("configured_inbox_folder", inbox),
+            ("configured_mvbox_folder", mvbox),
+            ("configured_sentbox_folder", sentbox),
+        ] {
+            let folder = self
+                .sql
+                .get_raw_config(self, folder_config)
+                .await
+                .unwrap_or_else(|| "-".to_string());
+            html += &format!(
+                "<li><b>{}:</b> {}",
+                escaper::encode_minimal(&folder),
+                store.to_html().await
+            );
+            if *folder_config == "configured_inbox_folder" {
+                // IMAP jobs of all folders are performed by the inbox loop.
+                html += &format!(
+                    "<br>Pending jobs: {}",
+                    job::count_pending(self, Thread::Imap).await
+                );
+            }
+            html += "</li>\n";
+        }
+        html += "</ul>\n";
+
+        html += "<h3>Outgoing messages</h3>\n<ul>\n";
+        html += &format!("<li><b>SMTP:</b> {}", smtp.to_html().await);
+        html += &format!(
+            "<br>Pending jobs: {}</li>\n",
+            job::count_pending(self, Thread::Smtp).await
+        );
+        html += "</ul>\n</body></html>\n";
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_utils::*;
+
+    #[async_std::test]
+    async fn test_connectivity_store() {
+        let t = dummy_context().await;
+        let store = ConnectivityStore::default();
+        assert_eq!(store.get_basic().await, Some(Connectivity::NotConnected));
+
+        store.set_connecting(&t.ctx).await;
+        assert_eq!(store.get_basic().await, Some(Connectivity::Connecting));
+
+        store.set_err(&t.ctx, "Connection <refused>").await;
+        assert_eq!(store.get_basic().await, Some(Connectivity::NotConnected));
+        assert_eq!(store.to_html().await, "Error: Connection &lt;refused&gt;");
+
+        store.set_connected(&t.ctx).await;
+        assert_eq!(store.get_basic().await, Some(Connectivity::Connected));
+        let html = store.to_html().await;
+        assert!(html.starts_with("Connected<br>Last error at "));
+        assert!(html.ends_with(": Connection &lt;refused&gt;"));
+
+        store.set_not_configured(&t.ctx).await;
+        assert_eq!(store.get_basic().await, None);
+    }
+
+    #[async_std::test]
+    async fn test_connectivity_not_started() {
+        let t = dummy_context().await;
+        assert_eq!(t.ctx.get_connectivity().await, Connectivity::NotConnected);
+        assert!(t.ctx.get_connectivity_html().await.contains("Not started"));
+    }
+}
@@ -139,6 +139,8 @@ pub enum Chattype {
     Single = 100,
     Group = 120,
     VerifiedGroup = 130,
+    Mailinglist = 140,
+    Broadcast = 160,
 }
 
 impl Default for Chattype {
@@ -227,6 +229,28 @@ pub const DC_BOB_SUCCESS: i32 = 1;
 // max. width/height of an avatar
 pub const AVATAR_SIZE: u32 = 192;
 
+// max. size in bytes and number of pixels of photos imported from vCards
+pub const VCARD_PHOTO_MAX_BYTES: usize = 512 * 1024;
+pub const VCARD_PHOTO_MAX_PIXELS: u64 = 4_000_000;
+
+// max. width/height and JPEG quality of images sent with MediaQuality::Balanced
+pub const BALANCED_IMAGE_SIZE: u32 = 1280;
+pub const BALANCED_IMAGE_QUALITY: u8 = 85;
+
+// max. width/height and JPEG quality of images sent with MediaQuality::Worse
+pub const WORSE_IMAGE_SIZE: u32 = 640;
+pub const WORSE_IMAGE_QUALITY: u8 = 60;
+
+// max. width/height and JPEG quality of thumbnails of images, GIFs and stickers
+pub const THUMBNAIL_SIZE: u32 = 256;
+pub const THUMBNAIL_QUALITY: u8 = 75;
+
+// max. number of pixels of images to create thumbnails for, larger images are not decoded at all
+pub const THUMBNAIL_MAX_PIXELS: u64 = 25_000_000;
+
+// max. number of messages pinned in a chat
+pub const DC_MAX_PINNED_MSGS: usize = 5;
+
 // this value can be increased if the folder configuration is changed and must be redone on next program start
 pub const DC_FOLDERS_CONFIGURED_VERSION: i32 = 3;
 
@@ -292,6 +316,12 @@ pub enum Viewtype {
     /// The file is set via dc_msg_set_file()
     /// and retrieved via dc_msg_get_file().
     File = 60,
+
+    /// Message containing one or more contacts as vCard attachment.
+    /// The file is set via dc_msg_set_vcard()
+    /// and retrieved via dc_msg_get_file(),
+    /// the contacts can be added using dc_import_vcard().
+    Vcard = 90,
 }
 
 impl Default for Viewtype {
@@ -1,12 +1,13 @@
 //! Contacts module
 
-use async_std::path::PathBuf;
+use async_std::path::{Path, PathBuf};
 use deltachat_derive::*;
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use regex::Regex;
 
-use crate::aheader::EncryptPreference;
+use crate::aheader::{Aheader, EncryptPreference};
+use crate::blob::BlobObject;
 use crate::chat::ChatId;
 use crate::config::Config;
 use crate::constants::*;
@@ -14,13 +15,15 @@ use crate::context::Context;
 use crate::dc_tools::*;
 use crate::error::{bail, ensure, format_err, Result};
 use crate::events::Event;
-use crate::key::{DcKey, SignedPublicKey};
+use crate::key::{DcKey, Fingerprint, SignedPublicKey};
 use crate::login_param::LoginParam;
 use crate::message::{MessageState, MsgId};
 use crate::mimeparser::AvatarAction;
 use crate::param::*;
 use crate::peerstate::*;
+use crate::securejoin::handle_degrade_event;
 use crate::stock::StockMessage;
+use crate::vcard::{self, VcardContact};
 
 /// An object representing a single contact in memory.
 ///
@@ -159,6 +162,34 @@ pub enum VerifiedStatus {
     BidirectVerified = 2,
 }
 
+/// Kind of a key known for a contact, see [Contact::get_keys].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, FromPrimitive, ToPrimitive)]
+#[repr(u8)]
+pub enum ContactKeyType {
+    /// Key received in an Autocrypt header from the contact
+    /// or imported by the user.
+    Public = 1,
+
+    /// Key gossiped by another member of a group.
+    Gossip = 2,
+
+    /// Key verified by a QR code scan.
+    Verified = 3,
+}
+
+/// A public key of a contact, as returned by [Contact::get_keys].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContactKey {
+    pub typ: ContactKeyType,
+    pub fingerprint: Fingerprint,
+
+    /// Timestamp of the last message the key was seen in,
+    /// 0 for verified keys.
+    pub timestamp: i64,
+
+    pub key: SignedPublicKey,
+}
+
 impl Contact {
     pub async fn load_from_db(context: &Context, contact_id: u32) -> crate::sql::Result<Self> {
         let mut res = context
@@ -539,6 +570,125 @@ impl Contact {
         Ok(modify_cnt)
     }
 
+    /// Adds the entries of a vCard file to the address book.
+    ///
+    /// Each e-mail address of an entry is added as a contact with the
+    /// name of the entry.  The photo of the entry is used as profile
+    /// image for the preferred address unless the contact already has
+    /// one; photos which are no images or too large are ignored.
+    ///
+    /// Anyone can put any key into a vCard, so keys are only imported
+    /// if `import_keys` is set, which must only be done after the user
+    /// confirmed to trust the file.  Then, if no Autocrypt state is known
+    /// for the preferred address, the key of the entry is stored as gossip
+    /// key; existing keys are never replaced.
+    ///
+    /// Returns the IDs of the added or updated contacts.
+    pub async fn import_vcard(
+        context: &Context,
+        vcard: &str,
+        import_keys: bool,
+    ) -> Result<Vec<u32>> {
+        let mut contact_ids = Vec::new();
+
+        for entry in vcard::parse_vcard(vcard) {
+            let name = normalize_name(&entry.display_name);
+            for (i, addr) in entry.addrs.iter().enumerate() {
+                let contact_id =
+                    match Contact::add_or_lookup(context, &name, addr, Origin::AddressBook).await {
+                        Ok((contact_id, _)) => contact_id,
+                        Err(err) => {
+                            warn!(
+                                context,
+                                "Failed to add address {} from vCard: {}", addr, err
+                            );
+                            continue;
+                        }
+                    };
+                if !contact_ids.contains(&contact_id) {
+                    contact_ids.push(contact_id);
+                }
+                if i > 0 || contact_id == DC_CONTACT_ID_SELF {
+                    continue;
+                }
+
+                if let Some(photo) = &entry.photo {
+                    if let Err(err) = import_vcard_photo(context, contact_id, photo).await {
+                        warn!(
+                            context,
+                            "Failed to import vCard photo for {}: {}", addr, err
+                        );
+                    }
+                }
+                if let Some(key) = entry.key.as_ref().filter(|_| import_keys) {
+                    if let Err(err) = import_vcard_key(context, addr, key).await {
+                        warn!(context, "Failed to import vCard key for {}: {}", addr, err);
+                    }
+                }
+            }
+        }
+        if !contact_ids.is_empty() {
+            context.emit_event(Event::ContactsChanged(None));
+        }
+
+        Ok(contact_ids)
+    }
+
+    /// Adds the entries of a vCard file on disk to the address book,
+    /// see [Contact::import_vcard].
+    pub async fn import_vcard_file(
+        context: &Context,
+        path: impl AsRef<Path>,
+        import_keys: bool,
+    ) -> Result<Vec<u32>> {
+        let vcard = dc_read_file(context, path).await?;
+        let vcard = String::from_utf8(vcard)?;
+        Contact::import_vcard(context, &vcard, import_keys).await
+    }
+
+    /// Creates a vCard file with an entry for each of the given contacts.
+    ///
+    /// Besides name and address, the entries contain the profile image
+    /// and the Autocrypt key of the contacts, if known.
+    pub async fn make_vcard(context: &Context, contact_ids: &[u32]) -> Result<String> {
+        let mut entries = Vec::new();
+        for &contact_id in contact_ids {
+            let contact = Contact::load_from_db(context, contact_id).await?;
+            let key = if contact_id == DC_CONTACT_ID_SELF {
+                Some(SignedPublicKey::load_self(context).await?)
+            } else {
+                Peerstate::from_addr(context, &contact.addr)
+                    .await
+                    .and_then(|peerstate| {
+                        peerstate
+                            .peek_key(PeerstateVerifiedStatus::Unverified)
+                            .cloned()
+                    })
+            };
+            let photo = match contact.get_profile_image(context).await {
+                Some(path) => Some(base64::encode(&dc_read_file(context, path).await?)),
+                None => None,
+            };
+            let display_name = if contact_id == DC_CONTACT_ID_SELF {
+                context
+                    .get_config(Config::Displayname)
+                    .await
+                    .unwrap_or_default()
+            } else if contact.name.is_empty() {
+                contact.authname.clone()
+            } else {
+                contact.name.clone()
+            };
+            entries.push(VcardContact {
+                display_name,
+                addrs: vec![contact.addr],
+                key: key.map(|key| key.to_base64()),
+                photo,
+            });
+        }
+        Ok(vcard::make_vcard(&entries))
+    }
+
     /// Returns known and unblocked contacts.
     ///
     /// To get information about a single contact, see dc_get_contact().
@@ -740,6 +890,134 @@ impl Contact {
         Ok(ret)
     }
 
+    /// Returns the public, gossip and verified keys known for a contact.
+    ///
+    /// The list is empty if no key of the contact is known.
+    pub async fn get_keys(context: &Context, contact_id: u32) -> Result<Vec<ContactKey>> {
+        let contact = Contact::load_from_db(context, contact_id).await?;
+        let peerstate = match Peerstate::from_addr(context, &contact.addr).await {
+            Some(peerstate) => peerstate,
+            None => return Ok(Vec::new()),
+        };
+
+        let keys = vec![
+            (
+                ContactKeyType::Public,
+                peerstate.public_key,
+                peerstate.last_seen_autocrypt,
+            ),
+            (
+                ContactKeyType::Gossip,
+                peerstate.gossip_key,
+                peerstate.gossip_timestamp,
+            ),
+            (ContactKeyType::Verified, peerstate.verified_key, 0),
+        ];
+        Ok(keys
+            .into_iter()
+            .filter_map(|(typ, key, timestamp)| {
+                key.map(|key| ContactKey {
+                    typ,
+                    fingerprint: key.fingerprint(),
+                    timestamp,
+                    key,
+                })
+            })
+            .collect())
+    }
+
+    /// Returns the key of the given type as ASCII armored string,
+    /// `None` if there is no such key for the contact.
+    pub async fn export_key(
+        context: &Context,
+        contact_id: u32,
+        typ: ContactKeyType,
+    ) -> Result<Option<String>> {
+        Ok(Contact::get_keys(context, contact_id)
+            .await?
+            .into_iter()
+            .find(|k| k.typ == typ)
+            .map(|k| k.key.to_asc(None)))
+    }
+
+    /// Imports an ASCII armored public key for a contact.
+    ///
+    /// The key replaces the public key of the contact as if it was
+    /// received in an Autocrypt header just now, so messages to the
+    /// contact will be encrypted to it.  Gossip and verified keys are
+    /// not changed.
+    pub async fn import_key(
+        context: &Context,
+        contact_id: u32,
+        armored: &str,
+    ) -> Result<Fingerprint> {
+        ensure!(
+            contact_id > DC_CONTACT_ID_LAST_SPECIAL,
+            "Can not import keys for special contacts"
+        );
+        let contact = Contact::load_from_db(context, contact_id).await?;
+        let (key, _headers) = SignedPublicKey::from_asc(armored)?;
+        key.verify()?;
+        let fingerprint = key.fingerprint();
+
+        let (mut peerstate, create) = match Peerstate::from_addr(context, &contact.addr).await {
+            Some(peerstate) => (peerstate, false),
+            None => (Peerstate::new(context, contact.addr.clone()), true),
+        };
+        let now = time();
+        peerstate.last_seen = now;
+        peerstate.last_seen_autocrypt = now;
+        peerstate.prefer_encrypt = EncryptPreference::Mutual;
+        peerstate.public_key = Some(key);
+        peerstate.to_save = Some(ToSave::All);
+        peerstate.recalc_fingerprint();
+        peerstate.save_to_db(&context.sql, create).await?;
+        // a key replacing a different one is announced in the chat with the contact
+        if peerstate.degrade_event.is_some() {
+            handle_degrade_event(context, &peerstate).await?;
+        }
+
+        info!(
+            context,
+            "Imported key {} for contact {}.", fingerprint, contact_id
+        );
+        Ok(fingerprint)
+    }
+
+    /// Imports a public key for a contact from an ASCII armored file,
+    /// see [Contact::import_key].
+    pub async fn import_key_from_file(
+        context: &Context,
+        contact_id: u32,
+        path: impl AsRef<Path>,
+    ) -> Result<Fingerprint> {
+        let armored = dc_read_file(context, path).await?;
+        let armored = String::from_utf8(armored)?;
+        Contact::import_key(context, contact_id, &armored).await
+    }
+
+    /// Forgets all keys and the Autocrypt state of a contact.
+    ///
+    /// Messages to the contact are sent unencrypted until a new key is
+    /// received or imported.  This also removes the verification of
+    /// the contact.
+    pub async fn reset_peerstate(context: &Context, contact_id: u32) -> Result<()> {
+        ensure!(
+            contact_id > DC_CONTACT_ID_LAST_SPECIAL,
+            "Can not reset special contacts"
+        );
+        let contact = Contact::load_from_db(context, contact_id).await?;
+        context
+            .sql
+            .execute(
+                "DELETE FROM acpeerstates WHERE addr=? COLLATE NOCASE;",
+                paramsv![contact.addr],
+            )
+            .await?;
+        info!(context, "Reset peerstate of contact {}.", contact_id);
+        Ok(())
+    }
+
     /// Delete a contact. The contact is deleted from the local device. It may happen that this is not
     /// possible as the contact is in use. In this case, the contact can be blocked.
     ///
@@ -1091,7 +1369,8 @@ pub(crate) async fn set_profile_image(
     profile_image: &AvatarAction,
 ) -> Result<()> {
     // the given profile image is expected to be already in the blob directory
-    // as profile images can be set only by receiving messages, this should be always the case, however.
+    // as profile images can be set only by receiving messages or importing vCards,
+    // this should be always the case, however.
     let mut contact = Contact::load_from_db(context, contact_id).await?;
     let changed = match profile_image {
         AvatarAction::Change(profile_image) => {
@@ -1110,6 +1389,62 @@ pub(crate) async fn set_profile_image(
     Ok(())
 }
 
+/// Sets the photo of a vCard entry as profile image of a contact
+/// that has no profile image yet.
+async fn import_vcard_photo(context: &Context, contact_id: u32, photo: &str) -> Result<()> {
+    let contact = Contact::load_from_db(context, contact_id).await?;
+    if contact.param.exists(Param::ProfileImage) {
+        return Ok(());
+    }
+    let cleaned: String = photo.split_whitespace().collect();
+    ensure!(
+        cleaned.len() <= VCARD_PHOTO_MAX_BYTES / 3 * 4 + 4,
+        "vCard photo is too large"
+    );
+    let data = base64::decode(cleaned.as_bytes())?;
+    let jpeg = recode_vcard_photo(&data)?;
+    let blob = BlobObject::create(context, "avatar.jpg", &jpeg).await?;
+    set_profile_image(
+        context,
+        contact_id,
+        &AvatarAction::Change(blob.as_name().to_string()),
+    )
+    .await
+}
+
+/// Checks that the photo of a vCard entry is an image of limited size
+/// and returns it as JPEG scaled down to the size of avatars.
+fn recode_vcard_photo(data: &[u8]) -> Result<Vec<u8>> {
+    ensure!(
+        data.len() <= VCARD_PHOTO_MAX_BYTES,
+        "vCard photo is too large"
+    );
+    let (width, height) = dc_get_filemeta(data)?;
+    ensure!(
+        u64::from(width) * u64::from(height) <= VCARD_PHOTO_MAX_PIXELS,
+        "vCard photo has too many pixels"
+    );
+    let img = image::load_from_memory(data)?;
+    let img = img.thumbnail(AVATAR_SIZE, AVATAR_SIZE).to_rgb();
+    let mut jpeg = Vec::new();
+    image::DynamicImage::ImageRgb8(img).write_to(&mut jpeg, image::ImageOutputFormat::JPEG(85))?;
+    Ok(jpeg)
+}
+
+/// Stores the key of a vCard entry as gossip key
+/// if no Autocrypt state is known for the address yet.
+async fn import_vcard_key(context: &Context, addr: &str, key: &str) -> Result<()> {
+    if Peerstate::from_addr(context, addr).await.is_some() {
+        return Ok(());
+    }
+    let key = SignedPublicKey::from_base64(key)?;
+    key.verify()?;
+    let header = Aheader::new(addr.to_string(), key, EncryptPreference::NoPreference);
+    let peerstate = Peerstate::from_gossip(context, &header, time());
+    peerstate.save_to_db(&context.sql, true).await?;
+    Ok(())
+}
+
 /// Normalize a name.
 ///
 /// - Remove quotes (come from some bad MUA implementations)
@@ -1617,4 +1952,186 @@ mod tests {
             .await
             .is_err());
     }
+
+    #[async_std::test]
+    async fn test_contact_keys() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let contact_id = Contact::create(&t.ctx, "Bob", "bob@example.net")
+            .await
+            .unwrap();
+        assert!(Contact::get_keys(&t.ctx, contact_id)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            Contact::export_key(&t.ctx, contact_id, ContactKeyType::Public)
+                .await
+                .unwrap(),
+            None
+        );
+
+        let bob_key = bob_keypair().public;
+        let fingerprint = Contact::import_key(&t.ctx, contact_id, &bob_key.to_asc(None))
+            .await
+            .unwrap();
+        assert_eq!(fingerprint, bob_key.fingerprint());
+
+        let keys = Contact::get_keys(&t.ctx, contact_id).await.unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].typ, ContactKeyType::Public);
+        assert_eq!(keys[0].fingerprint, fingerprint);
+        assert!(keys[0].timestamp > 0);
+        let peerstate = Peerstate::from_addr(&t.ctx, "bob@example.net")
+            .await
+            .unwrap();
+        assert_eq!(peerstate.prefer_encrypt, EncryptPreference::Mutual);
+
+        let armored = Contact::export_key(&t.ctx, contact_id, ContactKeyType::Public)
+            .await
+            .unwrap()
+            .unwrap();
+        let (key, _) = SignedPublicKey::from_asc(&armored).unwrap();
+        assert_eq!(key, bob_key);
+        assert_eq!(
+            Contact::export_key(&t.ctx, contact_id, ContactKeyType::Verified)
+                .await
+                .unwrap(),
+            None
+        );
+
+        // importing the same key again changes nothing
+        Contact::import_key(&t.ctx, contact_id, &armored)
+            .await
+            .unwrap();
+        let (chat_id, _) = crate::chat::lookup_by_contact_id(&t.ctx, contact_id)
+            .await
+            .unwrap_or_default();
+        assert!(chat_id.is_unset());
+
+        // a different key is announced in the chat with the contact
+        Contact::import_key(&t.ctx, contact_id, &alice_keypair().public.to_asc(None))
+            .await
+            .unwrap();
+        let (chat_id, _) = crate::chat::lookup_by_contact_id(&t.ctx, contact_id)
+            .await
+            .unwrap();
+        let msgs = crate::chat::get_chat_msgs(&t.ctx, chat_id, 0, None).await;
+        assert_eq!(msgs.len(), 1);
+        let msg = crate::message::Message::load_from_db(&t.ctx, msgs[0])
+            .await
+            .unwrap();
+        assert!(msg.is_info());
+        assert_eq!(msg.get_text().unwrap(), "Changed setup for bob@example.net");
+
+        assert!(Contact::import_key(&t.ctx, contact_id, "not a key")
+            .await
+            .is_err());
+        assert!(Contact::import_key(&t.ctx, DC_CONTACT_ID_SELF, &armored)
+            .await
+            .is_err());
+
+        Contact::reset_peerstate(&t.ctx, contact_id).await.unwrap();
+        assert!(Contact::get_keys(&t.ctx, contact_id)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(Peerstate::from_addr(&t.ctx, "bob@example.net")
+            .await
+            .is_none());
+    }
+
+    #[async_std::test]
+    async fn test_vcard() {
+        let t = dummy_context().await;
+        let bob_key = bob_keypair().public;
+        let vcard = format!(
+            "BEGIN:VCARD\r\n\
+             VERSION:4.0\r\n\
+             FN:Bob\r\n\
+             EMAIL:bob@example.net\r\n\
+             EMAIL:bob@example.org\r\n\
+             KEY:data:application/pgp-keys;base64,{}\r\n\
+             END:VCARD\r\n\
+             BEGIN:VCARD\r\n\
+             VERSION:3.0\r\n\
+             N:Miller;Claire;;;\r\n\
+             EMAIL;TYPE=INTERNET:claire@example.org\r\n\
+             END:VCARD\r\n",
+            bob_key.to_base64()
+        );
+
+        // keys are only imported if requested
+        let contact_ids = Contact::import_vcard(&t.ctx, &vcard, false).await.unwrap();
+        assert_eq!(contact_ids.len(), 3);
+        assert!(Contact::get_keys(&t.ctx, contact_ids[0])
+            .await
+            .unwrap()
+            .is_empty());
+
+        let contact_ids = Contact::import_vcard(&t.ctx, &vcard, true).await.unwrap();
+        assert_eq!(contact_ids.len(), 3);
+        let bob = Contact::load_from_db(&t.ctx, contact_ids[0]).await.unwrap();
+        assert_eq!(bob.get_name(), "Bob");
+        assert_eq!(bob.get_addr(), "bob@example.net");
+        let claire = Contact::load_from_db(&t.ctx, contact_ids[2]).await.unwrap();
+        assert_eq!(claire.get_name(), "Claire Miller");
+
+        // the key is known as gossip key only
+        let keys = Contact::get_keys(&t.ctx, bob.id).await.unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].typ, ContactKeyType::Gossip);
+        assert_eq!(keys[0].fingerprint, bob_key.fingerprint());
+        assert!(Contact::get_keys(&t.ctx, contact_ids[1])
+            .await
+            .unwrap()
+            .is_empty());
+
+        let vcard = Contact::make_vcard(&t.ctx, &[bob.id, claire.id])
+            .await
+            .unwrap();
+        let entries = vcard::parse_vcard(&vcard);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].display_name, "Bob");
+        assert_eq!(entries[0].addrs, vec!["bob@example.net".to_string()]);
+        assert_eq!(entries[0].key, Some(bob_key.to_base64()));
+        assert_eq!(entries[1].display_name, "Claire Miller");
+        assert_eq!(entries[1].key, None);
+    }
+
+    #[async_std::test]
+    async fn test_vcard_photo() {
+        use image::GenericImageView;
+
+        let t = dummy_context().await;
+        let vcard = format!(
+            "BEGIN:VCARD\r\n\
+             VERSION:4.0\r\n\
+             FN:Bob\r\n\
+             EMAIL:bob@example.net\r\n\
+             PHOTO:data:image/jpeg;base64,{}\r\n\
+             END:VCARD\r\n\
+             BEGIN:VCARD\r\n\
+             VERSION:4.0\r\n\
+             FN:Claire\r\n\
+             EMAIL:claire@example.org\r\n\
+             PHOTO:data:image/jpeg;base64,{}\r\n\
+             END:VCARD\r\n",
+            base64::encode(&include_bytes!("../test-data/image/avatar900x900.png")[..]),
+            base64::encode(b"no image")
+        );
+
+        let contact_ids = Contact::import_vcard(&t.ctx, &vcard, false).await.unwrap();
+        assert_eq!(contact_ids.len(), 2);
+
+        // the photo is recoded to a JPEG of avatar size
+        let bob = Contact::load_from_db(&t.ctx, contact_ids[0]).await.unwrap();
+        let avatar = bob.get_profile_image(&t.ctx).await.unwrap();
+        let img = image::open(avatar).unwrap();
+        assert_eq!(img.dimensions(), (AVATAR_SIZE, AVATAR_SIZE));
+
+        // other data is ignored
+        let claire = Contact::load_from_db(&t.ctx, contact_ids[1]).await.unwrap();
+        assert!(claire.get_profile_image(&t.ctx).await.is_none());
+    }
 }
@@ -6,12 +6,14 @@ use std::ops::Deref;
 
 use async_std::path::{Path, PathBuf};
 use async_std::sync::{channel, Arc, Mutex, Receiver, RwLock, Sender};
+use async_std::task;
 
 use crate::chat::*;
 use crate::config::Config;
 use crate::constants::*;
 use crate::contact::*;
 use crate::dc_tools::duration_to_str;
+use crate::ephemeral::schedule_ephemeral_task;
 use crate::error::*;
 use crate::events::{Event, EventEmitter, Events};
 use crate::job::{self, Action};
@@ -21,6 +23,7 @@ use crate::lot::Lot;
 use crate::message::{self, Message, MessengerMessage, MsgId};
 use crate::param::Params;
 use crate::scheduler::Scheduler;
+use crate::search;
 use crate::sql::Sql;
 use std::time::SystemTime;
 
@@ -56,6 +59,7 @@ pub struct InnerContext {
     pub(crate) events: Events,
 
     pub(crate) scheduler: RwLock<Scheduler>,
+    pub(crate) ephemeral_task: RwLock<Option<task::JoinHandle<()>>>,
 
     creation_time: SystemTime,
 }
@@ -121,6 +125,7 @@ impl Context {
             translated_stockstrings: RwLock::new(HashMap::new()),
             events: Events::default(),
             scheduler: RwLock::new(Scheduler::Stopped),
+            ephemeral_task: RwLock::new(None),
             creation_time: std::time::SystemTime::now(),
         };
 
@@ -147,6 +152,8 @@ impl Context {
             let l = &mut *self.inner.scheduler.write().await;
             l.start(self.clone()).await;
         }
+
+        schedule_ephemeral_task(self).await;
     }
 
     /// Returns if the IO scheduler is running.
@@ -390,10 +397,10 @@ impl Context {
     #[allow(non_snake_case)]
     pub async fn search_msgs(&self, chat_id: ChatId, query: impl AsRef<str>) -> Vec<MsgId> {
         let real_query = query.as_ref().trim();
-        if real_query.is_empty() {
-            return Vec::new();
-        }
-        let strLikeInText = format!("%{}%", real_query);
+        let fts_query = match search::fts_query(real_query) {
+            Some(fts_query) => fts_query,
+            None => return Vec::new(),
+        };
         let strLikeBeg = format!("{}%", real_query);
 
         let query = if !chat_id.is_unset() {
@@ -405,7 +412,7 @@ impl Context {
                 " WHERE m.chat_id=?",
                 "   AND m.hidden=0",
                 "   AND ct.blocked=0",
-                "   AND (txt LIKE ? OR ct.name LIKE ?)",
+                "   AND (m.id IN (SELECT rowid FROM msgs_fts WHERE msgs_fts MATCH ?) OR ct.name LIKE ?)",
                 " ORDER BY m.timestamp,m.id;"
             )
         } else {
@@ -420,7 +427,7 @@ impl Context {
                 "   AND m.hidden=0",
                 "   AND (c.blocked=0 OR c.blocked=?)",
                 "   AND ct.blocked=0",
-                "   AND (m.txt LIKE ? OR ct.name LIKE ?)",
+                "   AND (m.id IN (SELECT rowid FROM msgs_fts WHERE msgs_fts MATCH ?) OR ct.name LIKE ?)",
                 " ORDER BY m.timestamp DESC,m.id DESC;"
             )
         };
@@ -428,7 +435,7 @@ impl Context {
         self.sql
             .query_map(
                 query,
-                paramsv![chat_id, strLikeInText, strLikeBeg],
+                paramsv![chat_id, fts_query, strLikeBeg],
                 |row| row.get::<_, MsgId>("id"),
                 |rows| {
                     let mut ret = Vec::new();
@@ -515,6 +522,10 @@ impl InnerContext {
             let lock = &mut *self.scheduler.write().await;
             lock.stop(token).await;
         }
+
+        if let Some(ephemeral_task) = self.ephemeral_task.write().await.take() {
+            ephemeral_task.cancel().await;
+        }
     }
 }
 
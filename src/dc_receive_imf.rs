@@ -10,14 +10,20 @@ use crate::constants::*;
 use crate::contact::*;
 use crate::context::Context;
 use crate::dc_tools::*;
+use crate::ephemeral::{
+    schedule_ephemeral_task, stock_ephemeral_timer_changed, Timer as EphemeralTimer,
+};
 use crate::error::{bail, ensure, Result};
 use crate::events::Event;
+use crate::group_sync;
 use crate::headerdef::HeaderDef;
 use crate::job::{self, Action};
+use crate::mailinglist;
 use crate::message::{self, MessageState, MessengerMessage, MsgId};
 use crate::mimeparser::*;
 use crate::param::*;
 use crate::peerstate::*;
+use crate::reaction::set_msg_reaction;
 use crate::securejoin::{self, handle_securejoin_handshake, observe_securejoin_on_other_device};
 use crate::stock::StockMessage;
 use crate::{contact, location};
@@ -341,7 +347,11 @@ async fn add_parts(
     } else {
         MessengerMessage::No
     };
-    // incoming non-chat messages may be discarded
+    let mailinglist = mime_parser
+        .get(HeaderDef::ListId)
+        .and_then(|list_id| mailinglist::parse_list_id(list_id));
+
+    // incoming non-chat messages may be discarded, this includes mailing lists
     let mut allow_creation = true;
     let show_emails =
         ShowEmails::from_i32(context.get_config_int(Config::ShowEmails).await).unwrap_or_default();
@@ -407,6 +417,21 @@ async fn add_parts(
                 .await
                 .unwrap_or_default();
 
+        if chat_id.is_unset() {
+            if let Some((list_id, list_name)) = &mailinglist {
+                let (new_chat_id, new_chat_id_blocked) = create_or_lookup_mailinglist(
+                    context,
+                    allow_creation,
+                    list_id,
+                    list_name,
+                    mime_parser,
+                )
+                .await?;
+                *chat_id = new_chat_id;
+                chat_id_blocked = new_chat_id_blocked;
+            }
+        }
+
         // get the chat_id - a chat_id here is no indicator that the chat is displayed in the normal list,
         // it might also be blocked and displayed in the deaddrop as a result
         if chat_id.is_unset() {
@@ -501,6 +526,7 @@ async fn add_parts(
             && state == MessageState::InFresh
             && !incoming_origin.is_known()
             && msgrmsg == MessengerMessage::No
+            && mailinglist.is_none()
             && show_emails != ShowEmails::All
         {
             state = MessageState::InNoticed;
@@ -534,6 +560,27 @@ async fn add_parts(
             }
         }
 
+        if chat_id.is_unset() {
+            // own posts to a mailing list may come back from the list
+            if let Some((list_id, list_name)) = &mailinglist {
+                let (new_chat_id, new_chat_id_blocked) =
+                    create_or_lookup_mailinglist(context, false, list_id, list_name, mime_parser)
+                        .await?;
+                *chat_id = new_chat_id;
+                chat_id_blocked = new_chat_id_blocked;
+            }
+        }
+
+        if chat_id.is_unset() && to_ids.is_empty() {
+            // messages sent to a broadcast list from another device have hidden recipients
+            if let Some(grpid) = mime_parser.get(HeaderDef::ChatGroupId) {
+                if let Some((id, blocked)) = lookup_broadcast_list(context, grpid).await {
+                    *chat_id = id;
+                    chat_id_blocked = blocked;
+                }
+            }
+        }
+
         if !to_ids.is_empty() {
             if chat_id.is_unset() {
                 let (new_chat_id, new_chat_id_blocked) = create_or_lookup_group(
@@ -599,6 +646,75 @@ async fn add_parts(
             *chat_id = ChatId::new(DC_CHAT_ID_TRASH);
         }
     }
+    // reactions, edit and delete requests are not added to the chat,
+    // they are applied to the messages they refer to;
+    // member corrections are applied to the group in create_or_lookup_group()
+    let first_text = mime_parser
+        .parts
+        .first()
+        .map(|part| part.msg.as_str())
+        .unwrap_or_default();
+    let request_signed = mime_parser.was_encrypted() || mime_parser.signed_only;
+    let mut is_request = true;
+    if mime_parser.is_system_message == SystemMessage::Reaction {
+        if !chat_id.is_special() {
+            if let Some(in_reply_to) = mime_parser.get(HeaderDef::InReplyTo) {
+                if let Err(err) =
+                    set_msg_reaction(context, in_reply_to, *chat_id, from_id, first_text).await
+                {
+                    warn!(context, "Cannot set reaction: {}", err);
+                }
+            }
+        }
+    } else if let Some(rfc724_mid) = mime_parser.get(HeaderDef::ChatEdit) {
+        if !chat_id.is_special() {
+            if let Err(err) = message::apply_edit_request(
+                context,
+                *chat_id,
+                rfc724_mid,
+                from_id,
+                request_signed,
+                first_text,
+            )
+            .await
+            {
+                warn!(context, "Cannot apply edit request: {}", err);
+            }
+        }
+    } else if let Some(mid_list) = mime_parser.get(HeaderDef::ChatDelete) {
+        if !chat_id.is_special() {
+            if let Err(err) =
+                message::apply_delete_request(context, *chat_id, mid_list, from_id, request_signed)
+                    .await
+            {
+                warn!(context, "Cannot apply delete request: {}", err);
+            }
+        }
+    } else if let Some(rfc724_mid) = mime_parser.get(HeaderDef::ChatPin) {
+        if let Err(err) =
+            chat::apply_pin_request(context, *chat_id, rfc724_mid, from_id, true).await
+        {
+            warn!(context, "Cannot apply pin request: {}", err);
+        }
+    } else if let Some(rfc724_mid) = mime_parser.get(HeaderDef::ChatUnpin) {
+        if let Err(err) =
+            chat::apply_pin_request(context, *chat_id, rfc724_mid, from_id, false).await
+        {
+            warn!(context, "Cannot apply unpin request: {}", err);
+        }
+    } else if mime_parser.is_system_message == SystemMessage::GroupMemberCorrection {
+        // nothing more to do
+    } else {
+        is_request = false;
+    }
+    if is_request {
+        *chat_id = ChatId::new(DC_CHAT_ID_TRASH);
+        *hidden = true;
+        if incoming {
+            state = MessageState::InSeen;
+        }
+    }
+
     // correct message_timestamp, it should not be used before,
     // however, we cannot do this earlier as we need from_id to be set
     calc_timestamps(
@@ -616,6 +732,65 @@ async fn add_parts(
     // unarchive chat
     chat_id.unarchive(context).await?;
 
+    // Extract ephemeral timer from the message.
+    let ephemeral_timer = if let Some(value) = mime_parser.get(HeaderDef::EphemeralTimer) {
+        match value.parse::<EphemeralTimer>() {
+            Ok(timer) => Some(timer),
+            Err(err) => {
+                warn!(
+                    context,
+                    "can't parse ephemeral timer \"{}\": {}", value, err
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // Apply ephemeral timer changes to the chat.
+    if let Some(timer) = ephemeral_timer {
+        if !chat_id.is_special() && chat_id.get_ephemeral_timer(context).await? != timer {
+            info!(
+                context,
+                "received new ephemeral timer value {} for chat {}", timer, chat_id
+            );
+            if let Err(err) = chat_id.inner_set_ephemeral_timer(context, timer).await {
+                warn!(
+                    context,
+                    "failed to modify timer for chat {}: {}", chat_id, err
+                );
+            } else if mime_parser.is_system_message != SystemMessage::EphemeralTimerChanged {
+                // The timer was changed implicitly by a message
+                // sent with a different timer, inform the user.
+                chat::add_info_msg(
+                    context,
+                    *chat_id,
+                    stock_ephemeral_timer_changed(context, timer, from_id).await,
+                )
+                .await;
+            }
+        }
+    }
+
+    if mime_parser.is_system_message == SystemMessage::EphemeralTimerChanged {
+        set_better_msg(
+            mime_parser,
+            stock_ephemeral_timer_changed(context, ephemeral_timer.unwrap_or_default(), from_id)
+                .await,
+        );
+    }
+
+    // The timer of outgoing messages and of messages already seen
+    // on another device starts right away.
+    let ephemeral_timer = ephemeral_timer.unwrap_or_default();
+    let ephemeral_timestamp = match ephemeral_timer {
+        EphemeralTimer::Enabled { duration } if !incoming || state == MessageState::InSeen => {
+            rcvd_timestamp + i64::from(duration)
+        }
+        _ => 0,
+    };
+
     // if the mime-headers should be saved, find out its size
     // (the mime-header ends with an empty line)
     let save_mime_headers = context.get_config_bool(Config::SaveMimeHeaders).await;
@@ -648,6 +823,7 @@ async fn add_parts(
     let is_hidden = *hidden;
     let chat_id = *chat_id;
     let is_mdn = !mime_parser.reports.is_empty();
+    let mut quoted_text = mime_parser.quote.take();
 
     // TODO: can this clone be avoided?
     let rfc724_mid = rfc724_mid.to_string();
@@ -663,15 +839,20 @@ async fn add_parts(
                 let mut stmt = conn.prepare_cached(
                     "INSERT INTO msgs \
          (rfc724_mid, server_folder, server_uid, chat_id, from_id, to_id, timestamp, \
-         timestamp_sent, timestamp_rcvd, type, state, msgrmsg,  txt, txt_raw, param, \
-         bytes, hidden, mime_headers,  mime_in_reply_to, mime_references) \
-         VALUES (?,?,?,?,?,?, ?,?,?,?,?,?, ?,?,?,?,?,?, ?,?);",
+         timestamp_sent, timestamp_rcvd, type, state, msgrmsg,  txt, quoted_text, txt_raw, param, \
+         bytes, hidden, mime_headers,  mime_in_reply_to, mime_references, \
+         ephemeral_timer, ephemeral_timestamp) \
+         VALUES (?,?,?,?,?,?, ?,?,?,?,?,?, ?,?,?,?,?,?, ?,?,?,?,?);",
                 )?;
 
                 let is_location_kml = location_kml_is
                     && icnt == 1
                     && (part.msg == "-location-" || part.msg.is_empty());
 
+                if part.param.exists(Param::HeldBack) {
+                    is_hidden = true;
+                }
+
                 if is_mdn || is_location_kml {
                     is_hidden = true;
                     if state == MessageState::InFresh {
@@ -701,6 +882,8 @@ async fn add_parts(
                     state,
                     msgrmsg,
                     part.msg,
+                    // only the first part carries the quote
+                    quoted_text.take().unwrap_or_default(),
                     // txt_raw might contain invalid utf8
                     txt_raw,
                     part.param.to_string(),
@@ -709,6 +892,8 @@ async fn add_parts(
                     mime_headers,
                     mime_in_reply_to,
                     mime_references,
+                    ephemeral_timer,
+                    ephemeral_timestamp,
                 ])?;
 
                 drop(stmt);
@@ -727,6 +912,24 @@ async fn add_parts(
         *insert_msg_id = *id;
     }
 
+    // member-added/removed messages are stored for group member corrections
+    if (is_system_message == SystemMessage::MemberAddedToGroup
+        || is_system_message == SystemMessage::MemberRemovedFromGroup)
+        && !chat_id.is_special()
+    {
+        if let Err(err) = group_sync::save_received_member_change(
+            context,
+            chat_id,
+            mime_parser,
+            sent_timestamp,
+            imf_raw,
+        )
+        .await
+        {
+            warn!(context, "Cannot save member change: {}", err);
+        }
+    }
+
     *hidden = is_hidden;
     created_db_entries.extend(ids.iter().map(|id| (chat_id, *id)));
     mime_parser.parts = new_parts;
@@ -736,6 +939,10 @@ async fn add_parts(
         "Message has {} parts and is assigned to chat #{}.", icnt, chat_id,
     );
 
+    if ephemeral_timestamp != 0 {
+        schedule_ephemeral_task(context).await;
+    }
+
     // check event to send
     if chat_id.is_trash() || *hidden {
         *create_event_to_send = None;
@@ -849,6 +1056,17 @@ async fn calc_timestamps(
     }
 }
 
+/// Returns the broadcast list with the given group ID, if any.
+async fn lookup_broadcast_list(context: &Context, grpid: &str) -> Option<(ChatId, Blocked)> {
+    let (chat_id, _, blocked) = chat::get_chat_id_by_grpid(context, grpid).await.ok()?;
+    let chat = Chat::load_from_db(context, chat_id).await.ok()?;
+    if chat.typ == Chattype::Broadcast {
+        Some((chat_id, blocked))
+    } else {
+        None
+    }
+}
+
 /// This function tries extracts the group-id from the message and returns the
 /// corresponding chat_id. If the chat_id is not existent, it is created.
 /// If the message contains groups commands (name, profile image, changed members),
@@ -929,6 +1147,7 @@ async fn create_or_lookup_group(
             warn!(context, "removed {:?} has no contact_id", removed_addr);
         } else {
             mime_parser.is_system_message = SystemMessage::MemberRemovedFromGroup;
+            set_changed_member(mime_parser, &removed_addr);
             better_msg = context
                 .stock_system_msg(
                     if removed_id == from_id as u32 {
@@ -946,6 +1165,7 @@ async fn create_or_lookup_group(
         let field = mime_parser.get(HeaderDef::ChatGroupMemberAdded).cloned();
         if let Some(optional_field) = field {
             mime_parser.is_system_message = SystemMessage::MemberAddedToGroup;
+            set_changed_member(mime_parser, &optional_field);
             better_msg = context
                 .stock_system_msg(
                     StockMessage::MsgAddMember,
@@ -998,17 +1218,25 @@ async fn create_or_lookup_group(
     set_better_msg(mime_parser, &better_msg);
 
     // check, if we have a chat with this group ID
-    let (mut chat_id, chat_id_verified, _blocked) = chat::get_chat_id_by_grpid(context, &grpid)
+    let (mut chat_id, chat_id_verified, blocked) = chat::get_chat_id_by_grpid(context, &grpid)
         .await
         .unwrap_or((ChatId::new(0), false, Blocked::Not));
+    let chat_existed = !chat_id.is_error();
     if !chat_id.is_error() {
         if chat_id_verified {
             if let Err(err) =
                 check_verified_properties(context, mime_parser, from_id as u32, to_ids).await
             {
                 warn!(context, "verification problem: {}", err);
-                let s = format!("{}. See 'Info' for more details", err);
-                mime_parser.repl_msg_by_error(s);
+                if let Some(fingerprint) =
+                    get_changed_sender_key(context, mime_parser, from_id as u32).await
+                {
+                    hold_back_msg(context, mime_parser, chat_id, from_id as u32, &fingerprint)
+                        .await;
+                } else {
+                    let s = format!("{}. See 'Info' for more details", err);
+                    mime_parser.repl_msg_by_error(s);
+                }
             }
         }
         if !chat::is_contact_in_chat(context, chat_id, from_id as u32).await {
@@ -1143,6 +1371,31 @@ async fn create_or_lookup_group(
         }
     }
 
+    // compare the member list of the sender with our own view before applying changes,
+    // corrections are not sent to contact requests
+    let mut member_corrections = Vec::new();
+    if chat_existed
+        && blocked == Blocked::Not
+        && from_id != DC_CONTACT_ID_SELF
+        && chat::is_contact_in_chat(context, chat_id, DC_CONTACT_ID_SELF).await
+        && chat::is_contact_in_chat(context, chat_id, from_id).await
+    {
+        let changed_id = if let Some(ref added_addr) = X_MrAddToGrp {
+            Contact::lookup_id_by_addr(context, added_addr, Origin::Unknown).await
+        } else {
+            removed_id
+        };
+        if changed_id > DC_CONTACT_ID_LAST_SPECIAL {
+            let to_ids: Vec<u32> = to_ids.iter().copied().collect();
+            match group_sync::check_member_list(context, chat_id, from_id, &to_ids, changed_id)
+                .await
+            {
+                Ok(msg_ids) => member_corrections = msg_ids,
+                Err(err) => warn!(context, "Cannot check member list: {}", err),
+            }
+        }
+    }
+
     // add members to group/check members
     if recreate_member_list {
         if !chat::is_contact_in_chat(context, chat_id, DC_CONTACT_ID_SELF).await {
@@ -1180,6 +1433,25 @@ async fn create_or_lookup_group(
         send_EVENT_CHAT_MODIFIED = true;
     }
 
+    if mime_parser.is_system_message == SystemMessage::GroupMemberCorrection
+        && chat::is_contact_in_chat(context, chat_id, from_id).await
+    {
+        match group_sync::apply_member_changes(context, chat_id, &mime_parser.group_member_changes)
+            .await
+        {
+            Ok(modified) => send_EVENT_CHAT_MODIFIED |= modified,
+            Err(err) => warn!(context, "Cannot apply member correction: {}", err),
+        }
+    }
+
+    if !member_corrections.is_empty() {
+        if let Err(err) =
+            group_sync::send_member_correction(context, chat_id, &member_corrections).await
+        {
+            warn!(context, "Cannot send member correction: {}", err);
+        }
+    }
+
     if send_EVENT_CHAT_MODIFIED {
         context.emit_event(Event::ChatModified(chat_id));
     }
@@ -1355,6 +1627,94 @@ async fn create_group_record(
     chat_id
 }
 
+/// Returns the chat of the mailing list a message belongs to.
+///
+/// If there is no chat for the list yet, it is created as a contact
+/// request if `allow_creation` is set.  The `List-Post` and
+/// `List-Unsubscribe` headers are stored with every message, as lists
+/// may change them.
+async fn create_or_lookup_mailinglist(
+    context: &Context,
+    allow_creation: bool,
+    list_id: &str,
+    list_name: &str,
+    mime_parser: &MimeMessage,
+) -> Result<(ChatId, Blocked)> {
+    let res = context
+        .sql
+        .query_row_optional(
+            "SELECT id, blocked FROM chats WHERE type=? AND grpid=?;",
+            paramsv![Chattype::Mailinglist, list_id],
+            |row| {
+                let chat_id: ChatId = row.get(0)?;
+                let blocked = row.get::<_, Option<Blocked>>(1)?.unwrap_or_default();
+                Ok((chat_id, blocked))
+            },
+        )
+        .await?;
+    let (chat_id, blocked, created) = match res {
+        Some((chat_id, blocked)) => (chat_id, blocked, false),
+        None if allow_creation => {
+            context
+                .sql
+                .execute(
+                    "INSERT INTO chats (type, name, grpid, blocked, created_timestamp) VALUES(?, ?, ?, ?, ?);",
+                    paramsv![
+                        Chattype::Mailinglist,
+                        list_name,
+                        list_id,
+                        Blocked::Deaddrop,
+                        time()
+                    ],
+                )
+                .await?;
+            let chat_id = ChatId::new(
+                context
+                    .sql
+                    .get_rowid(context, "chats", "grpid", list_id)
+                    .await?,
+            );
+            info!(
+                context,
+                "Created mailing list '{}' list_id={} as {}", list_name, list_id, chat_id
+            );
+            (chat_id, Blocked::Deaddrop, true)
+        }
+        None => return Ok((ChatId::new(0), Blocked::Not)),
+    };
+
+    let mut chat = Chat::load_from_db(context, chat_id).await?;
+
+    // anyone can send a message with the List-Id of the list,
+    // do not take the headers from messages that obviously do not come from the list.
+    // the Return-Path can be forged as well, so this is no protection.
+    let return_path = mime_parser.get(HeaderDef::ReturnPath);
+    if !created
+        && !return_path.map_or(false, |return_path| {
+            mailinglist::is_from_list(list_id, chat.param.get(Param::ListPost), return_path)
+        })
+    {
+        return Ok((chat_id, blocked));
+    }
+
+    // a message without the headers does not remove them
+    let old_param = chat.param.clone();
+    if let Some(addr) = mime_parser
+        .get(HeaderDef::ListPost)
+        .and_then(|value| mailinglist::parse_list_post(value))
+    {
+        chat.param.set(Param::ListPost, addr);
+    }
+    if let Some(value) = mime_parser.get(HeaderDef::ListUnsubscribe) {
+        chat.param.set(Param::ListUnsubscribe, value);
+    }
+    if chat.param != old_param {
+        chat.update_param(context).await?;
+    }
+
+    Ok((chat_id, blocked))
+}
+
 async fn create_adhoc_grp_id(context: &Context, member_ids: &[u32]) -> String {
     /* algorithm:
     - sort normalized, lowercased, e-mail addresses alphabetically
@@ -1573,6 +1933,69 @@ async fn check_verified_properties(
     Ok(())
 }
 
+/// Returns the fingerprint of the sender's current key
+/// if the message is signed with it and the sender was verified with another key.
+async fn get_changed_sender_key(
+    context: &Context,
+    mimeparser: &MimeMessage,
+    from_id: u32,
+) -> Option<String> {
+    if from_id <= DC_CONTACT_ID_LAST_SPECIAL || !mimeparser.was_encrypted() {
+        return None;
+    }
+    let contact = Contact::load_from_db(context, from_id).await.ok()?;
+    let peerstate = Peerstate::from_addr(context, contact.get_addr()).await?;
+    let verified_fingerprint = peerstate.verified_key_fingerprint?;
+    let public_fingerprint = peerstate.public_key_fingerprint?;
+    if public_fingerprint != verified_fingerprint
+        && mimeparser.signatures.contains(&public_fingerprint)
+    {
+        Some(public_fingerprint)
+    } else {
+        None
+    }
+}
+
+/// Hides a message sent to a verified group with a new key of the sender
+/// until the sender is verified again with this key,
+/// see [securejoin::release_held_msgs].
+async fn hold_back_msg(
+    context: &Context,
+    mime_parser: &mut MimeMessage,
+    chat_id: ChatId,
+    from_id: u32,
+    fingerprint: &str,
+) {
+    let already_held = securejoin::get_held_msgs(context, from_id)
+        .await
+        .unwrap_or_default()
+        .iter()
+        .any(|(_, held_chat_id, _)| *held_chat_id == chat_id);
+    if !already_held {
+        if let Ok(contact) = Contact::load_from_db(context, from_id).await {
+            let msg = context
+                .stock_string_repl_str(StockMessage::MsgHeldBack, contact.get_addr())
+                .await;
+            chat::add_info_msg(context, chat_id, msg).await;
+        }
+    }
+    info!(
+        context,
+        "Holding back message of contact {} signed with {}.", from_id, fingerprint
+    );
+    for part in mime_parser.parts.iter_mut() {
+        part.param.set(Param::HeldBack, fingerprint);
+    }
+}
+
+/// Remembers the added or removed member in the parts of a member-added/removed message,
+/// as for sent messages, the address is stored in [Param::Arg].
+fn set_changed_member(mime_parser: &mut MimeMessage, addr: &str) {
+    for part in mime_parser.parts.iter_mut() {
+        part.param.set(Param::Arg, addr);
+    }
+}
+
 fn set_better_msg(mime_parser: &mut MimeMessage, better_msg: impl AsRef<str>) {
     let msg = better_msg.as_ref();
     if !msg.is_empty() && !mime_parser.parts.is_empty() {
@@ -1740,7 +2163,7 @@ mod tests {
     use crate::chat::ChatVisibility;
     use crate::chatlist::Chatlist;
     use crate::message::Message;
-    use crate::test_utils::{dummy_context, TestContext};
+    use crate::test_utils::{configure_alice_keypair, dummy_context, TestContext};
 
     #[test]
     fn test_hex_hash() {
@@ -2314,4 +2737,684 @@ mod tests {
             "Carl"
         );
     }
+
+    #[async_std::test]
+    async fn test_ephemeral_timer_header() {
+        let t = configured_offline_context().await;
+        let contact_id = Contact::create(&t.ctx, "foobar", "foobar@example.com")
+            .await
+            .unwrap();
+
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Foobar <foobar@example.com>\n\
+                 To: alice@example.org\n\
+                 Subject: foo\n\
+                 Message-ID: <ephemeral1@example.org>\n\
+                 Chat-Version: 1.0\n\
+                 Ephemeral-Timer: 60\n\
+                 Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+                 \n\
+                 hello\n",
+            "INBOX",
+            1,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let (chat_id, _) = chat::lookup_by_contact_id(&t.ctx, contact_id)
+            .await
+            .unwrap();
+        assert_eq!(
+            chat_id.get_ephemeral_timer(&t.ctx).await.unwrap(),
+            EphemeralTimer::Enabled { duration: 60 }
+        );
+
+        let msg_id = *chat::get_chat_msgs(&t.ctx, chat_id, 0, None)
+            .await
+            .last()
+            .unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert_eq!(msg.get_text().unwrap(), "hello");
+        assert_eq!(
+            msg.get_ephemeral_timer(),
+            EphemeralTimer::Enabled { duration: 60 }
+        );
+        // The timer is not started until the message is seen.
+        assert_eq!(msg.get_ephemeral_timestamp(), 0);
+
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Foobar <foobar@example.com>\n\
+                 To: alice@example.org\n\
+                 Subject: foo\n\
+                 Message-ID: <ephemeral2@example.org>\n\
+                 Chat-Version: 1.0\n\
+                 Chat-Content: ephemeral-timer-changed\n\
+                 Ephemeral-Timer: 0\n\
+                 Date: Sun, 22 Mar 2020 22:38:57 +0000\n\
+                 \n\
+                 Message deletion timer is disabled.\n",
+            "INBOX",
+            2,
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            chat_id.get_ephemeral_timer(&t.ctx).await.unwrap(),
+            EphemeralTimer::Disabled
+        );
+    }
+
+    #[async_std::test]
+    async fn test_quoted_reply() {
+        let t = configured_offline_context().await;
+        let contact_id = Contact::create(&t.ctx, "foobar", "foobar@example.com")
+            .await
+            .unwrap();
+
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Foobar <foobar@example.com>\n\
+                 To: alice@example.org\n\
+                 Subject: foo\n\
+                 Message-ID: <quoted1@example.org>\n\
+                 Chat-Version: 1.0\n\
+                 Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+                 \n\
+                 hello\n",
+            "INBOX",
+            1,
+            false,
+        )
+        .await
+        .unwrap();
+
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Foobar <foobar@example.com>\n\
+                 To: alice@example.org\n\
+                 Subject: foo\n\
+                 Message-ID: <quoted2@example.org>\n\
+                 In-Reply-To: <quoted1@example.org>\n\
+                 Chat-Version: 1.0\n\
+                 Date: Sun, 22 Mar 2020 22:38:57 +0000\n\
+                 \n\
+                 > hello\n\
+                 \n\
+                 world\n",
+            "INBOX",
+            2,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let (chat_id, _) = chat::lookup_by_contact_id(&t.ctx, contact_id)
+            .await
+            .unwrap();
+        let msgs = chat::get_chat_msgs(&t.ctx, chat_id, 0, None).await;
+        assert_eq!(msgs.len(), 2);
+
+        let msg = Message::load_from_db(&t.ctx, msgs[1]).await.unwrap();
+        assert_eq!(msg.get_text().unwrap(), "world");
+        assert_eq!(msg.quoted_text().unwrap(), "hello");
+
+        let quoted_msg = msg.quoted_message(&t.ctx).await.unwrap().unwrap();
+        assert_eq!(quoted_msg.id, msgs[0]);
+        assert_eq!(quoted_msg.get_text().unwrap(), "hello");
+
+        // the first message quotes nothing
+        let msg = Message::load_from_db(&t.ctx, msgs[0]).await.unwrap();
+        assert!(msg.quoted_text().is_none());
+        assert!(msg.quoted_message(&t.ctx).await.unwrap().is_none());
+    }
+
+    #[async_std::test]
+    async fn test_edit_and_delete_request() {
+        let t = configured_offline_context().await;
+        let contact_id = Contact::create(&t.ctx, "foobar", "foobar@example.com")
+            .await
+            .unwrap();
+
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Foobar <foobar@example.com>\n\
+                 To: alice@example.org\n\
+                 Subject: foo\n\
+                 Message-ID: <edit1@example.org>\n\
+                 Chat-Version: 1.0\n\
+                 Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+                 \n\
+                 helo\n",
+            "INBOX",
+            1,
+            false,
+        )
+        .await
+        .unwrap();
+        let (chat_id, _) = chat::lookup_by_contact_id(&t.ctx, contact_id)
+            .await
+            .unwrap();
+        let msgs = chat::get_chat_msgs(&t.ctx, chat_id, 0, None).await;
+        assert_eq!(msgs.len(), 1);
+        let msg_id = msgs[0];
+
+        // Edit requests by other contacts are ignored.
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Mallory <mallory@example.net>\n\
+                 To: alice@example.org\n\
+                 Subject: foo\n\
+                 Message-ID: <edit2@example.net>\n\
+                 Chat-Version: 1.0\n\
+                 Chat-Edit: <edit1@example.org>\n\
+                 Date: Sun, 22 Mar 2020 22:38:57 +0000\n\
+                 \n\
+                 hacked\n",
+            "INBOX",
+            2,
+            false,
+        )
+        .await
+        .unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert_eq!(msg.get_text().unwrap(), "helo");
+        assert!(!msg.is_edited());
+
+        // Edit requests sent to another chat are ignored.
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Foobar <foobar@example.com>\n\
+                 To: alice@example.org, claire@example.net\n\
+                 Subject: foo\n\
+                 Message-ID: <Gr.edit5@example.org>\n\
+                 Chat-Version: 1.0\n\
+                 Chat-Group-ID: editGroupId1\n\
+                 Chat-Group-Name: foo\n\
+                 Chat-Edit: <edit1@example.org>\n\
+                 Date: Sun, 22 Mar 2020 22:38:27 +0000\n\
+                 \n\
+                 hacked\n",
+            "INBOX",
+            5,
+            false,
+        )
+        .await
+        .unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert_eq!(msg.get_text().unwrap(), "helo");
+        assert!(!msg.is_edited());
+
+        // Signed messages can be edited only by signed requests.
+        let mut msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        msg.param.set_int(Param::GuaranteeE2ee, 1);
+        msg.save_param_to_disk(&t.ctx).await;
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Foobar <foobar@example.com>\n\
+                 To: alice@example.org\n\
+                 Subject: foo\n\
+                 Message-ID: <edit6@example.org>\n\
+                 Chat-Version: 1.0\n\
+                 Chat-Edit: <edit1@example.org>\n\
+                 Date: Sun, 22 Mar 2020 22:38:37 +0000\n\
+                 \n\
+                 hacked\n",
+            "INBOX",
+            6,
+            false,
+        )
+        .await
+        .unwrap();
+        let mut msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert_eq!(msg.get_text().unwrap(), "helo");
+        assert!(!msg.is_edited());
+        msg.param.remove(Param::GuaranteeE2ee);
+        msg.save_param_to_disk(&t.ctx).await;
+
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Foobar <foobar@example.com>\n\
+                 To: alice@example.org\n\
+                 Subject: foo\n\
+                 Message-ID: <edit3@example.org>\n\
+                 Chat-Version: 1.0\n\
+                 Chat-Edit: <edit1@example.org>\n\
+                 Date: Sun, 22 Mar 2020 22:39:57 +0000\n\
+                 \n\
+                 hello\n",
+            "INBOX",
+            3,
+            false,
+        )
+        .await
+        .unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert_eq!(msg.get_text().unwrap(), "hello");
+        assert!(msg.is_edited());
+        assert_eq!(chat::get_chat_msgs(&t.ctx, chat_id, 0, None).await, msgs);
+        assert_eq!(chat_id.get_fresh_msg_cnt(&t.ctx).await, 1);
+
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Foobar <foobar@example.com>\n\
+                 To: alice@example.org\n\
+                 Subject: foo\n\
+                 Message-ID: <edit4@example.org>\n\
+                 Chat-Version: 1.0\n\
+                 Chat-Delete: <edit1@example.org>\n\
+                 Date: Sun, 22 Mar 2020 22:40:57 +0000\n\
+                 \n\
+                 \n",
+            "INBOX",
+            4,
+            false,
+        )
+        .await
+        .unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert!(msg.chat_id.is_trash());
+        assert_eq!(msg.get_text().unwrap(), "");
+        assert!(chat::get_chat_msgs(&t.ctx, chat_id, 0, None)
+            .await
+            .is_empty());
+    }
+
+    #[async_std::test]
+    async fn test_pin_request() {
+        let t = configured_offline_context().await;
+
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Bob <bob@example.net>\n\
+                 To: alice@example.org, claire@example.com\n\
+                 Subject: foo\n\
+                 Message-ID: <Gr.pin1@example.net>\n\
+                 Chat-Version: 1.0\n\
+                 Chat-Group-ID: pinGroupId1\n\
+                 Chat-Group-Name: foo\n\
+                 Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+                 \n\
+                 rules\n",
+            "INBOX",
+            1,
+            false,
+        )
+        .await
+        .unwrap();
+        let (chat_id, _, _) = chat::get_chat_id_by_grpid(&t.ctx, "pinGroupId1")
+            .await
+            .unwrap();
+        let msgs = chat::get_chat_msgs(&t.ctx, chat_id, 0, None).await;
+        assert_eq!(msgs.len(), 1);
+
+        // Pin requests by non-members are ignored.
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Mallory <mallory@example.net>\n\
+                 To: alice@example.org\n\
+                 Subject: foo\n\
+                 Message-ID: <Gr.pin2@example.net>\n\
+                 Chat-Version: 1.0\n\
+                 Chat-Group-ID: pinGroupId1\n\
+                 Chat-Group-Name: foo\n\
+                 Chat-Pin: <Gr.pin1@example.net>\n\
+                 Date: Sun, 22 Mar 2020 22:38:57 +0000\n\
+                 \n\
+                 \n",
+            "INBOX",
+            2,
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(chat_id.get_pinned_msgs(&t.ctx).await.unwrap().is_empty());
+
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Claire <claire@example.com>\n\
+                 To: alice@example.org, bob@example.net\n\
+                 Subject: foo\n\
+                 Message-ID: <Gr.pin3@example.com>\n\
+                 Chat-Version: 1.0\n\
+                 Chat-Group-ID: pinGroupId1\n\
+                 Chat-Group-Name: foo\n\
+                 Chat-Pin: <Gr.pin1@example.net>\n\
+                 Date: Sun, 22 Mar 2020 22:39:57 +0000\n\
+                 \n\
+                 \n",
+            "INBOX",
+            3,
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(chat_id.get_pinned_msgs(&t.ctx).await.unwrap(), msgs);
+        let chat_msgs = chat::get_chat_msgs(&t.ctx, chat_id, 0, None).await;
+        assert_eq!(chat_msgs.len(), 2);
+        let info = Message::load_from_db(&t.ctx, chat_msgs[1]).await.unwrap();
+        assert!(info.is_info());
+
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Bob <bob@example.net>\n\
+                 To: alice@example.org, claire@example.com\n\
+                 Subject: foo\n\
+                 Message-ID: <Gr.pin4@example.net>\n\
+                 Chat-Version: 1.0\n\
+                 Chat-Group-ID: pinGroupId1\n\
+                 Chat-Group-Name: foo\n\
+                 Chat-Unpin: <Gr.pin1@example.net>\n\
+                 Date: Sun, 22 Mar 2020 22:40:57 +0000\n\
+                 \n\
+                 \n",
+            "INBOX",
+            4,
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(chat_id.get_pinned_msgs(&t.ctx).await.unwrap().is_empty());
+        assert_eq!(chat::get_chat_msgs(&t.ctx, chat_id, 0, None).await.len(), 3);
+    }
+
+    async fn get_job_recipients(t: &TestContext, msg_id: MsgId) -> String {
+        let param: String = t
+            .ctx
+            .sql
+            .query_get_value(
+                &t.ctx,
+                "SELECT param FROM jobs WHERE action=? AND foreign_id=?;",
+                paramsv![Action::SendMsgToSmtp, msg_id],
+            )
+            .await
+            .unwrap();
+        let param: Params = param.parse().unwrap();
+        param.get(Param::Recipients).unwrap().to_string()
+    }
+
+    #[async_std::test]
+    async fn test_pin_request_max() {
+        let t = configured_offline_context().await;
+
+        let uids = 1..=(DC_MAX_PINNED_MSGS as u32 + 1);
+        for uid in uids.clone() {
+            let raw = format!(
+                "From: Bob <bob@example.net>\n\
+                 To: alice@example.org, claire@example.com\n\
+                 Subject: foo\n\
+                 Message-ID: <Gr.max{}@example.net>\n\
+                 Chat-Version: 1.0\n\
+                 Chat-Group-ID: pinGroupId2\n\
+                 Chat-Group-Name: foo\n\
+                 Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+                 \n\
+                 message {}\n",
+                uid, uid
+            );
+            dc_receive_imf(&t.ctx, raw.as_bytes(), "INBOX", uid, false)
+                .await
+                .unwrap();
+        }
+        for uid in uids {
+            let raw = format!(
+                "From: Bob <bob@example.net>\n\
+                 To: alice@example.org, claire@example.com\n\
+                 Subject: foo\n\
+                 Message-ID: <Gr.maxpin{}@example.net>\n\
+                 Chat-Version: 1.0\n\
+                 Chat-Group-ID: pinGroupId2\n\
+                 Chat-Group-Name: foo\n\
+                 Chat-Pin: <Gr.max{}@example.net>\n\
+                 Date: Sun, 22 Mar 2020 22:38:57 +0000\n\
+                 \n\
+                 \n",
+                uid, uid
+            );
+            dc_receive_imf(&t.ctx, raw.as_bytes(), "INBOX", 100 + uid, false)
+                .await
+                .unwrap();
+        }
+
+        // Pin requests exceeding the maximum are ignored.
+        let (chat_id, _, _) = chat::get_chat_id_by_grpid(&t.ctx, "pinGroupId2")
+            .await
+            .unwrap();
+        let pinned = chat_id.get_pinned_msgs(&t.ctx).await.unwrap();
+        assert_eq!(pinned.len(), DC_MAX_PINNED_MSGS);
+        let (_, _, last_id) = message::rfc724_mid_exists(
+            &t.ctx,
+            &format!("Gr.max{}@example.net", DC_MAX_PINNED_MSGS + 1),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(!pinned.contains(&last_id));
+    }
+
+    #[async_std::test]
+    async fn test_broadcast_from_other_device() {
+        let t = configured_offline_context().await;
+        let chat_id = chat::create_broadcast_list(&t.ctx, "news").await.unwrap();
+        let chat = Chat::load_from_db(&t.ctx, chat_id).await.unwrap();
+
+        let raw = format!(
+            "From: alice@example.org\n\
+             To: hidden-recipients: ;\n\
+             Subject: foo\n\
+             Message-ID: <broadcast1@example.org>\n\
+             Chat-Version: 1.0\n\
+             Chat-Group-ID: {}\n\
+             Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+             \n\
+             news for all\n",
+            chat.grpid
+        );
+        dc_receive_imf(&t.ctx, raw.as_bytes(), "INBOX", 1, false)
+            .await
+            .unwrap();
+        let (_, _, msg_id) = message::rfc724_mid_exists(&t.ctx, "broadcast1@example.org")
+            .await
+            .unwrap()
+            .unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert_eq!(msg.chat_id, chat_id);
+        assert_eq!(msg.get_text().unwrap(), "news for all");
+
+        // recipients get broadcasts in their one-to-one chat with the sender
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Bob <bob@example.net>\n\
+                 To: hidden-recipients: ;\n\
+                 Subject: foo\n\
+                 Message-ID: <broadcast2@example.net>\n\
+                 Chat-Version: 1.0\n\
+                 Chat-Group-ID: bobsBroadcast\n\
+                 Date: Sun, 22 Mar 2020 22:38:57 +0000\n\
+                 \n\
+                 news from bob\n",
+            "INBOX",
+            2,
+            false,
+        )
+        .await
+        .unwrap();
+        let (_, _, msg_id) = message::rfc724_mid_exists(&t.ctx, "broadcast2@example.net")
+            .await
+            .unwrap()
+            .unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        let chat = Chat::load_from_db(&t.ctx, msg.chat_id).await.unwrap();
+        assert_eq!(chat.get_type(), Chattype::Single);
+        assert!(chat::get_chat_id_by_grpid(&t.ctx, "bobsBroadcast")
+            .await
+            .is_err());
+    }
+
+    #[async_std::test]
+    async fn test_mailing_list() {
+        let t = configured_offline_context().await;
+        configure_alice_keypair(&t.ctx).await;
+
+        // Mailing lists are classic emails, they are not shown by default.
+        assert_eq!(t.ctx.get_config_int(Config::ShowEmails).await, 0);
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Bob <bob@example.net>\n\
+                 To: Delta Chat <delta@lists.example.org>\n\
+                 Subject: [delta] first\n\
+                 Message-ID: <list0@example.net>\n\
+                 List-Id: Delta Chat <delta.lists.example.org>\n\
+                 Precedence: list\n\
+                 Date: Sun, 22 Mar 2020 22:36:57 +0000\n\
+                 \n\
+                 first\n",
+            "INBOX",
+            1,
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(
+            chat::get_chat_id_by_grpid(&t.ctx, "delta.lists.example.org")
+                .await
+                .is_err()
+        );
+        let chats = Chatlist::try_load(&t.ctx, 0, None, None).await.unwrap();
+        assert_eq!(chats.len(), 0);
+
+        t.ctx
+            .set_config(Config::ShowEmails, Some("2"))
+            .await
+            .unwrap();
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Bob <bob@example.net>\n\
+                 To: Delta Chat <delta@lists.example.org>\n\
+                 Subject: [delta] hello\n\
+                 Message-ID: <list1@example.net>\n\
+                 List-Id: Delta Chat <delta.lists.example.org>\n\
+                 List-Post: NO\n\
+                 List-Unsubscribe: <https://lists.example.org/unsubscribe>\n\
+                 Precedence: list\n\
+                 Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+                 \n\
+                 hello\n",
+            "INBOX",
+            2,
+            false,
+        )
+        .await
+        .unwrap();
+        let (_, _, msg_id) = message::rfc724_mid_exists(&t.ctx, "list1@example.net")
+            .await
+            .unwrap()
+            .unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        let chat = Chat::load_from_db(&t.ctx, msg.chat_id).await.unwrap();
+        assert_eq!(chat.get_type(), Chattype::Mailinglist);
+        assert_eq!(chat.get_name(), "Delta Chat");
+        assert_eq!(chat.grpid, "delta.lists.example.org");
+        assert!(!chat.can_send());
+
+        // New mailing lists are contact requests.
+        let chats = Chatlist::try_load(&t.ctx, 0, None, None).await.unwrap();
+        assert_eq!(chats.len(), 1);
+        assert!(chats.get_chat_id(0).is_deaddrop());
+        assert_eq!(
+            mailinglist::unsubscribe(&t.ctx, chat.id).await.unwrap(),
+            Some("https://lists.example.org/unsubscribe".to_string())
+        );
+
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Claire <claire@example.com>\n\
+                 To: delta@lists.example.org\n\
+                 Subject: [delta] Re: hello\n\
+                 Message-ID: <list2@example.com>\n\
+                 In-Reply-To: <list1@example.net>\n\
+                 Return-Path: <delta-bounces@lists.example.org>\n\
+                 Sender: delta-bounces@lists.example.org\n\
+                 List-Id: <delta.lists.example.org>\n\
+                 List-Post: <mailto:delta@lists.example.org>\n\
+                 List-Unsubscribe: <mailto:delta-request@lists.example.org?subject=unsubscribe>,\n \
+                  <https://lists.example.org/unsubscribe>\n\
+                 Date: Sun, 22 Mar 2020 22:38:57 +0000\n\
+                 \n\
+                 hi\n",
+            "INBOX",
+            3,
+            false,
+        )
+        .await
+        .unwrap();
+        let chat = Chat::load_from_db(&t.ctx, chat.id).await.unwrap();
+        assert_eq!(chat.get_name(), "Delta Chat");
+        assert!(chat.can_send());
+        assert_eq!(chat::get_chat_msgs(&t.ctx, chat.id, 0, None).await.len(), 2);
+
+        // Messages not coming from the list do not change where posts go to,
+        // missing headers do not remove them.
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Mallory <mallory@example.com>\n\
+                 To: delta@lists.example.org\n\
+                 Subject: [delta] Re: hello\n\
+                 Message-ID: <list3@example.com>\n\
+                 Return-Path: <mallory@example.com>\n\
+                 Sender: delta-bounces@lists.example.org\n\
+                 List-Id: <delta.lists.example.org>\n\
+                 List-Post: <mailto:mallory@example.com>\n\
+                 Date: Sun, 22 Mar 2020 22:39:57 +0000\n\
+                 \n\
+                 hi\n",
+            "INBOX",
+            4,
+            false,
+        )
+        .await
+        .unwrap();
+        let chat = Chat::load_from_db(&t.ctx, chat.id).await.unwrap();
+        assert_eq!(chat::get_chat_msgs(&t.ctx, chat.id, 0, None).await.len(), 3);
+        assert_eq!(
+            chat.param.get(Param::ListPost),
+            Some("delta@lists.example.org")
+        );
+        assert!(chat.param.exists(Param::ListUnsubscribe));
+
+        // Replies are sent to the list address only.
+        assert_eq!(
+            chat::create_by_msg_id(&t.ctx, msg_id).await.unwrap(),
+            chat.id
+        );
+        let reply_id = chat::send_text_msg(&t.ctx, chat.id, "thanks".to_string())
+            .await
+            .unwrap();
+        assert!(get_job_recipients(&t, reply_id)
+            .await
+            .starts_with("delta@lists.example.org"));
+        let reply = Message::load_from_db(&t.ctx, reply_id).await.unwrap();
+        assert!(!reply.get_showpadlock());
+
+        assert_eq!(
+            mailinglist::unsubscribe(&t.ctx, chat.id).await.unwrap(),
+            None
+        );
+        let request_id = t
+            .ctx
+            .sql
+            .query_get_value::<MsgId>(
+                &t.ctx,
+                "SELECT id FROM msgs WHERE chat_id=? AND hidden=1;",
+                paramsv![chat.id],
+            )
+            .await
+            .unwrap();
+        assert!(get_job_recipients(&t, request_id)
+            .await
+            .starts_with("delta-request@lists.example.org"));
+    }
 }
@@ -447,19 +447,22 @@ pub fn dc_open_file_std<P: AsRef<std::path::Path>>(
     }
 }
 
+/// Returns a path for a new backup file in `folder`,
+/// named after the date and ending with `suffix`.
 pub(crate) async fn dc_get_next_backup_path(
     folder: impl AsRef<Path>,
     backup_time: i64,
+    suffix: &str,
 ) -> Result<PathBuf, Error> {
     let folder = PathBuf::from(folder.as_ref());
     let stem = chrono::NaiveDateTime::from_timestamp(backup_time, 0)
-        .format("delta-chat-%Y-%m-%d")
+        .format("delta-chat-backup-%Y-%m-%d")
         .to_string();
 
     // 64 backup files per day should be enough for everyone
     for i in 0..64 {
         let mut path = folder.clone();
-        path.push(format!("{}-{}.bak", stem, i));
+        path.push(format!("{}-{}.{}", stem, i, suffix));
         if !path.exists().await {
             return Ok(path);
         }
@@ -113,6 +113,14 @@ impl EncryptHelper {
 
         Ok(ctext)
     }
+
+    /// Creates a detached signature of the passed in, already rendered `mail`
+    /// for use in a multipart/signed message.
+    pub async fn sign(self, context: &Context, mail: &[u8]) -> Result<String> {
+        let sign_key = SignedSecretKey::load_self(context).await?;
+        let signature = pgp::pk_calc_signature(mail, sign_key).await?;
+        Ok(signature)
+    }
 }
 
 pub async fn try_decrypt(
@@ -120,13 +128,7 @@ pub async fn try_decrypt(
     mail: &ParsedMail<'_>,
     message_time: i64,
 ) -> Result<(Option<Vec<u8>>, HashSet<String>)> {
-    let from = mail
-        .headers
-        .get_header(HeaderDef::From_)
-        .and_then(|from_addr| mailparse::addrparse_header(&from_addr).ok())
-        .and_then(|from| from.extract_single_info())
-        .map(|from| from.addr)
-        .unwrap_or_default();
+    let from = get_from_addr(mail);
 
     let mut peerstate = None;
     let autocryptheader = Aheader::from_headers(context, &from, &mail.headers);
@@ -149,25 +151,116 @@ pub async fn try_decrypt(
         }
     }
 
-    /* possibly perform decryption */
-    let private_keyring: Keyring<SignedSecretKey> = Keyring::new_self(context).await?;
-    let mut public_keyring_for_validate: Keyring<SignedPublicKey> = Keyring::new();
-    let mut signatures = HashSet::default();
-
     if peerstate.as_ref().map(|p| p.last_seen).unwrap_or_else(|| 0) == 0 {
         peerstate = Peerstate::from_addr(&context, &from).await;
     }
-    if let Some(peerstate) = peerstate {
+    if let Some(ref peerstate) = peerstate {
         if peerstate.degrade_event.is_some() {
-            handle_degrade_event(context, &peerstate).await?;
+            handle_degrade_event(context, peerstate).await?;
+        }
+    }
+
+    decrypt_with_peerstate(context, mail, peerstate).await
+}
+
+/// Tries to decrypt a message attached to another message,
+/// e.g. a member-added message attached to a group member correction.
+///
+/// Unlike [try_decrypt], the Autocrypt header of the message is ignored
+/// and the peerstate of the sender is not modified,
+/// signatures are validated using the keys already known for the sender.
+pub(crate) async fn try_decrypt_attached(
+    context: &Context,
+    mail: &ParsedMail<'_>,
+) -> Result<(Option<Vec<u8>>, HashSet<String>)> {
+    let peerstate = Peerstate::from_addr(context, &get_from_addr(mail)).await;
+    decrypt_with_peerstate(context, mail, peerstate).await
+}
+
+/// Validates the signature of an unencrypted multipart/signed message,
+/// see [RFC 3156](https://tools.ietf.org/html/rfc3156#section-5).
+///
+/// The signature is checked against the keys known for the sender.
+/// Returns the fingerprints of the valid signatures, this is empty
+/// if the message is not signed or the signature cannot be validated.
+pub(crate) async fn validate_detached_signature(
+    context: &Context,
+    mail: &ParsedMail<'_>,
+) -> Result<HashSet<String>> {
+    if mail.ctype.mimetype != "multipart/signed" {
+        return Ok(HashSet::new());
+    }
+    let (content, signature) = match &mail.subparts[..] {
+        [content, signature] if signature.ctype.mimetype == "application/pgp-signature" => {
+            (content, signature)
         }
+        _ => return Ok(HashSet::new()),
+    };
+
+    let from = get_from_addr(mail);
+    let peerstate = Peerstate::from_addr(context, &from).await;
+    let public_keyring_for_validate = keyring_for_validate(context, &from, peerstate).await;
+    if public_keyring_for_validate.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    // The line break before the boundary delimiter belongs to the
+    // delimiter and is not part of the signed content.
+    let raw = content.raw_bytes;
+    let raw = if raw.ends_with(b"\r\n") {
+        &raw[..raw.len() - 2]
+    } else {
+        raw
+    };
+    let signature = signature.get_body_raw()?;
+    pgp::pk_validate(raw, &signature, &public_keyring_for_validate)
+}
+
+fn get_from_addr(mail: &ParsedMail<'_>) -> String {
+    mail.headers
+        .get_header(HeaderDef::From_)
+        .and_then(|from_addr| mailparse::addrparse_header(&from_addr).ok())
+        .and_then(|from| from.extract_single_info())
+        .map(|from| from.addr)
+        .unwrap_or_default()
+}
+
+/// Returns the keys to validate signatures of messages from `from` with.
+///
+/// These are the keys of the peerstate of the sender, and our own key
+/// for messages sent by ourselves from another device.
+async fn keyring_for_validate(
+    context: &Context,
+    from: &str,
+    peerstate: Option<Peerstate<'_>>,
+) -> Keyring<SignedPublicKey> {
+    let mut keyring = Keyring::new();
+    if let Some(peerstate) = peerstate {
         if let Some(key) = peerstate.gossip_key {
-            public_keyring_for_validate.add(key);
+            keyring.add(key);
         }
         if let Some(key) = peerstate.public_key {
-            public_keyring_for_validate.add(key);
+            keyring.add(key);
         }
     }
+    if context.is_self_addr(from).await.unwrap_or_default() {
+        if let Ok(key) = SignedPublicKey::load_self(context).await {
+            keyring.add(key);
+        }
+    }
+    keyring
+}
+
+async fn decrypt_with_peerstate(
+    context: &Context,
+    mail: &ParsedMail<'_>,
+    peerstate: Option<Peerstate<'_>>,
+) -> Result<(Option<Vec<u8>>, HashSet<String>)> {
+    /* possibly perform decryption */
+    let private_keyring: Keyring<SignedSecretKey> = Keyring::new_self(context).await?;
+    let public_keyring_for_validate =
+        keyring_for_validate(context, &get_from_addr(mail), peerstate).await;
+    let mut signatures = HashSet::default();
 
     let out_mail = decrypt_if_autocrypt_message(
         context,
@@ -363,6 +456,94 @@ Sent with my Delta Chat Messenger: https://delta.chat";
         );
     }
 
+    #[async_std::test]
+    async fn test_signed_only() {
+        use crate::chat;
+        use crate::contact::Contact;
+        use crate::key;
+        use crate::message::Message;
+        use crate::mimefactory::MimeFactory;
+        use crate::mimeparser::MimeMessage;
+        use crate::param::Param;
+
+        let alice = dummy_context().await;
+        configure_alice_keypair(&alice.ctx).await;
+        alice
+            .ctx
+            .set_config(Config::SignUnencrypted, Some("1"))
+            .await
+            .unwrap();
+        let contact_id = Contact::create(&alice.ctx, "Bob", "bob@example.net")
+            .await
+            .unwrap();
+        let chat_id = chat::create_by_contact_id(&alice.ctx, contact_id)
+            .await
+            .unwrap();
+        let msg_id = chat::send_text_msg(&alice.ctx, chat_id, "hello bob".to_string())
+            .await
+            .unwrap();
+        let msg = Message::load_from_db(&alice.ctx, msg_id).await.unwrap();
+        let rendered = MimeFactory::from_msg(&alice.ctx, &msg, false)
+            .await
+            .unwrap()
+            .render()
+            .await
+            .unwrap();
+        assert!(!rendered.is_encrypted);
+        assert!(rendered.is_signed_only);
+        let payload = String::from_utf8_lossy(&rendered.message).to_string();
+        assert!(payload.contains("multipart/signed"));
+
+        let bob = dummy_context().await;
+        let bob_keypair = bob_keypair();
+        bob.ctx
+            .set_config(Config::ConfiguredAddr, Some("bob@example.net"))
+            .await
+            .unwrap();
+        key::store_self_keypair(&bob.ctx, &bob_keypair, key::KeyPairUse::Default)
+            .await
+            .unwrap();
+
+        let parsed = MimeMessage::from_bytes(&bob.ctx, payload.as_bytes())
+            .await
+            .unwrap();
+        assert!(parsed.signed_only);
+        assert!(!parsed.was_encrypted());
+        assert!(parsed.is_protected(HeaderDef::Subject));
+        assert_eq!(parsed.parts[0].msg, "hello bob");
+        assert_eq!(
+            parsed.parts[0].param.get_bool(Param::SignedOnly),
+            Some(true)
+        );
+
+        // a modified message is shown, but not as signed
+        let modified = payload.replace("hello bob", "hello eve");
+        let parsed = MimeMessage::from_bytes(&bob.ctx, modified.as_bytes())
+            .await
+            .unwrap();
+        assert!(!parsed.signed_only);
+        assert!(!parsed.is_protected(HeaderDef::Subject));
+        assert_eq!(parsed.parts[0].msg, "hello eve");
+        assert!(!parsed.parts[0].param.exists(Param::SignedOnly));
+
+        // mallory signs a message claiming to be from alice in the signed part,
+        // the outer sender has to be used then.
+        let (outer, signed) = payload.split_at(payload.find("\r\n\r\n").unwrap());
+        let spoofed = outer.replace("alice@example.com", "mallory@example.net") + signed;
+        let parsed = MimeMessage::from_bytes(&bob.ctx, spoofed.as_bytes())
+            .await
+            .unwrap();
+        assert!(parsed.signed_only);
+        assert_eq!(parsed.from.len(), 1);
+        assert_eq!(parsed.from[0].addr, "mallory@example.net");
+        assert!(parsed
+            .get(HeaderDef::From_)
+            .unwrap()
+            .contains("mallory@example.net"));
+        assert!(!parsed.is_protected(HeaderDef::From_));
+        assert!(parsed.is_protected(HeaderDef::Subject));
+    }
+
     #[test]
     fn test_has_decrypted_pgp_armor() {
         let data = b" -----BEGIN PGP MESSAGE-----";
@@ -0,0 +1,500 @@
+//! # Ephemeral messages
+//!
+//! Ephemeral messages are messages that have an Ephemeral-Timer
+//! header attached to them, which specifies the time in seconds after
+//! which the message should be deleted both from the device and from
+//! the server. The timer is started when the message is marked as
+//! seen, which usually happens when its contents is displayed on the
+//! device screen. Outgoing messages start their timer right away.
+//!
+//! Each chat, including 1:1, group chats and "saved messages" chat,
+//! has its own ephemeral timer setting, which is applied to all
+//! messages sent to the chat. The setting is synchronized to all the
+//! devices participating in the chat by applying the timer value from
+//! all received messages carrying an Ephemeral-Timer header, and by
+//! sending a system message when the user changes the timer.
+//!
+//! Expired messages are moved to the trash chat, their text and
+//! attachments are removed.  From there they are deleted from the
+//! server by a `DeleteMsgOnImap` job, the same way as messages expired
+//! according to the "delete_server_after" setting.
+//!
+//! A single background task is used to delete messages as soon as
+//! their timer ends.  It sleeps until the next message expires and
+//! is rescheduled every time a new timer is started.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+use std::time::Duration;
+
+use async_std::task;
+use serde::{Deserialize, Serialize};
+
+use crate::chat::{lookup_by_contact_id, send_msg, ChatId};
+use crate::constants::*;
+use crate::context::Context;
+use crate::dc_tools::time;
+use crate::error::{ensure, Error};
+use crate::events::Event;
+use crate::message::{Message, MsgId};
+use crate::mimeparser::SystemMessage;
+use crate::stock::StockMessage;
+
+/// Ephemeral timer value.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum Timer {
+    Disabled,
+    Enabled { duration: u32 },
+}
+
+impl Timer {
+    /// Returns the timer duration in seconds, 0 if the timer is disabled.
+    pub fn to_u32(self) -> u32 {
+        match self {
+            Self::Disabled => 0,
+            Self::Enabled { duration } => duration,
+        }
+    }
+
+    /// Creates a timer from the duration in seconds, 0 disables the timer.
+    pub fn from_u32(duration: u32) -> Self {
+        if duration == 0 {
+            Self::Disabled
+        } else {
+            Self::Enabled { duration }
+        }
+    }
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::Disabled
+    }
+}
+
+impl fmt::Display for Timer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_u32())
+    }
+}
+
+impl FromStr for Timer {
+    type Err = ParseIntError;
+
+    fn from_str(input: &str) -> Result<Timer, ParseIntError> {
+        input.trim().parse::<u32>().map(Self::from_u32)
+    }
+}
+
+impl rusqlite::types::ToSql for Timer {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput> {
+        let val = rusqlite::types::Value::Integer(i64::from(self.to_u32()));
+        let out = rusqlite::types::ToSqlOutput::Owned(val);
+        Ok(out)
+    }
+}
+
+impl rusqlite::types::FromSql for Timer {
+    fn column_result(value: rusqlite::types::ValueRef) -> rusqlite::types::FromSqlResult<Self> {
+        i64::column_result(value).and_then(|value| {
+            u32::try_from(value)
+                .map(Self::from_u32)
+                .map_err(|_| rusqlite::types::FromSqlError::OutOfRange(value))
+        })
+    }
+}
+
+impl ChatId {
+    /// Get ephemeral message timer value in seconds.
+    pub async fn get_ephemeral_timer(self, context: &Context) -> Result<Timer, Error> {
+        let timer = context
+            .sql
+            .query_get_value_result(
+                "SELECT ephemeral_timer FROM chats WHERE id=?;",
+                paramsv![self],
+            )
+            .await?;
+        Ok(timer.unwrap_or_default())
+    }
+
+    /// Set ephemeral timer value without sending a message.
+    ///
+    /// Used when a message arrives indicating that someone else has
+    /// changed the timer value for a chat.
+    pub(crate) async fn inner_set_ephemeral_timer(
+        self,
+        context: &Context,
+        timer: Timer,
+    ) -> Result<(), Error> {
+        ensure!(!self.is_special(), "Invalid chat ID");
+
+        context
+            .sql
+            .execute(
+                "UPDATE chats SET ephemeral_timer=? WHERE id=?;",
+                paramsv![timer, self],
+            )
+            .await?;
+
+        context.emit_event(Event::ChatEphemeralTimerModified {
+            chat_id: self,
+            timer: timer.to_u32(),
+        });
+        Ok(())
+    }
+
+    /// Set ephemeral message timer value in seconds.
+    ///
+    /// If timer value is 0, disable ephemeral message timer.
+    pub async fn set_ephemeral_timer(self, context: &Context, timer: Timer) -> Result<(), Error> {
+        if timer == self.get_ephemeral_timer(context).await? {
+            return Ok(());
+        }
+        self.inner_set_ephemeral_timer(context, timer).await?;
+
+        let mut msg = Message::new(Viewtype::Text);
+        msg.text = Some(stock_ephemeral_timer_changed(context, timer, DC_CONTACT_ID_SELF).await);
+        msg.param.set_cmd(SystemMessage::EphemeralTimerChanged);
+        if let Err(err) = send_msg(context, self, &mut msg).await {
+            error!(
+                context,
+                "Failed to send a message about ephemeral message timer change: {:?}", err
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Returns a stock message saying that ephemeral timer is changed to `timer` by `from_id`.
+pub(crate) async fn stock_ephemeral_timer_changed(
+    context: &Context,
+    timer: Timer,
+    from_id: u32,
+) -> String {
+    let stock_message = match timer {
+        Timer::Disabled => StockMessage::MsgEphemeralTimerDisabled,
+        Timer::Enabled { duration } => match duration {
+            60 => StockMessage::MsgEphemeralTimerMinute,
+            3600 => StockMessage::MsgEphemeralTimerHour,
+            86400 => StockMessage::MsgEphemeralTimerDay,
+            604_800 => StockMessage::MsgEphemeralTimerWeek,
+            _ => {
+                return context
+                    .stock_system_msg(
+                        StockMessage::MsgEphemeralTimerEnabled,
+                        duration.to_string(),
+                        "",
+                        from_id,
+                    )
+                    .await
+            }
+        },
+    };
+
+    context
+        .stock_system_msg(stock_message, "", "", from_id)
+        .await
+}
+
+impl MsgId {
+    /// Returns ephemeral message timer value for the message.
+    pub(crate) async fn ephemeral_timer(self, context: &Context) -> Result<Timer, Error> {
+        let timer = context
+            .sql
+            .query_get_value_result(
+                "SELECT ephemeral_timer FROM msgs WHERE id=?;",
+                paramsv![self],
+            )
+            .await?;
+        Ok(timer.unwrap_or_default())
+    }
+
+    /// Starts ephemeral message timer for the message if it is not started yet.
+    pub(crate) async fn start_ephemeral_timer(self, context: &Context) -> Result<(), Error> {
+        if let Timer::Enabled { duration } = self.ephemeral_timer(context).await? {
+            let ephemeral_timestamp = time() + i64::from(duration);
+
+            context
+                .sql
+                .execute(
+                    "UPDATE msgs SET ephemeral_timestamp = ? \
+                WHERE (ephemeral_timestamp == 0 OR ephemeral_timestamp > ?) \
+                AND id = ?",
+                    paramsv![ephemeral_timestamp, ephemeral_timestamp, self],
+                )
+                .await?;
+            schedule_ephemeral_task(context).await;
+        }
+        Ok(())
+    }
+}
+
+/// Deletes messages which are expired according to
+/// "delete_device_after" setting or ephemeral message timer.
+///
+/// Returns true if any message is deleted, so event can be emitted. If nothing
+/// has been deleted, returns false.
+pub async fn delete_expired_messages(context: &Context) -> Result<bool, Error> {
+    let mut updated = context
+        .sql
+        .execute(
+            "UPDATE msgs \
+             SET txt = '', txt_raw = '', param = '', chat_id = ? \
+             WHERE ephemeral_timestamp != 0 \
+             AND ephemeral_timestamp <= ? \
+             AND chat_id != ?",
+            paramsv![DC_CHAT_ID_TRASH, time(), DC_CHAT_ID_TRASH],
+        )
+        .await?
+        > 0;
+
+    if let Some(delete_device_after) = context.get_config_delete_device_after().await {
+        let threshold_timestamp = time() - delete_device_after;
+
+        let self_chat_id = lookup_by_contact_id(context, DC_CONTACT_ID_SELF)
+            .await
+            .unwrap_or_default()
+            .0;
+        let device_chat_id = lookup_by_contact_id(context, DC_CONTACT_ID_DEVICE)
+            .await
+            .unwrap_or_default()
+            .0;
+
+        // Delete expired messages
+        //
+        // Only update the rows that have to be updated, to avoid emitting
+        // unnecessary "chat modified" events.
+        let rows_modified = context
+            .sql
+            .execute(
+                "UPDATE msgs \
+             SET txt = 'DELETED', chat_id = ? \
+             WHERE timestamp < ? \
+             AND chat_id > ? \
+             AND chat_id != ? \
+             AND chat_id != ?",
+                paramsv![
+                    DC_CHAT_ID_TRASH,
+                    threshold_timestamp,
+                    DC_CHAT_ID_LAST_SPECIAL,
+                    self_chat_id,
+                    device_chat_id
+                ],
+            )
+            .await?;
+
+        updated |= rows_modified > 0;
+    }
+
+    Ok(updated)
+}
+
+/// Returns the timestamp of the message expiring next, if any.
+async fn next_expiration_timestamp(context: &Context) -> Option<i64> {
+    context
+        .sql
+        .query_get_value(
+            context,
+            "SELECT ephemeral_timestamp \
+         FROM msgs \
+         WHERE ephemeral_timestamp != 0 \
+           AND chat_id != ? \
+         ORDER BY ephemeral_timestamp ASC \
+         LIMIT 1",
+            paramsv![DC_CHAT_ID_TRASH],
+        )
+        .await
+}
+
+/// Schedules a task to delete the next expiring ephemeral message.
+///
+/// The previously scheduled task, if any, is cancelled.  The task
+/// keeps deleting messages as they expire until there are no more
+/// messages with a running timer.
+pub(crate) async fn schedule_ephemeral_task(context: &Context) {
+    let mut ephemeral_task = context.ephemeral_task.write().await;
+
+    // Cancel the previously scheduled task.
+    if let Some(handle) = ephemeral_task.take() {
+        handle.cancel().await;
+    }
+
+    if next_expiration_timestamp(context).await.is_none() {
+        return;
+    }
+
+    let context = context.clone();
+    *ephemeral_task = Some(task::spawn(async move {
+        while let Some(ephemeral_timestamp) = next_expiration_timestamp(&context).await {
+            let now = time();
+            if ephemeral_timestamp >= now {
+                // Sleep one extra second to make sure the message
+                // is expired by the time we try to delete it.
+                let duration = Duration::from_secs((ephemeral_timestamp - now + 1) as u64);
+                task::sleep(duration).await;
+            }
+
+            match delete_expired_messages(&context).await {
+                Ok(true) => {
+                    context.emit_event(Event::MsgsChanged {
+                        chat_id: ChatId::new(0),
+                        msg_id: MsgId::new(0),
+                    });
+                    // Wake up the inbox loop so it deletes the messages on the server.
+                    context.interrupt_inbox(false).await;
+                }
+                Ok(false) => {}
+                Err(err) => {
+                    warn!(context, "Failed to delete expired messages: {}", err);
+                    break;
+                }
+            }
+        }
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::chat::create_by_contact_id;
+    use crate::param::Params;
+    use crate::test_utils::*;
+
+    #[async_std::test]
+    async fn test_stock_ephemeral_messages() {
+        let context = dummy_context().await.ctx;
+
+        assert_eq!(
+            stock_ephemeral_timer_changed(&context, Timer::Disabled, DC_CONTACT_ID_SELF).await,
+            "Message deletion timer is disabled by me."
+        );
+
+        assert_eq!(
+            stock_ephemeral_timer_changed(&context, Timer::Disabled, 0).await,
+            "Message deletion timer is disabled."
+        );
+
+        assert_eq!(
+            stock_ephemeral_timer_changed(&context, Timer::Enabled { duration: 1 }, 0).await,
+            "Message deletion timer is set to 1 s."
+        );
+
+        assert_eq!(
+            stock_ephemeral_timer_changed(&context, Timer::Enabled { duration: 30 }, 0).await,
+            "Message deletion timer is set to 30 s."
+        );
+
+        assert_eq!(
+            stock_ephemeral_timer_changed(&context, Timer::Enabled { duration: 60 }, 0).await,
+            "Message deletion timer is set to 1 minute."
+        );
+
+        assert_eq!(
+            stock_ephemeral_timer_changed(&context, Timer::Enabled { duration: 3600 }, 0).await,
+            "Message deletion timer is set to 1 hour."
+        );
+
+        assert_eq!(
+            stock_ephemeral_timer_changed(&context, Timer::Enabled { duration: 86400 }, 0).await,
+            "Message deletion timer is set to 1 day."
+        );
+
+        assert_eq!(
+            stock_ephemeral_timer_changed(
+                &context,
+                Timer::Enabled {
+                    duration: 7 * 86400
+                },
+                0
+            )
+            .await,
+            "Message deletion timer is set to 1 week."
+        );
+    }
+
+    #[test]
+    fn test_timer_from_str() {
+        assert_eq!("0".parse::<Timer>().unwrap(), Timer::Disabled);
+        assert_eq!(
+            "3600".parse::<Timer>().unwrap(),
+            Timer::Enabled { duration: 3600 }
+        );
+        assert_eq!(Timer::Enabled { duration: 3600 }.to_string(), "3600");
+        assert_eq!(Timer::Disabled.to_string(), "0");
+        assert!("-1".parse::<Timer>().is_err());
+        assert!("foo".parse::<Timer>().is_err());
+    }
+
+    #[async_std::test]
+    async fn test_ephemeral_timer_roundtrip() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let chat_id = create_by_contact_id(&t.ctx, DC_CONTACT_ID_SELF)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            chat_id.get_ephemeral_timer(&t.ctx).await.unwrap(),
+            Timer::Disabled
+        );
+
+        chat_id
+            .set_ephemeral_timer(&t.ctx, Timer::Enabled { duration: 60 })
+            .await
+            .unwrap();
+        assert_eq!(
+            chat_id.get_ephemeral_timer(&t.ctx).await.unwrap(),
+            Timer::Enabled { duration: 60 }
+        );
+
+        chat_id
+            .set_ephemeral_timer(&t.ctx, Timer::Disabled)
+            .await
+            .unwrap();
+        assert_eq!(
+            chat_id.get_ephemeral_timer(&t.ctx).await.unwrap(),
+            Timer::Disabled
+        );
+    }
+
+    #[async_std::test]
+    async fn test_delete_expired_messages() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let chat_id = create_by_contact_id(&t.ctx, DC_CONTACT_ID_SELF)
+            .await
+            .unwrap();
+        chat_id
+            .set_ephemeral_timer(&t.ctx, Timer::Enabled { duration: 3600 })
+            .await
+            .unwrap();
+
+        let msg_id = crate::chat::send_text_msg(&t.ctx, chat_id, "foo".to_string())
+            .await
+            .unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert_eq!(msg.get_ephemeral_timer(), Timer::Enabled { duration: 3600 });
+        assert!(msg.get_ephemeral_timestamp() > time());
+
+        // Nothing is expired yet.
+        assert!(!delete_expired_messages(&t.ctx).await.unwrap());
+
+        t.ctx
+            .sql
+            .execute(
+                "UPDATE msgs SET ephemeral_timestamp=? WHERE id=?;",
+                paramsv![time() - 1, msg_id],
+            )
+            .await
+            .unwrap();
+        assert!(delete_expired_messages(&t.ctx).await.unwrap());
+
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert!(msg.chat_id.is_trash());
+        assert_eq!(msg.get_text(), Some("".to_string()));
+        assert_eq!(msg.param, Params::new());
+    }
+}
@@ -163,6 +163,11 @@ pub enum Event {
     #[strum(props(id = "2000"))]
     MsgsChanged { chat_id: ChatId, msg_id: MsgId },
 
+    /// Reactions to the message changed.
+    /// See dc_get_msg_reactions() and dc_send_reaction().
+    #[strum(props(id = "2001"))]
+    ReactionsChanged { chat_id: ChatId, msg_id: MsgId },
+
     /// There is a fresh message. Typically, the user will show an notification
     /// when receiving this message.
     ///
@@ -192,12 +197,32 @@ pub enum Event {
     #[strum(props(id = "2020"))]
     ChatModified(ChatId),
 
+    /// Chat ephemeral timer changed.
+    #[strum(props(id = "2021"))]
+    ChatEphemeralTimerModified { chat_id: ChatId, timer: u32 },
+
     /// Contact(s) created, renamed, blocked or deleted.
     ///
     /// @param data1 (int) If set, this is the contact_id of an added contact that should be selected.
     #[strum(props(id = "2030"))]
     ContactsChanged(Option<u32>),
 
+    /// A verified contact uses a new key.
+    ///
+    /// Messages of the contact in verified groups are held back
+    /// until the contact is verified again, eg. by scanning the QR code
+    /// returned by dc_get_securejoin_qr_for_contact().
+    ///
+    /// @param data1 (int) ID of the contact.
+    /// @param data2 (String) Fingerprint of the verified key and of the new key,
+    ///     separated by a space.
+    #[strum(props(id = "2032"))]
+    VerifiedContactKeyChanged {
+        contact_id: u32,
+        old_fingerprint: String,
+        new_fingerprint: String,
+    },
+
     /// Location of one or more contact has changed.
     ///
     /// @param data1 (u32) contact_id of the contact for which the location has changed.
@@ -229,6 +254,16 @@ pub enum Event {
     #[strum(props(id = "2052"))]
     ImexFileWritten(PathBuf),
 
+    /// A backup is ready to be sent to another device
+    /// by imex() with ImexMode::SendBackupOverNetwork.
+    ///
+    /// The QR code has to be shown to the user and scanned by the receiving device.
+    ///
+    /// @param data1 0
+    /// @param data2 (String) QR code text.
+    #[strum(props(id = "2053"))]
+    ImexBackupQr(String),
+
     /// Progress information of a secure-join handshake from the view of the inviter
     /// (Alice, the person who shows the QR code).
     ///
@@ -254,4 +289,11 @@ pub enum Event {
     ///     (Bob has verified alice and waits until Alice does the same for him)
     #[strum(props(id = "2061"))]
     SecurejoinJoinerProgress { contact_id: u32, progress: usize },
+
+    /// The connectivity returned by dc_get_connectivity() has changed.
+    ///
+    /// This event is emitted for changes of the SMTP connection as well,
+    /// so the report returned by dc_get_connectivity_html() can be updated.
+    #[strum(props(id = "2100"))]
+    ConnectivityChanged,
 }
@@ -0,0 +1,680 @@
+//! # Group membership consistency
+//!
+//! If members are added or removed concurrently, the members of a group
+//! may end up with different member lists, see `draft/group-sync.rst`.
+//!
+//! Therefore, the raw MIME of the latest sent or received
+//! Chat-Group-Member-Added/Removed message of every member is stored in
+//! the `group_member_changes` table.  If the member list of a
+//! received member-added/removed message is, apart from the added or
+//! removed member, not consistent with our own view, the stored
+//! messages concerning the mismatching members are sent to the group,
+//! attached to a message with a `Chat-Group-Member-Correction` header.
+//!
+//! Receivers apply the attached changes unless they already know about
+//! a newer change of the same member.  In encrypted groups, the attached
+//! changes must be signed by their sender.  Corrections are never checked
+//! for consistency themselves, so they do not cause further corrections.
+
+use std::collections::BTreeSet;
+
+use crate::chat::{self, Chat, ChatId};
+use crate::constants::*;
+use crate::contact::{addr_cmp, Contact, Origin};
+use crate::context::Context;
+use crate::e2ee;
+use crate::error::Result;
+use crate::headerdef::{HeaderDef, HeaderDefMap};
+use crate::message::{Message, MsgId};
+use crate::mimeparser::{MimeMessage, SystemMessage};
+use crate::param::Param;
+
+/// A member-added/removed message attached to a correction.
+#[derive(Debug, PartialEq, Eq)]
+struct MemberChange {
+    grpid: String,
+    from: String,
+    addr: String,
+    added: bool,
+    timestamp: i64,
+    signed: bool,
+}
+
+fn is_member_change(cmd: SystemMessage) -> bool {
+    cmd == SystemMessage::MemberAddedToGroup || cmd == SystemMessage::MemberRemovedFromGroup
+}
+
+/// Stores the raw MIME of a member-added/removed message concerning `addr`,
+/// so that it can be attached to corrections later.
+///
+/// Only the latest change of every member is kept.
+async fn store_member_change(
+    context: &Context,
+    chat_id: ChatId,
+    addr: &str,
+    timestamp: i64,
+    raw: &[u8],
+) -> Result<()> {
+    if let Some((_, latest_timestamp)) = latest_member_change(context, chat_id, addr).await? {
+        if latest_timestamp > timestamp {
+            return Ok(());
+        }
+    }
+    context
+        .sql
+        .execute(
+            "DELETE FROM group_member_changes WHERE chat_id=? AND addr=? COLLATE NOCASE;",
+            paramsv![chat_id, addr],
+        )
+        .await?;
+    context
+        .sql
+        .execute(
+            "INSERT INTO group_member_changes (chat_id, addr, timestamp, raw) VALUES (?, ?, ?, ?);",
+            paramsv![
+                chat_id,
+                addr,
+                timestamp,
+                String::from_utf8_lossy(raw).to_string()
+            ],
+        )
+        .await?;
+    Ok(())
+}
+
+/// Stores the raw MIME of a sent member-added/removed message.
+pub(crate) async fn save_member_change(context: &Context, msg: &Message, raw: &[u8]) -> Result<()> {
+    if is_member_change(msg.param.get_cmd()) {
+        if let Some(addr) = msg.param.get(Param::Arg) {
+            store_member_change(context, msg.chat_id, addr, msg.timestamp_sort, raw).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Stores the raw MIME of a received member-added/removed message.
+pub(crate) async fn save_received_member_change(
+    context: &Context,
+    chat_id: ChatId,
+    mime_parser: &MimeMessage,
+    timestamp: i64,
+    raw: &[u8],
+) -> Result<()> {
+    let addr = mime_parser
+        .get(HeaderDef::ChatGroupMemberAdded)
+        .or_else(|| mime_parser.get(HeaderDef::ChatGroupMemberRemoved));
+    if let Some(addr) = addr {
+        store_member_change(context, chat_id, addr, timestamp, raw).await?;
+    }
+    Ok(())
+}
+
+/// Returns the ID and the timestamp of the latest stored
+/// member-added/removed message of the chat concerning `addr`.
+async fn latest_member_change(
+    context: &Context,
+    chat_id: ChatId,
+    addr: &str,
+) -> Result<Option<(u32, i64)>> {
+    let change = context
+        .sql
+        .query_row_optional(
+            "SELECT id, timestamp FROM group_member_changes \
+             WHERE chat_id=? AND addr=? COLLATE NOCASE \
+             ORDER BY timestamp DESC, id DESC LIMIT 1;",
+            paramsv![chat_id, addr],
+            |row| Ok((row.get::<_, u32>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .await?;
+    Ok(change)
+}
+
+/// Compares the member list of a received member-added/removed message
+/// with the members of the chat.
+///
+/// `from_id` and `to_ids` are the sender and the recipients of the message,
+/// `changed_id` the added or removed member.  Returns the IDs of the stored
+/// member-added/removed messages the sender has missed, if any.
+pub(crate) async fn check_member_list(
+    context: &Context,
+    chat_id: ChatId,
+    from_id: u32,
+    to_ids: &[u32],
+    changed_id: u32,
+) -> Result<Vec<u32>> {
+    let ours: BTreeSet<u32> = chat::get_chat_contacts(context, chat_id)
+        .await
+        .into_iter()
+        .collect();
+    let mut theirs: BTreeSet<u32> = to_ids.iter().copied().collect();
+    theirs.insert(from_id);
+
+    let mut change_ids = Vec::new();
+    for &contact_id in ours.symmetric_difference(&theirs) {
+        if contact_id == DC_CONTACT_ID_SELF || contact_id == from_id || contact_id == changed_id {
+            continue;
+        }
+        let contact = Contact::get_by_id(context, contact_id).await?;
+        if let Some((change_id, _)) =
+            latest_member_change(context, chat_id, contact.get_addr()).await?
+        {
+            info!(
+                context,
+                "Member list of {} inconsistent for {}, correcting with change {}.",
+                chat_id,
+                contact.get_addr(),
+                change_id
+            );
+            change_ids.push(change_id);
+        }
+    }
+    Ok(change_ids)
+}
+
+/// Sends the given stored member-added/removed messages to all members of the chat.
+pub(crate) async fn send_member_correction(
+    context: &Context,
+    chat_id: ChatId,
+    change_ids: &[u32],
+) -> Result<MsgId> {
+    let mut msg = Message::new(Viewtype::Text);
+    msg.text = Some("Group members corrected.".to_string());
+    msg.hidden = true;
+    msg.param.set_cmd(SystemMessage::GroupMemberCorrection);
+    let change_ids: Vec<String> = change_ids.iter().map(|id| id.to_string()).collect();
+    msg.param.set(Param::Arg, change_ids.join(" "));
+    chat::send_msg(context, chat_id, &mut msg).await
+}
+
+/// Returns the raw member-added/removed messages to attach to a correction.
+pub(crate) async fn load_member_changes(context: &Context, msg: &Message) -> Result<Vec<String>> {
+    let mut raws = Vec::new();
+    for change_id in msg
+        .param
+        .get(Param::Arg)
+        .unwrap_or_default()
+        .split_whitespace()
+        .filter_map(|id| id.parse::<u32>().ok())
+    {
+        let raw: Option<String> = context
+            .sql
+            .query_get_value(
+                context,
+                "SELECT raw FROM group_member_changes WHERE id=?;",
+                paramsv![change_id],
+            )
+            .await;
+        match raw {
+            Some(raw) => raws.push(raw),
+            None => warn!(context, "Member change {} not stored anymore.", change_id),
+        }
+    }
+    Ok(raws)
+}
+
+/// Parses an attached member-added/removed message.
+///
+/// Encrypted messages are only read if they are encrypted to us;
+/// the keys and peerstates are never updated from attached messages.
+async fn parse_member_change(context: &Context, raw: &[u8]) -> Result<Option<MemberChange>> {
+    let mail = mailparse::parse_mail(raw)?;
+    let (decrypted, mut signatures) = e2ee::try_decrypt_attached(context, &mail).await?;
+    if decrypted.is_none() {
+        signatures = e2ee::validate_detached_signature(context, &mail).await?;
+    }
+    let decrypted_mail = match decrypted {
+        Some(ref decrypted) => Some(mailparse::parse_mail(decrypted)?),
+        None => None,
+    };
+
+    // protected headers from the encrypted part take precedence
+    let get = |headerdef: HeaderDef| {
+        decrypted_mail
+            .as_ref()
+            .and_then(|mail| mail.headers.get_header_value(headerdef.clone()))
+            .or_else(|| mail.headers.get_header_value(headerdef))
+    };
+
+    let grpid = get(HeaderDef::ChatGroupId).unwrap_or_default();
+    let from = mail
+        .headers
+        .get_header(HeaderDef::From_)
+        .and_then(|from| mailparse::addrparse_header(from).ok())
+        .and_then(|from| from.extract_single_info())
+        .map(|from| from.addr)
+        .unwrap_or_default();
+    let timestamp = mail
+        .headers
+        .get_header_value(HeaderDef::Date)
+        .and_then(|date| mailparse::dateparse(&date).ok())
+        .unwrap_or_default();
+
+    let (addr, added) = if let Some(addr) = get(HeaderDef::ChatGroupMemberAdded) {
+        (addr, true)
+    } else if let Some(addr) = get(HeaderDef::ChatGroupMemberRemoved) {
+        (addr, false)
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(MemberChange {
+        grpid,
+        from,
+        addr,
+        added,
+        timestamp,
+        signed: !signatures.is_empty(),
+    }))
+}
+
+/// Applies the member-added/removed messages attached to a correction
+/// received in the given chat.
+///
+/// Changes are only applied if they were sent by a member of the group,
+/// do not concern ourselves and are newer than the changes we know about.
+/// In verified and encrypted groups, the attached messages must be signed
+/// by their sender, unsigned ones are dropped.
+/// Returns true if the member list was modified.
+pub(crate) async fn apply_member_changes(
+    context: &Context,
+    chat_id: ChatId,
+    raws: &[Vec<u8>],
+) -> Result<bool> {
+    let chat = Chat::load_from_db(context, chat_id).await?;
+    let require_signature = chat.is_verified() || chat_id.parent_is_encrypted(context).await?;
+    let mut modified = false;
+
+    for raw in raws {
+        let change = match parse_member_change(context, raw).await {
+            Ok(Some(change)) => change,
+            Ok(None) => {
+                warn!(
+                    context,
+                    "Attachment of member correction is no member change."
+                );
+                continue;
+            }
+            Err(err) => {
+                warn!(context, "Cannot parse member change: {}", err);
+                continue;
+            }
+        };
+
+        if change.grpid != chat.grpid {
+            warn!(context, "Member change for another group ignored.");
+            continue;
+        }
+        if require_signature && !change.signed {
+            warn!(
+                context,
+                "Unsigned member change in encrypted group ignored."
+            );
+            continue;
+        }
+        let from_id = Contact::lookup_id_by_addr(context, &change.from, Origin::Unknown).await;
+        if from_id == 0 || !chat::is_contact_in_chat(context, chat_id, from_id).await {
+            warn!(
+                context,
+                "Member change by non-member {} ignored.", change.from
+            );
+            continue;
+        }
+        if context.is_self_addr(&change.addr).await? {
+            continue;
+        }
+        if let Some((_, timestamp)) = latest_member_change(context, chat_id, &change.addr).await? {
+            if timestamp >= change.timestamp {
+                info!(
+                    context,
+                    "Member change for {} outdated, ignored.", change.addr
+                );
+                continue;
+            }
+        }
+
+        store_member_change(context, chat_id, &change.addr, change.timestamp, raw).await?;
+
+        if change.added {
+            let (contact_id, _) =
+                Contact::add_or_lookup(context, "", &change.addr, Origin::IncomingUnknownTo)
+                    .await?;
+            if !chat::is_contact_in_chat(context, chat_id, contact_id).await {
+                info!(context, "Correction adds {} to {}.", change.addr, chat_id);
+                chat::add_to_chat_contacts_table(context, chat_id, contact_id).await;
+                modified = true;
+            }
+        } else {
+            let contact_id =
+                Contact::lookup_id_by_addr(context, &change.addr, Origin::Unknown).await;
+            if contact_id != 0 && chat::is_contact_in_chat(context, chat_id, contact_id).await {
+                info!(
+                    context,
+                    "Correction removes {} from {}.", change.addr, chat_id
+                );
+                chat::remove_from_chat_contacts_table(context, chat_id, contact_id).await;
+                modified = true;
+            }
+        }
+    }
+    Ok(modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dc_receive_imf::dc_receive_imf;
+    use crate::mimefactory::MimeFactory;
+    use crate::test_utils::*;
+
+    #[async_std::test]
+    async fn test_send_member_correction() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let bob = Contact::create(&t.ctx, "Bob", "bob@example.net")
+            .await
+            .unwrap();
+        chat::create_by_contact_id(&t.ctx, bob).await.unwrap();
+
+        // Bob creates a group with Alice and Erin, then adds Carol.
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Bob <bob@example.net>\n\
+              To: alice@example.com, erin@example.org\n\
+              Subject: Chat: foo\n\
+              Message-ID: <grp1@example.net>\n\
+              Chat-Version: 1.0\n\
+              Chat-Group-ID: abcdefghijk\n\
+              Chat-Group-Name: foo\n\
+              Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+              \n\
+              hello\n",
+            "INBOX",
+            1,
+            false,
+        )
+        .await
+        .unwrap();
+        let (chat_id, _, _) = chat::get_chat_id_by_grpid(&t.ctx, "abcdefghijk")
+            .await
+            .unwrap();
+        assert_eq!(chat::get_chat_contacts(&t.ctx, chat_id).await.len(), 3);
+
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Bob <bob@example.net>\n\
+              To: alice@example.com, erin@example.org, carol@example.org\n\
+              Subject: Chat: foo\n\
+              Message-ID: <grp2@example.net>\n\
+              Chat-Version: 1.0\n\
+              Chat-Group-ID: abcdefghijk\n\
+              Chat-Group-Name: foo\n\
+              Chat-Group-Member-Added: carol@example.org\n\
+              Date: Sun, 22 Mar 2020 22:38:57 +0000\n\
+              \n\
+              Member carol@example.org added.\n",
+            "INBOX",
+            2,
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(chat::get_chat_contacts(&t.ctx, chat_id).await.len(), 4);
+        let (add_carol, _) = latest_member_change(&t.ctx, chat_id, "carol@example.org")
+            .await
+            .unwrap()
+            .unwrap();
+
+        // The member list is consistent, no correction is sent.
+        let hidden_msgs = "SELECT COUNT(*) FROM msgs WHERE chat_id=? AND hidden=1;";
+        assert_eq!(
+            t.ctx
+                .sql
+                .query_get_value::<i32>(&t.ctx, hidden_msgs, paramsv![chat_id])
+                .await,
+            Some(0)
+        );
+
+        // Erin adds Doris, but missed that Bob added Carol.
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Erin <erin@example.org>\n\
+              To: alice@example.com, bob@example.net, doris@example.org\n\
+              Subject: Chat: foo\n\
+              Message-ID: <grp3@example.org>\n\
+              Chat-Version: 1.0\n\
+              Chat-Group-ID: abcdefghijk\n\
+              Chat-Group-Name: foo\n\
+              Chat-Group-Member-Added: doris@example.org\n\
+              Date: Sun, 22 Mar 2020 22:39:57 +0000\n\
+              \n\
+              Member doris@example.org added.\n",
+            "INBOX",
+            3,
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(chat::get_chat_contacts(&t.ctx, chat_id).await.len(), 5);
+
+        let correction_id: MsgId = t
+            .ctx
+            .sql
+            .query_get_value(
+                &t.ctx,
+                "SELECT id FROM msgs WHERE chat_id=? AND hidden=1;",
+                paramsv![chat_id],
+            )
+            .await
+            .unwrap();
+        let correction = Message::load_from_db(&t.ctx, correction_id).await.unwrap();
+        assert_eq!(
+            correction.param.get_cmd(),
+            SystemMessage::GroupMemberCorrection
+        );
+        assert_eq!(
+            correction.param.get(Param::Arg),
+            Some(add_carol.to_string().as_str())
+        );
+
+        let rendered = MimeFactory::from_msg(&t.ctx, &correction, false)
+            .await
+            .unwrap()
+            .render()
+            .await
+            .unwrap();
+        let payload = String::from_utf8_lossy(&rendered.message);
+        assert!(payload.contains("Chat-Group-Member-Correction: 1"));
+        assert!(payload.contains("message/rfc822"));
+        assert!(payload.contains("Message-ID: <grp2@example.net>"));
+        assert!(payload.contains("Chat-Group-Member-Added: carol@example.org"));
+    }
+
+    #[async_std::test]
+    async fn test_receive_member_correction() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+
+        // Erin adds Alice to a group with Bob.
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Erin <erin@example.org>\n\
+              To: alice@example.com, bob@example.net\n\
+              Subject: Chat: foo\n\
+              Message-ID: <grp1@example.org>\n\
+              Chat-Version: 1.0\n\
+              Chat-Group-ID: abcdefghijk\n\
+              Chat-Group-Name: foo\n\
+              Chat-Group-Member-Added: alice@example.com\n\
+              Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+              \n\
+              Member alice@example.com added.\n",
+            "INBOX",
+            1,
+            false,
+        )
+        .await
+        .unwrap();
+        let (chat_id, _, _) = chat::get_chat_id_by_grpid(&t.ctx, "abcdefghijk")
+            .await
+            .unwrap();
+        assert_eq!(chat::get_chat_contacts(&t.ctx, chat_id).await.len(), 3);
+        let msgs = chat::get_chat_msgs(&t.ctx, chat_id, 0, None).await;
+
+        // Bob tells Alice that he added Carol before,
+        // the member change by Mallory, who is no member, is ignored.
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Bob <bob@example.net>\n\
+              To: alice@example.com, erin@example.org, carol@example.org\n\
+              Subject: Chat: foo\n\
+              Message-ID: <grp2@example.net>\n\
+              Chat-Version: 1.0\n\
+              Chat-Group-ID: abcdefghijk\n\
+              Chat-Group-Name: foo\n\
+              Chat-Group-Member-Correction: 1\n\
+              Date: Sun, 22 Mar 2020 22:39:57 +0000\n\
+              Content-Type: multipart/mixed; boundary=\"XXX\"\n\
+              \n\
+              --XXX\n\
+              Content-Type: text/plain; charset=utf-8\n\
+              \n\
+              Group members corrected.\n\
+              --XXX\n\
+              Content-Type: message/rfc822\n\
+              \n\
+              From: Bob <bob@example.net>\n\
+              To: erin@example.org, carol@example.org\n\
+              Subject: Chat: foo\n\
+              Message-ID: <add1@example.net>\n\
+              Chat-Version: 1.0\n\
+              Chat-Group-ID: abcdefghijk\n\
+              Chat-Group-Name: foo\n\
+              Chat-Group-Member-Added: carol@example.org\n\
+              Date: Sun, 22 Mar 2020 22:38:57 +0000\n\
+              \n\
+              Member carol@example.org added.\n\
+              --XXX\n\
+              Content-Type: message/rfc822\n\
+              \n\
+              From: Mallory <mallory@example.net>\n\
+              To: erin@example.org, zoe@example.org\n\
+              Subject: Chat: foo\n\
+              Message-ID: <add2@example.net>\n\
+              Chat-Version: 1.0\n\
+              Chat-Group-ID: abcdefghijk\n\
+              Chat-Group-Name: foo\n\
+              Chat-Group-Member-Added: zoe@example.org\n\
+              Date: Sun, 22 Mar 2020 22:38:57 +0000\n\
+              \n\
+              Member zoe@example.org added.\n\
+              --XXX--\n",
+            "INBOX",
+            2,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let members = chat::get_chat_contacts(&t.ctx, chat_id).await;
+        assert_eq!(members.len(), 4);
+        let carol = Contact::lookup_id_by_addr(&t.ctx, "carol@example.org", Origin::Unknown).await;
+        assert!(members.contains(&carol));
+        assert_eq!(
+            Contact::lookup_id_by_addr(&t.ctx, "zoe@example.org", Origin::Unknown).await,
+            0
+        );
+
+        // The correction itself is not shown in the chat.
+        assert_eq!(chat::get_chat_msgs(&t.ctx, chat_id, 0, None).await, msgs);
+    }
+
+    #[async_std::test]
+    async fn test_unsigned_member_correction_in_encrypted_group() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Erin <erin@example.org>\n\
+              To: alice@example.com, bob@example.net\n\
+              Subject: Chat: foo\n\
+              Message-ID: <grp1@example.org>\n\
+              Chat-Version: 1.0\n\
+              Chat-Group-ID: abcdefghijk\n\
+              Chat-Group-Name: foo\n\
+              Chat-Group-Member-Added: alice@example.com\n\
+              Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+              \n\
+              Member alice@example.com added.\n",
+            "INBOX",
+            1,
+            false,
+        )
+        .await
+        .unwrap();
+        let (chat_id, _, _) = chat::get_chat_id_by_grpid(&t.ctx, "abcdefghijk")
+            .await
+            .unwrap();
+        assert!(latest_member_change(&t.ctx, chat_id, "alice@example.com")
+            .await
+            .unwrap()
+            .is_some());
+
+        // the group is encrypted from now on
+        t.ctx
+            .sql
+            .execute(
+                "UPDATE msgs SET param=? WHERE chat_id=?;",
+                paramsv!["c=1", chat_id],
+            )
+            .await
+            .unwrap();
+        assert!(chat_id.parent_is_encrypted(&t.ctx).await.unwrap());
+
+        // an unsigned member change, e.g. with a forged sender, is dropped
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Bob <bob@example.net>\n\
+              To: alice@example.com, erin@example.org\n\
+              Subject: Chat: foo\n\
+              Message-ID: <grp2@example.net>\n\
+              Chat-Version: 1.0\n\
+              Chat-Group-ID: abcdefghijk\n\
+              Chat-Group-Name: foo\n\
+              Chat-Group-Member-Correction: 1\n\
+              Date: Sun, 22 Mar 2020 22:39:57 +0000\n\
+              Content-Type: multipart/mixed; boundary=\"XXX\"\n\
+              \n\
+              --XXX\n\
+              Content-Type: text/plain; charset=utf-8\n\
+              \n\
+              Group members corrected.\n\
+              --XXX\n\
+              Content-Type: message/rfc822\n\
+              \n\
+              From: Erin <erin@example.org>\n\
+              To: bob@example.net, carol@example.org\n\
+              Subject: Chat: foo\n\
+              Message-ID: <add1@example.org>\n\
+              Chat-Version: 1.0\n\
+              Chat-Group-ID: abcdefghijk\n\
+              Chat-Group-Name: foo\n\
+              Chat-Group-Member-Added: carol@example.org\n\
+              Date: Sun, 22 Mar 2020 22:38:57 +0000\n\
+              \n\
+              Member carol@example.org added.\n\
+              --XXX--\n",
+            "INBOX",
+            2,
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(chat::get_chat_contacts(&t.ctx, chat_id).await.len(), 3);
+        assert!(latest_member_change(&t.ctx, chat_id, "carol@example.org")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -9,6 +9,10 @@ pub enum HeaderDef {
     Subject,
     Date,
     From_,
+    Sender,
+
+    /// Envelope sender, added by the receiving server
+    ReturnPath,
     To,
     Cc,
     Disposition,
@@ -18,6 +22,8 @@ pub enum HeaderDef {
     AdditionalMessageIds,
 
     ListId,
+    ListPost,
+    ListUnsubscribe,
     References,
     InReplyTo,
     Precedence,
@@ -31,9 +37,25 @@ pub enum HeaderDef {
     ChatVoiceMessage,
     ChatGroupMemberRemoved,
     ChatGroupMemberAdded,
+
+    /// Marks messages with attached member-added/removed messages
+    ChatGroupMemberCorrection,
     ChatContent,
     ChatDuration,
     ChatDispositionNotificationTo,
+    EphemeralTimer,
+
+    /// Message-ID of the message whose text is replaced
+    ChatEdit,
+
+    /// Space-separated Message-IDs of the messages deleted for everyone
+    ChatDelete,
+
+    /// Message-ID of the message pinned in a group
+    ChatPin,
+
+    /// Message-ID of the message unpinned in a group
+    ChatUnpin,
     Autocrypt,
     AutocryptSetupMessage,
     SecureJoin,
@@ -14,6 +14,7 @@ use async_std::sync::Receiver;
 use num_traits::FromPrimitive;
 
 use crate::config::*;
+use crate::connectivity::ConnectivityStore;
 use crate::constants::*;
 use crate::context::Context;
 use crate::dc_receive_imf::{
@@ -27,6 +28,7 @@ use crate::message::{self, update_server_uid};
 use crate::mimeparser;
 use crate::oauth2::dc_get_oauth2_access_token;
 use crate::param::Params;
+use crate::proxy::ProxyConfig;
 use crate::stock::StockMessage;
 
 mod client;
@@ -116,6 +118,7 @@ pub struct Imap {
     interrupt: Option<stop_token::StopSource>,
     skip_next_idle_wait: bool,
     should_reconnect: bool,
+    pub(crate) connectivity: ConnectivityStore,
 }
 
 #[derive(Debug)]
@@ -151,6 +154,7 @@ struct ImapConfig {
     pub imap_pw: String,
     pub certificate_checks: CertificateChecks,
     pub server_flags: usize,
+    pub proxy: Option<ProxyConfig>,
     pub selected_folder: Option<String>,
     pub selected_mailbox: Option<Mailbox>,
     pub selected_folder_needs_expunge: bool,
@@ -171,6 +175,7 @@ impl Default for ImapConfig {
             imap_pw: "".into(),
             certificate_checks: Default::default(),
             server_flags: 0,
+            proxy: None,
             selected_folder: None,
             selected_mailbox: None,
             selected_folder_needs_expunge: false,
@@ -190,6 +195,7 @@ impl Imap {
             interrupt: Default::default(),
             skip_next_idle_wait: Default::default(),
             should_reconnect: Default::default(),
+            connectivity: Default::default(),
         }
     }
 
@@ -217,6 +223,8 @@ impl Imap {
             return Ok(());
         }
 
+        self.connectivity.set_connecting(context).await;
+
         let server_flags = self.config.server_flags as i32;
 
         let connection_res: ImapResult<Client> =
@@ -225,7 +233,8 @@ impl Imap {
                 let imap_server: &str = config.imap_server.as_ref();
                 let imap_port = config.imap_port;
 
-                match Client::connect_insecure((imap_server, imap_port)).await {
+                match Client::connect_insecure(imap_server, imap_port, config.proxy.as_ref()).await
+                {
                     Ok(client) => {
                         if (server_flags & DC_LP_IMAP_SOCKET_STARTTLS) != 0 {
                             client.secure(imap_server, config.certificate_checks).await
@@ -241,9 +250,10 @@ impl Imap {
                 let imap_port = config.imap_port;
 
                 Client::connect_secure(
-                    (imap_server, imap_port),
                     imap_server,
+                    imap_port,
                     config.certificate_checks,
+                    config.proxy.as_ref(),
                 )
                 .await
             };
@@ -286,6 +296,7 @@ impl Imap {
                         .await
                 };
                 // IMAP connection failures are reported to users
+                self.connectivity.set_err(context, &message).await;
                 emit_event!(context, Event::ErrorNetwork(message));
                 return Err(Error::ConnectionFailed(err.to_string()));
             }
@@ -305,6 +316,9 @@ impl Imap {
                 let message = context
                     .stock_string_repl_str(StockMessage::CannotLogin, &imap_user)
                     .await;
+                self.connectivity
+                    .set_err(context, format!("{} ({})", message, err))
+                    .await;
 
                 emit_event!(
                     context,
@@ -335,6 +349,7 @@ impl Imap {
         cfg.imap_user = "".into();
         cfg.imap_pw = "".into();
         cfg.imap_port = 0;
+        cfg.proxy = None;
 
         cfg.can_idle = false;
         cfg.can_move = false;
@@ -381,6 +396,7 @@ impl Imap {
             config.imap_pw = imap_pw.to_string();
             config.certificate_checks = lp.imap_certificate_checks;
             config.server_flags = server_flags;
+            config.proxy = lp.proxy.clone();
         }
 
         if let Err(err) = self.setup_handle_if_needed(context).await {
@@ -409,6 +425,7 @@ impl Imap {
                         self.config.can_idle = can_idle;
                         self.config.can_move = can_move;
                         self.connected = true;
+                        self.connectivity.set_connected(context).await;
                         emit_event!(
                             context,
                             Event::ImapConnected(format!(
@@ -448,9 +465,62 @@ impl Imap {
         }
         self.setup_handle_if_needed(context).await?;
 
+        self.connectivity.set_working(context).await;
         while self.fetch_new_messages(context, &watch_folder).await? {
             // We fetch until no more new messages are there.
         }
+
+        if let Err(err) = self.sync_seen_flags(context, &watch_folder).await {
+            warn!(
+                context,
+                "Failed to sync seen flags for folder \"{}\": {}", watch_folder, err
+            );
+        }
+        self.connectivity.set_connected(context).await;
+        Ok(())
+    }
+
+    /// Marks messages as seen locally that are marked as seen on the
+    /// server, e.g. by another device.
+    ///
+    /// The flags of all messages in the folder are scanned, as the
+    /// async-imap version in use does not return the MODSEQ of fetched
+    /// messages that CONDSTORE would need to fetch only changed flags.
+    async fn sync_seen_flags(&mut self, context: &Context, folder: &str) -> Result<()> {
+        // resets the server UIDs of the folder if UIDVALIDITY has changed
+        self.select_with_uidvalidity(context, folder).await?;
+        let unseen_uids = message::get_unseen_server_uids(context, folder).await?;
+        if unseen_uids.is_empty() {
+            return Ok(());
+        }
+
+        let session = self.session.as_mut().ok_or(Error::NoConnection)?;
+        let mut list = session
+            .uid_fetch("1:*", "(FLAGS)")
+            .await
+            .map_err(Error::FetchFailed)?;
+
+        let mut server_seen_uids = Vec::new();
+        while let Some(fetch) = list.next().await {
+            let msg = fetch.map_err(|err| Error::Other(err.to_string()))?;
+            if let Some(uid) = msg.uid {
+                if msg.flags().any(|flag| flag == Flag::Seen) {
+                    server_seen_uids.push(uid);
+                }
+            }
+        }
+        drop(list);
+
+        let seen_uids = newly_seen_uids(&unseen_uids, &server_seen_uids);
+        if !seen_uids.is_empty() {
+            info!(
+                context,
+                "{} messages in \"{}\" were seen on the server.",
+                seen_uids.len(),
+                folder
+            );
+            message::markseen_by_server_uids(context, folder, &seen_uids).await?;
+        }
         Ok(())
     }
 
@@ -509,6 +579,19 @@ impl Imap {
             return Ok((uid_validity, last_seen_uid));
         }
 
+        // UIDs are only valid within the same UIDVALIDITY
+        if let Err(err) = message::reset_server_uids(context, folder).await {
+            warn!(
+                context,
+                "Failed to reset server UIDs of folder \"{}\": {}", folder, err
+            );
+        }
+        let mailbox = self
+            .config
+            .selected_mailbox
+            .as_ref()
+            .ok_or_else(|| Error::NoMailbox(folder.to_string()))?;
+
         if mailbox.exists == 0 {
             info!(context, "Folder \"{}\" is empty.", folder);
 
@@ -1513,6 +1596,33 @@ async fn message_needs_processing(
     true
 }
 
+/// Returns the UIDs of locally unseen messages that are seen on the server.
+///
+/// `unseen_uids` must be sorted.
+fn newly_seen_uids(unseen_uids: &[u32], server_seen_uids: &[u32]) -> Vec<u32> {
+    let mut seen_uids: Vec<u32> = server_seen_uids
+        .iter()
+        .copied()
+        .filter(|uid| unseen_uids.binary_search(uid).is_ok())
+        .collect();
+    seen_uids.sort();
+    seen_uids.dedup();
+    seen_uids
+}
+
 fn get_fallback_folder(delimiter: &str) -> String {
     format!("INBOX{}DeltaChat", delimiter)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_newly_seen_uids() {
+        assert!(newly_seen_uids(&[], &[1, 2, 3]).is_empty());
+        assert!(newly_seen_uids(&[4, 5], &[]).is_empty());
+        assert_eq!(newly_seen_uids(&[2, 4, 7], &[1, 2, 3, 7, 8]), vec![2, 7]);
+        assert_eq!(newly_seen_uids(&[2, 4, 7], &[7, 2, 7]), vec![2, 7]);
+    }
+}
@@ -2,10 +2,19 @@
 
 use std::any::Any;
 use std::cmp::{max, min};
+use std::ffi::OsString;
 
+use std::net::IpAddr;
+use std::time::Duration;
+
+use async_std::fs::File;
+use async_std::io::{self, Read, SeekFrom, Write};
+use async_std::net::{TcpListener, TcpStream};
 use async_std::path::{Path, PathBuf};
 use async_std::prelude::*;
+use num_traits::FromPrimitive;
 use rand::{thread_rng, Rng};
+use sha2::{Digest, Sha256};
 
 use crate::blob::BlobObject;
 use crate::chat;
@@ -18,10 +27,12 @@ use crate::e2ee;
 use crate::error::*;
 use crate::events::Event;
 use crate::key::{self, DcKey, DcSecretKey, SignedPublicKey, SignedSecretKey};
+use crate::lot::LotState;
 use crate::message::{Message, MsgId};
 use crate::mimeparser::SystemMessage;
 use crate::param::*;
 use crate::pgp;
+use crate::qr;
 use crate::sql::{self, Sql};
 use crate::stock::StockMessage;
 
@@ -42,14 +53,32 @@ pub enum ImexMode {
     /// Export a backup to the directory given as `param1`.
     /// The backup contains all contacts, chats, images and other data and device independent settings.
     /// The backup does not contain device dependent settings as ringtones or LED notification settings.
-    /// The name of the backup is typically `delta-chat.<day>.bak`, if more than one backup is create on a day,
-    /// the format is `delta-chat.<day>-<number>.bak`
+    /// The backup is a tar archive named `delta-chat-backup-<day>-<number>.tar`.
+    /// If a passphrase is given, the archive is split into chunks which are symmetrically
+    /// encrypted with OpenPGP, so that it never has to be held in memory,
+    /// and named `delta-chat-backup-<day>-<number>.tar.dcenc`.
     ExportBackup = 11,
 
     /// `param1` is the file (not: directory) to import. The file is normally
     /// created by DC_IMEX_EXPORT_BACKUP and detected by dc_imex_has_backup(). Importing a backup
     /// is only possible as long as the context is not configured or used in another way.
+    /// Encrypted backups require the passphrase used on export,
+    /// backups in the old `.bak` format can still be imported.
     ImportBackup = 12,
+
+    /// Serve a backup to another device in the local network.
+    /// An [Event::ImexBackupQr] with a QR code to be scanned by the other device is emitted,
+    /// the backup is sent to the first device connecting with the secret contained in it.
+    /// `param1` is the IP address to listen on and to put into the QR code,
+    /// by default a private IPv4 address of the device is used if there is one.
+    /// After some connections with a wrong secret, or if no device connects
+    /// within 10 minutes, serving the backup fails.
+    SendBackupOverNetwork = 21,
+
+    /// Receive and import a backup served by [ImexMode::SendBackupOverNetwork].
+    /// `param1` is the scanned QR code.
+    /// As for [ImexMode::ImportBackup], the context must not be configured.
+    ReceiveBackupOverNetwork = 22,
 }
 
 /// Import/export things.
@@ -66,15 +95,19 @@ pub enum ImexMode {
 ///
 /// Only one import-/export-progress can run at the same time.
 /// To cancel an import-/export-progress, drop the future returned by this function.
+///
+/// `passphrase` is used to encrypt exported and decrypt imported backups,
+/// it is ignored for keys.
 pub async fn imex(
     context: &Context,
     what: ImexMode,
     param1: Option<impl AsRef<Path>>,
+    passphrase: Option<String>,
 ) -> Result<()> {
     use futures::future::FutureExt;
 
     let cancel = context.alloc_ongoing().await?;
-    let res = imex_inner(context, what, param1)
+    let res = imex_inner(context, what, param1, passphrase)
         .race(cancel.recv().map(|_| Err(format_err!("canceled"))))
         .await;
 
@@ -94,13 +127,28 @@ pub async fn has_backup(context: &Context, dir_name: impl AsRef<Path>) -> Result
             let path = dirent.path();
             let name = dirent.file_name();
             let name = name.to_string_lossy();
-            if name.starts_with("delta-chat") && name.ends_with(".bak") {
+            if name.starts_with("delta-chat-backup")
+                && (name.ends_with(".tar") || name.ends_with(".tar.dcenc"))
+            {
+                let curr_backup_time = match dirent.metadata().await.and_then(|m| m.modified()) {
+                    Ok(modified) => modified
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map(|d| d.as_secs() as i64)
+                        .unwrap_or_default(),
+                    Err(_) => continue,
+                };
+                if curr_backup_time > newest_backup_time {
+                    newest_backup_path = Some(path);
+                    newest_backup_time = curr_backup_time;
+                }
+                info!(context, "backup_time of {} is {}", name, curr_backup_time);
+            } else if name.starts_with("delta-chat") && name.ends_with(".bak") {
                 let sql = Sql::new();
                 if sql.open(context, &path, true).await {
                     let curr_backup_time = sql
                         .get_raw_config_int(context, "backup_time")
                         .await
-                        .unwrap_or_default();
+                        .unwrap_or_default() as i64;
                     if curr_backup_time > newest_backup_time {
                         newest_backup_path = Some(path);
                         newest_backup_time = curr_backup_time;
@@ -264,6 +312,143 @@ pub async fn continue_key_transfer(
     msg_id: MsgId,
     setup_code: &str,
 ) -> Result<()> {
+    continue_key_transfer_with(context, msg_id, setup_code, KeyConflictResolution::KeepBoth).await
+}
+
+/// How to handle a key imported from an Autocrypt Setup Message
+/// that differs from the current key,
+/// e.g. because it was generated by another MUA.
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, FromPrimitive, ToPrimitive)]
+#[repr(i32)]
+pub enum KeyConflictResolution {
+    /// Keep using the current key, the imported key is dropped.
+    KeepCurrent = 1,
+
+    /// Use the imported key, the current key is deleted.
+    /// Messages encrypted to the current key cannot be read any longer.
+    Replace = 2,
+
+    /// Use the imported key,
+    /// the current key is kept to decrypt older messages.
+    KeepBoth = 3,
+}
+
+/// The fingerprints of the current key and of the key
+/// contained in an Autocrypt Setup Message if they differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyConflict {
+    pub current_fingerprint: key::Fingerprint,
+    pub imported_fingerprint: key::Fingerprint,
+}
+
+/// Decrypts an Autocrypt Setup Message and checks
+/// if the contained key differs from the current key.
+///
+/// Returns `None` if there is no current key or the keys are the same,
+/// in this case [continue_key_transfer] can be called without asking the user.
+/// Otherwise, the user should decide how to handle the conflict
+/// and [continue_key_transfer_with] should be called with the decision.
+pub async fn check_key_transfer(
+    context: &Context,
+    msg_id: MsgId,
+    setup_code: &str,
+) -> Result<Option<KeyConflict>> {
+    let armored_key = decrypt_setup_message(context, msg_id, setup_code).await?;
+    get_key_conflict(context, &armored_key).await
+}
+
+async fn get_key_conflict(context: &Context, armored_key: &str) -> Result<Option<KeyConflict>> {
+    let (private_key, _header) = SignedSecretKey::from_asc(armored_key)?;
+    let imported_fingerprint = private_key.split_public_key()?.fingerprint();
+
+    let current = key::get_self_key_history(context)
+        .await?
+        .into_iter()
+        .find(|info| info.is_default);
+    match current {
+        Some(current) if current.fingerprint != imported_fingerprint => Ok(Some(KeyConflict {
+            current_fingerprint: current.fingerprint,
+            imported_fingerprint,
+        })),
+        _ => Ok(None),
+    }
+}
+
+/// Continues the Autocrypt Key Transfer,
+/// handling a differing current key as given by `resolution`.
+///
+/// If there is a conflict, the decision is recorded in the setup message,
+/// see [get_key_transfer_resolution].
+pub async fn continue_key_transfer_with(
+    context: &Context,
+    msg_id: MsgId,
+    setup_code: &str,
+    resolution: KeyConflictResolution,
+) -> Result<()> {
+    let armored_key = decrypt_setup_message(context, msg_id, setup_code).await?;
+    let conflict = get_key_conflict(context, &armored_key).await?;
+
+    let conflict = match conflict {
+        Some(conflict) => conflict,
+        None => {
+            set_self_key(context, &armored_key, true, true).await?;
+            maybe_add_bcc_self_device_msg(context).await?;
+            return Ok(());
+        }
+    };
+
+    match resolution {
+        KeyConflictResolution::KeepCurrent => {
+            info!(
+                context,
+                "Key {} of setup message not imported, keeping {}",
+                conflict.imported_fingerprint.hex(),
+                conflict.current_fingerprint.hex()
+            );
+        }
+        KeyConflictResolution::Replace => {
+            set_self_key(context, &armored_key, true, true).await?;
+            key::delete_self_key(context, &conflict.current_fingerprint).await?;
+            info!(
+                context,
+                "Key {} replaced by key {} of setup message",
+                conflict.current_fingerprint.hex(),
+                conflict.imported_fingerprint.hex()
+            );
+            maybe_add_bcc_self_device_msg(context).await?;
+        }
+        KeyConflictResolution::KeepBoth => {
+            set_self_key(context, &armored_key, true, true).await?;
+            maybe_add_bcc_self_device_msg(context).await?;
+        }
+    }
+
+    let mut msg = Message::load_from_db(context, msg_id).await?;
+    msg.param
+        .set_int(Param::KeyTransferResolution, resolution as i32);
+    msg.save_param_to_disk(context).await;
+    Ok(())
+}
+
+/// Returns how the key conflict was resolved when the given
+/// Autocrypt Setup Message was imported,
+/// `None` if the message was not imported yet or without a conflict.
+pub async fn get_key_transfer_resolution(
+    context: &Context,
+    msg_id: MsgId,
+) -> Result<Option<KeyConflictResolution>> {
+    let msg = Message::load_from_db(context, msg_id).await?;
+    Ok(msg
+        .param
+        .get_int(Param::KeyTransferResolution)
+        .and_then(KeyConflictResolution::from_i32))
+}
+
+async fn decrypt_setup_message(
+    context: &Context,
+    msg_id: MsgId,
+    setup_code: &str,
+) -> Result<String> {
     ensure!(!msg_id.is_special(), "wrong id");
 
     let msg = Message::load_from_db(context, msg_id).await?;
@@ -275,11 +460,7 @@ pub async fn continue_key_transfer(
     if let Some(filename) = msg.get_file(context) {
         let file = dc_open_file_std(context, filename)?;
         let sc = normalize_setup_code(setup_code);
-        let armored_key = decrypt_setup_file(&sc, file).await?;
-        set_self_key(context, &armored_key, true, true).await?;
-        maybe_add_bcc_self_device_msg(context).await?;
-
-        Ok(())
+        decrypt_setup_file(&sc, file).await
     } else {
         bail!("Message is no Autocrypt Setup Message.");
     }
@@ -364,31 +545,53 @@ async fn imex_inner(
     context: &Context,
     what: ImexMode,
     param: Option<impl AsRef<Path>>,
+    passphrase: Option<String>,
 ) -> Result<()> {
-    ensure!(param.is_some(), "No Import/export dir/file given.");
+    ensure!(
+        param.is_some() || what == ImexMode::SendBackupOverNetwork,
+        "No Import/export dir/file given."
+    );
 
     info!(context, "Import/export process started.");
     context.emit_event(Event::ImexProgress(10));
 
     ensure!(context.sql.is_open().await, "Database not opened.");
 
-    let path = param.unwrap();
-    if what == ImexMode::ExportBackup || what == ImexMode::ExportSelfKeys {
+    if what == ImexMode::ExportBackup
+        || what == ImexMode::ExportSelfKeys
+        || what == ImexMode::SendBackupOverNetwork
+    {
         // before we export anything, make sure the private key exists
         if e2ee::ensure_secret_key_exists(context).await.is_err() {
             bail!("Cannot create private key or private key not available.");
-        } else {
-            dc_create_folder(context, &path).await?;
         }
     }
 
-    let success = match what {
-        ImexMode::ExportSelfKeys => export_self_keys(context, path).await,
-        ImexMode::ImportSelfKeys => import_self_keys(context, path).await,
-        ImexMode::ExportBackup => export_backup(context, path).await,
-        ImexMode::ImportBackup => import_backup(context, path).await,
+    let success = match (what, param) {
+        (ImexMode::SendBackupOverNetwork, listen_addr) => {
+            let listen_addr = listen_addr.map(|addr| addr.as_ref().to_string_lossy().into_owned());
+            send_backup_over_network(context, listen_addr).await
+        }
+        (_, None) => bail!("No Import/export dir/file given."),
+        (ImexMode::ExportSelfKeys, Some(path)) => {
+            dc_create_folder(context, &path).await?;
+            export_self_keys(context, path).await
+        }
+        (ImexMode::ImportSelfKeys, Some(path)) => import_self_keys(context, path).await,
+        (ImexMode::ExportBackup, Some(path)) => {
+            dc_create_folder(context, &path).await?;
+            export_backup(context, path, passphrase).await
+        }
+        (ImexMode::ImportBackup, Some(path)) => import_backup(context, path, passphrase).await,
+        (ImexMode::ReceiveBackupOverNetwork, Some(qr)) => {
+            receive_backup_over_network(context, &qr.as_ref().to_string_lossy()).await
+        }
     };
+    imex_result(context, success)
+}
 
+/// Reports the result of an import/export process.
+fn imex_result(context: &Context, success: Result<()>) -> Result<()> {
     match success {
         Ok(()) => {
             info!(context, "IMEX successfully completed");
@@ -402,12 +605,27 @@ async fn imex_inner(
     }
 }
 
+/// Name of the database file inside a backup archive.
+const DBFILE_BACKUP_NAME: &str = "dc_database_backup.sqlite";
+
+/// Name of the directory containing the blobs inside a backup archive.
+const BLOBS_BACKUP_NAME: &str = "blobs_backup";
+
 /// Import Backup
-async fn import_backup(context: &Context, backup_to_import: impl AsRef<Path>) -> Result<()> {
+///
+/// The format of the backup is detected from its content:
+/// old backups are plain SQLite databases, encrypted backups
+/// start with [pgp::SYMM_STREAM_MAGIC] and all other files are read as tar archive.
+async fn import_backup(
+    context: &Context,
+    backup_to_import: impl AsRef<Path>,
+    passphrase: Option<String>,
+) -> Result<()> {
+    let backup_to_import = backup_to_import.as_ref();
     info!(
         context,
         "Import \"{}\" to \"{}\".",
-        backup_to_import.as_ref().display(),
+        backup_to_import.display(),
         context.get_dbfile().display()
     );
 
@@ -415,6 +633,101 @@ async fn import_backup(context: &Context, backup_to_import: impl AsRef<Path>) ->
         !context.is_configured().await,
         "Cannot import backups to accounts in use."
     );
+
+    let mut file = File::open(backup_to_import).await?;
+    let mut magic = [0u8; 16];
+    file.read_exact(&mut magic).await?;
+    if &magic == b"SQLite format 3\0" {
+        drop(file);
+        return import_backup_old(context, backup_to_import).await;
+    }
+    file.seek(SeekFrom::Start(0)).await?;
+
+    // encrypted backups start with a byte having the highest bit set,
+    // tar archives start with an ASCII file name.
+    let size = file.metadata().await?.len();
+    if magic[0] & 0x80 != 0 {
+        let passphrase = match passphrase {
+            Some(passphrase) => passphrase,
+            None => bail!("Backup is encrypted, passphrase required."),
+        };
+        let archive = pgp::SymmDecryptReader::new(file, passphrase);
+        import_backup_tar(context, archive, size).await
+    } else {
+        import_backup_tar(context, file, size).await
+    }
+}
+
+async fn import_backup_tar<R: Read + Unpin + Send + Sync>(
+    context: &Context,
+    archive: R,
+    archive_size: u64,
+) -> Result<()> {
+    context.sql.close().await;
+    dc_delete_file(context, context.get_dbfile()).await;
+    ensure!(
+        !context.get_dbfile().exists().await,
+        "Cannot delete old database."
+    );
+
+    let res = unpack_backup_tar(context, archive, archive_size).await;
+
+    // Re-open the database even if unpacking failed,
+    // so that the context is usable again.
+    ensure!(
+        context
+            .sql
+            .open(&context, &context.get_dbfile(), false)
+            .await,
+        "could not re-open db"
+    );
+    res?;
+
+    delete_and_reset_all_device_msgs(&context).await?;
+    Ok(())
+}
+
+async fn unpack_backup_tar<R: Read + Unpin + Send + Sync>(
+    context: &Context,
+    mut archive: R,
+    archive_size: u64,
+) -> Result<()> {
+    let mut entries = async_tar::Archive::new(&mut archive).entries()?;
+    let mut has_dbfile = false;
+    let mut read_size = 0;
+    while let Some(entry) = entries.next().await {
+        let mut entry = entry?;
+        if context.shall_stop_ongoing().await {
+            bail!("received stop signal");
+        }
+
+        let path = entry.path()?.to_path_buf();
+        if path.as_path() == Path::new(DBFILE_BACKUP_NAME) {
+            entry.unpack(context.get_dbfile()).await?;
+            has_dbfile = true;
+        } else if path.starts_with(BLOBS_BACKUP_NAME) && path.file_name().is_some() {
+            // only use the file name, blobs are never stored in subdirectories
+            let name = path.file_name().unwrap_or_default();
+            entry.unpack(context.get_blobdir().join(name)).await?;
+        } else {
+            warn!(context, "Ignoring {} in backup.", path.display());
+        }
+
+        read_size += entry.header().size()?;
+        let permille = max(min(read_size * 1000 / max(archive_size, 1), 990), 10);
+        context.emit_event(Event::ImexProgress(permille as usize));
+    }
+    drop(entries);
+    ensure!(has_dbfile, "No database found in backup.");
+
+    // read up to the end, so that a truncated encrypted backup is detected
+    io::copy(&mut archive, &mut io::sink()).await?;
+    Ok(())
+}
+
+/// Imports a backup in the old format,
+/// a database with the blobs stored in the `backup_blobs` table.
+async fn import_backup_old(context: &Context, backup_to_import: &Path) -> Result<()> {
     context.sql.close().await;
     dc_delete_file(context, context.get_dbfile()).await;
     ensure!(
@@ -423,7 +736,7 @@ async fn import_backup(context: &Context, backup_to_import: impl AsRef<Path>) ->
     );
 
     ensure!(
-        dc_copy_file(context, backup_to_import.as_ref(), context.get_dbfile()).await,
+        dc_copy_file(context, backup_to_import, context.get_dbfile()).await,
         "could not copy file"
     );
     /* error already logged */
@@ -505,123 +818,386 @@ async fn import_backup(context: &Context, backup_to_import: impl AsRef<Path>) ->
 /*******************************************************************************
  * Export backup
  ******************************************************************************/
-/* the FILE_PROGRESS macro calls the callback with the permille of files processed.
-The macro avoids weird values of 0% or 100% while still working. */
-async fn export_backup(context: &Context, dir: impl AsRef<Path>) -> Result<()> {
+async fn export_backup(
+    context: &Context,
+    dir: impl AsRef<Path>,
+    passphrase: Option<String>,
+) -> Result<()> {
     // get a fine backup file name (the name includes the date so that multiple backup instances are possible)
-    // FIXME: we should write to a temporary file first and rename it on success. this would guarantee the backup is complete.
-    // let dest_path_filename = dc_get_next_backup_file(context, dir, res);
     let now = time();
-    let dest_path_filename = dc_get_next_backup_path(dir, now).await?;
-    let dest_path_string = dest_path_filename.to_string_lossy().to_string();
+    let suffix = if passphrase.is_some() {
+        "tar.dcenc"
+    } else {
+        "tar"
+    };
+    let dest_path_filename = dc_get_next_backup_path(dir, now, suffix).await?;
+    // the backup is written to a temporary file first and renamed on success,
+    // so an interrupted export never leaves a broken backup behind.
+    let mut temp_path_filename = dest_path_filename.clone().into_os_string();
+    temp_path_filename.push(".part");
+    let temp_path_filename = PathBuf::from(temp_path_filename);
 
     sql::housekeeping(context).await;
 
     context.sql.execute("VACUUM;", paramsv![]).await.ok();
 
-    // we close the database during the copy of the dbfile
-    context.sql.close().await;
     info!(
         context,
         "Backup '{}' to '{}'.",
         context.get_dbfile().display(),
         dest_path_filename.display(),
     );
-    let copied = dc_copy_file(context, context.get_dbfile(), &dest_path_filename).await;
-    context
-        .sql
-        .open(&context, &context.get_dbfile(), false)
-        .await;
 
-    if !copied {
-        bail!(
-            "could not copy file from '{}' to '{}'",
-            context.get_dbfile().display(),
-            dest_path_string
-        );
-    }
-    let dest_sql = Sql::new();
-    ensure!(
-        dest_sql.open(context, &dest_path_filename, false).await,
-        "could not open exported database {}",
-        dest_path_string
-    );
-    let res = match add_files_to_export(context, &dest_sql).await {
+    let res = write_backup_file(context, &temp_path_filename, passphrase).await;
+    let res = match res {
+        Ok(()) => async_std::fs::rename(&temp_path_filename, &dest_path_filename)
+            .await
+            .map_err(Into::into),
+        Err(err) => Err(err),
+    };
+
+    match res {
+        Ok(()) => {
+            context.emit_event(Event::ImexFileWritten(dest_path_filename));
+            Ok(())
+        }
         Err(err) => {
-            dc_delete_file(context, &dest_path_filename).await;
+            async_std::fs::remove_file(&temp_path_filename).await.ok();
             error!(context, "backup failed: {}", err);
             Err(err)
         }
-        Ok(()) => {
-            dest_sql
-                .set_raw_config_int(context, "backup_time", now as i32)
-                .await?;
-            context.emit_event(Event::ImexFileWritten(dest_path_filename));
-            Ok(())
+    }
+}
+
+/// Writes the backup archive to the file at `path`, encrypted if a passphrase is given.
+async fn write_backup_file(
+    context: &Context,
+    path: &Path,
+    passphrase: Option<String>,
+) -> Result<()> {
+    let file = File::create(path).await?;
+    let file = match passphrase {
+        Some(passphrase) => {
+            let writer = pgp::SymmEncryptWriter::new(file, passphrase);
+            write_backup_tar(context, writer).await?.finish().await?
         }
+        None => write_backup_tar(context, file).await?,
     };
-    dest_sql.close().await;
+    file.sync_all().await?;
+    Ok(())
+}
 
-    Ok(res?)
+/// Returns the blobs to add to a backup as path, name and size,
+/// together with the total size of the blobs and the database.
+async fn get_backup_blobs(context: &Context) -> Result<(Vec<(PathBuf, OsString, u64)>, u64)> {
+    let mut blobs = Vec::new();
+    let mut total_size = async_std::fs::metadata(context.get_dbfile()).await?.len();
+    let mut dir_handle = async_std::fs::read_dir(context.get_blobdir()).await?;
+    while let Some(entry) = dir_handle.next().await {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name_str = name.to_string_lossy();
+        if name_str.starts_with("delta-chat") && name_str.ends_with(".bak") {
+            continue;
+        }
+        let metadata = entry.metadata().await?;
+        if !metadata.is_file() || metadata.len() == 0 {
+            continue;
+        }
+        total_size += metadata.len();
+        blobs.push((entry.path(), name, metadata.len()));
+    }
+    Ok((blobs, total_size))
 }
 
-async fn add_files_to_export(context: &Context, sql: &Sql) -> Result<()> {
-    // add all files as blobs to the database copy (this does not require
-    // the source to be locked, neigher the destination as it is used only here)
-    if !sql.table_exists("backup_blobs").await? {
-        sql.execute(
-            "CREATE TABLE backup_blobs (id INTEGER PRIMARY KEY, file_name, file_content);",
-            paramsv![],
-        )
-        .await?;
+/// Writes the database and all blobs as tar archive to `writer`.
+///
+/// Returns the writer after the archive is finished.
+async fn write_backup_tar<W: Write + Unpin + Send + Sync>(
+    context: &Context,
+    writer: W,
+) -> Result<W> {
+    let (blobs, total_size) = get_backup_blobs(context).await?;
+    let dbfile_size = async_std::fs::metadata(context.get_dbfile()).await?.len();
+    info!(context, "EXPORT: total_files_cnt={}", blobs.len());
+
+    let mut builder = async_tar::Builder::new(writer);
+
+    // we close the database while it is added to the archive
+    context.sql.close().await;
+    let res = builder
+        .append_path_with_name(context.get_dbfile(), DBFILE_BACKUP_NAME)
+        .await;
+    context
+        .sql
+        .open(&context, &context.get_dbfile(), false)
+        .await;
+    res?;
+
+    let mut written_size = dbfile_size;
+    for (path, name, size) in blobs {
+        if context.shall_stop_ongoing().await {
+            bail!("received stop signal");
+        }
+        let permille = max(min(written_size * 1000 / total_size, 990), 10);
+        context.emit_event(Event::ImexProgress(permille as usize));
+
+        info!(
+            context,
+            "EXPORT: copying filename={}",
+            name.to_string_lossy()
+        );
+        builder
+            .append_path_with_name(&path, Path::new(BLOBS_BACKUP_NAME).join(&name))
+            .await?;
+        written_size += size;
     }
-    // copy all files from BLOBDIR into backup-db
-    let mut total_files_cnt = 0;
-    let dir = context.get_blobdir();
-    let dir_handle = async_std::fs::read_dir(&dir).await?;
-    total_files_cnt += dir_handle.filter(|r| r.is_ok()).count().await;
 
-    info!(context, "EXPORT: total_files_cnt={}", total_files_cnt);
+    Ok(builder.into_inner().await?)
+}
 
-    sql.with_conn_async(|conn| async move {
-        // scan directory, pass 2: copy files
-        let mut dir_handle = async_std::fs::read_dir(&dir).await?;
+/*******************************************************************************
+ * Backup transfer over the network
+ ******************************************************************************/
 
-        let mut processed_files_cnt = 0;
-        while let Some(entry) = dir_handle.next().await {
-            let entry = entry?;
-            if context.shall_stop_ongoing().await {
-                return Ok(());
-            }
-            processed_files_cnt += 1;
-            let permille = max(min(processed_files_cnt * 1000 / total_files_cnt, 990), 10);
-            context.emit_event(Event::ImexProgress(permille));
+/// Time to wait for the other device to connect after the QR code is shown.
+const BACKUP_TRANSFER_ACCEPT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
 
-            let name_f = entry.file_name();
-            let name = name_f.to_string_lossy();
-            if name.starts_with("delta-chat") && name.ends_with(".bak") {
-                continue;
+/// Time to wait for a connection to the serving device.
+const BACKUP_TRANSFER_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Time a connecting device has to authenticate.
+const BACKUP_TRANSFER_AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Number of connections failing to authenticate before serving the backup is given up.
+const BACKUP_TRANSFER_MAX_ATTEMPTS: usize = 5;
+
+/// Length of the random challenge sent to a connecting device.
+const BACKUP_TRANSFER_NONCE_LEN: usize = 32;
+
+/// Serves a backup to another device, see [ImexMode::SendBackupOverNetwork].
+///
+/// The backup is encrypted with the secret from the QR code and written
+/// to a temporary file first, so the database is only closed for a short
+/// time and not while waiting for the other device.
+/// A connecting device has to answer a random challenge with the SHA-256
+/// hash of the challenge and the secret, so the secret is never sent
+/// over the network and an answer cannot be replayed.
+async fn send_backup_over_network(context: &Context, listen_addr: Option<String>) -> Result<()> {
+    let ip = match listen_addr {
+        Some(addr) => addr.parse::<IpAddr>()?,
+        None => default_ip_addr()?,
+    };
+    let secret = format!("{}{}", dc_create_id(), dc_create_id());
+
+    let mut temp_path = context.get_dbfile().as_os_str().to_os_string();
+    temp_path.push("-transfer.tar.dcenc.part");
+    let temp_path = PathBuf::from(temp_path);
+    let res = match write_backup_file(context, &temp_path, Some(secret.clone())).await {
+        Ok(()) => serve_backup_file(context, ip, &secret, &temp_path).await,
+        Err(err) => Err(err),
+    };
+    async_std::fs::remove_file(&temp_path).await.ok();
+    res
+}
+
+/// Shows the QR code for the backup transfer and sends the encrypted
+/// backup at `path` to the first device authenticating with `secret`.
+async fn serve_backup_file(context: &Context, ip: IpAddr, secret: &str, path: &Path) -> Result<()> {
+    let listener = TcpListener::bind((ip, 0)).await?;
+    let qr = format!(
+        "{}{}@{}",
+        qr::DCBACKUP_SCHEME,
+        secret,
+        listener.local_addr()?
+    );
+    info!(
+        context,
+        "Backup transfer: listening on {}.",
+        listener.local_addr()?
+    );
+    context.emit_event(Event::ImexBackupQr(qr));
+
+    let mut failed_attempts = 0;
+    let (stream, peer) = loop {
+        ensure!(
+            failed_attempts < BACKUP_TRANSFER_MAX_ATTEMPTS,
+            "Too many failed attempts to receive the backup."
+        );
+        let (stream, peer) = io::timeout(BACKUP_TRANSFER_ACCEPT_TIMEOUT, listener.accept())
+            .await
+            .map_err(|err| format_err!("No device connected to receive the backup: {}", err))?;
+        match io::timeout(
+            BACKUP_TRANSFER_AUTH_TIMEOUT,
+            check_backup_transfer_auth(&stream, secret),
+        )
+        .await
+        {
+            Ok(true) => {
+                info!(context, "Backup transfer: {} authenticated.", peer);
+                break (stream, peer);
             }
-            info!(context, "EXPORT: copying filename={}", name);
-            let curr_path_filename = context.get_blobdir().join(entry.file_name());
-            if let Ok(buf) = dc_read_file(context, &curr_path_filename).await {
-                if buf.is_empty() {
-                    continue;
-                }
-                // bail out if we can't insert
-                let mut stmt = conn.prepare_cached(
-                    "INSERT INTO backup_blobs (file_name, file_content) VALUES (?, ?);",
-                )?;
-                stmt.execute(paramsv![name, buf])?;
+            Ok(false) => {
+                warn!(context, "Backup transfer: {} sent a wrong secret.", peer);
+            }
+            Err(err) => {
+                warn!(
+                    context,
+                    "Backup transfer: {} failed to authenticate: {}", peer, err
+                );
             }
         }
-        Ok(())
+        failed_attempts += 1;
+    };
+    drop(listener);
+
+    // the size is only used to show the progress on the receiving device
+    let mut file = File::open(path).await?;
+    let size = file.metadata().await?.len();
+    (&stream).write_all(&size.to_be_bytes()).await?;
+    io::copy(&mut file, &mut &stream).await?;
+
+    // wait until the receiver confirms that everything arrived
+    let mut ack = [0u8; 1];
+    (&stream).read_exact(&mut ack).await?;
+    info!(context, "Backup transfer: backup sent to {}.", peer);
+    Ok(())
+}
+
+/// Sends a random challenge to a connecting device and checks its answer.
+async fn check_backup_transfer_auth(stream: &TcpStream, secret: &str) -> io::Result<bool> {
+    let nonce: [u8; BACKUP_TRANSFER_NONCE_LEN] = thread_rng().gen();
+    let expected = backup_transfer_auth(&nonce, secret);
+    let mut stream = stream;
+    stream.write_all(&nonce).await?;
+    let mut received = vec![0u8; expected.len()];
+    stream.read_exact(&mut received).await?;
+    Ok(constant_time_eq(&received, expected.as_bytes()))
+}
+
+/// Receives and imports a backup, see [ImexMode::ReceiveBackupOverNetwork].
+///
+/// The backup is decrypted and unpacked while it is received.
+async fn receive_backup_over_network(context: &Context, qr: &str) -> Result<()> {
+    ensure!(
+        !context.is_configured().await,
+        "Cannot import backups to accounts in use."
+    );
+    let lot = qr::check_qr(context, qr).await;
+    ensure!(lot.state == LotState::QrBackup, "Not a backup QR code.");
+    let addr = lot.text1.unwrap_or_default();
+    let secret = lot.text2.unwrap_or_default();
+
+    info!(context, "Backup transfer: connecting to {}.", addr);
+    let stream = io::timeout(
+        BACKUP_TRANSFER_CONNECT_TIMEOUT,
+        TcpStream::connect(addr.as_str()),
+    )
+    .await?;
+    io::timeout(BACKUP_TRANSFER_AUTH_TIMEOUT, async {
+        let mut nonce = [0u8; BACKUP_TRANSFER_NONCE_LEN];
+        (&stream).read_exact(&mut nonce).await?;
+        (&stream)
+            .write_all(backup_transfer_auth(&nonce, &secret).as_bytes())
+            .await
     })
     .await?;
 
+    // the announced size is used for the progress only, not trusted otherwise
+    let mut size = [0u8; 8];
+    (&stream).read_exact(&mut size).await?;
+    let size = u64::from_be_bytes(size);
+
+    let archive = pgp::SymmDecryptReader::new(&stream, secret);
+    import_backup_tar(context, archive, size).await?;
+    (&stream).write_all(&[1]).await?;
     Ok(())
 }
 
+/// Returns the answer to a backup transfer challenge,
+/// derived from the challenge and the secret of the backup QR code.
+fn backup_transfer_auth(nonce: &[u8], secret: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.input(nonce);
+    hasher.input(secret.as_bytes());
+    hex::encode(hasher.result())
+}
+
+/// Compares two byte strings in a time not depending on their content.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Returns the IP address to serve a backup on if none is given.
+///
+/// This is a private IPv4 address of the device if there is one,
+/// as backups are transferred in the local network,
+/// otherwise any other IPv4 or global IPv6 address which is not a loopback address.
+fn default_ip_addr() -> Result<IpAddr> {
+    let addrs = local_ip_addrs();
+    let is_private = |ip: &IpAddr| match ip {
+        IpAddr::V4(ip) => ip.is_private(),
+        IpAddr::V6(_) => false,
+    };
+    // IPv6 link-local addresses are useless without the interface
+    let is_usable = |ip: &IpAddr| match ip {
+        IpAddr::V4(ip) => !ip.is_loopback() && !ip.is_unspecified(),
+        IpAddr::V6(ip) => {
+            !ip.is_loopback() && !ip.is_unspecified() && ip.segments()[0] & 0xffc0 != 0xfe80
+        }
+    };
+    addrs
+        .iter()
+        .find(|ip| is_private(ip))
+        .or_else(|| addrs.iter().find(|ip| ip.is_ipv4() && is_usable(ip)))
+        .or_else(|| addrs.iter().find(|ip| is_usable(ip)))
+        .copied()
+        .ok_or_else(|| format_err!("Cannot determine local IP address, please specify one."))
+}
+
+/// Returns the addresses of all network interfaces which are up.
+#[cfg(unix)]
+fn local_ip_addrs() -> Vec<IpAddr> {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    let mut addrs = Vec::new();
+    let mut ifaddrs: *mut libc::ifaddrs = std::ptr::null_mut();
+    // the list returned by getifaddrs() is only read and freed afterwards
+    unsafe {
+        if libc::getifaddrs(&mut ifaddrs) != 0 {
+            return addrs;
+        }
+        let mut cur = ifaddrs;
+        while !cur.is_null() {
+            let ifa = &*cur;
+            if !ifa.ifa_addr.is_null() && (ifa.ifa_flags & libc::IFF_UP as libc::c_uint) != 0 {
+                match i32::from((*ifa.ifa_addr).sa_family) {
+                    libc::AF_INET => {
+                        let sin = &*(ifa.ifa_addr as *const libc::sockaddr_in);
+                        let ip = Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr));
+                        addrs.push(IpAddr::V4(ip));
+                    }
+                    libc::AF_INET6 => {
+                        let sin6 = &*(ifa.ifa_addr as *const libc::sockaddr_in6);
+                        addrs.push(IpAddr::V6(Ipv6Addr::from(sin6.sin6_addr.s6_addr)));
+                    }
+                    _ => {}
+                }
+            }
+            cur = ifa.ifa_next;
+        }
+        libc::freeifaddrs(ifaddrs);
+    }
+    addrs
+}
+
+/// Network interfaces cannot be listed on this platform,
+/// the address to serve a backup on has to be given.
+#[cfg(not(unix))]
+fn local_ip_addrs() -> Vec<IpAddr> {
+    Vec::new()
+}
+
 /*******************************************************************************
  * Classic key import
  ******************************************************************************/
@@ -793,6 +1369,113 @@ mod tests {
         assert!(msg.contains("-----END PGP MESSAGE-----\n"));
     }
 
+    /// Creates a setup message containing Alice's key
+    /// and replaces Alice's key by Bob's key.
+    async fn setup_key_conflict(t: &TestContext) -> (MsgId, String) {
+        configure_alice_keypair(&t.ctx).await;
+        let setup_code = create_setup_code(&t.ctx);
+        let setup_file = render_setup_file(&t.ctx, &setup_code).await.unwrap();
+        let blob = BlobObject::create(
+            &t.ctx,
+            "autocrypt-setup-message.html",
+            setup_file.as_bytes(),
+        )
+        .await
+        .unwrap();
+        let mut msg = Message::default();
+        msg.viewtype = Viewtype::File;
+        msg.param.set(Param::File, blob.as_name());
+        msg.param.set_cmd(SystemMessage::AutocryptSetupMessage);
+        let msg_id = chat::add_device_msg(&t.ctx, None, Some(&mut msg))
+            .await
+            .unwrap();
+
+        let mut keypair = bob_keypair();
+        keypair.addr = alice_keypair().addr;
+        key::store_self_keypair(&t.ctx, &keypair, key::KeyPairUse::Default)
+            .await
+            .unwrap();
+        key::delete_self_key(&t.ctx, &alice_keypair().public.fingerprint())
+            .await
+            .unwrap();
+        (msg_id, setup_code)
+    }
+
+    #[async_std::test]
+    async fn test_key_transfer_conflict() {
+        let alice_fp = alice_keypair().public.fingerprint();
+        let bob_fp = bob_keypair().public.fingerprint();
+
+        for resolution in &[
+            KeyConflictResolution::KeepCurrent,
+            KeyConflictResolution::Replace,
+            KeyConflictResolution::KeepBoth,
+        ] {
+            let t = dummy_context().await;
+            let (msg_id, setup_code) = setup_key_conflict(&t).await;
+
+            assert!(check_key_transfer(&t.ctx, msg_id, "123").await.is_err());
+            let conflict = check_key_transfer(&t.ctx, msg_id, &setup_code)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(conflict.current_fingerprint, bob_fp);
+            assert_eq!(conflict.imported_fingerprint, alice_fp);
+            assert_eq!(
+                get_key_transfer_resolution(&t.ctx, msg_id).await.unwrap(),
+                None
+            );
+
+            continue_key_transfer_with(&t.ctx, msg_id, &setup_code, *resolution)
+                .await
+                .unwrap();
+            assert_eq!(
+                get_key_transfer_resolution(&t.ctx, msg_id).await.unwrap(),
+                Some(*resolution)
+            );
+
+            let keys: Vec<(key::Fingerprint, bool)> = key::get_self_key_history(&t.ctx)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|info| (info.fingerprint, info.is_default))
+                .collect();
+            match resolution {
+                KeyConflictResolution::KeepCurrent => {
+                    assert_eq!(keys, vec![(bob_fp.clone(), true)]);
+                }
+                KeyConflictResolution::Replace => {
+                    assert_eq!(keys, vec![(alice_fp.clone(), true)]);
+                }
+                KeyConflictResolution::KeepBoth => {
+                    assert_eq!(
+                        keys,
+                        vec![(alice_fp.clone(), true), (bob_fp.clone(), false)]
+                    );
+                }
+            }
+
+            // no conflict after importing the key
+            if *resolution != KeyConflictResolution::KeepCurrent {
+                assert_eq!(
+                    check_key_transfer(&t.ctx, msg_id, &setup_code)
+                        .await
+                        .unwrap(),
+                    None
+                );
+
+                // importing again without a conflict keeps the recorded resolution
+                continue_key_transfer(&t.ctx, msg_id, &setup_code)
+                    .await
+                    .unwrap();
+                assert_eq!(
+                    get_key_transfer_resolution(&t.ctx, msg_id).await.unwrap(),
+                    Some(*resolution)
+                );
+            }
+        }
+    }
+
     #[async_std::test]
     async fn test_render_setup_file_newline_replace() {
         let t = dummy_context().await;
@@ -836,6 +1519,151 @@ mod tests {
         assert_eq!(bytes, key.to_asc(None).into_bytes());
     }
 
+    #[async_std::test]
+    async fn test_export_and_import_backup() {
+        let backup_dir = tempfile::tempdir().unwrap();
+        let backup_dir: PathBuf = backup_dir.path().to_path_buf().into();
+
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let chat_id = chat::create_by_contact_id(&t.ctx, DC_CONTACT_ID_SELF)
+            .await
+            .unwrap();
+        chat::send_text_msg(&t.ctx, chat_id, "hello".to_string())
+            .await
+            .unwrap();
+        dc_write_file(&t.ctx, "$BLOBDIR/hello.txt", b"hello")
+            .await
+            .unwrap();
+
+        for passphrase in &[None, Some("foobar".to_string())] {
+            imex(
+                &t.ctx,
+                ImexMode::ExportBackup,
+                Some(&backup_dir),
+                passphrase.clone(),
+            )
+            .await
+            .unwrap();
+            let backup = has_backup(&t.ctx, &backup_dir).await.unwrap();
+            if passphrase.is_some() {
+                assert!(backup.ends_with(".tar.dcenc"));
+            } else {
+                assert!(backup.ends_with(".tar"));
+            }
+
+            let t2 = dummy_context().await;
+            if passphrase.is_some() {
+                assert!(imex(&t2.ctx, ImexMode::ImportBackup, Some(&backup), None)
+                    .await
+                    .is_err());
+                assert!(imex(
+                    &t2.ctx,
+                    ImexMode::ImportBackup,
+                    Some(&backup),
+                    Some("wrong".to_string())
+                )
+                .await
+                .is_err());
+            }
+            imex(
+                &t2.ctx,
+                ImexMode::ImportBackup,
+                Some(&backup),
+                passphrase.clone(),
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(
+                t2.ctx.get_config(Config::ConfiguredAddr).await,
+                Some("alice@example.com".to_string())
+            );
+            let msgs = chat::get_chat_msgs(&t2.ctx, chat_id, 0, None).await;
+            let msg = Message::load_from_db(&t2.ctx, *msgs.last().unwrap())
+                .await
+                .unwrap();
+            assert_eq!(msg.get_text(), Some("hello".to_string()));
+            assert_eq!(
+                dc_read_file(&t2.ctx, "$BLOBDIR/hello.txt").await.unwrap(),
+                b"hello"
+            );
+        }
+    }
+
+    #[async_std::test]
+    async fn test_send_receive_backup_over_network() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let chat_id = chat::create_by_contact_id(&t.ctx, DC_CONTACT_ID_SELF)
+            .await
+            .unwrap();
+        chat::send_text_msg(&t.ctx, chat_id, "hello".to_string())
+            .await
+            .unwrap();
+
+        let emitter = t.ctx.get_event_emitter();
+        let ctx = t.ctx.clone();
+        let sender = async_std::task::spawn(async move {
+            imex(
+                &ctx,
+                ImexMode::SendBackupOverNetwork,
+                Some("127.0.0.1"),
+                None,
+            )
+            .await
+        });
+        let qr = loop {
+            if let Some(Event::ImexBackupQr(qr)) = emitter.recv().await {
+                break qr;
+            }
+        };
+
+        // A wrong secret is rejected, the sender keeps waiting.
+        let t2 = dummy_context().await;
+        let wrong_qr = qr.replace("DCBACKUP:", "DCBACKUP:x");
+        assert!(imex(
+            &t2.ctx,
+            ImexMode::ReceiveBackupOverNetwork,
+            Some(&wrong_qr),
+            None
+        )
+        .await
+        .is_err());
+
+        imex(&t2.ctx, ImexMode::ReceiveBackupOverNetwork, Some(&qr), None)
+            .await
+            .unwrap();
+        sender.await.unwrap();
+
+        assert_eq!(
+            t2.ctx.get_config(Config::ConfiguredAddr).await,
+            Some("alice@example.com".to_string())
+        );
+        let msgs = chat::get_chat_msgs(&t2.ctx, chat_id, 0, None).await;
+        let msg = Message::load_from_db(&t2.ctx, *msgs.last().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(msg.get_text(), Some("hello".to_string()));
+    }
+
+    #[test]
+    fn test_backup_transfer_auth() {
+        let auth = backup_transfer_auth(b"nonce1", "secret");
+        assert_eq!(auth.len(), 64);
+        assert_eq!(auth, backup_transfer_auth(b"nonce1", "secret"));
+        assert_ne!(auth, backup_transfer_auth(b"nonce2", "secret"));
+        assert_ne!(auth, backup_transfer_auth(b"nonce1", "secret2"));
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
     #[test]
     fn test_normalize_setup_code() {
         let norm = normalize_setup_code("123422343234423452346234723482349234");
@@ -23,6 +23,7 @@ use crate::context::Context;
 use crate::dc_tools::*;
 use crate::error::{bail, ensure, format_err, Error, Result};
 use crate::events::Event;
+use crate::group_sync;
 use crate::imap::*;
 use crate::location;
 use crate::login_param::LoginParam;
@@ -106,6 +107,7 @@ pub enum Action {
     MaybeSendLocations = 5005, // low priority ...
     MaybeSendLocationsEnded = 5007,
     SendMdn = 5010,
+    SendScheduledMsg = 5900,
     SendMsgToSmtp = 5901, // ... high priority
 }
 
@@ -132,6 +134,7 @@ impl From<Action> for Thread {
             MaybeSendLocations => Thread::Smtp,
             MaybeSendLocationsEnded => Thread::Smtp,
             SendMdn => Thread::Smtp,
+            SendScheduledMsg => Thread::Smtp,
             SendMsgToSmtp => Thread::Smtp,
         }
     }
@@ -374,11 +377,25 @@ impl Job {
         };
 
         let foreign_id = self.foreign_id;
+        let broadcast_copies = self
+            .param
+            .get_int(Param::BroadcastCopies)
+            .unwrap_or_default();
+        let recipients = recipients.to_string();
         self.smtp_send(context, recipients_list, body, self.job_id, smtp, || {
             async move {
                 // smtp success, update db ASAP, then delete smtp file
                 if 0 != foreign_id {
-                    set_delivered(context, MsgId::new(foreign_id)).await;
+                    let msg_id = MsgId::new(foreign_id);
+                    if broadcast_copies > 0 {
+                        // the message is delivered when all copies are delivered
+                        let delivered_cnt = set_delivered_to(context, msg_id, &recipients).await?;
+                        if delivered_cnt >= broadcast_copies as usize {
+                            set_delivered(context, msg_id).await;
+                        }
+                    } else {
+                        set_delivered(context, msg_id).await;
+                    }
                 }
                 // now also delete the generated file
                 dc_delete_file(context, filename).await;
@@ -661,6 +678,19 @@ pub async fn kill_action(context: &Context, action: Action) -> bool {
         .is_ok()
 }
 
+/// Delete pending jobs with the given action and foreign ID,
+/// e.g. the jobs for a single message.
+pub(crate) async fn kill_action_for(context: &Context, action: Action, foreign_id: u32) -> bool {
+    context
+        .sql
+        .execute(
+            "DELETE FROM jobs WHERE action=? AND foreign_id=?;",
+            paramsv![action, foreign_id],
+        )
+        .await
+        .is_ok()
+}
+
 /// Remove jobs with specified IDs.
 async fn kill_ids(context: &Context, job_ids: &[u32]) -> sql::Result<()> {
     context
@@ -698,12 +728,37 @@ async fn set_delivered(context: &Context, msg_id: MsgId) {
     context.emit_event(Event::MsgDelivered { chat_id, msg_id });
 }
 
-/// Constructs a job for sending a message.
+/// Records that the copy of a broadcast list message sent to `addr` was delivered.
+///
+/// Returns the number of copies of the message delivered so far.
+async fn set_delivered_to(context: &Context, msg_id: MsgId, addr: &str) -> Result<usize> {
+    context
+        .sql
+        .execute(
+            "INSERT OR REPLACE INTO msgs_delivered (msg_id, addr, timestamp) VALUES (?, ?, ?);",
+            paramsv![msg_id, addr, time()],
+        )
+        .await?;
+    let delivered_cnt = context
+        .sql
+        .query_get_value::<isize>(
+            context,
+            "SELECT COUNT(*) FROM msgs_delivered WHERE msg_id=?;",
+            paramsv![msg_id],
+        )
+        .await
+        .unwrap_or_default() as usize;
+    Ok(delivered_cnt)
+}
+
+/// Constructs the jobs for sending a message.
 ///
-/// Returns `None` if no messages need to be sent out.
+/// Usually, this is a single job.  Messages to broadcast lists are sent
+/// as a separate copy to every recipient, each with its own job.
+/// Returns an empty vector if no messages need to be sent out.
 ///
-/// In order to be processed, must be `add`ded.
-pub async fn send_msg_job(context: &Context, msg_id: MsgId) -> Result<Option<Job>> {
+/// In order to be processed, the jobs must be `add`ded.
+pub async fn send_msg_job(context: &Context, msg_id: MsgId) -> Result<Vec<Job>> {
     let mut msg = Message::load_from_db(context, msg_id).await?;
     msg.try_calc_and_set_dimensions(context).await.ok();
 
@@ -746,36 +801,63 @@ pub async fn send_msg_job(context: &Context, msg_id: MsgId) -> Result<Option<Job
             "message {} has no recipient, skipping smtp-send", msg_id
         );
         set_delivered(context, msg_id).await;
-        return Ok(None);
+        return Ok(Vec::new());
     }
 
-    let rendered_msg = match mimefactory.render().await {
-        Ok(res) => Ok(res),
-        Err(err) => {
-            message::set_msg_failed(context, msg_id, Some(err.to_string())).await;
-            Err(err)
-        }
-    }?;
+    let is_broadcast = mimefactory.is_broadcast();
+    let copies: Vec<(MimeFactory, Vec<String>)> = if is_broadcast {
+        recipients
+            .into_iter()
+            .map(|addr| (mimefactory.for_recipient(&addr), vec![addr]))
+            .collect()
+    } else {
+        vec![(mimefactory, recipients)]
+    };
 
-    if needs_encryption && !rendered_msg.is_encrypted {
-        /* unrecoverable */
-        message::set_msg_failed(
-            context,
-            msg_id,
-            Some("End-to-end-encryption unavailable unexpectedly."),
-        )
-        .await;
-        bail!(
-            "e2e encryption unavailable {} - {:?}",
-            msg_id,
-            needs_encryption
-        );
+    let mut rendered_copies = Vec::with_capacity(copies.len());
+    for (mimefactory, recipients) in copies {
+        let rendered_msg = match mimefactory.render().await {
+            Ok(res) => Ok(res),
+            Err(err) => {
+                message::set_msg_failed(context, msg_id, Some(err.to_string())).await;
+                Err(err)
+            }
+        }?;
+
+        if needs_encryption && !rendered_msg.is_encrypted {
+            /* unrecoverable */
+            message::set_msg_failed(
+                context,
+                msg_id,
+                Some("End-to-end-encryption unavailable unexpectedly."),
+            )
+            .await;
+            bail!(
+                "e2e encryption unavailable {} - {:?}",
+                msg_id,
+                needs_encryption
+            );
+        }
+        rendered_copies.push((rendered_msg, recipients));
     }
 
+    let is_encrypted = rendered_copies
+        .iter()
+        .all(|(rendered_msg, _)| rendered_msg.is_encrypted);
+    let is_signed_only = rendered_copies
+        .iter()
+        .any(|(rendered_msg, _)| rendered_msg.is_signed_only);
+    // all copies are rendered from the same message
+    let rendered_msg = &rendered_copies[0].0;
+
     if rendered_msg.is_gossiped {
         chat::set_gossiped_timestamp(context, msg.chat_id, time()).await?;
     }
 
+    if let Err(err) = group_sync::save_member_change(context, &msg, &rendered_msg.message).await {
+        error!(context, "Failed to save member change: {:?}", err);
+    }
+
     if 0 != rendered_msg.last_added_location_id {
         if let Err(err) = location::set_kml_sent_timestamp(context, msg.chat_id, time()).await {
             error!(context, "Failed to set kml sent_timestamp: {:?}", err);
@@ -796,23 +878,40 @@ pub async fn send_msg_job(context: &Context, msg_id: MsgId) -> Result<Option<Job
         }
     }
 
-    if rendered_msg.is_encrypted && !needs_encryption {
+    if is_encrypted && !needs_encryption {
         msg.param.set_int(Param::GuaranteeE2ee, 1);
         msg.save_param_to_disk(context).await;
     }
 
-    ensure!(!recipients.is_empty(), "no recipients for smtp job set");
-    let mut param = Params::new();
-    let bytes = &rendered_msg.message;
-    let blob = BlobObject::create(context, &rendered_msg.rfc724_mid, bytes).await?;
+    if is_signed_only {
+        msg.param.set_int(Param::SignedOnly, 1);
+        msg.save_param_to_disk(context).await;
+    }
 
-    let recipients = recipients.join("\x1e");
-    param.set(Param::File, blob.as_name());
-    param.set(Param::Recipients, &recipients);
+    let copies_cnt = rendered_copies.len();
+    let mut jobs = Vec::with_capacity(copies_cnt);
+    for (rendered_msg, recipients) in rendered_copies {
+        ensure!(!recipients.is_empty(), "no recipients for smtp job set");
+        let mut param = Params::new();
+        let bytes = &rendered_msg.message;
+        let blob = BlobObject::create(context, &rendered_msg.rfc724_mid, bytes).await?;
+
+        let recipients = recipients.join("\x1e");
+        param.set(Param::File, blob.as_name());
+        param.set(Param::Recipients, &recipients);
+        if is_broadcast {
+            param.set_int(Param::BroadcastCopies, copies_cnt as i32);
+        }
 
-    let job = create(Action::SendMsgToSmtp, msg_id.to_u32() as i32, param, 0)?;
+        jobs.push(create(
+            Action::SendMsgToSmtp,
+            msg_id.to_u32() as i32,
+            param,
+            0,
+        )?);
+    }
 
-    Ok(Some(job))
+    Ok(jobs)
 }
 
 #[derive(Debug)]
@@ -822,22 +921,26 @@ pub enum Connection<'a> {
 }
 
 async fn load_imap_deletion_msgid(context: &Context) -> sql::Result<Option<MsgId>> {
-    if let Some(delete_server_after) = context.get_config_delete_server_after().await {
-        let threshold_timestamp = time() - delete_server_after;
+    let now = time();
 
-        context
-            .sql
-            .query_row_optional(
-                "SELECT id FROM msgs \
-             WHERE timestamp < ? \
-             AND server_uid != 0",
-                paramsv![threshold_timestamp],
-                |row| row.get::<_, MsgId>(0),
-            )
-            .await
-    } else {
-        Ok(None)
-    }
+    let threshold_timestamp = match context.get_config_delete_server_after().await {
+        None => 0,
+        Some(delete_server_after) => now - delete_server_after,
+    };
+
+    context
+        .sql
+        .query_row_optional(
+            "SELECT id FROM msgs \
+         WHERE ( \
+         timestamp < ? \
+         OR (ephemeral_timestamp != 0 AND ephemeral_timestamp <= ?) \
+         ) \
+         AND server_uid != 0",
+            paramsv![threshold_timestamp, now],
+            |row| row.get::<_, MsgId>(0),
+        )
+        .await
 }
 
 async fn load_imap_deletion_job(context: &Context) -> sql::Result<Option<Job>> {
@@ -958,6 +1061,9 @@ async fn perform_job_action(
         Action::Unknown => Status::Finished(Err(format_err!("Unknown job id found"))),
         Action::SendMsgToSmtp => job.send_msg_to_smtp(context, connection.smtp()).await,
         Action::SendMdn => job.send_mdn(context, connection.smtp()).await,
+        Action::SendScheduledMsg => {
+            Status::Finished(chat::send_scheduled_msg(context, MsgId::new(job.foreign_id)).await)
+        }
         Action::MaybeSendLocations => location::job_maybe_send_locations(context, job).await,
         Action::MaybeSendLocationsEnded => {
             location::job_maybe_send_locations_ended(context, job).await
@@ -1034,6 +1140,7 @@ pub async fn add(context: &Context, job: Job) {
             Action::MaybeSendLocations
             | Action::MaybeSendLocationsEnded
             | Action::SendMdn
+            | Action::SendScheduledMsg
             | Action::SendMsgToSmtp => {
                 info!(context, "interrupt: smtp");
                 context.interrupt_smtp(false).await;
@@ -1042,6 +1149,33 @@ pub async fn add(context: &Context, job: Job) {
     }
 }
 
+/// Returns the time when the next job of the given thread is due,
+/// `None` if there are no jobs.
+pub(crate) async fn load_next_timestamp(context: &Context, thread: Thread) -> Option<i64> {
+    context
+        .sql
+        .query_get_value::<Option<i64>>(
+            context,
+            "SELECT MIN(desired_timestamp) FROM jobs WHERE thread=?;",
+            paramsv![thread as i64],
+        )
+        .await
+        .flatten()
+}
+
+/// Returns the number of pending jobs of the given thread.
+pub(crate) async fn count_pending(context: &Context, thread: Thread) -> usize {
+    context
+        .sql
+        .query_get_value::<i32>(
+            context,
+            "SELECT COUNT(*) FROM jobs WHERE thread=?;",
+            paramsv![thread as i64],
+        )
+        .await
+        .unwrap_or_default() as usize
+}
+
 /// Load jobs from the database.
 ///
 /// Load jobs for this "[Thread]", i.e. either load SMTP jobs or load
@@ -1182,6 +1316,43 @@ mod tests {
             .unwrap();
     }
 
+    #[async_std::test]
+    async fn test_set_delivered_to() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let bob = Contact::create(&t.ctx, "Bob", "bob@example.net")
+            .await
+            .unwrap();
+        let chat_id = chat::create_broadcast_list(&t.ctx, "news").await.unwrap();
+        assert!(chat::add_contact_to_chat(&t.ctx, chat_id, bob).await);
+        let msg_id = chat::send_text_msg(&t.ctx, chat_id, "hi".to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            set_delivered_to(&t.ctx, msg_id, "bob@example.net")
+                .await
+                .unwrap(),
+            1
+        );
+        // a copy sent twice is counted once
+        assert_eq!(
+            set_delivered_to(&t.ctx, msg_id, "bob@example.net")
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            set_delivered_to(&t.ctx, msg_id, "alice@example.org")
+                .await
+                .unwrap(),
+            2
+        );
+        let info = message::get_msg_info(&t.ctx, msg_id).await;
+        assert!(info.contains(" to bob@example.net\n"));
+        assert!(info.contains(" to alice@example.org\n"));
+    }
+
     #[async_std::test]
     async fn test_load_next_job_two() {
         // We want to ensure that loading jobs skips over jobs which
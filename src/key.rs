@@ -80,6 +80,12 @@ pub trait DcKey: Serialize + Deserializable + KeyTrait + Clone {
     /// Load the users' default key from the database.
     async fn load_self(context: &Context) -> Result<Self::KeyType>;
 
+    /// Load the users' previous keys from the database, newest first.
+    ///
+    /// These keys are not used for new messages any more but are
+    /// kept to read old ones.
+    async fn load_self_history(context: &Context) -> Result<Vec<Self::KeyType>>;
+
     /// Serialise the key as bytes.
     fn to_bytes(&self) -> Vec<u8> {
         // Not using Serialize::to_bytes() to make clear *why* it is
@@ -138,6 +144,22 @@ impl DcKey for SignedPublicKey {
         }
     }
 
+    async fn load_self_history(context: &Context) -> Result<Vec<Self::KeyType>> {
+        let keys = context
+            .sql
+            .query_map(
+                "SELECT public_key FROM keypairs WHERE is_default=0 ORDER BY created DESC, id DESC;",
+                paramsv![],
+                |row| row.get::<_, Vec<u8>>(0),
+                |rows| {
+                    rows.collect::<std::result::Result<Vec<_>, _>>()
+                        .map_err(Into::into)
+                },
+            )
+            .await?;
+        keys.iter().map(|bytes| Self::from_slice(bytes)).collect()
+    }
+
     fn to_asc(&self, header: Option<(&str, &str)>) -> String {
         // Not using .to_armored_string() to make clear *why* it is
         // safe to ignore this error.
@@ -183,6 +205,22 @@ impl DcKey for SignedSecretKey {
         }
     }
 
+    async fn load_self_history(context: &Context) -> Result<Vec<Self::KeyType>> {
+        let keys = context
+            .sql
+            .query_map(
+                "SELECT private_key FROM keypairs WHERE is_default=0 ORDER BY created DESC, id DESC;",
+                paramsv![],
+                |row| row.get::<_, Vec<u8>>(0),
+                |rows| {
+                    rows.collect::<std::result::Result<Vec<_>, _>>()
+                        .map_err(Into::into)
+                },
+            )
+            .await?;
+        keys.iter().map(|bytes| Self::from_slice(bytes)).collect()
+    }
+
     fn to_asc(&self, header: Option<(&str, &str)>) -> String {
         // Not using .to_armored_string() to make clear *why* it is
         // safe to do these unwraps.
@@ -265,6 +303,109 @@ async fn generate_keypair(context: &Context) -> Result<KeyPair> {
     }
 }
 
+/// Generates a new default keypair of the given type.
+///
+/// The previous keys are kept to decrypt old messages, see
+/// [DcKey::load_self_history].  The new key is announced to other
+/// peers in the Autocrypt header of all following messages.
+///
+/// Returns the fingerprint of the new key.
+pub async fn rotate_self_key(context: &Context, keytype: KeyGenType) -> Result<Fingerprint> {
+    let addr = context
+        .get_config(Config::ConfiguredAddr)
+        .await
+        .ok_or_else(|| Error::NoConfiguredAddr)?;
+    let addr = EmailAddress::new(&addr)?;
+    let _guard = context.generating_key_mutex.lock().await;
+
+    let start = std::time::Instant::now();
+    info!(context, "Generating new keypair with type {}", keytype);
+    let keypair =
+        async_std::task::spawn_blocking(move || crate::pgp::create_keypair(addr, keytype)).await?;
+    store_self_keypair(context, &keypair, KeyPairUse::Default).await?;
+    info!(
+        context,
+        "Keypair generated in {:.3}s.",
+        start.elapsed().as_secs()
+    );
+    Ok(DcKey::fingerprint(&keypair.public))
+}
+
+/// A keypair owned by this device, as returned by [get_self_key_history].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfKeyInfo {
+    pub addr: String,
+    pub fingerprint: Fingerprint,
+
+    /// Timestamp of when the key was generated or imported.
+    pub created: i64,
+
+    /// Whether the key is used for new messages.
+    pub is_default: bool,
+}
+
+/// Returns all keypairs owned by this device, the default key first,
+/// followed by the previous keys, newest first.
+pub async fn get_self_key_history(context: &Context) -> Result<Vec<SelfKeyInfo>> {
+    let rows = context
+        .sql
+        .query_map(
+            "SELECT addr, public_key, created, is_default
+               FROM keypairs
+              ORDER BY is_default DESC, created DESC, id DESC;",
+            paramsv![],
+            |row| {
+                let addr: String = row.get(0)?;
+                let public_key: Vec<u8> = row.get(1)?;
+                let created: i64 = row.get(2)?;
+                let is_default: bool = row.get(3)?;
+                Ok((addr, public_key, created, is_default))
+            },
+            |rows| {
+                rows.collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(Into::into)
+            },
+        )
+        .await?;
+    rows.into_iter()
+        .map(|(addr, public_key, created, is_default)| {
+            Ok(SelfKeyInfo {
+                addr,
+                fingerprint: DcKey::fingerprint(&SignedPublicKey::from_slice(&public_key)?),
+                created,
+                is_default,
+            })
+        })
+        .collect()
+}
+
+/// Deletes the own keypair with the given fingerprint.
+///
+/// Messages encrypted to this key cannot be decrypted afterwards.
+pub(crate) async fn delete_self_key(context: &Context, fingerprint: &Fingerprint) -> Result<()> {
+    let rows = context
+        .sql
+        .query_map(
+            "SELECT id, public_key FROM keypairs;",
+            paramsv![],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?)),
+            |rows| {
+                rows.collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(Into::into)
+            },
+        )
+        .await?;
+    for (id, public_key) in rows {
+        if &DcKey::fingerprint(&SignedPublicKey::from_slice(&public_key)?) == fingerprint {
+            context
+                .sql
+                .execute("DELETE FROM keypairs WHERE id=?;", paramsv![id])
+                .await?;
+        }
+    }
+    Ok(())
+}
+
 /// Use of a [KeyPair] for encryption or decryption.
 ///
 /// This is used by [store_self_keypair] to know what kind of key is
@@ -662,6 +803,46 @@ i8pcjGO+IZffvyZJVRWfVooBJmWWbPB1pueo3tx8w3+fcuzpxz+RLFKaPyqXO+dD
         assert_eq!(nrows().await, 1);
     }
 
+    #[async_std::test]
+    async fn test_self_key_history() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let alice = alice_keypair();
+        // Simulate a rotation without the expensive key generation.
+        let mut new_keypair = bob_keypair();
+        new_keypair.addr = alice.addr.clone();
+        store_self_keypair(&t.ctx, &new_keypair, KeyPairUse::Default)
+            .await
+            .unwrap();
+
+        let seckey = SignedSecretKey::load_self(&t.ctx).await.unwrap();
+        assert_eq!(seckey, new_keypair.secret);
+        let history = SignedSecretKey::load_self_history(&t.ctx).await.unwrap();
+        assert_eq!(history, vec![alice.secret]);
+
+        let infos = get_self_key_history(&t.ctx).await.unwrap();
+        assert_eq!(infos.len(), 2);
+        assert!(infos[0].is_default);
+        assert_eq!(infos[0].fingerprint, new_keypair.public.fingerprint());
+        assert!(!infos[1].is_default);
+        assert_eq!(infos[1].fingerprint, alice.public.fingerprint());
+        assert_eq!(infos[1].addr, "alice@example.com");
+    }
+
+    #[async_std::test]
+    #[ignore] // generating keys is expensive
+    async fn test_rotate_self_key() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let alice = alice_keypair();
+        let fingerprint = rotate_self_key(&t.ctx, KeyGenType::Ed25519).await.unwrap();
+        assert_ne!(fingerprint, alice.public.fingerprint());
+        let pubkey = SignedPublicKey::load_self(&t.ctx).await.unwrap();
+        assert_eq!(pubkey.fingerprint(), fingerprint);
+        let history = SignedPublicKey::load_self_history(&t.ctx).await.unwrap();
+        assert_eq!(history, vec![alice.public]);
+    }
+
     // Convenient way to create a new key if you need one, run with
     // `cargo test key::tests::gen_key`.
     // #[test]
@@ -33,9 +33,15 @@ where
         Ok(keyring)
     }
 
-    /// Load the user's key into the keyring.
+    /// Load the user's keys into the keyring.
+    ///
+    /// The default key comes first, followed by the previous keys
+    /// which are still needed to read old messages.
     pub async fn load_self(&mut self, context: &Context) -> Result<(), key::Error> {
         self.add(T::load_self(context).await?);
+        for key in T::load_self_history(context).await? {
+            self.add(key);
+        }
         Ok(())
     }
 
@@ -35,16 +35,20 @@ pub mod headerdef;
 pub(crate) mod events;
 pub use events::*;
 
+pub mod accounts;
 mod aheader;
 mod blob;
 pub mod chat;
 pub mod chatlist;
 pub mod config;
 mod configure;
+pub mod connectivity;
 pub mod constants;
 pub mod contact;
 pub mod context;
 mod e2ee;
+pub mod ephemeral;
+mod group_sync;
 mod imap;
 pub mod imex;
 mod scheduler;
@@ -55,6 +59,7 @@ mod keyring;
 pub mod location;
 mod login_param;
 pub mod lot;
+pub mod mailinglist;
 pub mod message;
 mod mimefactory;
 pub mod mimeparser;
@@ -63,12 +68,16 @@ mod param;
 pub mod peerstate;
 pub mod pgp;
 pub mod provider;
+mod proxy;
 pub mod qr;
+pub mod reaction;
+pub mod search;
 pub mod securejoin;
 mod simplify;
 mod smtp;
 pub mod stock;
 mod token;
+pub mod vcard;
 #[macro_use]
 mod dehtml;
 
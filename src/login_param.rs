@@ -4,6 +4,7 @@ use std::borrow::Cow;
 use std::fmt;
 
 use crate::context::Context;
+use crate::proxy::ProxyConfig;
 
 #[derive(Copy, Clone, Debug, Display, FromPrimitive)]
 #[repr(i32)]
@@ -41,6 +42,8 @@ pub struct LoginParam {
     /// SMTP TLS options: whether to allow invalid certificates and/or invalid hostnames
     pub smtp_certificate_checks: CertificateChecks,
     pub server_flags: i32,
+    /// Proxy to connect through, `None` for direct connections.
+    pub proxy: Option<ProxyConfig>,
 }
 
 impl LoginParam {
@@ -114,6 +117,8 @@ impl LoginParam {
             .await
             .unwrap_or_default();
 
+        let proxy = ProxyConfig::from_database(context, prefix).await;
+
         LoginParam {
             addr,
             mail_server,
@@ -127,6 +132,7 @@ impl LoginParam {
             send_port,
             smtp_certificate_checks,
             server_flags,
+            proxy,
         }
     }
 
@@ -188,6 +194,8 @@ impl LoginParam {
         sql.set_raw_config_int(context, key, self.server_flags)
             .await?;
 
+        ProxyConfig::save_to_database(self.proxy.as_ref(), context, prefix).await?;
+
         Ok(())
     }
 }
@@ -201,7 +209,7 @@ impl fmt::Display for LoginParam {
 
         write!(
             f,
-            "{} imap:{}:{}:{}:{}:cert_{} smtp:{}:{}:{}:{}:cert_{} {}",
+            "{} imap:{}:{}:{}:{}:cert_{} smtp:{}:{}:{}:{}:cert_{} proxy:{} {}",
             unset_empty(&self.addr),
             unset_empty(&self.mail_user),
             if !self.mail_pw.is_empty() { pw } else { unset },
@@ -213,6 +221,10 @@ impl fmt::Display for LoginParam {
             unset_empty(&self.send_server),
             self.send_port,
             self.smtp_certificate_checks,
+            self.proxy
+                .as_ref()
+                .map(|proxy| proxy.to_string())
+                .unwrap_or_else(|| unset.to_string()),
             flags_readable,
         )
     }
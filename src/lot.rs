@@ -89,6 +89,9 @@ pub enum LotState {
     /// text1=domain
     QrAccount = 250,
 
+    /// text1=address of the sending device, text2=secret
+    QrBackup = 251,
+
     /// id=contact
     QrAddr = 320,
 
@@ -108,6 +111,7 @@ pub enum LotState {
     MsgOutPreparing = 18,
     MsgOutDraft = 19,
     MsgOutPending = 20,
+    MsgOutScheduled = 21,
     MsgOutFailed = 24,
     MsgOutDelivered = 26,
     MsgOutMdnRcvd = 28,
@@ -0,0 +1,266 @@
+//! # Mailing lists
+//!
+//! Messages with a `List-Id` header (RFC 2919) are assigned to a chat
+//! of the type `Chattype::Mailinglist`; the list ID is used as the
+//! grpid of the chat.  New mailing lists show up as contact requests.
+//!
+//! Mailing list messages are classic emails and are subject to the
+//! `show_emails` setting like all other classic emails.
+//!
+//! The `List-Post` and `List-Unsubscribe` headers (RFC 2369) are stored
+//! in the chat parameters.  They are taken from the message creating the
+//! chat and later only from messages whose `Return-Path` is in the
+//! domain of the list.  This is a heuristic against accidental changes,
+//! not a protection: anyone can send a message with the `List-Id` of a
+//! list, and all of these headers can be forged.
+//! The chat is read-only if `List-Post` contains no `mailto:` address;
+//! this only follows what the list announces and does not restrict
+//! who can post to the chat.
+
+use percent_encoding::percent_decode_str;
+use url::Url;
+
+use crate::chat::{self, Chat, ChatId};
+use crate::constants::*;
+use crate::contact::may_be_valid_addr;
+use crate::context::Context;
+use crate::error::{bail, ensure, Error};
+use crate::message::Message;
+use crate::mimeparser::SystemMessage;
+use crate::param::*;
+
+/// Target of a `mailto:` URI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Mailto {
+    addr: String,
+    subject: Option<String>,
+    body: Option<String>,
+}
+
+/// Parses a `List-Id` header into the list ID and the name of the list.
+///
+/// The list ID is lowercased; if the header contains no name,
+/// the list ID is used as the name.
+pub(crate) fn parse_list_id(value: &str) -> Option<(String, String)> {
+    let value = value.trim();
+    let (name, id) = match (value.rfind('<'), value.rfind('>')) {
+        (Some(start), Some(end)) if start < end => (&value[..start], &value[start + 1..end]),
+        _ => ("", value),
+    };
+    let id = id.trim();
+    if id.is_empty() || id.contains(char::is_whitespace) {
+        return None;
+    }
+    let name = name.trim().trim_matches('"').trim();
+    let name = if name.is_empty() { id } else { name };
+    Some((id.to_lowercase(), name.to_string()))
+}
+
+/// Returns the URIs of a `List-Post` or `List-Unsubscribe` header,
+/// which are enclosed in angle brackets and separated by commas.
+fn parse_list_uris(value: &str) -> Vec<&str> {
+    value
+        .split('<')
+        .skip(1)
+        .filter_map(|part| part.find('>').map(|end| part[..end].trim()))
+        .collect()
+}
+
+fn parse_mailto(uri: &str) -> Option<Mailto> {
+    let url = Url::parse(uri).ok()?;
+    if url.scheme() != "mailto" {
+        return None;
+    }
+    let addr = percent_decode_str(url.path()).decode_utf8().ok()?;
+    if !may_be_valid_addr(&addr) {
+        return None;
+    }
+
+    let mut mailto = Mailto {
+        addr: addr.into_owned(),
+        subject: None,
+        body: None,
+    };
+    for (key, value) in url.query_pairs() {
+        match key.to_lowercase().as_str() {
+            "subject" => mailto.subject = Some(value.into_owned()),
+            "body" => mailto.body = Some(value.into_owned()),
+            _ => {}
+        }
+    }
+    Some(mailto)
+}
+
+/// Returns the address to send posts to from a `List-Post` header,
+/// `None` if posting is not allowed, e.g. `List-Post: NO`.
+pub(crate) fn parse_list_post(value: &str) -> Option<String> {
+    parse_list_uris(value)
+        .into_iter()
+        .find_map(parse_mailto)
+        .map(|mailto| mailto.addr)
+}
+
+/// Returns true if the `Return-Path` of a message is in the domain of
+/// the mailing list.
+///
+/// The domain of the list is the domain of its `List-Post` address if
+/// known, otherwise the namespace of the list ID, e.g. `lists.example.org`
+/// for `delta.lists.example.org` (RFC 2919).  Only the exact domain is
+/// accepted, not its parent domains.
+///
+/// The `Return-Path` is set by the server from the envelope sender,
+/// which is usually the bounce address of the list.  It can be forged
+/// nevertheless, so this must not be used to authenticate messages.
+pub(crate) fn is_from_list(list_id: &str, list_post: Option<&str>, return_path: &str) -> bool {
+    let list_domain = match list_post.and_then(|addr| addr.rsplit('@').next()) {
+        Some(domain) => domain,
+        None => match list_id.find('.') {
+            Some(pos) => &list_id[pos + 1..],
+            None => return false,
+        },
+    }
+    .to_lowercase();
+    let addr = return_path
+        .trim()
+        .trim_start_matches('<')
+        .trim_end_matches('>');
+    match addr.rfind('@') {
+        Some(pos) => {
+            let domain = addr[pos + 1..].to_lowercase();
+            !domain.is_empty() && list_domain == domain
+        }
+        None => false,
+    }
+}
+
+/// Unsubscribes from a mailing list.
+///
+/// If the `List-Unsubscribe` header of the list contains a `mailto:`
+/// address, an unsubscribe request is sent there and `None` is
+/// returned.  Otherwise the web page to unsubscribe on is returned and
+/// has to be opened by the user.
+pub async fn unsubscribe(context: &Context, chat_id: ChatId) -> Result<Option<String>, Error> {
+    let chat = Chat::load_from_db(context, chat_id).await?;
+    ensure!(
+        chat.get_type() == Chattype::Mailinglist,
+        "{} is not a mailing list",
+        chat_id
+    );
+    let header = chat.param.get(Param::ListUnsubscribe).unwrap_or_default();
+    let uris = parse_list_uris(header);
+
+    if let Some(mailto) = uris.iter().copied().find_map(parse_mailto) {
+        let mut msg = Message::new(Viewtype::Text);
+        msg.text = Some(mailto.body.unwrap_or_else(|| "unsubscribe".to_string()));
+        msg.hidden = true;
+        msg.param.set_cmd(SystemMessage::MailinglistUnsubscribe);
+        msg.param.set(Param::Arg, mailto.addr);
+        msg.param.set(
+            Param::Arg2,
+            mailto.subject.unwrap_or_else(|| "unsubscribe".to_string()),
+        );
+        chat::send_msg(context, chat_id, &mut msg).await?;
+        return Ok(None);
+    }
+
+    if let Some(url) = uris
+        .iter()
+        .find(|uri| uri.starts_with("https://") || uri.starts_with("http://"))
+    {
+        return Ok(Some(url.to_string()));
+    }
+
+    bail!("Mailing list {} cannot be unsubscribed from", chat_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_list_id() {
+        assert_eq!(
+            parse_list_id("Delta Chat <Delta.Lists.Example.ORG>"),
+            Some((
+                "delta.lists.example.org".to_string(),
+                "Delta Chat".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_list_id("\"Foo Bar\" <foo.example.org>"),
+            Some(("foo.example.org".to_string(), "Foo Bar".to_string()))
+        );
+        assert_eq!(
+            parse_list_id("<foo.example.org>"),
+            Some(("foo.example.org".to_string(), "foo.example.org".to_string()))
+        );
+        assert_eq!(
+            parse_list_id("foo.example.org"),
+            Some(("foo.example.org".to_string(), "foo.example.org".to_string()))
+        );
+        assert_eq!(parse_list_id(""), None);
+        assert_eq!(parse_list_id("Foo <>"), None);
+    }
+
+    #[test]
+    fn test_parse_list_post() {
+        assert_eq!(
+            parse_list_post("<mailto:list@example.org>"),
+            Some("list@example.org".to_string())
+        );
+        assert_eq!(
+            parse_list_post("<https://example.org/post>, <mailto:list@example.org?subject=post>"),
+            Some("list@example.org".to_string())
+        );
+        assert_eq!(
+            parse_list_post("NO (posting not allowed on this list)"),
+            None
+        );
+        assert_eq!(parse_list_post("<https://example.org/post>"), None);
+    }
+
+    #[test]
+    fn test_is_from_list() {
+        let list_id = "delta.lists.example.org";
+        assert!(is_from_list(
+            list_id,
+            None,
+            "<delta-bounces@lists.example.org>"
+        ));
+        assert!(is_from_list(
+            list_id,
+            None,
+            "delta-bounces@Lists.Example.org"
+        ));
+        assert!(!is_from_list(list_id, None, "<bob@example.org>"));
+        assert!(!is_from_list(list_id, None, "<mallory@example.com>"));
+        assert!(!is_from_list(list_id, None, "<mallory@ample.org>"));
+        assert!(!is_from_list(list_id, None, "<>"));
+        assert!(!is_from_list(list_id, None, ""));
+        assert!(is_from_list(
+            list_id,
+            Some("delta@example.net"),
+            "<delta-bounces@example.net>"
+        ));
+        assert!(!is_from_list(
+            list_id,
+            Some("delta@example.net"),
+            "<delta-bounces@lists.example.org>"
+        ));
+        assert!(!is_from_list("localhost", None, "<bob@localhost>"));
+    }
+
+    #[test]
+    fn test_parse_mailto() {
+        assert_eq!(
+            parse_mailto("mailto:list-request@example.org?subject=unsubscribe%20me&body=bye"),
+            Some(Mailto {
+                addr: "list-request@example.org".to_string(),
+                subject: Some("unsubscribe me".to_string()),
+                body: Some("bye".to_string()),
+            })
+        );
+        assert_eq!(parse_mailto("mailto:nobody"), None);
+        assert_eq!(parse_mailto("https://example.org/"), None);
+    }
+}
@@ -5,11 +5,13 @@ use deltachat_derive::{FromSql, ToSql};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 
+use crate::blob::BlobObject;
 use crate::chat::{self, Chat, ChatId};
 use crate::constants::*;
 use crate::contact::*;
 use crate::context::*;
 use crate::dc_tools::*;
+use crate::ephemeral::Timer as EphemeralTimer;
 use crate::error::{ensure, Error};
 use crate::events::Event;
 use crate::job::{self, Action};
@@ -18,6 +20,7 @@ use crate::mimeparser::SystemMessage;
 use crate::param::*;
 use crate::pgp::*;
 use crate::stock::StockMessage;
+use crate::vcard;
 
 lazy_static! {
     static ref UNWRAP_RE: regex::Regex = regex::Regex::new(r"\s+").unwrap();
@@ -91,7 +94,7 @@ impl MsgId {
         context
             .sql
             .execute(
-                "UPDATE msgs SET chat_id=?, txt='', txt_raw='' WHERE id=?",
+                "UPDATE msgs SET chat_id=?, txt='', txt_raw='', quoted_text='' WHERE id=?",
                 paramsv![chat_id, self],
             )
             .await?;
@@ -99,14 +102,23 @@ impl MsgId {
         Ok(())
     }
 
-    /// Deletes a message and corresponding MDNs from the database.
+    /// Deletes a message, corresponding MDNs, reactions and delivery
+    /// records from the database.
     pub async fn delete_from_db(self, context: &Context) -> crate::sql::Result<()> {
-        // We don't use transactions yet, so remove MDNs first to make
-        // sure they are not left while the message is deleted.
+        // We don't use transactions yet, so remove MDNs and reactions first
+        // to make sure they are not left while the message is deleted.
         context
             .sql
             .execute("DELETE FROM msgs_mdns WHERE msg_id=?;", paramsv![self])
             .await?;
+        context
+            .sql
+            .execute("DELETE FROM reactions WHERE msg_id=?;", paramsv![self])
+            .await?;
+        context
+            .sql
+            .execute("DELETE FROM msgs_delivered WHERE msg_id=?;", paramsv![self])
+            .await?;
         context
             .sql
             .execute("DELETE FROM msgs WHERE id=?;", paramsv![self])
@@ -246,7 +258,10 @@ pub struct Message {
     pub(crate) timestamp_sort: i64,
     pub(crate) timestamp_sent: i64,
     pub(crate) timestamp_rcvd: i64,
+    pub(crate) ephemeral_timer: EphemeralTimer,
+    pub(crate) ephemeral_timestamp: i64,
     pub(crate) text: Option<String>,
+    pub(crate) quoted_text: Option<String>,
     pub(crate) rfc724_mid: String,
     pub(crate) in_reply_to: Option<String>,
     pub(crate) server_folder: Option<String>,
@@ -287,10 +302,13 @@ impl Message {
                     "    m.timestamp AS timestamp,",
                     "    m.timestamp_sent AS timestamp_sent,",
                     "    m.timestamp_rcvd AS timestamp_rcvd,",
+                    "    m.ephemeral_timer AS ephemeral_timer,",
+                    "    m.ephemeral_timestamp AS ephemeral_timestamp,",
                     "    m.type AS type,",
                     "    m.state AS state,",
                     "    m.msgrmsg AS msgrmsg,",
                     "    m.txt AS txt,",
+                    "    m.quoted_text AS quoted_text,",
                     "    m.param AS param,",
                     "    m.starred AS starred,",
                     "    m.hidden AS hidden,",
@@ -314,6 +332,8 @@ impl Message {
                     msg.timestamp_sort = row.get("timestamp")?;
                     msg.timestamp_sent = row.get("timestamp_sent")?;
                     msg.timestamp_rcvd = row.get("timestamp_rcvd")?;
+                    msg.ephemeral_timer = row.get("ephemeral_timer")?;
+                    msg.ephemeral_timestamp = row.get("ephemeral_timestamp")?;
                     msg.viewtype = row.get("type")?;
                     msg.state = row.get("state")?;
                     msg.is_dc_message = row.get("msgrmsg")?;
@@ -337,6 +357,9 @@ impl Message {
                         text = "".to_string();
                     }
                     msg.text = Some(text);
+                    msg.quoted_text = row
+                        .get::<_, Option<String>>("quoted_text")?
+                        .filter(|quote| !quote.is_empty());
 
                     msg.param = row.get::<_, String>("param")?.parse().unwrap_or_default();
                     msg.starred = row.get("starred")?;
@@ -372,6 +395,49 @@ impl Message {
         self.param.get_path(Param::File, context).unwrap_or(None)
     }
 
+    /// Returns the path of a small preview of an image, GIF or sticker.
+    ///
+    /// Previews are created when media is received;
+    /// for older messages, the preview is created on first use.
+    /// Returns `None` for other types or if no preview can be created.
+    pub async fn get_thumbnail(&mut self, context: &Context) -> Option<PathBuf> {
+        match self.viewtype {
+            Viewtype::Image | Viewtype::Gif | Viewtype::Sticker => {}
+            _ => return None,
+        }
+        if let Some(path) = self
+            .param
+            .get_path(Param::Thumbnail, context)
+            .unwrap_or(None)
+        {
+            if path.exists().await {
+                return Some(path);
+            }
+        }
+
+        let blob = self
+            .param
+            .get_blob(Param::File, context, false)
+            .await
+            .unwrap_or(None)?;
+        match blob.create_thumbnail(context).await {
+            Ok(thumbnail) => {
+                self.param.set(Param::Thumbnail, thumbnail.as_name());
+                if !self.id.is_unset() {
+                    self.save_param_to_disk(context).await;
+                }
+                Some(thumbnail.to_abs_path())
+            }
+            Err(err) => {
+                warn!(
+                    context,
+                    "Could not create thumbnail for {}: {}", self.id, err
+                );
+                None
+            }
+        }
+    }
+
     pub async fn try_calc_and_set_dimensions(&mut self, context: &Context) -> Result<(), Error> {
         if chat::msgtype_has_file(self.viewtype) {
             let file_param = self.param.get_path(Param::File, context)?;
@@ -470,6 +536,17 @@ impl Message {
         self.timestamp_sort
     }
 
+    /// Returns the ephemeral timer duration for the message.
+    pub fn get_ephemeral_timer(&self) -> EphemeralTimer {
+        self.ephemeral_timer
+    }
+
+    /// Returns the timestamp when the message expires and gets deleted,
+    /// or 0 if the timer has not started yet.
+    pub fn get_ephemeral_timestamp(&self) -> i64 {
+        self.ephemeral_timestamp
+    }
+
     pub fn get_text(&self) -> Option<String> {
         self.text
             .as_ref()
@@ -507,6 +584,12 @@ impl Message {
         self.param.get_int(Param::GuaranteeE2ee).unwrap_or_default() != 0
     }
 
+    /// Returns true if the message is not end-to-end encrypted
+    /// but signed with a valid signature of the sender.
+    pub fn is_signed_only(&self) -> bool {
+        self.param.get_bool(Param::SignedOnly).unwrap_or_default()
+    }
+
     pub async fn get_summary(&mut self, context: &Context, chat: Option<&Chat>) -> Lot {
         let mut ret = Lot::new();
 
@@ -521,7 +604,9 @@ impl Message {
         };
 
         let contact = if self.from_id != DC_CONTACT_ID_SELF as u32
-            && (chat.typ == Chattype::Group || chat.typ == Chattype::VerifiedGroup)
+            && (chat.typ == Chattype::Group
+                || chat.typ == Chattype::VerifiedGroup
+                || chat.typ == Chattype::Mailinglist)
         {
             Contact::get_by_id(context, self.from_id).await.ok()
         } else {
@@ -564,6 +649,11 @@ impl Message {
         0 != self.param.get_int(Param::Forwarded).unwrap_or_default()
     }
 
+    /// Returns true if the text of the message was changed by its sender.
+    pub fn is_edited(&self) -> bool {
+        self.param.get_bool(Param::IsEdited).unwrap_or_default()
+    }
+
     pub fn is_info(&self) -> bool {
         let cmd = self.param.get_cmd();
         self.from_id == DC_CONTACT_ID_INFO as u32
@@ -613,6 +703,55 @@ impl Message {
         self.text = text;
     }
 
+    /// Sets the message to quote when sending.
+    ///
+    /// The quoted text is rendered at the top of the outgoing
+    /// message and the quoted message is referenced as the parent
+    /// message in the `In-Reply-To` header.
+    pub async fn set_quote(&mut self, context: &Context, quote: &Message) -> Result<(), Error> {
+        ensure!(
+            !quote.rfc724_mid.is_empty(),
+            "Message without Message-Id cannot be quoted"
+        );
+        self.in_reply_to = Some(quote.rfc724_mid.clone());
+
+        let text = quote.get_text().unwrap_or_default();
+        self.quoted_text = if text.is_empty() {
+            // Use summary, similar to "Image" to avoid sending empty quote.
+            Some(quote.get_summarytext(context, 500).await)
+        } else {
+            Some(text)
+        };
+
+        Ok(())
+    }
+
+    /// Returns the text quoted by the message, if any.
+    pub fn quoted_text(&self) -> Option<String> {
+        self.quoted_text.clone()
+    }
+
+    /// Returns the message quoted by this message, if it is known.
+    ///
+    /// The quoted message is looked up by the Message-ID
+    /// of the `In-Reply-To` header.
+    pub async fn quoted_message(&self, context: &Context) -> Result<Option<Message>, Error> {
+        if self.quoted_text.is_none() {
+            return Ok(None);
+        }
+
+        if let Some(in_reply_to) = &self.in_reply_to {
+            let rfc724_mid = crate::mimeparser::parse_message_id(in_reply_to)?;
+            if let Some((_, _, msg_id)) = rfc724_mid_exists(context, &rfc724_mid).await? {
+                let msg = Message::load_from_db(context, msg_id).await?;
+                if !msg.chat_id.is_trash() {
+                    return Ok(Some(msg));
+                }
+            }
+        }
+        Ok(None)
+    }
+
     pub fn set_file(&mut self, file: impl AsRef<str>, filemime: Option<&str>) {
         self.param.set(Param::File, file);
         if let Some(filemime) = filemime {
@@ -629,6 +768,24 @@ impl Message {
         self.param.set_int(Param::Duration, duration);
     }
 
+    /// Attaches the given contacts as vCard and changes the type of the message
+    /// to `Viewtype::Vcard`, see [Contact::make_vcard].
+    pub async fn set_vcard(&mut self, context: &Context, contact_ids: &[u32]) -> Result<(), Error> {
+        ensure!(!contact_ids.is_empty(), "No contacts to attach");
+        let vcard = Contact::make_vcard(context, contact_ids).await?;
+        let name = vcard::parse_vcard(&vcard)
+            .first()
+            .map(|entry| entry.get_display_name().to_string())
+            .unwrap_or_default();
+        let blob = BlobObject::create(context, format!("{}.vcf", name), vcard.as_bytes()).await?;
+
+        self.viewtype = Viewtype::Vcard;
+        self.param.set(Param::File, blob.as_name());
+        self.param.set(Param::MimeType, "text/vcard");
+        self.param.set(Param::VcardName, name);
+        Ok(())
+    }
+
     pub async fn latefiling_mediasize(
         &mut self,
         context: &Context,
@@ -701,6 +858,11 @@ pub enum MessageState {
     /// checkmark).
     OutPending = 20,
 
+    /// Message scheduled for sending at a later time with
+    /// `chat::send_msg_at()`. It can still be edited or cancelled and
+    /// enters OutPending when it is due.
+    OutScheduled = 21,
+
     /// *Unrecoverable* error (*recoverable* errors result in pending
     /// messages).
     OutFailed = 24,
@@ -734,6 +896,7 @@ impl std::fmt::Display for MessageState {
                 Self::OutPreparing => "Preparing",
                 Self::OutDraft => "Draft",
                 Self::OutPending => "Pending",
+                Self::OutScheduled => "Scheduled",
                 Self::OutFailed => "Failed",
                 Self::OutDelivered => "Delivered",
                 Self::OutMdnRcvd => "Read",
@@ -753,6 +916,7 @@ impl From<MessageState> for LotState {
             OutPreparing => LotState::MsgOutPreparing,
             OutDraft => LotState::MsgOutDraft,
             OutPending => LotState::MsgOutPending,
+            OutScheduled => LotState::MsgOutScheduled,
             OutFailed => LotState::MsgOutFailed,
             OutDelivered => LotState::MsgOutDelivered,
             OutMdnRcvd => LotState::MsgOutMdnRcvd,
@@ -804,7 +968,10 @@ impl Lot {
                 );
                 self.text1_meaning = Meaning::Text1Self;
             }
-        } else if chat.typ == Chattype::Group || chat.typ == Chattype::VerifiedGroup {
+        } else if chat.typ == Chattype::Group
+            || chat.typ == Chattype::VerifiedGroup
+            || chat.typ == Chattype::Mailinglist
+        {
             if msg.is_info() || contact.is_none() {
                 self.text1 = None;
                 self.text1_meaning = Meaning::None;
@@ -887,6 +1054,17 @@ pub async fn get_msg_info(context: &Context, msg_id: MsgId) -> String {
         ret += "\n";
     }
 
+    if let EphemeralTimer::Enabled { duration } = msg.ephemeral_timer {
+        ret += &format!("Ephemeral timer: {}\n", duration);
+    }
+
+    if msg.ephemeral_timestamp != 0 {
+        ret += &format!(
+            "Expires: {}\n",
+            dc_timestamp_to_str(msg.ephemeral_timestamp)
+        );
+    }
+
     if msg.from_id == DC_CONTACT_ID_INFO || msg.to_id == DC_CONTACT_ID_INFO {
         // device-internal message, no further details needed
         return ret;
@@ -920,6 +1098,26 @@ pub async fn get_msg_info(context: &Context, msg_id: MsgId) -> String {
         }
     }
 
+    // copies of broadcast list messages are delivered to every recipient separately
+    if let Ok(rows) = context
+        .sql
+        .query_map(
+            "SELECT addr, timestamp FROM msgs_delivered WHERE msg_id=? ORDER BY timestamp;",
+            paramsv![msg_id],
+            |row| {
+                let addr: String = row.get(0)?;
+                let ts: i64 = row.get(1)?;
+                Ok((addr, ts))
+            },
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await
+    {
+        for (addr, ts) in rows {
+            ret += &format!("Delivered: {} to {}\n", dc_timestamp_to_str(ts), addr);
+        }
+    }
+
     ret += &format!("State: {}", msg.state);
 
     if msg.has_location() {
@@ -1040,6 +1238,197 @@ pub async fn delete_msgs(context: &Context, msg_ids: &[MsgId]) {
     }
 }
 
+/// Replaces the text of a sent message for all chat members.
+///
+/// The text is changed locally right away and a hidden message
+/// with a `Chat-Edit` header is sent to the chat, so the
+/// recipients replace the text as well.
+pub async fn send_edit_request(
+    context: &Context,
+    msg_id: MsgId,
+    new_text: String,
+) -> Result<(), Error> {
+    let mut msg = Message::load_from_db(context, msg_id).await?;
+    ensure!(
+        msg.from_id == DC_CONTACT_ID_SELF,
+        "Can edit only own messages"
+    );
+    ensure!(
+        !msg.chat_id.is_special(),
+        "Cannot edit message in special chat"
+    );
+    ensure!(!msg.is_info(), "Cannot edit info messages");
+    ensure!(!msg.rfc724_mid.is_empty(), "Cannot edit unsent message");
+
+    let mut edit_msg = Message::new(Viewtype::Text);
+    edit_msg.text = Some(new_text.clone());
+    edit_msg.hidden = true;
+    edit_msg.param.set(Param::TextEditFor, &msg.rfc724_mid);
+    chat::send_msg(context, msg.chat_id, &mut edit_msg).await?;
+
+    msg.text = Some(new_text);
+    msg.param.set_int(Param::IsEdited, 1);
+    save_edited_text(context, &msg).await?;
+    Ok(())
+}
+
+/// Deletes sent messages for all chat members.
+///
+/// All messages must belong to the same chat.
+/// A hidden message with a `Chat-Delete` header is sent to the chat,
+/// so the recipients delete the messages as well,
+/// then the messages are deleted locally as with [delete_msgs].
+pub async fn send_delete_request(context: &Context, msg_ids: &[MsgId]) -> Result<(), Error> {
+    ensure!(!msg_ids.is_empty(), "No messages to delete");
+
+    let mut chat_id = None;
+    let mut rfc724_mids = Vec::with_capacity(msg_ids.len());
+    for msg_id in msg_ids {
+        let msg = Message::load_from_db(context, *msg_id).await?;
+        ensure!(
+            msg.from_id == DC_CONTACT_ID_SELF,
+            "Can delete only own messages for everyone"
+        );
+        ensure!(
+            !msg.chat_id.is_special(),
+            "Cannot delete message in special chat for everyone"
+        );
+        ensure!(
+            chat_id.is_none() || chat_id == Some(msg.chat_id),
+            "Messages to delete for everyone must belong to the same chat"
+        );
+        chat_id = Some(msg.chat_id);
+        if !msg.rfc724_mid.is_empty() {
+            rfc724_mids.push(msg.rfc724_mid);
+        }
+    }
+
+    if let Some(chat_id) = chat_id {
+        if !rfc724_mids.is_empty() {
+            let mut delete_msg = Message::new(Viewtype::Text);
+            delete_msg.hidden = true;
+            delete_msg
+                .param
+                .set(Param::DeleteRequestFor, rfc724_mids.join(" "));
+            chat::send_msg(context, chat_id, &mut delete_msg).await?;
+        }
+    }
+
+    delete_msgs(context, msg_ids).await;
+    Ok(())
+}
+
+/// Checks whether a received edit or delete request may change a message.
+///
+/// The request must be sent by the sender of the message to the chat
+/// of the message.  As the `From` header can be forged, requests for
+/// messages that were signed must be signed by the sender as well,
+/// `request_signed` tells whether the request carries a valid signature.
+fn may_change_msg(
+    context: &Context,
+    msg: &Message,
+    chat_id: ChatId,
+    from_id: u32,
+    request_signed: bool,
+) -> bool {
+    if msg.from_id != from_id {
+        warn!(
+            context,
+            "Request for message {} from contact {} ignored.", msg.id, from_id
+        );
+        false
+    } else if msg.chat_id != chat_id {
+        warn!(
+            context,
+            "Request for message {} of another chat ignored.", msg.id
+        );
+        false
+    } else if !request_signed
+        && (msg.param.get_bool(Param::GuaranteeE2ee).unwrap_or_default()
+            || msg.param.get_bool(Param::SignedOnly).unwrap_or_default())
+    {
+        warn!(
+            context,
+            "Unsigned request for signed message {} ignored.", msg.id
+        );
+        false
+    } else {
+        true
+    }
+}
+
+/// Applies a received edit request to the message with the given Message-ID.
+///
+/// The request is ignored unless it is allowed to change the message,
+/// see [may_change_msg].
+pub(crate) async fn apply_edit_request(
+    context: &Context,
+    chat_id: ChatId,
+    rfc724_mid: &str,
+    from_id: u32,
+    request_signed: bool,
+    new_text: &str,
+) -> Result<(), Error> {
+    let rfc724_mid = crate::mimeparser::parse_message_id(rfc724_mid)?;
+    if let Some((_, _, msg_id)) = rfc724_mid_exists(context, &rfc724_mid).await? {
+        let mut msg = Message::load_from_db(context, msg_id).await?;
+        if may_change_msg(context, &msg, chat_id, from_id, request_signed) {
+            msg.text = Some(new_text.to_string());
+            msg.param.set_int(Param::IsEdited, 1);
+            save_edited_text(context, &msg).await?;
+        }
+    } else {
+        info!(
+            context,
+            "Edit request for unknown message {} ignored.", rfc724_mid
+        );
+    }
+    Ok(())
+}
+
+/// Applies a received delete request to the messages with the given Message-IDs.
+///
+/// Messages the request is not allowed to change are not deleted,
+/// see [may_change_msg].
+pub(crate) async fn apply_delete_request(
+    context: &Context,
+    chat_id: ChatId,
+    mid_list: &str,
+    from_id: u32,
+    request_signed: bool,
+) -> Result<(), Error> {
+    let mut msg_ids = Vec::new();
+    for rfc724_mid in crate::mimeparser::parse_message_ids(mid_list)? {
+        if let Some((_, _, msg_id)) = rfc724_mid_exists(context, &rfc724_mid).await? {
+            let msg = Message::load_from_db(context, msg_id).await?;
+            if may_change_msg(context, &msg, chat_id, from_id, request_signed) {
+                msg_ids.push(msg_id);
+            }
+        }
+    }
+    delete_msgs(context, &msg_ids).await;
+    Ok(())
+}
+
+async fn save_edited_text(context: &Context, msg: &Message) -> Result<(), Error> {
+    context
+        .sql
+        .execute(
+            "UPDATE msgs SET txt=?, param=? WHERE id=?;",
+            paramsv![
+                msg.text.as_deref().unwrap_or_default(),
+                msg.param.to_string(),
+                msg.id
+            ],
+        )
+        .await?;
+    context.emit_event(Event::MsgsChanged {
+        chat_id: msg.chat_id,
+        msg_id: msg.id,
+    });
+    Ok(())
+}
+
 async fn delete_poi_location(context: &Context, location_id: u32) -> bool {
     context
         .sql
@@ -1096,6 +1485,13 @@ pub async fn markseen_msgs(context: &Context, msg_ids: Vec<MsgId>) -> bool {
                 update_msg_state(context, id, MessageState::InSeen).await;
                 info!(context, "Seen message {}.", id);
 
+                if let Err(err) = id.start_ephemeral_timer(context).await {
+                    warn!(
+                        context,
+                        "Failed to start ephemeral timer for message {}: {}", id, err
+                    );
+                }
+
                 job::add(
                     context,
                     job::Job::new(Action::MarkseenMsgOnImap, id.to_u32(), Params::new(), 0),
@@ -1119,6 +1515,98 @@ pub async fn markseen_msgs(context: &Context, msg_ids: Vec<MsgId>) -> bool {
     true
 }
 
+/// Forgets the server UIDs of all messages in the given folder.
+///
+/// This is needed when the UIDVALIDITY of the folder changed,
+/// the old UIDs may refer to other messages then.
+pub(crate) async fn reset_server_uids(context: &Context, folder: &str) -> Result<(), Error> {
+    context
+        .sql
+        .execute(
+            "UPDATE msgs SET server_uid=0 WHERE server_folder=?;",
+            paramsv![folder],
+        )
+        .await?;
+    Ok(())
+}
+
+/// Returns the sorted server UIDs of messages in the given folder
+/// that are not yet seen locally.
+pub(crate) async fn get_unseen_server_uids(
+    context: &Context,
+    folder: &str,
+) -> Result<Vec<u32>, Error> {
+    let uids = context
+        .sql
+        .query_map(
+            "SELECT server_uid FROM msgs \
+             WHERE server_folder=? AND server_uid>0 AND chat_id>9 AND state IN (?, ?) \
+             ORDER BY server_uid;",
+            paramsv![folder, MessageState::InFresh, MessageState::InNoticed],
+            |row| row.get::<_, u32>(0),
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await?;
+    Ok(uids)
+}
+
+/// Marks messages as seen that were marked as seen on the server,
+/// e.g. by another device or another mail client.
+///
+/// Unlike `markseen_msgs()`, no `\Seen` flag is set on the server and
+/// no read receipts are sent.  Messages in contact requests are only
+/// marked as noticed.
+pub(crate) async fn markseen_by_server_uids(
+    context: &Context,
+    folder: &str,
+    server_uids: &[u32],
+) -> Result<(), Error> {
+    for &server_uid in server_uids {
+        let row = context
+            .sql
+            .query_row_optional(
+                "SELECT m.id, m.chat_id, m.state, c.blocked \
+                 FROM msgs m LEFT JOIN chats c ON c.id=m.chat_id \
+                 WHERE m.server_folder=? AND m.server_uid=? AND m.chat_id>9;",
+                paramsv![folder, server_uid],
+                |row| {
+                    Ok((
+                        row.get::<_, MsgId>(0)?,
+                        row.get::<_, ChatId>(1)?,
+                        row.get::<_, MessageState>(2)?,
+                        row.get::<_, Option<Blocked>>(3)?.unwrap_or_default(),
+                    ))
+                },
+            )
+            .await?;
+        let (msg_id, chat_id, state, blocked) = match row {
+            Some(row) => row,
+            None => continue,
+        };
+
+        if blocked == Blocked::Not {
+            if state == MessageState::InFresh || state == MessageState::InNoticed {
+                update_msg_state(context, msg_id, MessageState::InSeen).await;
+                info!(context, "Message {} was seen on the server.", msg_id);
+                if let Err(err) = msg_id.start_ephemeral_timer(context).await {
+                    warn!(
+                        context,
+                        "Failed to start ephemeral timer for message {}: {}", msg_id, err
+                    );
+                }
+            } else {
+                continue;
+            }
+        } else if state == MessageState::InFresh {
+            update_msg_state(context, msg_id, MessageState::InNoticed).await;
+        } else {
+            continue;
+        }
+        context.emit_event(Event::MsgsChanged { chat_id, msg_id });
+    }
+    Ok(())
+}
+
 pub async fn update_msg_state(context: &Context, msg_id: MsgId, state: MessageState) -> bool {
     context
         .sql
@@ -1191,6 +1679,13 @@ pub async fn get_summarytext_by_raw(
                 format!("{} – {}", label, file_name)
             }
         }
+        Viewtype::Vcard => {
+            let label = context.stock_str(StockMessage::Contact).await;
+            match param.get(Param::VcardName) {
+                Some(name) => format!("{} – {}", label, name),
+                None => label.into_owned(),
+            }
+        }
         _ => {
             if param.get_cmd() != SystemMessage::LocationOnly {
                 "".to_string()
@@ -1368,7 +1863,14 @@ pub async fn mdn_from_ext(
                 (S=Sender, R=Recipient)
                  */
                 // for rounding, SELF is already included!
-                let soll_cnt = (chat::get_chat_contact_cnt(context, chat_id).await + 1) / 2;
+                let contact_cnt = chat::get_chat_contact_cnt(context, chat_id).await;
+                let soll_cnt = if chat_type == Chattype::Broadcast {
+                    // recipients of broadcast lists do not see each other,
+                    // so the message is read by all only if every recipient sent an MDN
+                    std::cmp::max(contact_cnt, 2) - 1
+                } else {
+                    (contact_cnt + 1) / 2
+                };
                 if ist_cnt >= soll_cnt {
                     update_msg_state(context, msg_id, MessageState::OutMdnRcvd).await;
                     read_by_all = true;
@@ -1681,4 +2183,104 @@ mod tests {
             "Autocrypt Setup Message" // file name is not added for autocrypt setup messages
         );
     }
+
+    #[async_std::test]
+    async fn test_send_edit_and_delete_request() {
+        let t = test::dummy_context().await;
+        test::configure_alice_keypair(&t.ctx).await;
+        let chat_id = chat::create_by_contact_id(&t.ctx, DC_CONTACT_ID_SELF)
+            .await
+            .unwrap();
+        let msg_id = chat::send_text_msg(&t.ctx, chat_id, "helo".to_string())
+            .await
+            .unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert!(!msg.is_edited());
+
+        send_edit_request(&t.ctx, msg_id, "hello".to_string())
+            .await
+            .unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert_eq!(msg.get_text().unwrap(), "hello");
+        assert!(msg.is_edited());
+
+        // The request itself is hidden.
+        assert_eq!(
+            chat::get_chat_msgs(&t.ctx, chat_id, 0, None).await,
+            vec![msg_id]
+        );
+
+        send_delete_request(&t.ctx, &[msg_id]).await.unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert!(msg.chat_id.is_trash());
+        assert!(chat::get_chat_msgs(&t.ctx, chat_id, 0, None)
+            .await
+            .is_empty());
+
+        // Deleted messages cannot be edited.
+        assert!(send_edit_request(&t.ctx, msg_id, "foo".to_string())
+            .await
+            .is_err());
+    }
+
+    #[async_std::test]
+    async fn test_markseen_by_server_uids() {
+        use crate::config::Config;
+        use crate::dc_receive_imf::dc_receive_imf;
+
+        let t = test::dummy_context().await;
+        t.ctx
+            .set_config(Config::ConfiguredAddr, Some("alice@example.org"))
+            .await
+            .unwrap();
+        let bob_id = Contact::create(&t.ctx, "Bob", "bob@example.net")
+            .await
+            .unwrap();
+        let chat_id = chat::create_by_contact_id(&t.ctx, bob_id).await.unwrap();
+
+        for uid in 1..=2 {
+            let imf = format!(
+                "From: Bob <bob@example.net>\n\
+                 To: alice@example.org\n\
+                 Chat-Version: 1.0\n\
+                 Subject: Chat message\n\
+                 Message-ID: <{}@example.net>\n\
+                 Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+                 \n\
+                 hello {}\n",
+                uid, uid
+            );
+            dc_receive_imf(&t.ctx, imf.as_bytes(), "INBOX", uid, false)
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            get_unseen_server_uids(&t.ctx, "INBOX").await.unwrap(),
+            vec![1, 2]
+        );
+        assert!(get_unseen_server_uids(&t.ctx, "Sent")
+            .await
+            .unwrap()
+            .is_empty());
+
+        markseen_by_server_uids(&t.ctx, "INBOX", &[2, 3])
+            .await
+            .unwrap();
+        assert_eq!(
+            get_unseen_server_uids(&t.ctx, "INBOX").await.unwrap(),
+            vec![1]
+        );
+        let msgs = chat::get_chat_msgs(&t.ctx, chat_id, 0, None).await;
+        assert_eq!(msgs.len(), 2);
+        let msg = Message::load_from_db(&t.ctx, msgs[1]).await.unwrap();
+        assert_eq!(msg.get_state(), MessageState::InSeen);
+        assert_eq!(chat_id.get_fresh_msg_cnt(&t.ctx).await, 1);
+
+        // after a UIDVALIDITY change, the old UIDs must not be used anymore
+        reset_server_uids(&t.ctx, "INBOX").await.unwrap();
+        assert!(get_unseen_server_uids(&t.ctx, "INBOX")
+            .await
+            .unwrap()
+            .is_empty());
+    }
 }
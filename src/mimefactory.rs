@@ -9,7 +9,9 @@ use crate::contact::*;
 use crate::context::{get_version_str, Context};
 use crate::dc_tools::*;
 use crate::e2ee::*;
+use crate::ephemeral::Timer as EphemeralTimer;
 use crate::error::{bail, ensure, format_err, Error};
+use crate::group_sync;
 use crate::location;
 use crate::message::{self, Message};
 use crate::mimeparser::SystemMessage;
@@ -58,6 +60,8 @@ pub struct RenderedEmail {
     pub message: Vec<u8>,
     // pub envelope: Envelope,
     pub is_encrypted: bool,
+    /// The message is not encrypted but signed.
+    pub is_signed_only: bool,
     pub is_gossiped: bool,
     pub last_added_location_id: u32,
 
@@ -86,6 +90,16 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
 
         if chat.is_self_talk() {
             recipients.push((from_displayname.to_string(), from_addr.to_string()));
+        } else if chat.typ == Chattype::Mailinglist {
+            // mailing lists have no members, posts go to the list address
+            let addr = if msg.param.get_cmd() == SystemMessage::MailinglistUnsubscribe {
+                msg.param.get(Param::Arg)
+            } else {
+                chat.param.get(Param::ListPost)
+            };
+            if let Some(addr) = addr {
+                recipients.push(("".to_string(), addr.to_string()));
+            }
         } else {
             context
                 .sql
@@ -116,6 +130,7 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
 
             if command != SystemMessage::AutocryptSetupMessage
                 && command != SystemMessage::SecurejoinMessage
+                && !msg.hidden // hidden messages are never marked as seen
                 && context.get_config_bool(Config::MdnsEnabled).await
             {
                 req_mdn = true;
@@ -293,6 +308,11 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
     async fn should_do_gossip(&self) -> bool {
         match &self.loaded {
             Loaded::Message { chat } => {
+                // members of broadcast lists must not learn about each other
+                if chat.typ == Chattype::Broadcast {
+                    return false;
+                }
+
                 // beside key- and member-changes, force re-gossip every 48 hours
                 let gossiped_timestamp = chat.get_gossiped_timestamp(self.context).await;
                 if time() > gossiped_timestamp + (2 * 24 * 60 * 60) {
@@ -305,6 +325,27 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
         }
     }
 
+    pub fn is_broadcast(&self) -> bool {
+        match &self.loaded {
+            Loaded::Message { chat } => chat.typ == Chattype::Broadcast,
+            Loaded::MDN { .. } => false,
+        }
+    }
+
+    /// Returns a factory for the copy of the message sent to `addr` only.
+    ///
+    /// Messages to broadcast lists are sent as separate copies,
+    /// so that recipients learn neither the addresses of each other
+    /// nor the keys the message is encrypted to.
+    /// For the own address, the copy is sent to no recipient but self.
+    pub fn for_recipient(&self, addr: &str) -> MimeFactory<'a, 'b> {
+        let mut factory = self.clone();
+        factory
+            .recipients
+            .retain(|(_, recipient)| recipient == addr);
+        factory
+    }
+
     fn grpimage(&self) -> Option<String> {
         match &self.loaded {
             Loaded::Message { chat } => {
@@ -343,7 +384,16 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
                         .stock_str(StockMessage::AcSetupMsgSubject)
                         .await
                         .into_owned()
-                } else if chat.typ == Chattype::Group || chat.typ == Chattype::VerifiedGroup {
+                } else if self.msg.param.get_cmd() == SystemMessage::MailinglistUnsubscribe {
+                    self.msg
+                        .param
+                        .get(Param::Arg2)
+                        .unwrap_or("unsubscribe")
+                        .to_string()
+                } else if chat.typ == Chattype::Group
+                    || chat.typ == Chattype::VerifiedGroup
+                    || chat.typ == Chattype::Mailinglist
+                {
                     let re = if self.in_reply_to.is_empty() {
                         ""
                     } else {
@@ -383,6 +433,7 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
         // - Chat-*, except Chat-Version
         // - Secure-Join*
         // - Subject
+        // - X-Mailer
         let mut protected_headers: Vec<Header> = Vec::new();
 
         // All other headers
@@ -435,7 +486,7 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
         // This is only informational for debugging and may be removed in the release.
         // We do not rely on this header as it may be removed by MTAs.
 
-        unprotected_headers.push(Header::new(
+        protected_headers.push(Header::new(
             "X-Mailer".into(),
             format!("Delta Chat Core {}{}", version, os_part),
         ));
@@ -487,6 +538,9 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
         let should_encrypt =
             encrypt_helper.should_encrypt(self.context, e2ee_guaranteed, &peerstates)?;
         let is_encrypted = should_encrypt && force_plaintext == 0;
+        let is_signed_only = !is_encrypted
+            && force_plaintext != ForcePlaintext::NoAutocryptHeader as i32
+            && self.context.get_config_bool(Config::SignUnencrypted).await;
 
         let rfc724_mid = match self.loaded {
             Loaded::Message { .. } => self.msg.rfc724_mid.clone(),
@@ -504,7 +558,13 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
             render_rfc724_mid(&rfc724_mid),
         ));
 
-        unprotected_headers.push(Header::new_with_value("To".into(), to).unwrap());
+        if self.is_broadcast() && self.recipients.is_empty() {
+            // the copy of a broadcast list message sent to self,
+            // other devices assign it to the list by its Chat-Group-ID
+            unprotected_headers.push(Header::new("To".into(), "hidden-recipients: ;".into()));
+        } else {
+            unprotected_headers.push(Header::new_with_value("To".into(), to).unwrap());
+        }
         unprotected_headers.push(Header::new_with_value("From".into(), vec![from]).unwrap());
 
         let mut is_gossiped = false;
@@ -527,20 +587,7 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
             for header in protected_headers.into_iter() {
                 message = message.header(header);
             }
-
-            // Set the appropriate Content-Type for the inner message.
-            let mut existing_ct = message
-                .get_header("Content-Type".to_string())
-                .and_then(|h| h.get_value::<String>().ok())
-                .unwrap_or_else(|| "text/plain; charset=utf-8;".to_string());
-
-            if !existing_ct.ends_with(';') {
-                existing_ct += ";";
-            }
-            message = message.replace_header(Header::new(
-                "Content-Type".to_string(),
-                format!("{} protected-headers=\"v1\";", existing_ct),
-            ));
+            message = mark_protected_headers(message);
 
             // Set the appropriate Content-Type for the outer message
             let mut outer_message = PartBuilder::new().header((
@@ -588,6 +635,45 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
                 .header(("Subject".to_string(), "...".to_string()));
 
             outer_message
+        } else if is_signed_only {
+            // Copy the protected headers to the signed part, so they
+            // are covered by the signature. Other MUAs may not look
+            // there, so all headers go to the outer message as well.
+            for header in protected_headers.iter() {
+                message = message.header(header.clone());
+            }
+            let message = mark_protected_headers(message).build();
+            let signature = encrypt_helper
+                .sign(self.context, message.as_string().as_bytes())
+                .await?;
+
+            let mut outer_message = PartBuilder::new().header((
+                "Content-Type".to_string(),
+                "multipart/signed; protocol=\"application/pgp-signature\"; micalg=pgp-sha256"
+                    .to_string(),
+            ));
+            for header in protected_headers.into_iter() {
+                outer_message = outer_message.header(header);
+            }
+            for header in unprotected_headers.into_iter() {
+                outer_message = outer_message.header(header);
+            }
+
+            outer_message.child(message).child(
+                PartBuilder::new()
+                    .content_type(
+                        &"application/pgp-signature; name=\"signature.asc\""
+                            .parse::<mime::Mime>()
+                            .unwrap(),
+                    )
+                    .header(("Content-Description", "OpenPGP digital signature"))
+                    .header((
+                        "Content-Disposition",
+                        "attachment; filename=\"signature.asc\";",
+                    ))
+                    .body(signature)
+                    .build(),
+            )
         } else {
             // In the unencrypted case, we add all headers to the outer message.
             for header in protected_headers.into_iter() {
@@ -608,6 +694,7 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
             message: outer_message.build().as_string().into_bytes(),
             // envelope: Envelope::new,
             is_encrypted,
+            is_signed_only,
             is_gossiped,
             last_added_location_id,
             rfc724_mid,
@@ -674,6 +761,12 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
             protected_headers.push(Header::new("Chat-Verified".to_string(), "1".to_string()));
         }
 
+        if chat.typ == Chattype::Broadcast {
+            // lets other devices of the sender assign their copy to the list;
+            // without a group name, recipients do not create a group
+            protected_headers.push(Header::new("Chat-Group-ID".into(), chat.grpid.clone()));
+        }
+
         if chat.typ == Chattype::Group || chat.typ == Chattype::VerifiedGroup {
             protected_headers.push(Header::new("Chat-Group-ID".into(), chat.grpid.clone()));
 
@@ -720,6 +813,12 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
                         value_to_add.into(),
                     ));
                 }
+                SystemMessage::GroupMemberCorrection => {
+                    protected_headers.push(Header::new(
+                        "Chat-Group-Member-Correction".into(),
+                        "1".into(),
+                    ));
+                }
                 SystemMessage::GroupImageChanged => {
                     protected_headers.push(Header::new(
                         "Chat-Content".to_string(),
@@ -736,6 +835,40 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
             }
         }
 
+        if self.msg.ephemeral_timer != EphemeralTimer::Disabled
+            || command == SystemMessage::EphemeralTimerChanged
+        {
+            protected_headers.push(Header::new(
+                "Ephemeral-Timer".into(),
+                self.msg.ephemeral_timer.to_string(),
+            ));
+        }
+
+        if let Some(rfc724_mid) = self.msg.param.get(Param::TextEditFor) {
+            protected_headers.push(Header::new(
+                "Chat-Edit".into(),
+                render_rfc724_mid(rfc724_mid),
+            ));
+        }
+        if let Some(mid_list) = self.msg.param.get(Param::DeleteRequestFor) {
+            protected_headers.push(Header::new(
+                "Chat-Delete".into(),
+                render_rfc724_mid_list(mid_list),
+            ));
+        }
+        if let Some(rfc724_mid) = self.msg.param.get(Param::PinRequestFor) {
+            protected_headers.push(Header::new(
+                "Chat-Pin".into(),
+                render_rfc724_mid(rfc724_mid),
+            ));
+        }
+        if let Some(rfc724_mid) = self.msg.param.get(Param::UnpinRequestFor) {
+            protected_headers.push(Header::new(
+                "Chat-Unpin".into(),
+                render_rfc724_mid(rfc724_mid),
+            ));
+        }
+
         match command {
             SystemMessage::LocationStreamingEnabled => {
                 protected_headers.push(Header::new(
@@ -743,6 +876,15 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
                     "location-streaming-enabled".into(),
                 ));
             }
+            SystemMessage::EphemeralTimerChanged => {
+                protected_headers.push(Header::new(
+                    "Chat-Content".into(),
+                    "ephemeral-timer-changed".into(),
+                ));
+            }
+            SystemMessage::Reaction => {
+                protected_headers.push(Header::new("Chat-Content".into(), "reaction".into()));
+            }
             SystemMessage::AutocryptSetupMessage => {
                 unprotected_headers
                     .push(Header::new("Autocrypt-Setup-Message".into(), "v1".into()));
@@ -846,10 +988,24 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
             }
         };
 
+        let quoted_text = self
+            .msg
+            .quoted_text
+            .as_ref()
+            .map(|quote| {
+                quote
+                    .lines()
+                    .map(|line| "> ".to_owned() + line + "\r\n")
+                    .collect::<String>()
+                    + "\r\n"
+            })
+            .unwrap_or_default();
+
         let footer = &self.selfstatus;
         let message_text = format!(
-            "{}{}{}{}{}",
+            "{}{}{}{}{}{}",
             fwdhint.unwrap_or_default(),
+            quoted_text,
             escape_message_footer_marks(final_text),
             if !final_text.is_empty() && !footer.is_empty() {
                 "\r\n\r\n"
@@ -883,6 +1039,16 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
             parts.push(meta_part);
         }
 
+        if command == SystemMessage::GroupMemberCorrection {
+            for raw in group_sync::load_member_changes(context, &self.msg).await? {
+                parts.push(
+                    PartBuilder::new()
+                        .content_type(&"message/rfc822".parse::<mime::Mime>().unwrap())
+                        .body(raw),
+                );
+            }
+        }
+
         if let Some(msg_kml_part) = self.get_message_kml_part() {
             parts.push(msg_kml_part);
         }
@@ -1138,6 +1304,23 @@ async fn is_file_size_okay(context: &Context, msg: &Message) -> bool {
     }
 }
 
+/// Sets the `protected-headers` parameter of the Content-Type
+/// of a part carrying protected headers.
+fn mark_protected_headers(message: PartBuilder) -> PartBuilder {
+    let mut existing_ct = message
+        .get_header("Content-Type".to_string())
+        .and_then(|h| h.get_value::<String>().ok())
+        .unwrap_or_else(|| "text/plain; charset=utf-8;".to_string());
+
+    if !existing_ct.ends_with(';') {
+        existing_ct += ";";
+    }
+    message.replace_header(Header::new(
+        "Content-Type".to_string(),
+        format!("{} protected-headers=\"v1\";", existing_ct),
+    ))
+}
+
 fn render_rfc724_mid(rfc724_mid: &str) -> String {
     let rfc724_mid = rfc724_mid.trim().to_string();
 
@@ -24,6 +24,7 @@ use crate::peerstate::Peerstate;
 use crate::securejoin::handle_degrade_event;
 use crate::simplify::*;
 use crate::stock::StockMessage;
+use crate::vcard;
 
 /// A parsed MIME message.
 ///
@@ -39,20 +40,31 @@ pub struct MimeMessage {
     pub parts: Vec<Part>,
     header: HashMap<String, String>,
 
+    /// Lowercased names of the headers found in the encrypted or signed part,
+    /// see [MimeMessage::is_protected].
+    protected_headers: HashSet<String>,
+
     /// Addresses are normalized and lowercased:
     pub recipients: Vec<SingleInfo>,
     pub from: Vec<SingleInfo>,
     pub chat_disposition_notification_to: Option<SingleInfo>,
     pub decrypting_failed: bool,
     pub signatures: HashSet<String>,
+    /// The message is not encrypted but has a valid signature of the sender.
+    pub signed_only: bool,
     pub gossipped_addr: HashSet<String>,
     pub is_forwarded: bool,
+    /// Text quoted at the top of the message, without quote markers.
+    pub quote: Option<String>,
     pub is_system_message: SystemMessage,
     pub location_kml: Option<location::Kml>,
     pub message_kml: Option<location::Kml>,
     pub(crate) user_avatar: Option<AvatarAction>,
     pub(crate) group_avatar: Option<AvatarAction>,
     pub(crate) reports: Vec<Report>,
+
+    /// Raw member-added/removed messages attached to a group member correction.
+    pub(crate) group_member_changes: Vec<Vec<u8>>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -73,6 +85,20 @@ pub enum SystemMessage {
     SecurejoinMessage = 7,
     LocationStreamingEnabled = 8,
     LocationOnly = 9,
+
+    /// Chat ephemeral message timer is changed.
+    EphemeralTimerChanged = 10,
+
+    /// Reaction to a message, the message text is the reaction.
+    Reaction = 11,
+
+    /// Member-added/removed messages missed by other members,
+    /// sent attached to the message.
+    GroupMemberCorrection = 12,
+
+    /// Request to unsubscribe from a mailing list,
+    /// sent to the address given in its `List-Unsubscribe` header.
+    MailinglistUnsubscribe = 13,
 }
 
 impl Default for SystemMessage {
@@ -106,6 +132,7 @@ impl MimeMessage {
             &mut from,
             &mut chat_disposition_notification_to,
             &mail.headers,
+            false,
         );
 
         // remove headers that are allowed _only_ in the encrypted part
@@ -114,6 +141,8 @@ impl MimeMessage {
         // Memory location for a possible decrypted message.
         let mail_raw;
         let mut gossipped_addr = Default::default();
+        let mut signed_only = false;
+        let mut protected_headers = HashSet::new();
 
         let (mail, signatures) = match e2ee::try_decrypt(context, &mail, message_time).await {
             Ok((raw, signatures)) => {
@@ -142,11 +171,60 @@ impl MimeMessage {
                         &mut from,
                         &mut chat_disposition_notification_to,
                         &decrypted_mail.headers,
+                        true,
                     );
+                    protected_headers = header_names(&decrypted_mail.headers);
 
                     (decrypted_mail, signatures)
                 } else {
-                    // Message was not encrypted
+                    // Message was not encrypted, but may be signed
+                    match e2ee::validate_detached_signature(context, &mail).await {
+                        Ok(valid_signatures) => signed_only = !valid_signatures.is_empty(),
+                        Err(err) => warn!(context, "signature validation failed: {}", err),
+                    }
+                    if signed_only {
+                        // headers of the signed part cannot be altered on the way
+                        let signed_part = &mail.subparts[0];
+                        let unsigned_addr_headers: Vec<(&str, Option<String>)> = ADDR_HEADERS
+                            .iter()
+                            .map(|key| (*key, headers.get(*key).cloned()))
+                            .collect();
+                        let mut signed_recipients = recipients.clone();
+                        let mut signed_from = from.clone();
+                        MimeMessage::merge_headers(
+                            context,
+                            &mut headers,
+                            &mut signed_recipients,
+                            &mut signed_from,
+                            &mut chat_disposition_notification_to,
+                            &signed_part.headers,
+                            true,
+                        );
+                        protected_headers = header_names(&signed_part.headers);
+
+                        // the signature was validated with the keys of the outer sender,
+                        // so the addresses of the signed part are only trusted
+                        // if the signed part was sent by the same address.
+                        if same_addrs(&signed_from, &from) {
+                            recipients = signed_recipients;
+                        } else {
+                            warn!(
+                                context,
+                                "sender of signed part does not match the sender, ignoring its addresses"
+                            );
+                            for (key, value) in unsigned_addr_headers {
+                                match value {
+                                    Some(value) => headers.insert(key.to_string(), value),
+                                    None => headers.remove(key),
+                                };
+                                protected_headers.remove(key);
+                            }
+                        }
+
+                        // a signature does not make the fingerprint secret,
+                        // it is only accepted from the encrypted part
+                        protected_headers.remove("secure-join-fingerprint");
+                    }
                     (mail, signatures)
                 }
             }
@@ -163,9 +241,15 @@ impl MimeMessage {
             }
         };
 
+        // drop a fingerprint taken from a signed-only part
+        if !protected_headers.contains("secure-join-fingerprint") {
+            headers.remove("secure-join-fingerprint");
+        }
+
         let mut parser = MimeMessage {
             parts: Vec::new(),
             header: headers,
+            protected_headers,
             recipients,
             from,
             chat_disposition_notification_to,
@@ -173,9 +257,12 @@ impl MimeMessage {
 
             // only non-empty if it was a valid autocrypt message
             signatures,
+            signed_only,
             gossipped_addr,
             is_forwarded: false,
+            quote: None,
             reports: Vec::new(),
+            group_member_changes: Vec::new(),
             is_system_message: SystemMessage::Unknown,
             location_kml: None,
             message_kml: None,
@@ -209,7 +296,13 @@ impl MimeMessage {
         } else if let Some(value) = self.get(HeaderDef::ChatContent) {
             if value == "location-streaming-enabled" {
                 self.is_system_message = SystemMessage::LocationStreamingEnabled;
+            } else if value == "ephemeral-timer-changed" {
+                self.is_system_message = SystemMessage::EphemeralTimerChanged;
+            } else if value == "reaction" {
+                self.is_system_message = SystemMessage::Reaction;
             }
+        } else if self.get(HeaderDef::ChatGroupMemberCorrection).is_some() {
+            self.is_system_message = SystemMessage::GroupMemberCorrection;
         }
         Ok(())
     }
@@ -239,7 +332,8 @@ impl MimeMessage {
                         || filepart.typ == Viewtype::Audio
                         || filepart.typ == Viewtype::Voice
                         || filepart.typ == Viewtype::Video
-                        || filepart.typ == Viewtype::File)
+                        || filepart.typ == Viewtype::File
+                        || filepart.typ == Viewtype::Vcard)
             };
 
             if need_drop {
@@ -413,6 +507,13 @@ impl MimeMessage {
         self.header.get(headerdef.get_headername())
     }
 
+    /// Returns true if the header was found in the encrypted part
+    /// or in the signed part of a message with a valid signature
+    /// and thus could not be altered on the way.
+    pub fn is_protected(&self, headerdef: HeaderDef) -> bool {
+        self.protected_headers.contains(headerdef.get_headername())
+    }
+
     fn parse_mime_recursive<'a>(
         &'a mut self,
         context: &'a Context,
@@ -465,6 +566,11 @@ impl MimeMessage {
                     if raw.is_empty() {
                         return Ok(false);
                     }
+                    if self.get(HeaderDef::ChatGroupMemberCorrection).is_some() {
+                        // attached member changes are applied to the group, not shown
+                        self.group_member_changes.push(raw);
+                        return Ok(false);
+                    }
                     let mail = mailparse::parse_mail(&raw).unwrap();
 
                     self.parse_mime_recursive(context, &mail).await
@@ -628,8 +734,8 @@ impl MimeMessage {
                             }
                         };
 
-                        let (simplified_txt, is_forwarded) = if decoded_data.is_empty() {
-                            ("".into(), false)
+                        let (simplified_txt, is_forwarded, top_quote) = if decoded_data.is_empty() {
+                            ("".into(), false, None)
                         } else {
                             let is_html = mime_type == mime::TEXT_HTML;
                             let out = if is_html {
@@ -652,6 +758,10 @@ impl MimeMessage {
                         if is_forwarded {
                             self.is_forwarded = true;
                         }
+
+                        if self.quote.is_none() {
+                            self.quote = top_quote;
+                        }
                     }
                     _ => {}
                 }
@@ -665,7 +775,7 @@ impl MimeMessage {
     async fn do_add_single_file_part(
         &mut self,
         context: &Context,
-        msg_type: Viewtype,
+        mut msg_type: Viewtype,
         mime_type: Mime,
         raw_mime: &str,
         decoded_data: &[u8],
@@ -709,6 +819,16 @@ impl MimeMessage {
 
         /* create and register Mime part referencing the new Blob object */
         let mut part = Part::default();
+        if msg_type == Viewtype::File && is_vcard(&mime_type, filename) {
+            // show vCards as contacts if they contain at least one usable entry
+            if let Some(entry) = std::str::from_utf8(decoded_data)
+                .ok()
+                .and_then(|vcard| vcard::parse_vcard(vcard).into_iter().next())
+            {
+                msg_type = Viewtype::Vcard;
+                part.param.set(Param::VcardName, entry.get_display_name());
+            }
+        }
         if mime_type.type_() == mime::IMAGE {
             if let Ok((width, height)) = dc_get_filemeta(decoded_data) {
                 part.param.set_int(Param::Width, width as i32);
@@ -723,12 +843,25 @@ impl MimeMessage {
         part.param.set(Param::File, blob.as_name());
         part.param.set(Param::MimeType, raw_mime);
 
+        if msg_type == Viewtype::Image || msg_type == Viewtype::Gif || msg_type == Viewtype::Sticker
+        {
+            match blob.create_thumbnail(context).await {
+                Ok(thumbnail) => part.param.set(Param::Thumbnail, thumbnail.as_name()),
+                Err(err) => warn!(
+                    context,
+                    "Could not create thumbnail for {}: {}", filename, err
+                ),
+            }
+        }
+
         self.do_add_single_part(part);
     }
 
     fn do_add_single_part(&mut self, mut part: Part) {
         if self.was_encrypted() {
             part.param.set_int(Param::GuaranteeE2ee, 1);
+        } else if self.signed_only {
+            part.param.set_int(Param::SignedOnly, 1);
         }
         self.parts.push(part);
     }
@@ -770,12 +903,14 @@ impl MimeMessage {
         from: &mut Vec<SingleInfo>,
         chat_disposition_notification_to: &mut Option<SingleInfo>,
         fields: &[mailparse::MailHeader<'_>],
+        protected: bool,
     ) {
         for field in fields {
             // lowercasing all headers is technically not correct, but makes things work better
             let key = field.get_key().to_lowercase();
             if !headers.contains_key(&key) || // key already exists, only overwrite known types (protected headers)
-                    is_known(&key) || key.starts_with("chat-")
+                    is_known(&key) || key.starts_with("chat-") ||
+                    (protected && is_protected_header(&key))
             {
                 if key == HeaderDef::ChatDispositionNotificationTo.get_headername() {
                     match addrparse_header(field) {
@@ -938,6 +1073,32 @@ pub(crate) fn parse_message_id(ids: &str) -> Result<String> {
     }
 }
 
+/// Returns the lowercased names of the given headers.
+fn header_names(fields: &[mailparse::MailHeader<'_>]) -> HashSet<String> {
+    fields
+        .iter()
+        .map(|field| field.get_key().to_lowercase())
+        .collect()
+}
+
+/// Headers whose addresses are taken from a signed part
+/// only if it has the same sender as the message.
+const ADDR_HEADERS: [&str; 3] = ["from", "to", "cc"];
+
+fn same_addrs(a: &[SingleInfo], b: &[SingleInfo]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.addr == b.addr)
+}
+
+/// Returns true if the header may be overridden by the encrypted
+/// or signed part of a message.
+fn is_protected_header(key: &str) -> bool {
+    match key {
+        "subject" | "from" | "to" | "cc" | "date" | "message-id" | "references" | "in-reply-to"
+        | "autocrypt-gossip" => true,
+        _ => key.starts_with("chat-"),
+    }
+}
+
 fn is_known(key: &str) -> bool {
     match key {
         "return-path" | "date" | "from" | "sender" | "reply-to" | "to" | "cc" | "bcc"
@@ -997,6 +1158,12 @@ fn get_mime_type(mail: &mailparse::ParsedMail<'_>) -> Result<(Mime, Viewtype)> {
     Ok((mimetype, viewtype))
 }
 
+fn is_vcard(mime_type: &Mime, filename: &str) -> bool {
+    (mime_type.type_() == mime::TEXT
+        && (mime_type.subtype() == "vcard" || mime_type.subtype() == "x-vcard"))
+        || filename.to_lowercase().ends_with(".vcf")
+}
+
 fn is_attachment_disposition(mail: &mailparse::ParsedMail<'_>) -> bool {
     let ct = mail.get_content_disposition();
     ct.disposition == DispositionType::Attachment
@@ -1260,6 +1427,7 @@ mod tests {
         // however, as this is not the case, the outer things stay valid.
         // for Chat-Version, also the case-insensivity is tested.
         assert_eq!(mimeparser.get_subject(), Some("outer-subject".into()));
+        assert!(!mimeparser.is_protected(HeaderDef::Subject));
 
         let of = mimeparser.get(HeaderDef::ChatVersion).unwrap();
         assert_eq!(of, "0.0");
@@ -1270,6 +1438,17 @@ mod tests {
         assert!(mimeparser.get(HeaderDef::SecureJoinFingerprint).is_none());
     }
 
+    #[test]
+    fn test_is_protected_header() {
+        assert!(is_protected_header("subject"));
+        assert!(is_protected_header("message-id"));
+        assert!(is_protected_header("chat-group-id"));
+        assert!(is_protected_header("autocrypt-gossip"));
+        assert!(!is_protected_header("content-type"));
+        assert!(!is_protected_header("secure-join-fingerprint"));
+        assert!(!is_protected_header("list-id"));
+    }
+
     #[async_std::test]
     async fn test_mimeparser_with_avatars() {
         let t = dummy_context().await;
@@ -1576,6 +1755,43 @@ MDYyMDYxNTE1RTlDOEE4Cj4+CnN0YXJ0eHJlZgo4Mjc4CiUlRU9GCg==
         assert_eq!(message.parts[0].msg, "Hello!");
     }
 
+    #[async_std::test]
+    async fn test_parse_vcard_attachment() {
+        let context = dummy_context().await;
+        let raw = br#"Date: Thu, 13 Feb 2020 22:41:20 +0000 (UTC)
+From: sender@example.com
+To: receiver@example.com
+Subject: Contact
+MIME-Version: 1.0
+Content-Type: multipart/mixed; boundary="==BREAK=="
+
+--==BREAK==
+Content-Type: text/plain; charset=utf-8
+
+This is Bob.
+
+--==BREAK==
+Content-Type: text/vcard; name="bob.vcf"
+Content-Disposition: attachment; filename="bob.vcf"
+
+BEGIN:VCARD
+VERSION:4.0
+FN:Bob
+EMAIL:bob@example.net
+END:VCARD
+
+--==BREAK==--
+"#;
+
+        let message = MimeMessage::from_bytes(&context.ctx, &raw[..])
+            .await
+            .unwrap();
+        assert_eq!(message.parts.len(), 1);
+        assert_eq!(message.parts[0].typ, Viewtype::Vcard);
+        assert_eq!(message.parts[0].msg, "This is Bob.");
+        assert_eq!(message.parts[0].param.get(Param::VcardName), Some("Bob"));
+    }
+
     #[async_std::test]
     async fn parse_inline_image() {
         let context = dummy_context().await;
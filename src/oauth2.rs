@@ -7,6 +7,7 @@ use serde::Deserialize;
 
 use crate::context::Context;
 use crate::dc_tools::*;
+use crate::proxy;
 
 const OAUTH2_GMAIL: Oauth2 = Oauth2 {
     // see https://developers.google.com/identity/protocols/OAuth2InstalledApp
@@ -161,7 +162,7 @@ pub async fn dc_get_oauth2_access_token(
         }
 
         // ... and POST
-        let response = surf::post(post_url).body_form(&post_param);
+        let response = proxy::http_post_form(context, post_url, &post_param).await;
         if response.is_err() {
             warn!(
                 context,
@@ -170,7 +171,7 @@ pub async fn dc_get_oauth2_access_token(
             return None;
         }
 
-        let parsed: Result<Response, _> = response.unwrap().recv_json().await;
+        let parsed: Result<Response, _> = serde_json::from_str(&response.unwrap());
         if parsed.is_err() {
             warn!(
                 context,
@@ -291,14 +292,20 @@ impl Oauth2 {
         //   "verified_email": true,
         //   "picture": "https://lh4.googleusercontent.com/-Gj5jh_9R0BY/AAAAAAAAAAI/AAAAAAAAAAA/IAjtjfjtjNA/photo.jpg"
         // }
-        let response: Result<HashMap<String, serde_json::Value>, surf::Error> =
-            surf::get(userinfo_url).recv_json().await;
+        let response = proxy::http_get(context, &userinfo_url).await;
         if response.is_err() {
             warn!(context, "Error getting userinfo: {:?}", response);
             return None;
         }
 
-        let parsed = response.unwrap();
+        let parsed: HashMap<String, serde_json::Value> =
+            match serde_json::from_str(&response.unwrap()) {
+                Ok(parsed) => parsed,
+                Err(err) => {
+                    warn!(context, "Failed to parse userinfo: {}", err);
+                    return None;
+                }
+            };
         // CAVE: serde_json::Value.as_str() removes the quotes of json-strings
         // but serde_json::Value.to_string() does not!
         if let Some(addr) = parsed.get("email") {
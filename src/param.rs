@@ -117,6 +117,48 @@ pub enum Param {
 
     /// For MDN-sending job
     MsgId = b'I',
+
+    /// For Messages: the text of the message was changed by its sender
+    IsEdited = b'X',
+
+    /// For Messages: Message-ID of the message whose text is replaced by this message
+    TextEditFor = b'T',
+
+    /// For Messages: space-separated Message-IDs of the messages deleted by this message
+    DeleteRequestFor = b'V',
+
+    /// For Messages: small preview of an image, GIF or sticker
+    Thumbnail = b'p',
+
+    /// For Messages: message is not encrypted, but signed by the sender
+    SignedOnly = b'o',
+
+    /// For Autocrypt Setup Messages: how a conflicting key was handled on import,
+    /// see `imex::KeyConflictResolution`
+    KeyTransferResolution = b'k',
+
+    /// For Messages: message in a verified group is hidden until the sender
+    /// is verified again with the key of the given fingerprint
+    HeldBack = b'j',
+
+    /// For Messages: Message-ID of the message to pin for all chat members
+    PinRequestFor = b'y',
+
+    /// For Messages: Message-ID of the message to unpin for all chat members
+    UnpinRequestFor = b'Y',
+
+    /// For Mailing lists: address posts are sent to, from the `List-Post` header;
+    /// not set if posting is not allowed
+    ListPost = b'q',
+
+    /// For Mailing lists: the `List-Unsubscribe` header of the last received message
+    ListUnsubscribe = b'Q',
+
+    /// For Messages: display name of the first contact in an attached vCard
+    VcardName = b'N',
+
+    /// For Jobs: number of separate copies a broadcast list message is sent as
+    BroadcastCopies = b'B',
 }
 
 /// Possible values for `Param::ForcePlaintext`.
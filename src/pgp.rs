@@ -1,15 +1,23 @@
 //! OpenPGP helper module using [rPGP facilities](https://github.com/rpgp/rpgp)
 
+use std::cmp::min;
 use std::collections::{BTreeMap, HashSet};
+use std::future::Future;
 use std::io;
 use std::io::Cursor;
+use std::pin::Pin;
+use std::task::{Context as TaskContext, Poll};
+
+use async_std::io::{Read as AsyncRead, Write as AsyncWrite};
+use async_std::task::{self, JoinHandle};
 
 use pgp::armor::BlockType;
 use pgp::composed::{
     Deserializable, KeyType as PgpKeyType, Message, SecretKeyParamsBuilder, SignedPublicKey,
-    SignedPublicSubKey, SignedSecretKey, SubkeyParamsBuilder,
+    SignedPublicSubKey, SignedSecretKey, StandaloneSignature, SubkeyParamsBuilder,
 };
 use pgp::crypto::{HashAlgorithm, SymmetricKeyAlgorithm};
+use pgp::ser::Serialize;
 use pgp::types::{
     CompressionAlgorithm, KeyTrait, Mpi, PublicKeyTrait, SecretKeyTrait, StringToKey,
 };
@@ -272,6 +280,44 @@ pub async fn pk_encrypt(
     .await
 }
 
+/// Creates an ASCII-armored detached signature of `plain`
+/// using `private_key_for_signing`.
+pub async fn pk_calc_signature(
+    plain: &[u8],
+    private_key_for_signing: SignedSecretKey,
+) -> Result<String> {
+    let lit_msg = Message::new_literal_bytes("", plain);
+
+    async_std::task::spawn_blocking(move || {
+        let signed_msg =
+            lit_msg.sign(&private_key_for_signing, || "".into(), Default::default())?;
+        let signature = signed_msg.into_signature().to_armored_string(None)?;
+
+        Ok(signature)
+    })
+    .await
+}
+
+/// Validates the ASCII-armored detached `signature` of `content`.
+///
+/// Returns the fingerprints of the keys of `public_keys_for_validation`
+/// with which the signature is valid.
+pub fn pk_validate(
+    content: &[u8],
+    signature: &[u8],
+    public_keys_for_validation: &Keyring<SignedPublicKey>,
+) -> Result<HashSet<String>> {
+    let (signature, _) = StandaloneSignature::from_armor_single(Cursor::new(signature))?;
+
+    let mut fingerprints = HashSet::new();
+    for pkey in public_keys_for_validation.keys() {
+        if signature.verify(pkey, content).is_ok() {
+            fingerprints.insert(DcKey::fingerprint(pkey).hex());
+        }
+    }
+    Ok(fingerprints)
+}
+
 #[allow(clippy::implicit_hasher)]
 pub async fn pk_decrypt(
     ctext: Vec<u8>,
@@ -346,20 +392,391 @@ pub async fn symm_decrypt<T: std::io::Read + std::io::Seek>(
     ctext: T,
 ) -> Result<Vec<u8>> {
     let (enc_msg, _) = Message::from_armor_single(ctext)?;
+    decrypt_with_password(enc_msg, passphrase).await
+}
+
+/// Symmetric encryption to a binary, not ASCII-armored, message.
+///
+/// Used for large data like backups, where armoring would only
+/// waste space.
+fn encrypt_binary_with_password(passphrase: String, plain: &[u8]) -> Result<Vec<u8>> {
+    let lit_msg = Message::new_literal_bytes("", plain);
+    let mut rng = thread_rng();
+    let s2k = StringToKey::new_default(&mut rng);
+    let msg = lit_msg.encrypt_with_password(&mut rng, s2k, Default::default(), || passphrase)?;
+
+    Ok(msg.to_bytes()?)
+}
 
+async fn decrypt_with_password(enc_msg: Message, passphrase: &str) -> Result<Vec<u8>> {
     let passphrase = passphrase.to_string();
-    async_std::task::spawn_blocking(move || {
-        let decryptor = enc_msg.decrypt_with_password(|| passphrase)?;
+    async_std::task::spawn_blocking(move || decrypt_with_password_blocking(enc_msg, passphrase))
+        .await
+}
+
+fn decrypt_with_password_blocking(enc_msg: Message, passphrase: String) -> Result<Vec<u8>> {
+    let decryptor = enc_msg.decrypt_with_password(|| passphrase)?;
+
+    let msgs = decryptor.collect::<pgp::errors::Result<Vec<_>>>()?;
+    ensure!(!msgs.is_empty(), "No valid messages found");
 
-        let msgs = decryptor.collect::<pgp::errors::Result<Vec<_>>>()?;
-        ensure!(!msgs.is_empty(), "No valid messages found");
+    match msgs[0].get_content()? {
+        Some(content) => Ok(content),
+        None => bail!("Decrypted message is empty"),
+    }
+}
+
+/// Magic bytes at the start of a stream encrypted by [SymmEncryptWriter].
+///
+/// The stream is not an OpenPGP message and cannot be read by other
+/// OpenPGP implementations, the first byte only has the highest bit set
+/// like every OpenPGP packet so that it is not mistaken for text.
+pub(crate) const SYMM_STREAM_MAGIC: &[u8; 8] = b"\x89DCENC1\n";
+
+/// Length of the random nonce following [SYMM_STREAM_MAGIC].
+const SYMM_STREAM_NONCE_LEN: usize = 16;
+
+/// Size of the plaintext chunks of an encrypted stream.
+const SYMM_STREAM_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Room for the OpenPGP packets and the chunk header around the plaintext.
+const SYMM_STREAM_CHUNK_OVERHEAD: usize = 4096;
+
+/// Length of the header encrypted together with the data of every chunk.
+const SYMM_CHUNK_HEADER_LEN: usize = SYMM_STREAM_NONCE_LEN + 9;
+
+/// Encrypts a chunk of a stream.
+///
+/// The chunk is prefixed with the nonce of the stream, its index and
+/// a flag marking the last chunk, so that reordered, missing and
+/// truncated chunks as well as chunks taken from another stream
+/// encrypted with the same passphrase are detected on decryption.
+fn encrypt_chunk(
+    passphrase: String,
+    nonce: [u8; SYMM_STREAM_NONCE_LEN],
+    index: u64,
+    last: bool,
+    data: &[u8],
+) -> Result<Vec<u8>> {
+    let mut plain = Vec::with_capacity(data.len() + SYMM_CHUNK_HEADER_LEN);
+    plain.extend_from_slice(&nonce);
+    plain.extend_from_slice(&index.to_be_bytes());
+    plain.push(last as u8);
+    plain.extend_from_slice(data);
+    let ctext = encrypt_binary_with_password(passphrase, &plain)?;
+
+    let mut chunk = Vec::with_capacity(ctext.len() + 4);
+    chunk.extend_from_slice(&(ctext.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(&ctext);
+    Ok(chunk)
+}
+
+/// Decrypts a chunk encrypted by [encrypt_chunk], returns the data
+/// and whether it is the last chunk.
+fn decrypt_chunk(
+    passphrase: String,
+    nonce: [u8; SYMM_STREAM_NONCE_LEN],
+    index: u64,
+    ctext: &[u8],
+) -> Result<(Vec<u8>, bool)> {
+    let enc_msg = Message::from_bytes(Cursor::new(ctext))?;
+    let mut plain = decrypt_with_password_blocking(enc_msg, passphrase)?;
+    ensure!(
+        plain.len() >= SYMM_CHUNK_HEADER_LEN,
+        "Encrypted chunk is too short"
+    );
+    ensure!(
+        plain[..SYMM_STREAM_NONCE_LEN] == nonce[..],
+        "Encrypted chunk {} belongs to another stream",
+        index
+    );
+    let mut chunk_index = [0u8; 8];
+    chunk_index.copy_from_slice(&plain[SYMM_STREAM_NONCE_LEN..SYMM_STREAM_NONCE_LEN + 8]);
+    ensure!(
+        u64::from_be_bytes(chunk_index) == index,
+        "Encrypted chunk {} is out of order",
+        index
+    );
+    let last = plain[SYMM_CHUNK_HEADER_LEN - 1] != 0;
+    plain.drain(..SYMM_CHUNK_HEADER_LEN);
+    Ok((plain, last))
+}
+
+fn to_io_error(err: anyhow::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+enum EncryptState {
+    /// Collecting plaintext for the next chunk.
+    Buffering,
+    /// Encrypting a chunk, `true` for the last one.
+    Encrypting(JoinHandle<Result<Vec<u8>>>, bool),
+    /// Writing out the encrypted chunk from the given position.
+    Writing(Vec<u8>, usize, bool),
+    Finished,
+}
+
+/// Symmetrically encrypts everything written to it and writes the result
+/// to the wrapped writer.
+///
+/// rPGP cannot encrypt streams, so the data is split into chunks which
+/// are encrypted as separate binary OpenPGP messages, each preceded by
+/// its length as 32-bit big-endian integer.  The stream starts with
+/// [SYMM_STREAM_MAGIC] and a random nonce which is encrypted into every
+/// chunk.  Only one chunk is kept in memory, the encryption runs on the
+/// blocking thread pool.
+///
+/// The writer must be closed to write out the last chunk, see [SymmEncryptWriter::finish].
+pub(crate) struct SymmEncryptWriter<W> {
+    inner: W,
+    passphrase: String,
+    nonce: [u8; SYMM_STREAM_NONCE_LEN],
+    chunk_size: usize,
+    plain: Vec<u8>,
+    index: u64,
+    state: EncryptState,
+}
 
-        match msgs[0].get_content()? {
-            Some(content) => Ok(content),
-            None => bail!("Decrypted message is empty"),
+impl<W: AsyncWrite + Unpin> SymmEncryptWriter<W> {
+    pub fn new(inner: W, passphrase: String) -> Self {
+        let nonce: [u8; SYMM_STREAM_NONCE_LEN] = thread_rng().gen();
+        let mut header = SYMM_STREAM_MAGIC.to_vec();
+        header.extend_from_slice(&nonce);
+        SymmEncryptWriter {
+            inner,
+            passphrase,
+            nonce,
+            chunk_size: SYMM_STREAM_CHUNK_SIZE,
+            plain: Vec::new(),
+            index: 0,
+            state: EncryptState::Writing(header, 0, false),
         }
-    })
-    .await
+    }
+
+    /// Encrypts and writes out the last chunk and returns the wrapped writer.
+    ///
+    /// The wrapped writer is flushed, but not closed.
+    pub async fn finish(mut self) -> io::Result<W> {
+        futures::future::poll_fn(|cx| Pin::new(&mut self).poll_close(cx)).await?;
+        Ok(self.inner)
+    }
+
+    fn start_chunk(&mut self, last: bool) {
+        let plain = std::mem::take(&mut self.plain);
+        let passphrase = self.passphrase.clone();
+        let nonce = self.nonce;
+        let index = self.index;
+        self.index += 1;
+        let handle =
+            task::spawn_blocking(move || encrypt_chunk(passphrase, nonce, index, last, &plain));
+        self.state = EncryptState::Encrypting(handle, last);
+    }
+
+    /// Finishes encrypting and writing out the current chunk.
+    fn poll_pending(&mut self, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        loop {
+            let next = match &mut self.state {
+                EncryptState::Buffering | EncryptState::Finished => return Poll::Ready(Ok(())),
+                EncryptState::Encrypting(handle, last) => {
+                    match futures::ready!(Pin::new(handle).poll(cx)) {
+                        Ok(chunk) => EncryptState::Writing(chunk, 0, *last),
+                        Err(err) => return Poll::Ready(Err(to_io_error(err))),
+                    }
+                }
+                EncryptState::Writing(chunk, pos, last) => {
+                    while *pos < chunk.len() {
+                        let n = futures::ready!(
+                            Pin::new(&mut self.inner).poll_write(cx, &chunk[*pos..])
+                        )?;
+                        if n == 0 {
+                            return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+                        }
+                        *pos += n;
+                    }
+                    if *last {
+                        EncryptState::Finished
+                    } else {
+                        EncryptState::Buffering
+                    }
+                }
+            };
+            self.state = next;
+        }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for SymmEncryptWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        futures::ready!(this.poll_pending(cx))?;
+        if let EncryptState::Finished = this.state {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::Other,
+                "write to finished encryption",
+            )));
+        }
+        let n = min(buf.len(), this.chunk_size - this.plain.len());
+        this.plain.extend_from_slice(&buf[..n]);
+        if this.plain.len() == this.chunk_size {
+            this.start_chunk(false);
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    /// Flushes the encrypted chunks, the plaintext of an incomplete chunk is kept.
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        futures::ready!(this.poll_pending(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            futures::ready!(this.poll_pending(cx))?;
+            match this.state {
+                EncryptState::Finished => break,
+                _ => this.start_chunk(true),
+            }
+        }
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+}
+
+enum DecryptStep {
+    /// The magic bytes followed by the nonce.
+    Header,
+    Length,
+    Chunk,
+}
+
+enum DecryptState {
+    /// Reading the given number of bytes, filled up to the position.
+    Reading(Vec<u8>, usize, DecryptStep),
+    Decrypting(JoinHandle<Result<(Vec<u8>, bool)>>),
+    /// Serving the plaintext of a chunk from the given position.
+    Serving(Vec<u8>, usize, bool),
+    Finished,
+}
+
+/// Decrypts a stream encrypted by [SymmEncryptWriter].
+///
+/// The length of the chunks is checked before reading them, so
+/// a manipulated stream cannot make it allocate more memory than
+/// for a regular chunk.  An error is returned if the stream ends
+/// before the last chunk.
+pub(crate) struct SymmDecryptReader<R> {
+    inner: R,
+    passphrase: String,
+    nonce: [u8; SYMM_STREAM_NONCE_LEN],
+    chunk_size: usize,
+    index: u64,
+    state: DecryptState,
+}
+
+impl<R: AsyncRead + Unpin> SymmDecryptReader<R> {
+    pub fn new(inner: R, passphrase: String) -> Self {
+        SymmDecryptReader {
+            inner,
+            passphrase,
+            nonce: [0; SYMM_STREAM_NONCE_LEN],
+            chunk_size: SYMM_STREAM_CHUNK_SIZE,
+            index: 0,
+            state: DecryptState::Reading(
+                vec![0; SYMM_STREAM_MAGIC.len() + SYMM_STREAM_NONCE_LEN],
+                0,
+                DecryptStep::Header,
+            ),
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for SymmDecryptReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        loop {
+            let next = match &mut this.state {
+                DecryptState::Reading(data, filled, step) => {
+                    while *filled < data.len() {
+                        let n = futures::ready!(
+                            Pin::new(&mut this.inner).poll_read(cx, &mut data[*filled..])
+                        )?;
+                        if n == 0 {
+                            return Poll::Ready(Err(io::Error::new(
+                                io::ErrorKind::UnexpectedEof,
+                                "encrypted stream is truncated",
+                            )));
+                        }
+                        *filled += n;
+                    }
+                    match step {
+                        DecryptStep::Header => {
+                            let (magic, nonce) = data.split_at(SYMM_STREAM_MAGIC.len());
+                            if magic != SYMM_STREAM_MAGIC {
+                                return Poll::Ready(Err(io::Error::new(
+                                    io::ErrorKind::InvalidData,
+                                    "not an encrypted stream",
+                                )));
+                            }
+                            this.nonce.copy_from_slice(nonce);
+                            DecryptState::Reading(vec![0; 4], 0, DecryptStep::Length)
+                        }
+                        DecryptStep::Length => {
+                            let mut len = [0u8; 4];
+                            len.copy_from_slice(&data[..]);
+                            let len = u32::from_be_bytes(len) as usize;
+                            if len == 0 || len > this.chunk_size + SYMM_STREAM_CHUNK_OVERHEAD {
+                                return Poll::Ready(Err(io::Error::new(
+                                    io::ErrorKind::InvalidData,
+                                    "invalid chunk length in encrypted stream",
+                                )));
+                            }
+                            DecryptState::Reading(vec![0; len], 0, DecryptStep::Chunk)
+                        }
+                        DecryptStep::Chunk => {
+                            let ctext = std::mem::take(data);
+                            let passphrase = this.passphrase.clone();
+                            let nonce = this.nonce;
+                            let index = this.index;
+                            this.index += 1;
+                            DecryptState::Decrypting(task::spawn_blocking(move || {
+                                decrypt_chunk(passphrase, nonce, index, &ctext)
+                            }))
+                        }
+                    }
+                }
+                DecryptState::Decrypting(handle) => {
+                    match futures::ready!(Pin::new(handle).poll(cx)) {
+                        Ok((plain, last)) => DecryptState::Serving(plain, 0, last),
+                        Err(err) => return Poll::Ready(Err(to_io_error(err))),
+                    }
+                }
+                DecryptState::Serving(plain, pos, last) => {
+                    if *pos < plain.len() {
+                        let n = min(buf.len(), plain.len() - *pos);
+                        buf[..n].copy_from_slice(&plain[*pos..*pos + n]);
+                        *pos += n;
+                        return Poll::Ready(Ok(n));
+                    }
+                    if *last {
+                        DecryptState::Finished
+                    } else {
+                        DecryptState::Reading(vec![0; 4], 0, DecryptStep::Length)
+                    }
+                }
+                DecryptState::Finished => return Poll::Ready(Ok(0)),
+            };
+            this.state = next;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -579,4 +996,70 @@ mod tests {
         .unwrap();
         assert_eq!(plain, CLEARTEXT);
     }
+
+    #[async_std::test]
+    async fn test_detached_signature() {
+        let signature = pk_calc_signature(CLEARTEXT, KEYS.alice_secret.clone())
+            .await
+            .unwrap();
+        assert!(signature.starts_with("-----BEGIN PGP SIGNATURE-----"));
+
+        let mut keyring = Keyring::new();
+        keyring.add(KEYS.alice_public.clone());
+        keyring.add(KEYS.bob_public.clone());
+        let fingerprints = pk_validate(CLEARTEXT, signature.as_bytes(), &keyring).unwrap();
+        assert_eq!(fingerprints.len(), 1);
+        assert!(fingerprints.contains(&DcKey::fingerprint(&KEYS.alice_public).hex()));
+
+        let fingerprints =
+            pk_validate(b"This is another test", signature.as_bytes(), &keyring).unwrap();
+        assert!(fingerprints.is_empty());
+    }
+
+    async fn decrypt_stream(passphrase: &str, ctext: &[u8]) -> io::Result<Vec<u8>> {
+        use async_std::prelude::*;
+
+        let mut reader = SymmDecryptReader::new(ctext, passphrase.to_string());
+        let mut plain = Vec::new();
+        reader.read_to_end(&mut plain).await?;
+        Ok(plain)
+    }
+
+    #[async_std::test]
+    async fn test_symm_stream() {
+        use async_std::prelude::*;
+
+        let plain = b"This is a test, spanning three chunks";
+        let mut writer = SymmEncryptWriter::new(Vec::new(), "passphrase".to_string());
+        writer.chunk_size = 16;
+        writer.write_all(plain).await.unwrap();
+        let ctext = writer.finish().await.unwrap();
+        assert!(ctext.starts_with(SYMM_STREAM_MAGIC));
+
+        assert_eq!(decrypt_stream("passphrase", &ctext).await.unwrap(), plain);
+        assert!(decrypt_stream("wrong", &ctext).await.is_err());
+
+        // a stream ending after a chunk which is not the last one is truncated
+        let header_len = SYMM_STREAM_MAGIC.len() + SYMM_STREAM_NONCE_LEN;
+        let mut len = [0u8; 4];
+        len.copy_from_slice(&ctext[header_len..header_len + 4]);
+        let first_chunk_end = header_len + 4 + u32::from_be_bytes(len) as usize;
+        assert!(decrypt_stream("passphrase", &ctext[..first_chunk_end])
+            .await
+            .is_err());
+        assert!(decrypt_stream("passphrase", &ctext[..ctext.len() - 1])
+            .await
+            .is_err());
+        assert!(decrypt_stream("passphrase", &ctext[8..]).await.is_err());
+
+        // chunks cannot be moved to another stream with the same passphrase
+        let mut writer = SymmEncryptWriter::new(Vec::new(), "passphrase".to_string());
+        writer.chunk_size = 16;
+        writer.write_all(plain).await.unwrap();
+        let other = writer.finish().await.unwrap();
+        assert_ne!(other[..header_len], ctext[..header_len]);
+        let mut spliced = other[..header_len].to_vec();
+        spliced.extend_from_slice(&ctext[header_len..]);
+        assert!(decrypt_stream("passphrase", &spliced).await.is_err());
+    }
 }
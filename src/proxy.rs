@@ -0,0 +1,708 @@
+//! # Proxy support
+//!
+//! Outgoing IMAP, SMTP and HTTP connections can be routed through a
+//! SOCKS5 proxy ([RFC 1928](https://tools.ietf.org/html/rfc1928), with
+//! username/password authentication as of
+//! [RFC 1929](https://tools.ietf.org/html/rfc1929)) or through an HTTP
+//! proxy supporting the `CONNECT` method.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+
+use async_std::io;
+use async_std::net::{TcpListener, TcpStream};
+use async_std::prelude::*;
+use async_std::task;
+use futures::future::{abortable, AbortHandle};
+
+use crate::context::Context;
+use crate::login_param::{dc_build_tls, CertificateChecks};
+
+/// Maximum size of the response header we accept from an HTTP proxy.
+const MAX_HEADER_SIZE: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, FromPrimitive)]
+#[repr(i32)]
+#[strum(serialize_all = "snake_case")]
+pub enum ProxyType {
+    Socks5 = 1,
+    Http = 2,
+}
+
+impl ProxyType {
+    fn default_port(self) -> u16 {
+        match self {
+            ProxyType::Socks5 => 1080,
+            ProxyType::Http => 8080,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyConfig {
+    pub typ: ProxyType,
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Invalid URL {0:?}")]
+    InvalidUrl(String),
+
+    #[error("HTTP request failed: {0}")]
+    Request(surf::Error),
+
+    #[error("HTTP request to {url} failed with status {status}")]
+    BadStatus { url: String, status: String },
+
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("TLS error: {0}")]
+    Tls(#[from] async_native_tls::Error),
+}
+
+impl ProxyConfig {
+    /// Reads the proxy settings from the database.
+    ///
+    /// Returns `None` if no proxy is configured.
+    pub async fn from_database(context: &Context, prefix: impl AsRef<str>) -> Option<Self> {
+        let prefix = prefix.as_ref();
+        let sql = &context.sql;
+
+        let key = format!("{}proxy_type", prefix);
+        let typ: ProxyType = sql
+            .get_raw_config_int(context, key)
+            .await
+            .and_then(num_traits::FromPrimitive::from_i32)?;
+
+        let key = format!("{}proxy_host", prefix);
+        let host = sql.get_raw_config(context, key).await.unwrap_or_default();
+        if host.is_empty() {
+            return None;
+        }
+
+        let key = format!("{}proxy_port", prefix);
+        let port = match sql.get_raw_config_int(context, key).await {
+            Some(port) if port > 0 && port <= 0xffff => port as u16,
+            _ => typ.default_port(),
+        };
+
+        let key = format!("{}proxy_user", prefix);
+        let user = sql.get_raw_config(context, key).await.unwrap_or_default();
+
+        let key = format!("{}proxy_pw", prefix);
+        let password = sql.get_raw_config(context, key).await.unwrap_or_default();
+
+        Some(ProxyConfig {
+            typ,
+            host,
+            port,
+            user,
+            password,
+        })
+    }
+
+    /// Saves the proxy settings to the database, `None` removes them.
+    pub async fn save_to_database(
+        proxy: Option<&Self>,
+        context: &Context,
+        prefix: impl AsRef<str>,
+    ) -> crate::sql::Result<()> {
+        let prefix = prefix.as_ref();
+        let sql = &context.sql;
+
+        let key = format!("{}proxy_type", prefix);
+        sql.set_raw_config_int(
+            context,
+            key,
+            proxy.map(|p| p.typ as i32).unwrap_or_default(),
+        )
+        .await?;
+
+        let key = format!("{}proxy_host", prefix);
+        sql.set_raw_config(context, key, proxy.map(|p| p.host.as_str()))
+            .await?;
+
+        let key = format!("{}proxy_port", prefix);
+        sql.set_raw_config_int(
+            context,
+            key,
+            proxy.map(|p| p.port as i32).unwrap_or_default(),
+        )
+        .await?;
+
+        let key = format!("{}proxy_user", prefix);
+        sql.set_raw_config(context, key, proxy.map(|p| p.user.as_str()))
+            .await?;
+
+        let key = format!("{}proxy_pw", prefix);
+        sql.set_raw_config(context, key, proxy.map(|p| p.password.as_str()))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Checks that the proxy is reachable.
+    ///
+    /// For SOCKS5 proxies, this also checks that the proxy accepts our
+    /// credentials. HTTP proxies only check credentials together with a
+    /// `CONNECT` request, so for them only the connection is tested.
+    pub async fn check(&self) -> io::Result<()> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+        if self.typ == ProxyType::Socks5 {
+            self.socks5_handshake(&mut stream).await?;
+        }
+        Ok(())
+    }
+
+    /// Opens a TCP connection to `host:port` through the proxy.
+    pub async fn connect(&self, host: &str, port: u16) -> io::Result<TcpStream> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+        match self.typ {
+            ProxyType::Socks5 => {
+                self.socks5_handshake(&mut stream).await?;
+                self.socks5_connect(&mut stream, host, port).await?;
+            }
+            ProxyType::Http => self.http_connect(&mut stream, host, port).await?,
+        }
+        Ok(stream)
+    }
+
+    async fn socks5_handshake(&self, stream: &mut TcpStream) -> io::Result<()> {
+        let use_auth = !self.user.is_empty();
+        if use_auth {
+            stream.write_all(&[5, 2, 0, 2]).await?;
+        } else {
+            stream.write_all(&[5, 1, 0]).await?;
+        }
+
+        let mut buf = [0u8; 2];
+        stream.read_exact(&mut buf).await?;
+        if buf[0] != 5 {
+            return Err(proxy_error("not a SOCKS5 proxy"));
+        }
+        match buf[1] {
+            0 => Ok(()),
+            2 if use_auth => {
+                let user = self.user.as_bytes();
+                let password = self.password.as_bytes();
+                if user.len() > 255 || password.len() > 255 {
+                    return Err(proxy_error("proxy username or password too long"));
+                }
+                let mut req = vec![1, user.len() as u8];
+                req.extend_from_slice(user);
+                req.push(password.len() as u8);
+                req.extend_from_slice(password);
+                stream.write_all(&req).await?;
+
+                stream.read_exact(&mut buf).await?;
+                if buf[1] != 0 {
+                    return Err(proxy_error("proxy authentication failed"));
+                }
+                Ok(())
+            }
+            _ => Err(proxy_error("proxy requires unsupported authentication")),
+        }
+    }
+
+    async fn socks5_connect(
+        &self,
+        stream: &mut TcpStream,
+        host: &str,
+        port: u16,
+    ) -> io::Result<()> {
+        let host = host.as_bytes();
+        if host.len() > 255 {
+            return Err(proxy_error("hostname too long"));
+        }
+        let mut req = vec![5, 1, 0, 3, host.len() as u8];
+        req.extend_from_slice(host);
+        req.extend_from_slice(&port.to_be_bytes());
+        stream.write_all(&req).await?;
+
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).await?;
+        if buf[0] != 5 {
+            return Err(proxy_error("not a SOCKS5 proxy"));
+        }
+        if buf[1] != 0 {
+            let reason = match buf[1] {
+                1 => "general failure",
+                2 => "connection not allowed by ruleset",
+                3 => "network unreachable",
+                4 => "host unreachable",
+                5 => "connection refused",
+                6 => "TTL expired",
+                7 => "command not supported",
+                8 => "address type not supported",
+                _ => "unknown error",
+            };
+            return Err(proxy_error(&format!("proxy cannot connect: {}", reason)));
+        }
+
+        // Skip the bound address, we do not need it.
+        let addr_len = match buf[3] {
+            1 => 4,
+            3 => {
+                let mut len = [0u8; 1];
+                stream.read_exact(&mut len).await?;
+                len[0] as usize
+            }
+            4 => 16,
+            _ => return Err(proxy_error("invalid SOCKS5 reply")),
+        };
+        let mut bound = vec![0u8; addr_len + 2];
+        stream.read_exact(&mut bound).await?;
+        Ok(())
+    }
+
+    async fn http_connect(&self, stream: &mut TcpStream, host: &str, port: u16) -> io::Result<()> {
+        let authority = if host.contains(':') {
+            format!("[{}]:{}", host, port)
+        } else {
+            format!("{}:{}", host, port)
+        };
+        let mut req = format!("CONNECT {} HTTP/1.1\r\nHost: {}\r\n", authority, authority);
+        if !self.user.is_empty() {
+            let credentials = base64::encode(format!("{}:{}", self.user, self.password));
+            req += &format!("Proxy-Authorization: Basic {}\r\n", credentials);
+        }
+        req += "\r\n";
+        stream.write_all(req.as_bytes()).await?;
+
+        // Read the header byte by byte, the tunnelled data must stay in the stream.
+        let mut header = Vec::new();
+        let mut byte = [0u8; 1];
+        while !header.ends_with(b"\r\n\r\n") {
+            if header.len() >= MAX_HEADER_SIZE {
+                return Err(proxy_error("proxy response too long"));
+            }
+            stream.read_exact(&mut byte).await?;
+            header.push(byte[0]);
+        }
+
+        let header = String::from_utf8_lossy(&header);
+        let status_line = header.lines().next().unwrap_or_default();
+        let status = status_line.splitn(3, ' ').nth(1).unwrap_or_default();
+        match status {
+            "200" => Ok(()),
+            "407" => Err(proxy_error("proxy authentication failed")),
+            _ => Err(proxy_error(&format!(
+                "proxy cannot connect: {}",
+                status_line.trim()
+            ))),
+        }
+    }
+
+    /// Starts a local tunnel to `host:port` through the proxy.
+    ///
+    /// This is needed for libraries which insist on opening their own
+    /// connections. The tunnel accepts exactly one connection on its
+    /// local address and forwards it through the proxy, the listener is
+    /// closed right after, so the tunnel cannot be used as an open relay
+    /// by other local processes. The connection is closed when the
+    /// tunnel is dropped.
+    pub(crate) async fn tunnel(
+        &self,
+        context: &Context,
+        host: &str,
+        port: u16,
+    ) -> io::Result<Tunnel> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+
+        let proxy = self.clone();
+        let context = context.clone();
+        let host = host.to_string();
+        let (forward, abort_handle) = abortable(async move {
+            let client = match listener.accept().await {
+                Ok((client, _)) => client,
+                Err(err) => {
+                    warn!(context, "proxy tunnel: accept failed: {}", err);
+                    return;
+                }
+            };
+            drop(listener);
+            let upstream = match proxy.connect(&host, port).await {
+                Ok(upstream) => upstream,
+                Err(err) => {
+                    warn!(context, "proxy tunnel to {}:{} failed: {}", host, port, err);
+                    return;
+                }
+            };
+            let upload = io::copy(&mut &client, &mut &upstream);
+            let download = io::copy(&mut &upstream, &mut &client);
+            upload.race(download).await.ok();
+        });
+        task::spawn(forward);
+
+        Ok(Tunnel { addr, abort_handle })
+    }
+
+    /// Performs an HTTP request through the proxy and returns the response body.
+    ///
+    /// Only the subset of HTTP needed for autoconfiguration and OAuth2 is
+    /// supported: the request is sent as HTTP/1.0, so the server does not
+    /// use chunked transfer encoding and closes the connection after the
+    /// response. Redirects are not followed, a redirect is reported as
+    /// [Error::BadStatus].
+    async fn http_request(&self, method: &str, url: &str, body: &str) -> Result<String, Error> {
+        let parsed = url::Url::parse(url).map_err(|_| Error::InvalidUrl(url.to_string()))?;
+        let host = parsed
+            .host_str()
+            .ok_or_else(|| Error::InvalidUrl(url.to_string()))?;
+        let port = parsed
+            .port_or_known_default()
+            .ok_or_else(|| Error::InvalidUrl(url.to_string()))?;
+        let path = match parsed.query() {
+            Some(query) => format!("{}?{}", parsed.path(), query),
+            None => parsed.path().to_string(),
+        };
+
+        let mut req = format!(
+            "{} {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n",
+            method, path, host
+        );
+        if method == "POST" {
+            req += "Content-Type: application/x-www-form-urlencoded\r\n";
+            req += &format!("Content-Length: {}\r\n", body.len());
+        }
+        req += "\r\n";
+        req += body;
+
+        let stream = self.connect(host, port).await?;
+        let mut response = Vec::new();
+        match parsed.scheme() {
+            "https" => {
+                let tls = dc_build_tls(CertificateChecks::Strict);
+                let mut stream = tls.connect(host, stream).await?;
+                stream.write_all(req.as_bytes()).await?;
+                stream.read_to_end(&mut response).await?;
+            }
+            "http" => {
+                let mut stream = stream;
+                stream.write_all(req.as_bytes()).await?;
+                stream.read_to_end(&mut response).await?;
+            }
+            _ => return Err(Error::InvalidUrl(url.to_string())),
+        }
+
+        let response = String::from_utf8_lossy(&response);
+        let mut parts = response.splitn(2, "\r\n\r\n");
+        let header = parts.next().unwrap_or_default();
+        let body = parts.next().unwrap_or_default();
+        let status = header
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .splitn(3, ' ')
+            .nth(1)
+            .unwrap_or_default();
+        if !status.starts_with('2') {
+            return Err(Error::BadStatus {
+                url: url.to_string(),
+                status: status.to_string(),
+            });
+        }
+        Ok(body.to_string())
+    }
+}
+
+impl fmt::Display for ProxyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}@{}:{}",
+            self.typ,
+            if self.user.is_empty() {
+                "unset"
+            } else {
+                self.user.as_str()
+            },
+            self.host,
+            self.port
+        )
+    }
+}
+
+/// Local end of a tunnel through a proxy, see [ProxyConfig::tunnel].
+#[derive(Debug)]
+pub(crate) struct Tunnel {
+    addr: SocketAddr,
+    abort_handle: AbortHandle,
+}
+
+impl Tunnel {
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for Tunnel {
+    fn drop(&mut self) {
+        self.abort_handle.abort();
+    }
+}
+
+fn proxy_error(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, msg)
+}
+
+/// Opens a TCP connection to `host:port`, through `proxy` if given.
+pub(crate) async fn connect_tcp(
+    host: &str,
+    port: u16,
+    proxy: Option<&ProxyConfig>,
+) -> io::Result<TcpStream> {
+    match proxy {
+        Some(proxy) => proxy.connect(host, port).await,
+        None => TcpStream::connect((host, port)).await,
+    }
+}
+
+/// Performs an HTTP GET request and returns the response body.
+///
+/// The request goes through the proxy set by the user, if any.
+/// Through a proxy, redirects are not followed,
+/// see [ProxyConfig::http_request].
+pub(crate) async fn http_get(context: &Context, url: &str) -> Result<String, Error> {
+    match ProxyConfig::from_database(context, "").await {
+        Some(proxy) => proxy.http_request("GET", url, "").await,
+        None => surf::get(url).recv_string().await.map_err(Error::Request),
+    }
+}
+
+/// Performs an HTTP POST request with a form body and returns the response body.
+///
+/// The request goes through the proxy set by the user, if any.
+/// Through a proxy, redirects are not followed,
+/// see [ProxyConfig::http_request].
+pub(crate) async fn http_post_form(
+    context: &Context,
+    url: &str,
+    form: &HashMap<&str, &str>,
+) -> Result<String, Error> {
+    match ProxyConfig::from_database(context, "").await {
+        Some(proxy) => {
+            let body = url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(form.iter())
+                .finish();
+            proxy.http_request("POST", url, &body).await
+        }
+        None => surf::post(url)
+            .body_form(form)
+            .map_err(Error::Request)?
+            .recv_string()
+            .await
+            .map_err(Error::Request),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_utils::*;
+
+    /// Starts a server which echoes everything back.
+    async fn start_echo_server() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        task::spawn(async move {
+            let mut incoming = listener.incoming();
+            while let Some(Ok(stream)) = incoming.next().await {
+                task::spawn(async move {
+                    io::copy(&mut &stream, &mut &stream).await.ok();
+                });
+            }
+        });
+        addr
+    }
+
+    /// Serves one client of the stand-in SOCKS5 proxy, requiring `user:pass`.
+    async fn serve_socks5(mut client: TcpStream) -> io::Result<()> {
+        let mut buf = [0u8; 2];
+        client.read_exact(&mut buf).await?;
+        let mut methods = vec![0u8; buf[1] as usize];
+        client.read_exact(&mut methods).await?;
+        if !methods.contains(&2) {
+            return client.write_all(&[5, 0xff]).await;
+        }
+        client.write_all(&[5, 2]).await?;
+
+        client.read_exact(&mut buf).await?;
+        let mut user = vec![0u8; buf[1] as usize];
+        client.read_exact(&mut user).await?;
+        client.read_exact(&mut buf[..1]).await?;
+        let mut pass = vec![0u8; buf[0] as usize];
+        client.read_exact(&mut pass).await?;
+        if user != b"user" || pass != b"pass" {
+            return client.write_all(&[1, 1]).await;
+        }
+        client.write_all(&[1, 0]).await?;
+
+        let mut req = [0u8; 5];
+        client.read_exact(&mut req).await?;
+        assert_eq!(&req[..4], &[5, 1, 0, 3]);
+        let mut host = vec![0u8; req[4] as usize];
+        client.read_exact(&mut host).await?;
+        client.read_exact(&mut buf).await?;
+        let host = String::from_utf8(host).unwrap();
+        let upstream = TcpStream::connect((host.as_str(), u16::from_be_bytes(buf))).await?;
+        client.write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 0]).await?;
+
+        let upload = io::copy(&mut &client, &mut &upstream);
+        let download = io::copy(&mut &upstream, &mut &client);
+        upload.race(download).await?;
+        Ok(())
+    }
+
+    /// Serves one client of the stand-in HTTP proxy, requiring `user:pass`.
+    async fn serve_http(mut client: TcpStream) -> io::Result<()> {
+        let mut header = Vec::new();
+        let mut byte = [0u8; 1];
+        while !header.ends_with(b"\r\n\r\n") {
+            client.read_exact(&mut byte).await?;
+            header.push(byte[0]);
+        }
+        let header = String::from_utf8(header).unwrap();
+        let auth = format!("Proxy-Authorization: Basic {}", base64::encode("user:pass"));
+        if !header.contains(&auth) {
+            return client
+                .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+                .await;
+        }
+        let authority = header.split(' ').nth(1).unwrap();
+        let upstream = TcpStream::connect(authority).await?;
+        client
+            .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+            .await?;
+
+        let upload = io::copy(&mut &client, &mut &upstream);
+        let download = io::copy(&mut &upstream, &mut &client);
+        upload.race(download).await?;
+        Ok(())
+    }
+
+    /// Starts a local stand-in proxy of the given type.
+    async fn start_proxy(typ: ProxyType) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        task::spawn(async move {
+            let mut incoming = listener.incoming();
+            while let Some(Ok(client)) = incoming.next().await {
+                task::spawn(async move {
+                    match typ {
+                        ProxyType::Socks5 => serve_socks5(client).await.ok(),
+                        ProxyType::Http => serve_http(client).await.ok(),
+                    };
+                });
+            }
+        });
+        addr
+    }
+
+    async fn check_echo(mut stream: TcpStream) {
+        stream.write_all(b"hello").await.unwrap();
+        let mut buf = [0u8; 5];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+    }
+
+    fn proxy_config(typ: ProxyType, addr: SocketAddr, password: &str) -> ProxyConfig {
+        ProxyConfig {
+            typ,
+            host: addr.ip().to_string(),
+            port: addr.port(),
+            user: "user".to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    #[async_std::test]
+    async fn test_socks5_proxy() {
+        let echo = start_echo_server().await;
+        let proxy_addr = start_proxy(ProxyType::Socks5).await;
+
+        let proxy = proxy_config(ProxyType::Socks5, proxy_addr, "pass");
+        proxy.check().await.unwrap();
+        let stream = proxy.connect("127.0.0.1", echo.port()).await.unwrap();
+        check_echo(stream).await;
+
+        let proxy = proxy_config(ProxyType::Socks5, proxy_addr, "wrong");
+        assert!(proxy.check().await.is_err());
+        assert!(proxy.connect("127.0.0.1", echo.port()).await.is_err());
+    }
+
+    #[async_std::test]
+    async fn test_http_proxy() {
+        let echo = start_echo_server().await;
+        let proxy_addr = start_proxy(ProxyType::Http).await;
+
+        let proxy = proxy_config(ProxyType::Http, proxy_addr, "pass");
+        proxy.check().await.unwrap();
+        let stream = proxy.connect("127.0.0.1", echo.port()).await.unwrap();
+        check_echo(stream).await;
+
+        let proxy = proxy_config(ProxyType::Http, proxy_addr, "wrong");
+        assert!(proxy.connect("127.0.0.1", echo.port()).await.is_err());
+    }
+
+    #[async_std::test]
+    async fn test_proxy_tunnel() {
+        let t = dummy_context().await;
+        let echo = start_echo_server().await;
+        let proxy_addr = start_proxy(ProxyType::Socks5).await;
+
+        let proxy = proxy_config(ProxyType::Socks5, proxy_addr, "pass");
+        let tunnel = proxy
+            .tunnel(&t.ctx, "127.0.0.1", echo.port())
+            .await
+            .unwrap();
+        let stream = TcpStream::connect(tunnel.addr()).await.unwrap();
+        check_echo(stream).await;
+
+        // only one connection is accepted
+        assert!(TcpStream::connect(tunnel.addr()).await.is_err());
+    }
+
+    #[async_std::test]
+    async fn test_proxy_config_database() {
+        let t = dummy_context().await;
+        assert_eq!(ProxyConfig::from_database(&t.ctx, "").await, None);
+
+        t.ctx
+            .set_config(crate::config::Config::ProxyType, Some("1"))
+            .await
+            .unwrap();
+        t.ctx
+            .set_config(crate::config::Config::ProxyHost, Some("proxy.example.org"))
+            .await
+            .unwrap();
+        let proxy = ProxyConfig::from_database(&t.ctx, "").await.unwrap();
+        assert_eq!(proxy.typ, ProxyType::Socks5);
+        assert_eq!(proxy.port, 1080);
+        assert_eq!(proxy.user, "");
+
+        ProxyConfig::save_to_database(Some(&proxy), &t.ctx, "configured_")
+            .await
+            .unwrap();
+        assert_eq!(
+            ProxyConfig::from_database(&t.ctx, "configured_").await,
+            Some(proxy)
+        );
+
+        ProxyConfig::save_to_database(None, &t.ctx, "configured_")
+            .await
+            .unwrap();
+        assert_eq!(
+            ProxyConfig::from_database(&t.ctx, "configured_").await,
+            None
+        );
+    }
+}
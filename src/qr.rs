@@ -1,6 +1,7 @@
 //! # QR code module
 
-use lazy_static::lazy_static;
+use std::collections::HashMap;
+
 use percent_encoding::percent_decode_str;
 use serde::Deserialize;
 
@@ -15,9 +16,12 @@ use crate::key::dc_normalize_fingerprint;
 use crate::lot::{Lot, LotState};
 use crate::param::*;
 use crate::peerstate::*;
+use crate::proxy;
+use crate::vcard;
 
 const OPENPGP4FPR_SCHEME: &str = "OPENPGP4FPR:"; // yes: uppercase
 const DCACCOUNT_SCHEME: &str = "DCACCOUNT:";
+pub(crate) const DCBACKUP_SCHEME: &str = "DCBACKUP:";
 const MAILTO_SCHEME: &str = "mailto:";
 const MATMSG_SCHEME: &str = "MATMSG:";
 const VCARD_SCHEME: &str = "BEGIN:VCARD";
@@ -52,6 +56,8 @@ pub async fn check_qr(context: &Context, qr: impl AsRef<str>) -> Lot {
         decode_openpgp(context, qr).await
     } else if starts_with_ignore_case(qr, DCACCOUNT_SCHEME) {
         decode_account(context, qr)
+    } else if starts_with_ignore_case(qr, DCBACKUP_SCHEME) {
+        decode_backup(context, qr)
     } else if qr.starts_with(MAILTO_SCHEME) {
         decode_mailto(context, qr).await
     } else if qr.starts_with(SMTP_SCHEME) {
@@ -208,6 +214,30 @@ fn decode_account(_context: &Context, qr: &str) -> Lot {
     lot
 }
 
+/// scheme: `DCBACKUP:SECRET@HOST:PORT`
+fn decode_backup(_context: &Context, qr: &str) -> Lot {
+    let payload = &qr[DCBACKUP_SCHEME.len()..];
+
+    let mut lot = Lot::new();
+
+    let mut parts = payload.splitn(2, '@');
+    match (parts.next(), parts.next()) {
+        (Some(secret), Some(addr))
+            if !secret.is_empty() && addr.parse::<std::net::SocketAddr>().is_ok() =>
+        {
+            lot.state = LotState::QrBackup;
+            lot.text1 = Some(addr.to_string());
+            lot.text2 = Some(secret.to_string());
+        }
+        _ => {
+            lot.state = LotState::QrError;
+            lot.text1 = Some(format!("Invalid backup QR code: {}", payload));
+        }
+    }
+
+    lot
+}
+
 #[derive(Debug, Deserialize)]
 struct CreateAccountResponse {
     email: String,
@@ -220,12 +250,15 @@ struct CreateAccountResponse {
 pub async fn set_config_from_qr(context: &Context, qr: &str) -> Result<(), Error> {
     let url_str = &qr[DCACCOUNT_SCHEME.len()..];
 
-    let response: Result<CreateAccountResponse, surf::Error> =
-        surf::post(url_str).recv_json().await;
-    if response.is_err() {
-        bail!("Cannot create account, request to {} failed", url_str);
-    }
-    let parsed = response.unwrap();
+    let response = proxy::http_post_form(context, url_str, &HashMap::new()).await;
+    let parsed: CreateAccountResponse = match response {
+        Ok(body) => serde_json::from_str(&body)?,
+        Err(err) => bail!(
+            "Cannot create account, request to {} failed: {}",
+            url_str,
+            err
+        ),
+    };
 
     context
         .set_config(Config::Addr, Some(&parsed.email))
@@ -306,37 +339,21 @@ async fn decode_matmsg(context: &Context, qr: &str) -> Lot {
     Lot::from_address(context, name, addr).await
 }
 
-lazy_static! {
-    static ref VCARD_NAME_RE: regex::Regex =
-        regex::Regex::new(r"(?m)^N:([^;]*);([^;\n]*)").unwrap();
-    static ref VCARD_EMAIL_RE: regex::Regex =
-        regex::Regex::new(r"(?m)^EMAIL([^:\n]*):([^;\n]*)").unwrap();
-}
-
-/// Extract address for the matmsg scheme.
+/// Extract address for the vcard scheme.
 ///
-/// Scheme: `VCARD:BEGIN\nN:last name;first name;...;\nEMAIL;<type>:addr...;
+/// Only the first entry of the vCard is used, see [vcard::parse_vcard].
 async fn decode_vcard(context: &Context, qr: &str) -> Lot {
-    let name = VCARD_NAME_RE
-        .captures(qr)
-        .map(|caps| {
-            let last_name = &caps[1];
-            let first_name = &caps[2];
-
-            format!("{} {}", first_name.trim(), last_name.trim())
-        })
-        .unwrap_or_default();
-
-    let addr = if let Some(caps) = VCARD_EMAIL_RE.captures(qr) {
-        match normalize_address(caps[2].trim()) {
-            Ok(addr) => addr,
-            Err(err) => return err.into(),
-        }
-    } else {
-        return format_err!("Bad e-mail address").into();
+    let entry = match vcard::parse_vcard(qr).into_iter().next() {
+        Some(entry) => entry,
+        None => return format_err!("Bad e-mail address").into(),
     };
 
-    Lot::from_address(context, name, addr).await
+    let addr = match normalize_address(entry.addr()) {
+        Ok(addr) => addr,
+        Err(err) => return err.into(),
+    };
+
+    Lot::from_address(context, entry.display_name, addr).await
 }
 
 impl Lot {
@@ -611,6 +628,26 @@ mod tests {
         assert_eq!(res.get_text1().unwrap(), "example.org");
     }
 
+    #[async_std::test]
+    async fn test_decode_backup() {
+        let ctx = dummy_context().await;
+
+        let res = check_qr(&ctx.ctx, "DCBACKUP:s3cr3t@192.168.1.2:4321").await;
+        assert_eq!(res.get_state(), LotState::QrBackup);
+        assert_eq!(res.get_text1().unwrap(), "192.168.1.2:4321");
+        assert_eq!(res.get_text2().unwrap(), "s3cr3t");
+
+        let res = check_qr(&ctx.ctx, "DCBACKUP:s3cr3t@[fe80::1]:4321").await;
+        assert_eq!(res.get_state(), LotState::QrBackup);
+        assert_eq!(res.get_text1().unwrap(), "[fe80::1]:4321");
+
+        let res = check_qr(&ctx.ctx, "DCBACKUP:192.168.1.2:4321").await;
+        assert_eq!(res.get_state(), LotState::QrError);
+
+        let res = check_qr(&ctx.ctx, "DCBACKUP:s3cr3t@example.org").await;
+        assert_eq!(res.get_state(), LotState::QrError);
+    }
+
     #[async_std::test]
     async fn test_decode_account_bad_scheme() {
         let ctx = dummy_context().await;
@@ -0,0 +1,289 @@
+//! # Reactions
+//!
+//! Reactions are short messages, typically a single emoji, attached
+//! to another message.  Each contact has at most one reaction to a
+//! message, a new reaction replaces the previous one and an empty
+//! reaction removes it.
+//!
+//! A reaction is sent as a hidden message with a `Chat-Content:
+//! reaction` header, the reaction as the message text and the
+//! Message-ID of the message reacted to in the `In-Reply-To` header.
+//! Received reactions are not added to the chat, they are stored in
+//! the `reactions` table and moved to the trash chat, so they neither
+//! show up in the chatlist nor count as fresh messages.
+
+use std::collections::BTreeMap;
+
+use crate::chat::{send_msg, ChatId};
+use crate::constants::*;
+use crate::context::Context;
+use crate::error::{ensure, Error};
+use crate::events::Event;
+use crate::message::{self, Message, MsgId};
+use crate::mimeparser::{parse_message_id, SystemMessage};
+
+/// Reactions to a single message.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Reactions {
+    /// Map from a contact ID to the reaction of the contact.
+    reactions: BTreeMap<u32, String>,
+}
+
+impl Reactions {
+    /// Returns the IDs of the contacts who reacted to the message.
+    pub fn contacts(&self) -> Vec<u32> {
+        self.reactions.keys().copied().collect()
+    }
+
+    /// Returns the reaction of the contact,
+    /// or an empty string if the contact did not react.
+    pub fn get(&self, contact_id: u32) -> &str {
+        self.reactions
+            .get(&contact_id)
+            .map(|reaction| reaction.as_str())
+            .unwrap_or_default()
+    }
+
+    /// Returns true if nobody reacted to the message.
+    pub fn is_empty(&self) -> bool {
+        self.reactions.is_empty()
+    }
+}
+
+/// Sends a reaction to a message.
+///
+/// An empty `reaction` removes a previously sent reaction.
+/// Returns the ID of the hidden message carrying the reaction.
+pub async fn send_reaction(
+    context: &Context,
+    msg_id: MsgId,
+    reaction: &str,
+) -> Result<MsgId, Error> {
+    let msg = Message::load_from_db(context, msg_id).await?;
+    ensure!(
+        !msg.chat_id.is_special(),
+        "cannot react to message {} in special chat",
+        msg_id
+    );
+    ensure!(
+        !msg.rfc724_mid.is_empty(),
+        "cannot react to message {} without Message-ID",
+        msg_id
+    );
+    let reaction = reaction.trim();
+
+    let mut reaction_msg = Message::new(Viewtype::Text);
+    reaction_msg.text = Some(reaction.to_string());
+    reaction_msg.hidden = true;
+    reaction_msg.param.set_cmd(SystemMessage::Reaction);
+    reaction_msg.in_reply_to = Some(msg.rfc724_mid.clone());
+    let reaction_msg_id = send_msg(context, msg.chat_id, &mut reaction_msg).await?;
+
+    set_reaction_raw(context, msg.chat_id, msg_id, DC_CONTACT_ID_SELF, reaction).await?;
+    Ok(reaction_msg_id)
+}
+
+/// Returns the reactions to a message.
+pub async fn get_reactions(context: &Context, msg_id: MsgId) -> Result<Reactions, Error> {
+    let reactions = context
+        .sql
+        .query_map(
+            "SELECT contact_id, reaction FROM reactions WHERE msg_id=?;",
+            paramsv![msg_id],
+            |row| Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?)),
+            |rows| {
+                rows.collect::<Result<BTreeMap<_, _>, _>>()
+                    .map_err(Into::into)
+            },
+        )
+        .await?;
+    Ok(Reactions { reactions })
+}
+
+/// Applies a received reaction to the message referenced by `in_reply_to`.
+///
+/// The reaction is ignored if the message is unknown
+/// or does not belong to `chat_id`, the chat the reaction was sent to.
+pub(crate) async fn set_msg_reaction(
+    context: &Context,
+    in_reply_to: &str,
+    chat_id: ChatId,
+    contact_id: u32,
+    reaction: &str,
+) -> Result<(), Error> {
+    let rfc724_mid = parse_message_id(in_reply_to)?;
+    if let Some((_, _, msg_id)) = message::rfc724_mid_exists(context, &rfc724_mid).await? {
+        let msg = Message::load_from_db(context, msg_id).await?;
+        if msg.chat_id == chat_id {
+            set_reaction_raw(context, chat_id, msg_id, contact_id, reaction.trim()).await?;
+        } else {
+            warn!(
+                context,
+                "Reaction to message {} of another chat ignored.", msg_id
+            );
+        }
+    } else {
+        info!(
+            context,
+            "Reaction to unknown message {} ignored.", rfc724_mid
+        );
+    }
+    Ok(())
+}
+
+async fn set_reaction_raw(
+    context: &Context,
+    chat_id: ChatId,
+    msg_id: MsgId,
+    contact_id: u32,
+    reaction: &str,
+) -> Result<(), Error> {
+    if reaction.is_empty() {
+        context
+            .sql
+            .execute(
+                "DELETE FROM reactions WHERE msg_id=? AND contact_id=?;",
+                paramsv![msg_id, contact_id as i32],
+            )
+            .await?;
+    } else {
+        context
+            .sql
+            .execute(
+                "INSERT OR REPLACE INTO reactions (msg_id, contact_id, reaction) VALUES (?,?,?);",
+                paramsv![msg_id, contact_id as i32, reaction],
+            )
+            .await?;
+    }
+    context.emit_event(Event::ReactionsChanged { chat_id, msg_id });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::chat;
+    use crate::contact::Contact;
+    use crate::dc_receive_imf::dc_receive_imf;
+    use crate::test_utils::*;
+
+    #[async_std::test]
+    async fn test_receive_reaction() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let contact_id = Contact::create(&t.ctx, "Bob", "bob@example.net")
+            .await
+            .unwrap();
+
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Bob <bob@example.net>\n\
+              To: alice@example.com\n\
+              Subject: foo\n\
+              Message-ID: <reaction1@example.net>\n\
+              Chat-Version: 1.0\n\
+              Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+              \n\
+              hello\n",
+            "INBOX",
+            1,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let (chat_id, _) = chat::lookup_by_contact_id(&t.ctx, contact_id)
+            .await
+            .unwrap();
+        let msgs = chat::get_chat_msgs(&t.ctx, chat_id, 0, None).await;
+        assert_eq!(msgs.len(), 1);
+        let msg_id = msgs[0];
+        assert!(get_reactions(&t.ctx, msg_id).await.unwrap().is_empty());
+
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Bob <bob@example.net>\n\
+              To: alice@example.com\n\
+              Subject: foo\n\
+              Message-ID: <reaction2@example.net>\n\
+              In-Reply-To: <reaction1@example.net>\n\
+              Chat-Version: 1.0\n\
+              Chat-Content: reaction\n\
+              Date: Sun, 22 Mar 2020 22:38:57 +0000\n\
+              \n\
+              \xf0\x9f\x91\x8d\n",
+            "INBOX",
+            2,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let reactions = get_reactions(&t.ctx, msg_id).await.unwrap();
+        assert_eq!(reactions.contacts(), vec![contact_id]);
+        assert_eq!(reactions.get(contact_id), "👍");
+        assert_eq!(reactions.get(DC_CONTACT_ID_SELF), "");
+
+        // The reaction is neither shown in the chat nor counted as fresh message.
+        assert_eq!(chat::get_chat_msgs(&t.ctx, chat_id, 0, None).await, msgs);
+        assert_eq!(chat_id.get_fresh_msg_cnt(&t.ctx).await, 1);
+
+        // An empty reaction removes the reaction.
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Bob <bob@example.net>\n\
+              To: alice@example.com\n\
+              Subject: foo\n\
+              Message-ID: <reaction3@example.net>\n\
+              In-Reply-To: <reaction1@example.net>\n\
+              Chat-Version: 1.0\n\
+              Chat-Content: reaction\n\
+              Date: Sun, 22 Mar 2020 22:39:57 +0000\n\
+              \n\
+              \n",
+            "INBOX",
+            3,
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(get_reactions(&t.ctx, msg_id).await.unwrap().is_empty());
+    }
+
+    #[async_std::test]
+    async fn test_send_reaction() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let chat_id = chat::create_by_contact_id(&t.ctx, DC_CONTACT_ID_SELF)
+            .await
+            .unwrap();
+        let msg_id = chat::send_text_msg(&t.ctx, chat_id, "hello".to_string())
+            .await
+            .unwrap();
+
+        let reaction_msg_id = send_reaction(&t.ctx, msg_id, "👍").await.unwrap();
+        let reaction_msg = Message::load_from_db(&t.ctx, reaction_msg_id)
+            .await
+            .unwrap();
+        assert!(reaction_msg.hidden);
+        assert_eq!(reaction_msg.param.get_cmd(), SystemMessage::Reaction);
+        assert_eq!(reaction_msg.get_text().unwrap(), "👍");
+
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert_eq!(reaction_msg.in_reply_to, Some(msg.rfc724_mid));
+
+        let reactions = get_reactions(&t.ctx, msg_id).await.unwrap();
+        assert_eq!(reactions.contacts(), vec![DC_CONTACT_ID_SELF]);
+        assert_eq!(reactions.get(DC_CONTACT_ID_SELF), "👍");
+
+        // The hidden reaction message is not shown in the chat.
+        assert_eq!(
+            chat::get_chat_msgs(&t.ctx, chat_id, 0, None).await,
+            vec![msg_id]
+        );
+
+        send_reaction(&t.ctx, msg_id, "").await.unwrap();
+        assert!(get_reactions(&t.ctx, msg_id).await.unwrap().is_empty());
+    }
+}
@@ -1,8 +1,14 @@
+use std::cmp::max;
+use std::time::Duration;
+
+use async_std::future;
 use async_std::prelude::*;
 use async_std::sync::{channel, Receiver, Sender};
 use async_std::task;
 
+use crate::connectivity::ConnectivityStore;
 use crate::context::Context;
+use crate::dc_tools::time;
 use crate::imap::Imap;
 use crate::job::{self, Thread};
 use crate::smtp::Smtp;
@@ -79,6 +85,7 @@ async fn inbox_loop(ctx: Context, started: Sender<()>, inbox_handlers: ImapConne
     let fut = async move {
         let ctx = ctx1;
         if let Err(err) = connection.connect_configured(&ctx).await {
+            connection.connectivity.set_err(&ctx, &err).await;
             error!(ctx, "{}", err);
             return;
         }
@@ -126,11 +133,13 @@ async fn fetch(ctx: &Context, connection: &mut Imap) {
             // fetch
             if let Err(err) = connection.fetch(&ctx, &watch_folder).await {
                 connection.trigger_reconnect();
+                connection.connectivity.set_err(&ctx, &err).await;
                 error!(ctx, "{}", err);
             }
         }
         None => {
             warn!(ctx, "Can not fetch inbox folder, not set");
+            connection.connectivity.set_not_configured(&ctx).await;
             connection.fake_idle(&ctx, None).await;
         }
     }
@@ -142,25 +151,28 @@ async fn fetch_idle(ctx: &Context, connection: &mut Imap, folder: &str) -> bool
             // fetch
             if let Err(err) = connection.fetch(&ctx, &watch_folder).await {
                 connection.trigger_reconnect();
+                connection.connectivity.set_err(&ctx, &err).await;
                 error!(ctx, "{}", err);
             }
 
             // idle
             if connection.can_idle() {
-                connection
-                    .idle(&ctx, Some(watch_folder))
-                    .await
-                    .unwrap_or_else(|err| {
+                match connection.idle(&ctx, Some(watch_folder)).await {
+                    Ok(probe_network) => probe_network,
+                    Err(err) => {
                         connection.trigger_reconnect();
+                        connection.connectivity.set_err(&ctx, &err).await;
                         error!(ctx, "{}", err);
                         false
-                    })
+                    }
+                }
             } else {
                 connection.fake_idle(&ctx, Some(watch_folder)).await
             }
         }
         None => {
             warn!(ctx, "Can not watch inbox folder, not set");
+            connection.connectivity.set_not_configured(&ctx).await;
             connection.fake_idle(&ctx, None).await
         }
     }
@@ -186,6 +198,7 @@ async fn simple_imap_loop(
     let fut = async move {
         let ctx = ctx1;
         if let Err(err) = connection.connect_configured(&ctx).await {
+            connection.connectivity.set_err(&ctx, &err).await;
             error!(ctx, "{}", err);
             return;
         }
@@ -232,9 +245,20 @@ async fn smtp_loop(ctx: Context, started: Sender<()>, smtp_handlers: SmtpConnect
                     probe_network = false;
                 }
                 None => {
-                    // Fake Idle
+                    // Fake Idle, until interrupted or a delayed job is due,
+                    // e.g. a scheduled message.
                     info!(ctx, "smtp fake idle - started");
-                    probe_network = idle_interrupt_receiver.recv().await.unwrap_or_default();
+                    probe_network = match job::load_next_timestamp(&ctx, Thread::Smtp).await {
+                        Some(desired_timestamp) => {
+                            let wait =
+                                Duration::from_secs(max(desired_timestamp - time(), 1) as u64);
+                            match future::timeout(wait, idle_interrupt_receiver.recv()).await {
+                                Ok(res) => res.unwrap_or_default(),
+                                Err(_) => false,
+                            }
+                        }
+                        None => idle_interrupt_receiver.recv().await.unwrap_or_default(),
+                    };
                     info!(ctx, "smtp fake idle - interrupted")
                 }
             }
@@ -450,6 +474,7 @@ impl ConnectionState {
 #[derive(Debug)]
 pub(crate) struct SmtpConnectionState {
     state: ConnectionState,
+    pub(crate) connectivity: ConnectivityStore,
 }
 
 impl SmtpConnectionState {
@@ -458,8 +483,10 @@ impl SmtpConnectionState {
         let (shutdown_sender, shutdown_receiver) = channel(1);
         let (idle_interrupt_sender, idle_interrupt_receiver) = channel(1);
 
+        let connection = Smtp::new();
+        let connectivity = connection.connectivity.clone();
         let handlers = SmtpConnectionHandlers {
-            connection: Smtp::new(),
+            connection,
             stop_receiver,
             shutdown_sender,
             idle_interrupt_receiver,
@@ -471,7 +498,10 @@ impl SmtpConnectionState {
             stop_sender,
         };
 
-        let conn = SmtpConnectionState { state };
+        let conn = SmtpConnectionState {
+            state,
+            connectivity,
+        };
 
         (conn, handlers)
     }
@@ -498,6 +528,7 @@ struct SmtpConnectionHandlers {
 #[derive(Debug)]
 pub(crate) struct ImapConnectionState {
     state: ConnectionState,
+    pub(crate) connectivity: ConnectivityStore,
 }
 
 impl ImapConnectionState {
@@ -507,8 +538,10 @@ impl ImapConnectionState {
         let (shutdown_sender, shutdown_receiver) = channel(1);
         let (idle_interrupt_sender, idle_interrupt_receiver) = channel(1);
 
+        let connection = Imap::new(idle_interrupt_receiver);
+        let connectivity = connection.connectivity.clone();
         let handlers = ImapConnectionHandlers {
-            connection: Imap::new(idle_interrupt_receiver),
+            connection,
             stop_receiver,
             shutdown_sender,
         };
@@ -519,7 +552,10 @@ impl ImapConnectionState {
             stop_sender,
         };
 
-        let conn = ImapConnectionState { state };
+        let conn = ImapConnectionState {
+            state,
+            connectivity,
+        };
 
         (conn, handlers)
     }
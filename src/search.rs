@@ -0,0 +1,259 @@
+//! # Full-text search
+//!
+//! Message texts are indexed in the `msgs_fts` FTS5 table.  The index
+//! is kept up to date by triggers whenever a message is inserted,
+//! deleted or its text is changed, so callers modifying the `msgs`
+//! table do not have to care about it.
+//!
+//! Words are matched case-insensitively, ignoring diacritics, and as
+//! prefixes, so "wor" matches "Wörld".  All words of the query have
+//! to be found in a message, results are ranked by relevance.
+
+use crate::chat::ChatId;
+use crate::constants::*;
+use crate::context::Context;
+use crate::error::Error;
+use crate::message::MsgId;
+
+/// Marks the start of a match in the snippets returned by SQLite.
+const HIGHLIGHT_START: char = '\u{2}';
+
+/// Marks the end of a match in the snippets returned by SQLite.
+const HIGHLIGHT_END: char = '\u{3}';
+
+/// A message found by [search].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub msg_id: MsgId,
+    pub chat_id: ChatId,
+
+    /// Part of the message text around the matches.
+    pub snippet: String,
+
+    /// Start and end of the matches in `snippet`, counted in characters.
+    /// The end is exclusive.
+    pub highlights: Vec<(usize, usize)>,
+}
+
+/// Converts a query entered by the user into an FTS5 query.
+///
+/// Every word is quoted, so that FTS5 operators and special characters
+/// are searched for literally, and matched as prefix.
+/// Returns `None` if there are no words in the query.
+pub(crate) fn fts_query(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    if words.is_empty() {
+        None
+    } else {
+        Some(words.join(" "))
+    }
+}
+
+/// Searches the texts of all messages or, if `chat_id` is given, the
+/// messages of a single chat.
+///
+/// Returns at most `limit` results, skipping the first `offset` ones,
+/// the best matching messages first.  Messages of blocked contacts,
+/// hidden messages and, unless a chat is given, messages of contact
+/// requests are not returned.
+pub async fn search(
+    context: &Context,
+    query: &str,
+    chat_id: Option<ChatId>,
+    offset: usize,
+    limit: usize,
+) -> Result<Vec<SearchResult>, Error> {
+    let fts_query = match fts_query(query) {
+        Some(fts_query) => fts_query,
+        None => return Ok(Vec::new()),
+    };
+
+    context
+        .sql
+        .query_map(
+            format!(
+                "SELECT m.id, m.chat_id, snippet(msgs_fts, 0, '{}', '{}', '…', 12) \
+                 FROM msgs_fts \
+                 JOIN msgs m ON m.id=msgs_fts.rowid \
+                 LEFT JOIN contacts ct ON m.from_id=ct.id \
+                 LEFT JOIN chats c ON m.chat_id=c.id \
+                 WHERE msgs_fts MATCH ?1 \
+                   AND m.chat_id>?2 \
+                   AND (?3=0 OR m.chat_id=?3) \
+                   AND (?3!=0 OR c.blocked=0) \
+                   AND m.hidden=0 \
+                   AND ct.blocked=0 \
+                 ORDER BY bm25(msgs_fts), m.timestamp DESC, m.id DESC \
+                 LIMIT ?4 OFFSET ?5;",
+                HIGHLIGHT_START, HIGHLIGHT_END
+            ),
+            paramsv![
+                fts_query,
+                DC_CHAT_ID_LAST_SPECIAL,
+                chat_id.unwrap_or_default(),
+                limit as i64,
+                offset as i64,
+            ],
+            |row| {
+                let msg_id: MsgId = row.get(0)?;
+                let chat_id: ChatId = row.get(1)?;
+                let raw_snippet: String = row.get(2)?;
+                Ok((msg_id, chat_id, raw_snippet))
+            },
+            |rows| {
+                let mut ret = Vec::new();
+                for row in rows {
+                    let (msg_id, chat_id, raw_snippet) = row?;
+                    let (snippet, highlights) = parse_snippet(&raw_snippet);
+                    ret.push(SearchResult {
+                        msg_id,
+                        chat_id,
+                        snippet,
+                        highlights,
+                    });
+                }
+                Ok(ret)
+            },
+        )
+        .await
+        .map_err(Into::into)
+}
+
+/// Removes the highlight markers from a snippet
+/// and returns the positions of the highlighted parts.
+fn parse_snippet(raw: &str) -> (String, Vec<(usize, usize)>) {
+    let mut snippet = String::with_capacity(raw.len());
+    let mut highlights = Vec::new();
+    let mut pos = 0;
+    let mut start = None;
+    for c in raw.chars() {
+        match c {
+            HIGHLIGHT_START => start = Some(pos),
+            HIGHLIGHT_END => {
+                if let Some(start) = start.take() {
+                    highlights.push((start, pos));
+                }
+            }
+            _ => {
+                snippet.push(c);
+                pos += 1;
+            }
+        }
+    }
+    (snippet, highlights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::chat;
+    use crate::contact::Contact;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(fts_query(""), None);
+        assert_eq!(fts_query("  \n"), None);
+        assert_eq!(fts_query("foo"), Some("\"foo\"*".to_string()));
+        assert_eq!(
+            fts_query(" foo  bar "),
+            Some("\"foo\"* \"bar\"*".to_string())
+        );
+        assert_eq!(
+            fts_query("foo\" OR bar"),
+            Some("\"foo\"\"\"* \"OR\"* \"bar\"*".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_snippet() {
+        assert_eq!(parse_snippet("foo"), ("foo".to_string(), vec![]));
+        assert_eq!(
+            parse_snippet("\u{2}Hällo\u{3} \u{2}wörld\u{3}!"),
+            ("Hällo wörld!".to_string(), vec![(0, 5), (6, 11)])
+        );
+    }
+
+    #[async_std::test]
+    async fn test_search() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let self_chat = chat::create_by_contact_id(&t.ctx, DC_CONTACT_ID_SELF)
+            .await
+            .unwrap();
+        let contact_id = Contact::create(&t.ctx, "Bob", "bob@example.net")
+            .await
+            .unwrap();
+        let bob_chat = chat::create_by_contact_id(&t.ctx, contact_id)
+            .await
+            .unwrap();
+
+        let msg1 = chat::send_text_msg(&t.ctx, self_chat, "Hello wörld".to_string())
+            .await
+            .unwrap();
+        let msg2 = chat::send_text_msg(&t.ctx, bob_chat, "hello there".to_string())
+            .await
+            .unwrap();
+        chat::send_text_msg(&t.ctx, bob_chat, "something else".to_string())
+            .await
+            .unwrap();
+
+        let res = search(&t.ctx, "hello", None, 0, 10).await.unwrap();
+        let mut ids: Vec<MsgId> = res.iter().map(|r| r.msg_id).collect();
+        ids.sort();
+        assert_eq!(ids, vec![msg1, msg2]);
+
+        // Prefixes match, diacritics are ignored.
+        let res = search(&t.ctx, "hel WOR", None, 0, 10).await.unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].msg_id, msg1);
+        assert_eq!(res[0].chat_id, self_chat);
+        assert_eq!(res[0].snippet, "Hello wörld");
+        assert_eq!(res[0].highlights, vec![(0, 5), (6, 11)]);
+
+        // Search in a single chat.
+        let res = search(&t.ctx, "hello", Some(bob_chat), 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].msg_id, msg2);
+
+        // Pagination.
+        let page1 = search(&t.ctx, "hello", None, 0, 1).await.unwrap();
+        let page2 = search(&t.ctx, "hello", None, 1, 1).await.unwrap();
+        assert_eq!(page1.len(), 1);
+        assert_eq!(page2.len(), 1);
+        assert_ne!(page1[0].msg_id, page2[0].msg_id);
+        assert!(search(&t.ctx, "hello", None, 2, 1)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // The index follows changes of the message text.
+        t.ctx
+            .sql
+            .execute("UPDATE msgs SET txt='goodbye' WHERE id=?;", paramsv![msg2])
+            .await
+            .unwrap();
+        let res = search(&t.ctx, "hello", None, 0, 10).await.unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].msg_id, msg1);
+        assert_eq!(
+            search(&t.ctx, "goodbye", None, 0, 10).await.unwrap()[0].msg_id,
+            msg2
+        );
+
+        msg1.delete_from_db(&t.ctx).await.unwrap();
+        assert!(search(&t.ctx, "hello", None, 0, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // The old API uses the index as well.
+        assert_eq!(t.ctx.search_msgs(ChatId::new(0), "good").await, vec![msg2]);
+    }
+}
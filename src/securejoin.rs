@@ -16,7 +16,7 @@ use crate::events::Event;
 use crate::headerdef::HeaderDef;
 use crate::key::{dc_normalize_fingerprint, DcKey, SignedPublicKey};
 use crate::lot::LotState;
-use crate::message::Message;
+use crate::message::{Message, MsgId};
 use crate::mimeparser::*;
 use crate::param::*;
 use crate::peerstate::*;
@@ -68,6 +68,48 @@ macro_rules! get_qr_attr {
 }
 
 pub async fn dc_get_securejoin_qr(context: &Context, group_chat_id: ChatId) -> Option<String> {
+    get_securejoin_qr(context, group_chat_id, group_chat_id).await
+}
+
+/// Returns a setup-contact QR code that can only be used by the given contact.
+///
+/// This is used to verify a contact again after they changed their key,
+/// the messages they sent to verified groups with the new key
+/// are shown as soon as the handshake is done.
+pub async fn dc_get_securejoin_qr_for_contact(
+    context: &Context,
+    contact_id: u32,
+) -> Option<String> {
+    if contact_id <= DC_CONTACT_ID_LAST_SPECIAL {
+        error!(
+            context,
+            "Cannot get QR code for special contact {}.", contact_id
+        );
+        return None;
+    }
+    let contact_chat_id =
+        match chat::create_or_lookup_by_contact_id(context, contact_id, Blocked::Not).await {
+            Ok((chat_id, _)) => chat_id,
+            Err(err) => {
+                error!(
+                    context,
+                    "Cannot get chat for contact {}: {}", contact_id, err
+                );
+                return None;
+            }
+        };
+    get_securejoin_qr(context, contact_chat_id, ChatId::new(0)).await
+}
+
+/// Creates the QR code for [dc_get_securejoin_qr] and [dc_get_securejoin_qr_for_contact].
+///
+/// The tokens are bound to `token_chat_id`, which is either the group to join,
+/// the chat with the contact who may use the QR code or unset.
+async fn get_securejoin_qr(
+    context: &Context,
+    token_chat_id: ChatId,
+    group_chat_id: ChatId,
+) -> Option<String> {
     /*=======================================================
     ====             Alice - the inviter side            ====
     ====   Step 1 in "Setup verified contact" protocol   ====
@@ -80,8 +122,8 @@ pub async fn dc_get_securejoin_qr(context: &Context, group_chat_id: ChatId) -> O
     // invitenumber will be used to allow starting the handshake,
     // auth will be used to verify the fingerprint
     let invitenumber =
-        token::lookup_or_new(context, token::Namespace::InviteNumber, group_chat_id).await;
-    let auth = token::lookup_or_new(context, token::Namespace::Auth, group_chat_id).await;
+        token::lookup_or_new(context, token::Namespace::InviteNumber, token_chat_id).await;
+    let auth = token::lookup_or_new(context, token::Namespace::Auth, token_chat_id).await;
     let self_addr = match context.get_config(Config::ConfiguredAddr).await {
         Some(addr) => addr,
         None => {
@@ -480,6 +522,20 @@ pub(crate) async fn handle_securejoin_handshake(
                 warn!(context, "Secure-join denied (bad invitenumber).");
                 return Ok(HandshakeMessage::Ignore);
             }
+            if !token_matches_contact(
+                context,
+                token::Namespace::InviteNumber,
+                &invitenumber,
+                contact_chat_id,
+            )
+            .await
+            {
+                warn!(
+                    context,
+                    "Secure-join denied (invitenumber of other contact)."
+                );
+                return Ok(HandshakeMessage::Ignore);
+            }
             info!(context, "Secure-join requested.",);
 
             inviter_progress!(context, contact_id, 300);
@@ -620,7 +676,10 @@ pub(crate) async fn handle_securejoin_handshake(
                     return Ok(HandshakeMessage::Ignore);
                 }
             };
-            if !token::exists(context, token::Namespace::Auth, &auth_0).await {
+            if !token::exists(context, token::Namespace::Auth, &auth_0).await
+                || !token_matches_contact(context, token::Namespace::Auth, &auth_0, contact_chat_id)
+                    .await
+            {
                 could_not_establish_secure_connection(context, contact_chat_id, "Auth invalid.")
                     .await;
                 return Ok(HandshakeMessage::Ignore);
@@ -929,6 +988,26 @@ pub(crate) async fn observe_securejoin_on_other_device(
     }
 }
 
+/// Checks that a token created by [dc_get_securejoin_qr_for_contact]
+/// is used by the contact it was created for.
+///
+/// Tokens of other QR codes may be used by anyone.
+async fn token_matches_contact(
+    context: &Context,
+    namespace: token::Namespace,
+    token: &str,
+    contact_chat_id: ChatId,
+) -> bool {
+    let token_chat_id = match token::lookup_foreign_id(context, namespace, token).await {
+        Some(chat_id) if !chat_id.is_unset() => chat_id,
+        _ => return true,
+    };
+    match Chat::load_from_db(context, token_chat_id).await {
+        Ok(chat) if chat.typ == Chattype::Single => token_chat_id == contact_chat_id,
+        _ => true,
+    }
+}
+
 async fn secure_connection_established(context: &Context, contact_chat_id: ChatId) {
     let contact_id: u32 = chat_id_2_contact_id(context, contact_chat_id).await;
     let contact = Contact::get_by_id(context, contact_id).await;
@@ -985,6 +1064,14 @@ async fn mark_peer_as_verified(
                 .save_to_db(&context.sql, false)
                 .await
                 .unwrap_or_default();
+            let contact_id =
+                Contact::lookup_id_by_addr(context, &peerstate.addr, Origin::Unknown).await;
+            if contact_id != 0 {
+                if let Err(err) = release_held_msgs(context, contact_id, fingerprint.as_ref()).await
+                {
+                    warn!(context, "Failed to release held back messages: {}", err);
+                }
+            }
             return Ok(());
         }
     }
@@ -1027,6 +1114,77 @@ fn encrypted_and_signed(
     }
 }
 
+/// Returns the messages of a contact held back in verified groups
+/// because the contact changed their key, see [Param::HeldBack].
+pub(crate) async fn get_held_msgs(
+    context: &Context,
+    contact_id: u32,
+) -> Result<Vec<(MsgId, ChatId, Params)>, Error> {
+    let rows = context
+        .sql
+        .query_map(
+            "SELECT id, chat_id, param FROM msgs WHERE from_id=? AND hidden=1;",
+            paramsv![contact_id as i32],
+            |row| {
+                let msg_id: MsgId = row.get(0)?;
+                let chat_id: ChatId = row.get(1)?;
+                let param: String = row.get(2)?;
+                Ok((msg_id, chat_id, param))
+            },
+            |rows| {
+                rows.collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(Into::into)
+            },
+        )
+        .await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(msg_id, chat_id, param)| {
+            let param: Params = param.parse().unwrap_or_default();
+            if param.exists(Param::HeldBack) {
+                Some((msg_id, chat_id, param))
+            } else {
+                None
+            }
+        })
+        .collect())
+}
+
+/// Shows the messages held back because the contact changed their key
+/// once the contact is verified with the new key.
+///
+/// Returns the number of released messages.
+pub(crate) async fn release_held_msgs(
+    context: &Context,
+    contact_id: u32,
+    fingerprint: &str,
+) -> Result<usize, Error> {
+    let fingerprint = dc_normalize_fingerprint(fingerprint);
+    let mut released = 0;
+    for (msg_id, chat_id, mut param) in get_held_msgs(context, contact_id).await? {
+        if param.get(Param::HeldBack) != Some(fingerprint.as_str()) {
+            continue;
+        }
+        param.remove(Param::HeldBack);
+        context
+            .sql
+            .execute(
+                "UPDATE msgs SET hidden=0, param=? WHERE id=?;",
+                paramsv![param.to_string(), msg_id],
+            )
+            .await?;
+        emit_event!(context, Event::IncomingMsg { chat_id, msg_id });
+        released += 1;
+    }
+    if released > 0 {
+        info!(
+            context,
+            "Released {} held back messages of contact {}.", released, contact_id
+        );
+    }
+    Ok(released)
+}
+
 pub async fn handle_degrade_event(
     context: &Context,
     peerstate: &Peerstate<'_>,
@@ -1064,7 +1222,114 @@ pub async fn handle_degrade_event(
 
             chat::add_info_msg(context, contact_chat_id, msg).await;
             emit_event!(context, Event::ChatModified(contact_chat_id));
+
+            if let (Some(old_fingerprint), Some(new_fingerprint)) = (
+                &peerstate.verified_key_fingerprint,
+                &peerstate.public_key_fingerprint,
+            ) {
+                if old_fingerprint != new_fingerprint {
+                    emit_event!(
+                        context,
+                        Event::VerifiedContactKeyChanged {
+                            contact_id: contact_id as u32,
+                            old_fingerprint: old_fingerprint.clone(),
+                            new_fingerprint: new_fingerprint.clone(),
+                        }
+                    );
+                }
+            }
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_utils::*;
+
+    #[async_std::test]
+    async fn test_qr_for_contact() {
+        let t = dummy_context().await;
+        configure_alice_keypair(&t.ctx).await;
+        let bob_id = Contact::create(&t.ctx, "Bob", "bob@example.net")
+            .await
+            .unwrap();
+        let claire_id = Contact::create(&t.ctx, "Claire", "claire@example.org")
+            .await
+            .unwrap();
+        let bob_chat_id = chat::create_by_contact_id(&t.ctx, bob_id).await.unwrap();
+        let claire_chat_id = chat::create_by_contact_id(&t.ctx, claire_id).await.unwrap();
+
+        let qr = dc_get_securejoin_qr_for_contact(&t.ctx, bob_id)
+            .await
+            .unwrap();
+        assert!(qr.starts_with("OPENPGP4FPR:"));
+        assert!(!qr.contains("&g="));
+        let auth = qr.rsplit("&s=").next().unwrap();
+        assert!(token_matches_contact(&t.ctx, token::Namespace::Auth, auth, bob_chat_id).await);
+        assert!(!token_matches_contact(&t.ctx, token::Namespace::Auth, auth, claire_chat_id).await);
+
+        // The general setup-contact QR code can be used by anyone.
+        let qr = dc_get_securejoin_qr(&t.ctx, ChatId::new(0)).await.unwrap();
+        let auth = qr.rsplit("&s=").next().unwrap();
+        assert!(token_matches_contact(&t.ctx, token::Namespace::Auth, auth, claire_chat_id).await);
+
+        assert!(dc_get_securejoin_qr_for_contact(&t.ctx, DC_CONTACT_ID_SELF)
+            .await
+            .is_none());
+    }
+
+    #[async_std::test]
+    async fn test_release_held_msgs() {
+        let t = dummy_context().await;
+        let bob_id = Contact::create(&t.ctx, "Bob", "bob@example.net")
+            .await
+            .unwrap();
+        let chat_id = chat::create_group_chat(&t.ctx, VerifiedStatus::Verified, "group")
+            .await
+            .unwrap();
+        let fingerprint = "0102040810204080FF0102040810204080FF0000";
+        let mut param = Params::new();
+        param.set(Param::HeldBack, fingerprint);
+        t.ctx
+            .sql
+            .execute(
+                "INSERT INTO msgs (chat_id, from_id, type, state, txt, hidden, param) \
+                 VALUES (?, ?, ?, ?, 'hi', 1, ?);",
+                paramsv![
+                    chat_id,
+                    bob_id as i32,
+                    Viewtype::Text,
+                    crate::message::MessageState::InFresh,
+                    param.to_string()
+                ],
+            )
+            .await
+            .unwrap();
+        let held = get_held_msgs(&t.ctx, bob_id).await.unwrap();
+        assert_eq!(held.len(), 1);
+        let msg_id = held[0].0;
+        assert_eq!(held[0].1, chat_id);
+        assert!(chat::get_chat_msgs(&t.ctx, chat_id, 0, None)
+            .await
+            .is_empty());
+
+        // Verifying another key does not release the message.
+        assert_eq!(release_held_msgs(&t.ctx, bob_id, "0000").await.unwrap(), 0);
+        assert_eq!(
+            release_held_msgs(&t.ctx, bob_id, &fingerprint.to_lowercase())
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(get_held_msgs(&t.ctx, bob_id).await.unwrap().is_empty());
+        assert_eq!(
+            chat::get_chat_msgs(&t.ctx, chat_id, 0, None).await,
+            vec![msg_id]
+        );
+        let msg = Message::load_from_db(&t.ctx, msg_id).await.unwrap();
+        assert!(!msg.param.exists(Param::HeldBack));
+    }
+}
@@ -64,7 +64,10 @@ fn split_lines(buf: &str) -> Vec<&str> {
 
 /// Simplify message text for chat display.
 /// Remove quotes, signatures, trailing empty lines etc.
-pub fn simplify(mut input: String, is_chat_message: bool) -> (String, bool) {
+///
+/// Returns the simplified text, whether the message is forwarded
+/// and the top quote, if any, with the quote markers removed.
+pub fn simplify(mut input: String, is_chat_message: bool) -> (String, bool, Option<String>) {
     input.retain(|c| c != '\r');
     let lines = split_lines(&input);
     let (lines, is_forwarded) = skip_forward_header(&lines);
@@ -73,24 +76,28 @@ pub fn simplify(mut input: String, is_chat_message: bool) -> (String, bool) {
 
     let lines = remove_message_footer(lines);
 
-    let text = if is_chat_message {
-        render_message(lines, false, false)
+    let (text, top_quote) = if is_chat_message {
+        let (unquoted_lines, top_quote) = remove_top_quote(lines);
+        if unquoted_lines.iter().all(|it| it.trim().is_empty()) {
+            (render_message(lines, false), None)
+        } else {
+            (render_message(unquoted_lines, false), top_quote)
+        }
     } else {
         let (lines, has_nonstandard_footer) = remove_nonstandard_footer(lines);
         let (lines, has_bottom_quote) = remove_bottom_quote(lines);
-        let (lines, has_top_quote) = remove_top_quote(lines);
+        let (lines, top_quote) = remove_top_quote(lines);
 
         if lines.iter().all(|it| it.trim().is_empty()) {
-            render_message(original_lines, false, false)
+            (render_message(original_lines, false), None)
         } else {
-            render_message(
-                lines,
-                has_top_quote,
-                has_nonstandard_footer || has_bottom_quote,
+            (
+                render_message(lines, has_nonstandard_footer || has_bottom_quote),
+                top_quote,
             )
         }
     };
-    (text, is_forwarded)
+    (text, is_forwarded, top_quote)
 }
 
 /// Skips "forwarded message" header.
@@ -132,11 +139,20 @@ fn remove_bottom_quote<'a>(lines: &'a [&str]) -> (&'a [&'a str], bool) {
     }
 }
 
-fn remove_top_quote<'a>(lines: &'a [&str]) -> (&'a [&'a str], bool) {
+/// Removes the quote at the beginning of the message, including a
+/// headline like "On 01.02.2016, xy@z wrote:".
+///
+/// Returns the remaining lines and the quoted text with the quote
+/// markers removed, if a quote was found.
+fn remove_top_quote<'a>(lines: &'a [&str]) -> (&'a [&'a str], Option<String>) {
+    let mut first_quoted_line = 0;
     let mut last_quoted_line = None;
     let mut has_quoted_headline = false;
     for (l, line) in lines.iter().enumerate() {
         if is_plain_quote(line) {
+            if last_quoted_line.is_none() {
+                first_quoted_line = l;
+            }
             last_quoted_line = Some(l)
         } else if !is_empty_line(line) {
             if is_quoted_headline(line) && !has_quoted_headline && last_quoted_line.is_none() {
@@ -148,17 +164,33 @@ fn remove_top_quote<'a>(lines: &'a [&str]) -> (&'a [&'a str], bool) {
         }
     }
     if let Some(last_quoted_line) = last_quoted_line {
-        (&lines[last_quoted_line + 1..], true)
+        let quote = lines[first_quoted_line..=last_quoted_line]
+            .iter()
+            .map(|line| unquote_line(line))
+            .collect::<Vec<_>>()
+            .join("\n");
+        (&lines[last_quoted_line + 1..], Some(quote))
     } else {
-        (lines, false)
+        (lines, None)
     }
 }
 
-fn render_message(lines: &[&str], is_cut_at_begin: bool, is_cut_at_end: bool) -> String {
-    let mut ret = String::new();
-    if is_cut_at_begin {
-        ret += "[...]";
+/// Removes one level of quote markers from a quoted line.
+fn unquote_line(line: &str) -> &str {
+    let line = if line.starts_with('>') {
+        &line[1..]
+    } else {
+        line
+    };
+    if line.starts_with(' ') {
+        &line[1..]
+    } else {
+        line
     }
+}
+
+fn render_message(lines: &[&str], is_cut_at_end: bool) -> String {
+    let mut ret = String::new();
     /* we write empty lines only in case and non-empty line follows */
     let mut pending_linebreaks = 0;
     let mut empty_body = true;
@@ -181,7 +213,7 @@ fn render_message(lines: &[&str], is_cut_at_begin: bool, is_cut_at_end: bool) ->
             pending_linebreaks = 1
         }
     }
-    if is_cut_at_end && (!is_cut_at_begin || !empty_body) {
+    if is_cut_at_end {
         ret += " [...]";
     }
     // redo escaping done by escape_message_footer_marks()
@@ -229,7 +261,7 @@ mod tests {
         #[test]
         // proptest does not support [[:graphical:][:space:]] regex.
         fn test_simplify_plain_text_fuzzy(input in "[!-~\t \n]+") {
-            let (output, _is_forwarded) = simplify(input, true);
+            let (output, _is_forwarded, _top_quote) = simplify(input, true);
             assert!(output.split('\n').all(|s| s != "-- "));
         }
     }
@@ -237,7 +269,7 @@ mod tests {
     #[test]
     fn test_dont_remove_whole_message() {
         let input = "\n------\nFailed\n------\n\nUh-oh, this workflow did not succeed!\n\nlots of other text".to_string();
-        let (plain, is_forwarded) = simplify(input, false);
+        let (plain, is_forwarded, _) = simplify(input, false);
         assert_eq!(
             plain,
             "------\nFailed\n------\n\nUh-oh, this workflow did not succeed!\n\nlots of other text"
@@ -248,7 +280,7 @@ mod tests {
     #[test]
     fn test_chat_message() {
         let input = "Hi! How are you?\n\n---\n\nI am good.\n-- \nSent with my Delta Chat Messenger: https://delta.chat".to_string();
-        let (plain, is_forwarded) = simplify(input, true);
+        let (plain, is_forwarded, _) = simplify(input, true);
         assert_eq!(plain, "Hi! How are you?\n\n---\n\nI am good.");
         assert!(!is_forwarded);
     }
@@ -256,7 +288,7 @@ mod tests {
     #[test]
     fn test_simplify_trim() {
         let input = "line1\n\r\r\rline2".to_string();
-        let (plain, is_forwarded) = simplify(input, false);
+        let (plain, is_forwarded, _) = simplify(input, false);
 
         assert_eq!(plain, "line1\nline2");
         assert!(!is_forwarded);
@@ -265,7 +297,7 @@ mod tests {
     #[test]
     fn test_simplify_forwarded_message() {
         let input = "---------- Forwarded message ----------\r\nFrom: test@example.com\r\n\r\nForwarded message\r\n-- \r\nSignature goes here".to_string();
-        let (plain, is_forwarded) = simplify(input, false);
+        let (plain, is_forwarded, _) = simplify(input, false);
 
         assert_eq!(plain, "Forwarded message");
         assert!(is_forwarded);
@@ -285,17 +317,48 @@ mod tests {
 
     #[test]
     fn test_remove_top_quote() {
-        let (lines, has_top_quote) = remove_top_quote(&["> first", "> second"]);
+        let (lines, top_quote) = remove_top_quote(&["> first", "> second"]);
         assert!(lines.is_empty());
-        assert!(has_top_quote);
+        assert_eq!(top_quote.unwrap(), "first\nsecond");
 
-        let (lines, has_top_quote) = remove_top_quote(&["> first", "> second", "not a quote"]);
+        let (lines, top_quote) = remove_top_quote(&["> first", "> second", "not a quote"]);
         assert_eq!(lines, &["not a quote"]);
-        assert!(has_top_quote);
+        assert_eq!(top_quote.unwrap(), "first\nsecond");
 
-        let (lines, has_top_quote) = remove_top_quote(&["not a quote", "> first", "> second"]);
+        let (lines, top_quote) = remove_top_quote(&["not a quote", "> first", "> second"]);
         assert_eq!(lines, &["not a quote", "> first", "> second"]);
-        assert!(!has_top_quote);
+        assert!(top_quote.is_none());
+
+        let (lines, top_quote) = remove_top_quote(&[
+            "On 01.02.2016, xy@z wrote:",
+            "",
+            ">first",
+            ">> nested",
+            "",
+            "not a quote",
+        ]);
+        assert_eq!(lines, &["", "not a quote"]);
+        assert_eq!(top_quote.unwrap(), "first\n> nested");
+    }
+
+    #[test]
+    fn test_simplify_top_quote() {
+        let input = "> Hi! How are you?\n> I am good.\n\nGreat!".to_string();
+        let (plain, _, top_quote) = simplify(input, true);
+        assert_eq!(plain, "Great!");
+        assert_eq!(top_quote.unwrap(), "Hi! How are you?\nI am good.");
+
+        let input =
+            "On Mon, 1 Jun 2020, bob@example.net wrote:\n> Hi!\n\nHello!\n-- \nfooter".to_string();
+        let (plain, _, top_quote) = simplify(input, false);
+        assert_eq!(plain, "Hello!");
+        assert_eq!(top_quote.unwrap(), "Hi!");
+
+        // messages consisting of a quote only are not simplified
+        let input = "> just a quote".to_string();
+        let (plain, _, top_quote) = simplify(input, true);
+        assert_eq!(plain, "> just a quote");
+        assert!(top_quote.is_none());
     }
 
     #[test]
@@ -310,33 +373,33 @@ mod tests {
     #[test]
     fn test_remove_message_footer() {
         let input = "text\n--\nno footer".to_string();
-        let (plain, _) = simplify(input, true);
+        let (plain, _, _) = simplify(input, true);
         assert_eq!(plain, "text\n--\nno footer");
 
         let input = "text\n\n--\n\nno footer".to_string();
-        let (plain, _) = simplify(input, true);
+        let (plain, _, _) = simplify(input, true);
         assert_eq!(plain, "text\n\n--\n\nno footer");
 
         let input = "text\n\n-- no footer\n\n".to_string();
-        let (plain, _) = simplify(input, true);
+        let (plain, _, _) = simplify(input, true);
         assert_eq!(plain, "text\n\n-- no footer");
 
         let input = "text\n\n--\nno footer\n-- \nfooter".to_string();
-        let (plain, _) = simplify(input, true);
+        let (plain, _, _) = simplify(input, true);
         assert_eq!(plain, "text\n\n--\nno footer");
 
         let input = "text\n\n--\ntreated as footer when unescaped".to_string();
-        let (plain, _) = simplify(input.clone(), true);
+        let (plain, _, _) = simplify(input.clone(), true);
         assert_eq!(plain, "text"); // see remove_message_footer() for some explanations
         let escaped = escape_message_footer_marks(&input);
-        let (plain, _) = simplify(escaped, true);
+        let (plain, _, _) = simplify(escaped, true);
         assert_eq!(plain, "text\n\n--\ntreated as footer when unescaped");
 
         let input = "--\ntreated as footer when unescaped".to_string();
-        let (plain, _) = simplify(input.clone(), true);
+        let (plain, _, _) = simplify(input.clone(), true);
         assert_eq!(plain, ""); // see remove_message_footer() for some explanations
         let escaped = escape_message_footer_marks(&input);
-        let (plain, _) = simplify(escaped, true);
+        let (plain, _, _) = simplify(escaped, true);
         assert_eq!(plain, "--\ntreated as footer when unescaped");
     }
 }
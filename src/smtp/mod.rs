@@ -7,11 +7,13 @@ use std::time::{Duration, Instant};
 use async_smtp::smtp::client::net::*;
 use async_smtp::*;
 
+use crate::connectivity::ConnectivityStore;
 use crate::constants::*;
 use crate::context::Context;
 use crate::events::Event;
 use crate::login_param::{dc_build_tls, LoginParam};
 use crate::oauth2::*;
+use crate::proxy::Tunnel;
 use crate::stock::StockMessage;
 
 /// SMTP write and read timeout in seconds.
@@ -35,6 +37,9 @@ pub enum Error {
     #[error("SMTP: failed to setup connection {0:?}")]
     ConnectionSetupFailure(#[source] smtp::error::Error),
 
+    #[error("SMTP: failed to setup proxy tunnel: {0}")]
+    ProxyTunnel(#[source] std::io::Error),
+
     #[error("SMTP: oauth2 error {address}")]
     Oauth2Error { address: String },
 
@@ -49,6 +54,9 @@ pub struct Smtp {
     #[debug_stub(some = "SmtpTransport")]
     transport: Option<smtp::SmtpTransport>,
 
+    /// Local tunnel through the proxy, if one is used.
+    tunnel: Option<Tunnel>,
+
     /// Email address we are sending from.
     from: Option<EmailAddress>,
 
@@ -56,6 +64,8 @@ pub struct Smtp {
     /// (eg connect or send succeeded). On initialization and disconnect
     /// it is set to None.
     last_success: Option<Instant>,
+
+    pub(crate) connectivity: ConnectivityStore,
 }
 
 impl Smtp {
@@ -69,6 +79,7 @@ impl Smtp {
         if let Some(mut transport) = self.transport.take() {
             transport.close().await.ok();
         }
+        self.tunnel = None;
         self.last_success = None;
     }
 
@@ -97,6 +108,16 @@ impl Smtp {
             return Ok(());
         }
 
+        self.connectivity.set_connecting(context).await;
+        let res = self.inner_connect(context, lp).await;
+        match &res {
+            Ok(()) => self.connectivity.set_connected(context).await,
+            Err(err) => self.connectivity.set_err(context, err).await,
+        }
+        res
+    }
+
+    async fn inner_connect(&mut self, context: &Context, lp: &LoginParam) -> Result<()> {
         if lp.send_server.is_empty() || lp.send_port == 0 {
             context.emit_event(Event::ErrorNetwork("SMTP bad parameters.".into()));
             return Err(Error::BadParameters);
@@ -155,9 +176,23 @@ impl Smtp {
             smtp::ClientSecurity::Wrapper(tls_parameters)
         };
 
-        let client = smtp::SmtpClient::with_security((domain.as_str(), port), security)
-            .await
-            .map_err(Error::ConnectionSetupFailure)?;
+        // async-smtp opens the connection itself,
+        // so with a proxy it has to connect to a local tunnel.
+        let (client, tunnel) = if let Some(proxy) = &lp.proxy {
+            let tunnel = proxy
+                .tunnel(context, domain, port)
+                .await
+                .map_err(Error::ProxyTunnel)?;
+            let client = smtp::SmtpClient::with_security(tunnel.addr(), security)
+                .await
+                .map_err(Error::ConnectionSetupFailure)?;
+            (client, Some(tunnel))
+        } else {
+            let client = smtp::SmtpClient::with_security((domain.as_str(), port), security)
+                .await
+                .map_err(Error::ConnectionSetupFailure)?;
+            (client, None)
+        };
 
         let client = client
             .smtp_utf8(true)
@@ -181,6 +216,7 @@ impl Smtp {
         }
 
         self.transport = Some(trans);
+        self.tunnel = tunnel;
         self.last_success = Some(Instant::now());
 
         context.emit_event(Event::SmtpConnected(format!(
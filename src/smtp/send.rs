@@ -47,7 +47,12 @@ impl Smtp {
         );
 
         if let Some(ref mut transport) = self.transport {
-            transport.send(mail).await.map_err(Error::SendError)?;
+            self.connectivity.set_working(context).await;
+            if let Err(err) = transport.send(mail).await {
+                self.connectivity.set_err(context, &err).await;
+                return Err(Error::SendError(err));
+            }
+            self.connectivity.set_connected(context).await;
 
             context.emit_event(Event::SmtpMessageSent(format!(
                 "Message len={} was smtp-sent to {}",
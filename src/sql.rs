@@ -463,6 +463,13 @@ pub async fn housekeeping(context: &Context) {
         Param::File,
     )
     .await;
+    maybe_add_from_param(
+        context,
+        &mut files_in_use,
+        "SELECT param FROM msgs  WHERE chat_id!=3   AND type!=10;",
+        Param::Thumbnail,
+    )
+    .await;
     maybe_add_from_param(
         context,
         &mut files_in_use,
@@ -1241,6 +1248,114 @@ async fn open(
                 .await?;
             sql.set_raw_config_int(context, "dbversion", 63).await?;
         }
+        if dbversion < 64 {
+            info!(context, "[migration] v64");
+            sql.execute(
+                "ALTER TABLE chats ADD COLUMN ephemeral_timer INTEGER DEFAULT 0;",
+                paramsv![],
+            )
+            .await?;
+            sql.execute(
+                "ALTER TABLE msgs ADD COLUMN ephemeral_timer INTEGER DEFAULT 0;",
+                paramsv![],
+            )
+            .await?;
+            sql.execute(
+                "ALTER TABLE msgs ADD COLUMN ephemeral_timestamp INTEGER DEFAULT 0;",
+                paramsv![],
+            )
+            .await?;
+            sql.set_raw_config_int(context, "dbversion", 64).await?;
+        }
+        if dbversion < 65 {
+            info!(context, "[migration] v65");
+            sql.execute(
+                "ALTER TABLE msgs ADD COLUMN quoted_text TEXT DEFAULT '';",
+                paramsv![],
+            )
+            .await?;
+            sql.set_raw_config_int(context, "dbversion", 65).await?;
+        }
+        if dbversion < 66 {
+            info!(context, "[migration] v66");
+            sql.execute(
+                "CREATE TABLE reactions (msg_id INTEGER NOT NULL, contact_id INTEGER NOT NULL, reaction TEXT DEFAULT '' NOT NULL, PRIMARY KEY(msg_id, contact_id));",
+                paramsv![],
+            )
+            .await?;
+            sql.set_raw_config_int(context, "dbversion", 66).await?;
+        }
+        if dbversion < 67 {
+            info!(context, "[migration] v67");
+            // full-text index of the message texts,
+            // the text itself is not duplicated but read from the msgs table.
+            sql.execute(
+                "CREATE VIRTUAL TABLE msgs_fts USING fts5(txt, content='msgs', content_rowid='id', tokenize='unicode61 remove_diacritics 2');",
+                paramsv![],
+            )
+            .await?;
+            sql.execute(
+                "CREATE TRIGGER msgs_fts_insert AFTER INSERT ON msgs BEGIN \
+                 INSERT INTO msgs_fts(rowid, txt) VALUES (new.id, new.txt); \
+                 END;",
+                paramsv![],
+            )
+            .await?;
+            sql.execute(
+                "CREATE TRIGGER msgs_fts_delete AFTER DELETE ON msgs BEGIN \
+                 INSERT INTO msgs_fts(msgs_fts, rowid, txt) VALUES ('delete', old.id, old.txt); \
+                 END;",
+                paramsv![],
+            )
+            .await?;
+            sql.execute(
+                "CREATE TRIGGER msgs_fts_update AFTER UPDATE OF txt ON msgs BEGIN \
+                 INSERT INTO msgs_fts(msgs_fts, rowid, txt) VALUES ('delete', old.id, old.txt); \
+                 INSERT INTO msgs_fts(rowid, txt) VALUES (new.id, new.txt); \
+                 END;",
+                paramsv![],
+            )
+            .await?;
+            // index existing messages
+            sql.execute(
+                "INSERT INTO msgs_fts(msgs_fts) VALUES ('rebuild');",
+                paramsv![],
+            )
+            .await?;
+            sql.set_raw_config_int(context, "dbversion", 67).await?;
+        }
+        if dbversion < 68 {
+            info!(context, "[migration] v68");
+            sql.execute(
+                "CREATE TABLE pinned_msgs (chat_id INTEGER NOT NULL, msg_id INTEGER NOT NULL, timestamp INTEGER DEFAULT 0, PRIMARY KEY(chat_id, msg_id));",
+                paramsv![],
+            )
+            .await?;
+            sql.set_raw_config_int(context, "dbversion", 68).await?;
+        }
+        if dbversion < 69 {
+            info!(context, "[migration] v69");
+            sql.execute(
+                "CREATE TABLE msgs_delivered (msg_id INTEGER NOT NULL, addr TEXT NOT NULL, timestamp INTEGER DEFAULT 0, PRIMARY KEY(msg_id, addr));",
+                paramsv![],
+            )
+            .await?;
+            sql.set_raw_config_int(context, "dbversion", 69).await?;
+        }
+        if dbversion < 70 {
+            info!(context, "[migration] v70");
+            sql.execute(
+                "CREATE TABLE group_member_changes (id INTEGER PRIMARY KEY AUTOINCREMENT, chat_id INTEGER NOT NULL, addr TEXT NOT NULL, timestamp INTEGER DEFAULT 0, raw TEXT DEFAULT '');",
+                paramsv![],
+            )
+            .await?;
+            sql.execute(
+                "CREATE INDEX group_member_changes_index1 ON group_member_changes (chat_id, addr);",
+                paramsv![],
+            )
+            .await?;
+            sql.set_raw_config_int(context, "dbversion", 70).await?;
+        }
 
         // (2) updates that require high-level objects
         // (the structure is complete now and all objects are usable)
@@ -179,6 +179,38 @@ pub enum StockMessage {
 
     #[strum(props(fallback = "Unknown Sender for this chat. See 'info' for more details."))]
     UnknownSenderForChat = 72,
+
+    #[strum(props(fallback = "Message deletion timer is disabled."))]
+    MsgEphemeralTimerDisabled = 73,
+
+    #[strum(props(fallback = "Message deletion timer is set to %1$s s."))]
+    MsgEphemeralTimerEnabled = 74,
+
+    #[strum(props(fallback = "Message deletion timer is set to 1 minute."))]
+    MsgEphemeralTimerMinute = 75,
+
+    #[strum(props(fallback = "Message deletion timer is set to 1 hour."))]
+    MsgEphemeralTimerHour = 76,
+
+    #[strum(props(fallback = "Message deletion timer is set to 1 day."))]
+    MsgEphemeralTimerDay = 77,
+
+    #[strum(props(fallback = "Message deletion timer is set to 1 week."))]
+    MsgEphemeralTimerWeek = 78,
+
+    #[strum(props(
+        fallback = "Messages from %1$s are held back until the contact is verified again."
+    ))]
+    MsgHeldBack = 79,
+
+    #[strum(props(fallback = "Message \"%1$s\" pinned."))]
+    MsgPinned = 80,
+
+    #[strum(props(fallback = "Message \"%1$s\" unpinned."))]
+    MsgUnpinned = 81,
+
+    #[strum(props(fallback = "Contact"))]
+    Contact = 82,
 }
 
 /*
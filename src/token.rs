@@ -60,6 +60,22 @@ pub async fn lookup_or_new(context: &Context, namespace: Namespace, foreign_id:
     save(context, namespace, foreign_id).await
 }
 
+/// Returns the chat the token was created for, see [save].
+pub async fn lookup_foreign_id(
+    context: &Context,
+    namespace: Namespace,
+    token: &str,
+) -> Option<ChatId> {
+    context
+        .sql
+        .query_get_value::<ChatId>(
+            context,
+            "SELECT foreign_id FROM tokens WHERE namespc=? AND token=?;",
+            paramsv![namespace, token],
+        )
+        .await
+}
+
 pub async fn exists(context: &Context, namespace: Namespace, token: &str) -> bool {
     context
         .sql
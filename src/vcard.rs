@@ -0,0 +1,390 @@
+//! # vCard parsing and generation
+//!
+//! Supports vCard 3.0 (RFC 2426) and 4.0 (RFC 6350) as far as needed
+//! to exchange contacts: the name, e-mail addresses, the photo and the
+//! OpenPGP key of each entry.  Other properties are ignored.
+
+use crate::contact::{addr_normalize, may_be_valid_addr};
+
+/// Maximum length of a line in generated vCards, in octets.
+const MAX_LINE_LEN: usize = 75;
+
+/// A contact read from or written to a vCard.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct VcardContact {
+    /// Display name, empty if the entry has no name.
+    pub display_name: String,
+
+    /// E-mail addresses, the preferred address first.
+    pub addrs: Vec<String>,
+
+    /// Base64 encoded OpenPGP public key.
+    pub key: Option<String>,
+
+    /// Base64 encoded JPEG photo.
+    pub photo: Option<String>,
+}
+
+impl VcardContact {
+    /// Returns the preferred e-mail address of the contact.
+    pub fn addr(&self) -> &str {
+        self.addrs.first().map(|s| s.as_str()).unwrap_or_default()
+    }
+
+    /// Returns the display name or, if unset, the preferred address.
+    pub fn get_display_name(&self) -> &str {
+        if self.display_name.is_empty() {
+            self.addr()
+        } else {
+            &self.display_name
+        }
+    }
+}
+
+/// A single content line of a vCard, `NAME;PARAM=VALUE:value`.
+struct Property<'a> {
+    name: String,
+    params: Vec<(String, String)>,
+    value: &'a str,
+}
+
+impl<'a> Property<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        // the first colon outside of double quotes separates the value
+        let mut quoted = false;
+        let colon = line.char_indices().find_map(|(i, c)| match c {
+            '"' => {
+                quoted = !quoted;
+                None
+            }
+            ':' if !quoted => Some(i),
+            _ => None,
+        })?;
+        let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+        let mut head = head.split(';');
+        let name = head.next()?;
+        // strip group prefixes as in `item1.EMAIL`
+        let name = name.rsplit('.').next().unwrap_or(name).trim();
+        let params = head
+            .map(|param| {
+                let mut kv = param.splitn(2, '=');
+                let key = kv.next().unwrap_or_default().trim();
+                match kv.next() {
+                    Some(value) => (
+                        key.to_lowercase(),
+                        value.trim().trim_matches('"').to_lowercase(),
+                    ),
+                    // vCard 2.1 style parameters without a name, e.g. `EMAIL;PREF:`
+                    None => ("type".to_string(), key.to_lowercase()),
+                }
+            })
+            .collect();
+
+        Some(Property {
+            name: name.to_lowercase(),
+            params,
+            value,
+        })
+    }
+
+    fn param_contains(&self, key: &str, value: &str) -> bool {
+        self.params
+            .iter()
+            .filter(|(k, _)| k == key)
+            .any(|(_, v)| v.split(',').any(|v| v.trim() == value))
+    }
+
+    fn is_pref(&self) -> bool {
+        self.param_contains("type", "pref") || self.params.iter().any(|(k, _)| k == "pref")
+    }
+
+    /// Returns base64 data of binary properties such as `PHOTO` and `KEY`,
+    /// either as vCard 4.0 `data:` URI or as vCard 3.0 inline data,
+    /// together with the media type if known.
+    fn base64_data(&self) -> Option<(String, String)> {
+        let value = self.value.trim();
+        if value.starts_with("data:") {
+            let comma = value.find(',')?;
+            let (mediatype, data) = (&value[5..comma], &value[comma + 1..]);
+            let mut mediatype = mediatype.split(';');
+            let mime = mediatype.next().unwrap_or_default().to_lowercase();
+            if !mediatype.any(|p| p.eq_ignore_ascii_case("base64")) {
+                return None;
+            }
+            Some((mime, data.to_string()))
+        } else if self.param_contains("encoding", "b") || self.param_contains("encoding", "base64")
+        {
+            let mime = self
+                .params
+                .iter()
+                .find(|(k, _)| k == "type")
+                .map(|(_, v)| v.clone())
+                .unwrap_or_default();
+            Some((mime, value.to_string()))
+        } else {
+            None
+        }
+    }
+}
+
+/// Joins folded lines: a line starting with a space or tab
+/// continues the previous line.
+fn unfold(vcard: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in vcard.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(&line[1..]);
+                continue;
+            }
+        }
+        lines.push(line.to_string());
+    }
+    lines
+}
+
+fn unescape(value: &str) -> String {
+    let mut res = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => res.push('\n'),
+                Some(c) => res.push(c),
+                None => {}
+            }
+        } else {
+            res.push(c);
+        }
+    }
+    res
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace('\n', "\\n")
+}
+
+/// Splits a structured value at unescaped semicolons.
+fn split_structured(value: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut escaped = false;
+    for c in value.chars() {
+        if escaped {
+            parts.last_mut().unwrap().push('\\');
+            parts.last_mut().unwrap().push(c);
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == ';' {
+            parts.push(String::new());
+        } else {
+            parts.last_mut().unwrap().push(c);
+        }
+    }
+    parts.iter().map(|part| unescape(part)).collect()
+}
+
+/// Parses all entries of a vCard file.
+///
+/// Entries without a valid e-mail address are skipped.
+pub fn parse_vcard(vcard: &str) -> Vec<VcardContact> {
+    let mut contacts = Vec::new();
+    let mut current: Option<(VcardContact, String)> = None;
+
+    for line in unfold(vcard) {
+        let prop = match Property::parse(&line) {
+            Some(prop) => prop,
+            None => continue,
+        };
+        match prop.name.as_str() {
+            "begin" if prop.value.trim().eq_ignore_ascii_case("vcard") => {
+                current = Some((VcardContact::default(), String::new()));
+            }
+            "end" if prop.value.trim().eq_ignore_ascii_case("vcard") => {
+                if let Some((mut contact, name_from_n)) = current.take() {
+                    if contact.display_name.is_empty() {
+                        contact.display_name = name_from_n;
+                    }
+                    if !contact.addrs.is_empty() {
+                        contacts.push(contact);
+                    }
+                }
+            }
+            name => {
+                let (contact, name_from_n) = match current.as_mut() {
+                    Some(current) => (&mut current.0, &mut current.1),
+                    None => continue,
+                };
+                match name {
+                    "fn" => contact.display_name = unescape(prop.value.trim()),
+                    "n" => {
+                        // family;given;additional;prefixes;suffixes
+                        let parts = split_structured(prop.value);
+                        *name_from_n = parts
+                            .get(1)
+                            .into_iter()
+                            .chain(parts.get(0))
+                            .map(|s| s.trim())
+                            .filter(|s| !s.is_empty())
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                    }
+                    "email" => {
+                        let addr = addr_normalize(&unescape(prop.value)).to_string();
+                        if may_be_valid_addr(&addr)
+                            && !contact.addrs.iter().any(|a| a.eq_ignore_ascii_case(&addr))
+                        {
+                            if prop.is_pref() {
+                                contact.addrs.insert(0, addr);
+                            } else {
+                                contact.addrs.push(addr);
+                            }
+                        }
+                    }
+                    "photo" => {
+                        if let Some((_, data)) = prop.base64_data() {
+                            contact.photo = Some(data);
+                        }
+                    }
+                    "key" => {
+                        if let Some((mime, data)) = prop.base64_data() {
+                            if mime.is_empty() || mime.contains("pgp") {
+                                contact.key = Some(data);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    contacts
+}
+
+/// Appends a content line, folded to lines of at most 75 octets.
+fn push_line(vcard: &mut String, line: &str) {
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > MAX_LINE_LEN {
+            vcard.push_str("\r\n ");
+            len = 1;
+        }
+        vcard.push(c);
+        len += c.len_utf8();
+    }
+    vcard.push_str("\r\n");
+}
+
+/// Creates a vCard 4.0 file with an entry for each contact.
+pub fn make_vcard(contacts: &[VcardContact]) -> String {
+    let mut vcard = String::new();
+    for contact in contacts {
+        push_line(&mut vcard, "BEGIN:VCARD");
+        push_line(&mut vcard, "VERSION:4.0");
+        push_line(
+            &mut vcard,
+            &format!("FN:{}", escape(contact.get_display_name())),
+        );
+        for (i, addr) in contact.addrs.iter().enumerate() {
+            if i == 0 && contact.addrs.len() > 1 {
+                push_line(&mut vcard, &format!("EMAIL;PREF=1:{}", addr));
+            } else {
+                push_line(&mut vcard, &format!("EMAIL:{}", addr));
+            }
+        }
+        if let Some(key) = &contact.key {
+            push_line(
+                &mut vcard,
+                &format!("KEY:data:application/pgp-keys;base64,{}", key),
+            );
+        }
+        if let Some(photo) = &contact.photo {
+            push_line(
+                &mut vcard,
+                &format!("PHOTO:data:image/jpeg;base64,{}", photo),
+            );
+        }
+        push_line(&mut vcard, "END:VCARD");
+    }
+    vcard
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vcard3() {
+        let contacts = parse_vcard(
+            "BEGIN:VCARD\r\n\
+             VERSION:3.0\r\n\
+             N:Miller;Alan;;;\r\n\
+             item1.EMAIL;TYPE=INTERNET:alan@example.org\r\n\
+             EMAIL;TYPE=INTERNET,PREF:am@example.com\r\n\
+             PHOTO;ENCODING=b;TYPE=JPEG:/9j/4AAQ\r\n \
+             SkZJRg==\r\n\
+             KEY;TYPE=PGP;ENCODING=b:xjMEXkey\r\n\
+             END:VCARD\r\n\
+             BEGIN:VCARD\r\n\
+             VERSION:3.0\r\n\
+             FN:No Address\r\n\
+             END:VCARD\r\n",
+        );
+        assert_eq!(
+            contacts,
+            vec![VcardContact {
+                display_name: "Alan Miller".to_string(),
+                addrs: vec!["am@example.com".to_string(), "alan@example.org".to_string()],
+                key: Some("xjMEXkey".to_string()),
+                photo: Some("/9j/4AAQSkZJRg==".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_vcard4() {
+        let contacts = parse_vcard(
+            "BEGIN:VCARD\n\
+             VERSION:4.0\n\
+             FN:Bob\\, the builder\n\
+             EMAIL:bob@example.net\n\
+             KEY:data:application/pgp-keys;base64,xjMEXkey\n\
+             PHOTO:https://example.net/bob.jpg\n\
+             END:VCARD\n\
+             BEGIN:VCARD\n\
+             VERSION:4.0\n\
+             EMAIL:mailto:claire@example.org\n\
+             KEY:https://example.org/claire.asc\n\
+             END:VCARD\n",
+        );
+        assert_eq!(contacts.len(), 2);
+        assert_eq!(contacts[0].display_name, "Bob, the builder");
+        assert_eq!(contacts[0].addr(), "bob@example.net");
+        assert_eq!(contacts[0].key, Some("xjMEXkey".to_string()));
+        assert_eq!(contacts[0].photo, None);
+        assert_eq!(contacts[1].get_display_name(), "claire@example.org");
+        assert_eq!(contacts[1].key, None);
+    }
+
+    #[test]
+    fn test_make_vcard() {
+        let contact = VcardContact {
+            display_name: "Alan; Miller".to_string(),
+            addrs: vec!["am@example.com".to_string(), "alan@example.org".to_string()],
+            key: Some("x".repeat(100)),
+            photo: None,
+        };
+        let vcard = make_vcard(&[contact.clone()]);
+        assert!(vcard.starts_with("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Alan\\; Miller\r\n"));
+        assert!(vcard.contains("EMAIL;PREF=1:am@example.com\r\n"));
+        assert!(vcard.lines().all(|line| line.len() <= MAX_LINE_LEN));
+        assert_eq!(parse_vcard(&vcard), vec![contact]);
+    }
+}